@@ -564,7 +564,8 @@ export class BrowserPrivacyCash {
         // 2. Serialize ZkProof struct
         // struct ZkProof {
         //     proof_data: [u8; 256],
-        //     public_inputs: ZkPublicInputs { commitment, nullifier_hash, amount, merkle_root }
+        //     public_inputs: ZkPublicInputs { commitment, nullifier_hash, amount, merkle_root },
+        //     proof_slot: u64,
         // }
 
         // Construct Proof Data (256 bytes)
@@ -602,15 +603,21 @@ export class BrowserPrivacyCash {
             Buffer.from(new Uint8Array(32))                // merkle_root (32) - Mock for now
         ])
 
+        // Slot the proof was generated at (checked against the vault's max proof age)
+        const proofSlot = await this.connection.getSlot()
+
         const zkProofBuffer = Buffer.concat([
             proofData,
-            publicInputsBuffer
+            publicInputsBuffer,
+            new BN(proofSlot).toArrayLike(Buffer, 'le', 8) // proof_slot (8)
         ])
 
         // 3. Construct Instruction
+        // Trailing byte is the `attestation: Option<RangeAttestation>` arg (None)
         const instructionData = Buffer.concat([
             FUND_AGENT_IX_DISCRIMINATOR,
-            zkProofBuffer
+            zkProofBuffer,
+            Buffer.from([0])
         ])
 
         const instruction = new TransactionInstruction({
@@ -1194,13 +1201,22 @@ export class BrowserPrivacyCash {
             SPECTRE_PROGRAM_ID
         )
 
-        // WithdrawalRequest PDA: [WITHDRAWAL_SEED, Vault, Requester, UserDeposit]
+        // The request PDA is keyed by the deposit's next withdrawal nonce
+        // 8 (disc) + 32 (owner) + 32 (commitment) + 32 (nullifier) + 8 (amount)
+        // + 1 (delegated) + 8 (created_at) + 1 (is_active) + 32 (vault)
+        // + 16 (rebate_index) + 8 (accrued_rebate) + 4 (pending_withdrawals) = 182
+        const depositInfo = await this.connection.getAccountInfo(userDepositPDA)
+        if (!depositInfo) throw new Error('User Deposit account not found')
+        const withdrawalNonce = depositInfo.data.subarray(182, 190)
+
+        // WithdrawalRequest PDA: [WITHDRAWAL_SEED, Vault, Requester, UserDeposit, Nonce]
         const [withdrawalRequestPDA] = PublicKey.findProgramAddressSync(
             [
                 Buffer.from(WITHDRAWAL_SEED),
                 vaultPDA.toBuffer(),
                 this.publicKey.toBuffer(),
-                userDepositPDA.toBuffer()
+                userDepositPDA.toBuffer(),
+                withdrawalNonce
             ],
             SPECTRE_PROGRAM_ID
         )
@@ -322,7 +322,7 @@ class SpectreCrank {
       };
 
       const tx = await this.program.methods
-        .executeTrade(marketInput)
+        .executeTrade(marketInput, null)
         .accounts({
           authority: this.wallet.publicKey,
           vault: this.vaultPda,
@@ -151,6 +151,8 @@ export interface RangeAttestation {
   oracleSignature: number[];
   /** Whether malicious connections were found */
   hasMaliciousConnections: boolean;
+  /** Nonce binding the attestation to a single use */
+  nonce: BN;
 }
 
 // ============================================
@@ -365,12 +367,14 @@ export class RangeClient {
  * @param address - The verified address
  * @param assessment - Risk assessment from Range API
  * @param currentSlot - Current blockchain slot
+ * @param nonce - Single-use nonce (checked when the vault requires one)
  * @returns Attestation object for use with SPECTRE program
  */
 export function createRangeAttestation(
   address: PublicKey,
   assessment: RiskAssessment,
-  currentSlot: number | BN
+  currentSlot: number | BN,
+  nonce: number | BN = 0
 ): RangeAttestation {
   const slot = typeof currentSlot === 'number' ? new BN(currentSlot) : currentSlot;
 
@@ -399,6 +403,7 @@ export function createRangeAttestation(
     numHops: assessment.numHops,
     oracleSignature: new Array(64).fill(0), // Mock signature for now
     hasMaliciousConnections: assessment.hasMaliciousConnections,
+    nonce: typeof nonce === 'number' ? new BN(nonce) : nonce,
   };
 }
 
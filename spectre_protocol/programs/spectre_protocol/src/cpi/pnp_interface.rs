@@ -34,12 +34,39 @@ pub const MIN_TRADE_AMOUNT: u64 = 1_000_000;
 /// Maximum trade amount in lamports (1000 SOL)
 pub const MAX_TRADE_AMOUNT: u64 = 1_000_000_000_000;
 
+/// Minimum initial liquidity for a mock market (two minimum trades)
+pub const MIN_MARKET_LIQUIDITY: u64 = 2 * MIN_TRADE_AMOUNT;
+
+/// Market `end_time` sentinel meaning the market never expires
+pub const NO_END_TIME: i64 = i64::MAX;
+
+/// Default dispute window after market resolution (24 hours)
+pub const DEFAULT_DISPUTE_WINDOW_SECONDS: i64 = 86_400;
+
 /// Price scaling factor (1e6 = 100%)
 pub const PRICE_SCALE: u64 = 1_000_000;
 
+/// Maximum sane price for a prediction market share (100%)
+/// Also keeps shares * price well inside u128 range
+pub const MAX_PRICE: u64 = PRICE_SCALE;
+
 /// Maximum slippage allowed for market orders (5%)
 pub const MAX_SLIPPAGE_BPS: u64 = 500;
 
+/// Lowest price a trade may leave a mock market at (1%)
+pub const MIN_MARKET_PRICE: u64 = PRICE_SCALE / 100;
+
+/// Highest price a trade may leave a mock market at (99%)
+pub const MAX_MARKET_PRICE: u64 = PRICE_SCALE - MIN_MARKET_PRICE;
+
+/// Fee charged on fills at a quoted price (bps), matching the mock market's fee
+pub const QUOTED_FILL_FEE_BPS: u64 = 30;
+
+/// Check that a price is within the valid range (0, MAX_PRICE]
+pub fn is_valid_price(price: u64) -> bool {
+    price > 0 && price <= MAX_PRICE
+}
+
 // ============================================
 // Trade Side Enum
 // ============================================
@@ -127,6 +154,14 @@ pub struct TradeParams {
 
     /// Maximum slippage in basis points (only for Market orders)
     pub max_slippage_bps: u64,
+
+    /// Required improvement of the execution price over the limit price in
+    /// basis points (only for Limit orders, 0 = fill at the limit)
+    pub min_price_improvement_bps: u64,
+
+    /// Extra slippage tolerance in basis points granted per 100% of market
+    /// liquidity traded (only for Market orders, 0 = no size-scaled check)
+    pub slippage_size_coefficient_bps: u64,
 }
 
 impl Default for TradeParams {
@@ -137,6 +172,8 @@ impl Default for TradeParams {
             order_type: OrderType::Market,
             limit_price: 0,
             max_slippage_bps: MAX_SLIPPAGE_BPS,
+            min_price_improvement_bps: 0,
+            slippage_size_coefficient_bps: 0,
         }
     }
 }
@@ -150,6 +187,8 @@ impl TradeParams {
             order_type: OrderType::Market,
             limit_price: 0,
             max_slippage_bps: MAX_SLIPPAGE_BPS,
+            min_price_improvement_bps: 0,
+            slippage_size_coefficient_bps: 0,
         }
     }
 
@@ -161,9 +200,29 @@ impl TradeParams {
             order_type: OrderType::Limit,
             limit_price,
             max_slippage_bps: 0,
+            min_price_improvement_bps: 0,
+            slippage_size_coefficient_bps: 0,
         }
     }
 
+    /// Require limit fills to beat the limit price by at least `bps`
+    pub fn with_min_price_improvement(mut self, bps: u64) -> Self {
+        self.min_price_improvement_bps = bps;
+        self
+    }
+
+    /// Scale the market order slippage tolerance with trade size
+    pub fn with_size_scaled_slippage(mut self, coefficient_bps: u64) -> Self {
+        self.slippage_size_coefficient_bps = coefficient_bps;
+        self
+    }
+
+    /// Highest execution price a limit order accepts after the required improvement
+    pub fn max_fill_price(&self) -> u64 {
+        let improvement_bps = self.min_price_improvement_bps.min(10000);
+        (self.limit_price as u128 * (10000 - improvement_bps) as u128 / 10000) as u64
+    }
+
     /// Validate trade parameters
     pub fn validate(&self) -> bool {
         // Check amount bounds
@@ -176,6 +235,11 @@ impl TradeParams {
             if self.limit_price == 0 || self.limit_price > PRICE_SCALE {
                 return false;
             }
+
+            // Improvement must leave a positive fill price
+            if self.min_price_improvement_bps >= 10000 {
+                return false;
+            }
         }
 
         // For market orders, slippage must be reasonable
@@ -212,6 +276,19 @@ pub struct TradeResult {
     pub fees_paid: u64,
 }
 
+/// Errors for market operations that cannot succeed in the market's state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarketError {
+    /// Market has already resolved
+    MarketResolved,
+    /// Trade parameters failed validation
+    InvalidParams,
+    /// Market has not resolved yet
+    MarketUnresolved,
+    /// Resolved outcome can still be disputed
+    DisputeWindowActive,
+}
+
 impl Default for TradeResult {
     fn default() -> Self {
         Self {
@@ -247,6 +324,38 @@ impl TradeResult {
     }
 }
 
+/// Fill a market order at a quoted YES price (scaled by PRICE_SCALE)
+///
+/// Used when the caller already supplies market data, so no `MockMarket`
+/// is simulated. The fill has no price impact: the fee comes off the amount
+/// and the rest buys shares at the quoted price for `params.side`. Quotes
+/// outside the market price band fail the trade.
+pub fn fill_at_quote(params: &TradeParams, yes_price: u64) -> std::result::Result<TradeResult, MarketError> {
+    if !params.validate() {
+        return Err(MarketError::InvalidParams);
+    }
+
+    let price = match params.side {
+        TradeSide::Yes => yes_price,
+        TradeSide::No => PRICE_SCALE.saturating_sub(yes_price),
+    };
+    if !(MIN_MARKET_PRICE..=MAX_MARKET_PRICE).contains(&price) {
+        return Ok(TradeResult::failed());
+    }
+
+    let fees = params.amount * QUOTED_FILL_FEE_BPS / 10_000;
+    let shares = (params.amount - fees) as u128 * PRICE_SCALE as u128 / price as u128;
+    let shares = match u64::try_from(shares) {
+        Ok(shares) if shares > 0 => shares,
+        _ => return Ok(TradeResult::failed()),
+    };
+
+    // All-in price per share, like `MockMarket::effective_price`
+    let execution_price = (params.amount as u128 * PRICE_SCALE as u128 / shares as u128) as u64;
+
+    Ok(TradeResult::success(params.amount, shares, execution_price, fees))
+}
+
 // ============================================
 // Market Data Structures
 // ============================================
@@ -319,6 +428,134 @@ impl PnpMarketData {
     }
 }
 
+// ============================================
+// Market Making
+// ============================================
+
+/// Current YES/NO share inventory held by the market maker
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Inventory {
+    /// YES shares held
+    pub yes_shares: u64,
+
+    /// NO shares held
+    pub no_shares: u64,
+}
+
+impl Inventory {
+    /// Shift to apply to the mid price (scaled by PRICE_SCALE) to work off
+    /// an inventory imbalance
+    ///
+    /// Proportional to (yes - no) / (yes + no), reaching `skew_bps` of
+    /// PRICE_SCALE when holding only one side. Positive when long YES.
+    pub fn skew(&self, skew_bps: u64) -> i64 {
+        let total = self.yes_shares as i128 + self.no_shares as i128;
+        if total == 0 {
+            return 0;
+        }
+        let imbalance = self.yes_shares as i128 - self.no_shares as i128;
+        let max_shift = PRICE_SCALE as i128 * skew_bps as i128 / 10_000;
+        (max_shift * imbalance / total) as i64
+    }
+}
+
+/// Generate a two-sided quote around the market's mid price
+///
+/// The bid is a YES limit buy at `mid - spread/2`. The ask sells YES at
+/// `mid + spread/2`, expressed as the equivalent NO limit buy at
+/// `PRICE_SCALE - (mid + spread/2)`. `spread_bps` is the full spread in basis
+/// points of PRICE_SCALE.
+///
+/// The mid is shifted against the larger side of `inventory` (see
+/// `Inventory::skew`): when long YES both quotes move down, so YES is
+/// cheaper to buy from us and less attractive to sell to us.
+///
+/// Returns None if the market is inactive, the quotes would fall outside the
+/// valid price range, or the resulting params fail validation.
+pub fn generate_quotes(
+    market_data: &PnpMarketData,
+    spread_bps: u64,
+    size: u64,
+    inventory: &Inventory,
+    skew_bps: u64,
+) -> Option<(TradeParams, TradeParams)> {
+    if !market_data.is_active {
+        return None;
+    }
+
+    // Average the YES price with the YES price implied by NO
+    let implied_yes = PRICE_SCALE.checked_sub(market_data.no_price)?;
+    let fair = market_data.yes_price.checked_add(implied_yes)? / 2;
+    let mid = u64::try_from(fair as i64 - inventory.skew(skew_bps)).ok()?;
+    let half_spread = PRICE_SCALE.checked_mul(spread_bps)? / 20_000;
+
+    let bid_price = mid.checked_sub(half_spread)?;
+    let ask_price = mid.checked_add(half_spread)?;
+    let no_price = PRICE_SCALE.checked_sub(ask_price)?;
+
+    let bid = TradeParams::limit_order(TradeSide::Yes, size, bid_price);
+    let ask = TradeParams::limit_order(TradeSide::No, size, no_price);
+
+    if !bid.validate() || !ask.validate() {
+        return None;
+    }
+    Some((bid, ask))
+}
+
+/// A two-sided quote stamped with the slot its market data was read at
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct Quote {
+    /// YES limit buy
+    pub bid: TradeParams,
+
+    /// NO limit buy (equivalent to selling YES)
+    pub ask: TradeParams,
+
+    /// Slot the quote was generated at
+    pub generated_slot: u64,
+}
+
+impl Quote {
+    /// Generate a quote from current market data (see `generate_quotes`)
+    pub fn generate(
+        market_data: &PnpMarketData,
+        spread_bps: u64,
+        size: u64,
+        inventory: &Inventory,
+        skew_bps: u64,
+        generated_slot: u64,
+    ) -> Option<Self> {
+        let (bid, ask) = generate_quotes(market_data, spread_bps, size, inventory, skew_bps)?;
+        Some(Self {
+            bid,
+            ask,
+            generated_slot,
+        })
+    }
+
+    /// Check if the quote is older than `max_age_slots` (0 disables the check)
+    /// Quotes claiming a future slot are treated as stale
+    pub fn is_stale(&self, current_slot: u64, max_age_slots: u64) -> bool {
+        if max_age_slots == 0 {
+            return false;
+        }
+        match current_slot.checked_sub(self.generated_slot) {
+            Some(age) => age > max_age_slots,
+            None => true,
+        }
+    }
+
+    /// Check that both legs are valid, correctly sided limit orders
+    pub fn is_valid(&self) -> bool {
+        self.bid.side == TradeSide::Yes
+            && self.ask.side == TradeSide::No
+            && self.bid.order_type == OrderType::Limit
+            && self.ask.order_type == OrderType::Limit
+            && self.bid.validate()
+            && self.ask.validate()
+    }
+}
+
 // ============================================
 // Mock Market Implementation
 // ============================================
@@ -345,11 +582,23 @@ pub struct MockMarket {
     /// Whether market is resolved
     pub is_resolved: bool,
 
-    /// Winning side (only valid if resolved)
-    pub winning_side: TradeSide,
+    /// Winning side (None until resolved)
+    pub winning_side: Option<TradeSide>,
 
     /// Fee in basis points (e.g., 30 = 0.3%)
     pub fee_bps: u64,
+
+    /// Lowest YES/NO price a trade may push the market to (scaled by PRICE_SCALE)
+    pub min_price: u64,
+
+    /// Highest YES/NO price a trade may push the market to (scaled by PRICE_SCALE)
+    pub max_price: u64,
+
+    /// Unix timestamp when the market was resolved (only valid if resolved)
+    pub resolved_at: i64,
+
+    /// Seconds after resolution during which the outcome can be disputed
+    pub dispute_window_seconds: i64,
 }
 
 impl Default for MockMarket {
@@ -359,17 +608,32 @@ impl Default for MockMarket {
             no_reserve: 1_000_000_000,  // 1 SOL worth of NO tokens
             sol_liquidity: 2_000_000_000, // 2 SOL total liquidity
             total_volume: 0,
-            end_time: i64::MAX,
+            end_time: NO_END_TIME,
             is_resolved: false,
-            winning_side: TradeSide::Yes,
+            winning_side: None,
             fee_bps: 30, // 0.3% fee
+            min_price: MIN_MARKET_PRICE,
+            max_price: MAX_MARKET_PRICE,
+            resolved_at: 0,
+            dispute_window_seconds: DEFAULT_DISPUTE_WINDOW_SECONDS,
         }
     }
 }
 
 impl MockMarket {
+    /// Create a new mock market with initial liquidity,
+    /// rejecting liquidity below `MIN_MARKET_LIQUIDITY`
+    pub fn try_new(initial_liquidity: u64, end_time: i64) -> Option<Self> {
+        if initial_liquidity < MIN_MARKET_LIQUIDITY {
+            return None;
+        }
+        Some(Self::new(initial_liquidity, end_time))
+    }
+
     /// Create a new mock market with initial liquidity
+    /// (clamped up to `MIN_MARKET_LIQUIDITY`)
     pub fn new(initial_liquidity: u64, end_time: i64) -> Self {
+        let initial_liquidity = initial_liquidity.max(MIN_MARKET_LIQUIDITY);
         let half_liquidity = initial_liquidity / 2;
         Self {
             yes_reserve: half_liquidity,
@@ -378,18 +642,36 @@ impl MockMarket {
             total_volume: 0,
             end_time,
             is_resolved: false,
-            winning_side: TradeSide::Yes,
+            winning_side: None,
             fee_bps: 30,
+            min_price: MIN_MARKET_PRICE,
+            max_price: MAX_MARKET_PRICE,
+            resolved_at: 0,
+            dispute_window_seconds: DEFAULT_DISPUTE_WINDOW_SECONDS,
         }
     }
 
     /// Calculate current YES price using AMM formula
     pub fn yes_price(&self) -> u64 {
-        if self.yes_reserve + self.no_reserve == 0 {
+        Self::yes_price_for_reserves(self.yes_reserve, self.no_reserve)
+    }
+
+    /// YES price implied by a pair of reserves
+    fn yes_price_for_reserves(yes_reserve: u64, no_reserve: u64) -> u64 {
+        let total = yes_reserve as u128 + no_reserve as u128;
+        if total == 0 {
             return PRICE_SCALE / 2;
         }
-        (self.no_reserve as u128 * PRICE_SCALE as u128 /
-            (self.yes_reserve + self.no_reserve) as u128) as u64
+        (no_reserve as u128 * PRICE_SCALE as u128 / total) as u64
+    }
+
+    /// Check that a YES price (and the complementary NO price) is within the price band
+    pub fn is_price_in_bounds(&self, yes_price: u64) -> bool {
+        let no_price = PRICE_SCALE.saturating_sub(yes_price);
+        yes_price >= self.min_price
+            && yes_price <= self.max_price
+            && no_price >= self.min_price
+            && no_price <= self.max_price
     }
 
     /// Calculate current NO price
@@ -431,53 +713,127 @@ impl MockMarket {
         (shares_out, fee)
     }
 
+    /// All-in price per share for a hypothetical trade, including fees and slippage
+    /// Returns 0 if the trade would receive no shares
+    pub fn effective_price(&self, side: TradeSide, amount: u64) -> u64 {
+        let (shares_out, _) = self.calculate_shares_out(side, amount);
+        if shares_out == 0 {
+            return 0;
+        }
+        (amount as u128 * PRICE_SCALE as u128 / shares_out as u128) as u64
+    }
+
+    /// Read-only order book view: `(shares_out, execution_price)` for each
+    /// requested size against the current reserves, without mutating the market
+    pub fn depth(&self, side: TradeSide, sizes: &[u64]) -> Vec<(u64, u64)> {
+        sizes
+            .iter()
+            .map(|&size| {
+                let (shares_out, _) = self.calculate_shares_out(side, size);
+                (shares_out, self.effective_price(side, size))
+            })
+            .collect()
+    }
+
+    /// Pre-trade price per share of an infinitesimal, fee-free trade
+    fn marginal_price(&self, side: TradeSide) -> u64 {
+        let (reserve_in, reserve_out) = match side {
+            TradeSide::Yes => (self.no_reserve, self.yes_reserve),
+            TradeSide::No => (self.yes_reserve, self.no_reserve),
+        };
+        if reserve_out == 0 {
+            return 0;
+        }
+        (reserve_in as u128 * PRICE_SCALE as u128 / reserve_out as u128) as u64
+    }
+
+    /// Slippage tolerance in basis points for a market order against this market:
+    /// `max_slippage_bps + coefficient * amount / liquidity`, capped at 100%
+    pub fn effective_slippage_tolerance(&self, params: &TradeParams) -> u64 {
+        let size_bps = if self.sol_liquidity == 0 {
+            10000
+        } else {
+            (params.slippage_size_coefficient_bps as u128 * params.amount as u128
+                / self.sol_liquidity as u128)
+                .min(10000) as u64
+        };
+        params.max_slippage_bps.saturating_add(size_bps).min(10000)
+    }
+
     /// Execute a mock trade
-    pub fn execute_trade(&mut self, params: &TradeParams) -> TradeResult {
+    ///
+    /// Returns `Err` for trades that can never execute (resolved market,
+    /// invalid params) and `Ok` with a failed result for trades the market
+    /// declined (limit or slippage not met, price band exceeded).
+    pub fn execute_trade(&mut self, params: &TradeParams) -> std::result::Result<TradeResult, MarketError> {
         // Validate market is active
         if self.is_resolved {
-            return TradeResult::failed();
+            return Err(MarketError::MarketResolved);
         }
 
         // Validate params
         if !params.validate() {
-            return TradeResult::failed();
+            return Err(MarketError::InvalidParams);
         }
 
         // Calculate shares and execution
         let (shares_out, fees) = self.calculate_shares_out(params.side, params.amount);
 
         if shares_out == 0 {
-            return TradeResult::failed();
+            return Ok(TradeResult::failed());
         }
 
         // Calculate execution price
-        let execution_price = (params.amount as u128 * PRICE_SCALE as u128 / shares_out as u128) as u64;
+        let execution_price = self.effective_price(params.side, params.amount);
 
         // For limit orders, check price
         if params.order_type == OrderType::Limit {
-            // For buying, execution price must be at or below limit
-            if execution_price > params.limit_price {
-                return TradeResult::failed();
+            // For buying, execution price must be at or below limit,
+            // less any required improvement
+            if execution_price > params.max_fill_price() {
+                return Ok(TradeResult::failed());
             }
         }
 
-        // Update reserves
-        let amount_after_fee = params.amount.saturating_sub(fees);
-        match params.side {
-            TradeSide::Yes => {
-                self.no_reserve = self.no_reserve.saturating_add(amount_after_fee);
-                self.yes_reserve = self.yes_reserve.saturating_sub(shares_out);
-            }
-            TradeSide::No => {
-                self.yes_reserve = self.yes_reserve.saturating_add(amount_after_fee);
-                self.no_reserve = self.no_reserve.saturating_sub(shares_out);
+        // For size-scaled market orders, the fill must stay within the
+        // tolerance of the pre-trade marginal price
+        if params.order_type == OrderType::Market && params.slippage_size_coefficient_bps > 0 {
+            let max_price = self.marginal_price(params.side) as u128
+                * (10000 + self.effective_slippage_tolerance(params)) as u128
+                / 10000;
+            if execution_price as u128 > max_price {
+                return Ok(TradeResult::failed());
             }
         }
 
+        // Compute new reserves
+        let amount_after_fee = params.amount.saturating_sub(fees);
+        let (new_yes_reserve, new_no_reserve) = match params.side {
+            TradeSide::Yes => (
+                self.yes_reserve.saturating_sub(shares_out),
+                self.no_reserve.saturating_add(amount_after_fee),
+            ),
+            TradeSide::No => (
+                self.yes_reserve.saturating_add(amount_after_fee),
+                self.no_reserve.saturating_sub(shares_out),
+            ),
+        };
+
+        // Reject trades that would push the price outside the band
+        let new_yes_price = Self::yes_price_for_reserves(new_yes_reserve, new_no_reserve);
+        if !self.is_price_in_bounds(new_yes_price) {
+            return Ok(TradeResult::failed());
+        }
+
+        // Update reserves and net SOL inflow
+        self.yes_reserve = new_yes_reserve;
+        self.no_reserve = new_no_reserve;
+        self.sol_liquidity = self.sol_liquidity.saturating_add(amount_after_fee);
+
         // Update volume
         self.total_volume = self.total_volume.saturating_add(params.amount);
 
-        TradeResult::success(params.amount, shares_out, execution_price, fees)
+        Ok(TradeResult::success(params.amount, shares_out, execution_price, fees))
     }
 
     /// Get market data
@@ -492,28 +848,172 @@ impl MockMarket {
         }
     }
 
-    /// Resolve the market with a winning side
-    pub fn resolve(&mut self, winning_side: TradeSide) {
+    /// Add liquidity, growing both reserves proportionally so the price is unchanged
+    pub fn add_liquidity(&mut self, amount: u64) {
+        if self.sol_liquidity == 0 {
+            self.yes_reserve = self.yes_reserve.saturating_add(amount / 2);
+            self.no_reserve = self.no_reserve.saturating_add(amount / 2);
+        } else {
+            let yes_added = self.reserve_share(self.yes_reserve, amount);
+            let no_added = self.reserve_share(self.no_reserve, amount);
+            self.yes_reserve = self.yes_reserve.saturating_add(yes_added);
+            self.no_reserve = self.no_reserve.saturating_add(no_added);
+        }
+        self.sol_liquidity = self.sol_liquidity.saturating_add(amount);
+    }
+
+    /// Remove liquidity, shrinking both reserves proportionally so the price is unchanged
+    /// Returns false if the market would drop below `MIN_MARKET_LIQUIDITY`
+    pub fn remove_liquidity(&mut self, amount: u64) -> bool {
+        let remaining = match self.sol_liquidity.checked_sub(amount) {
+            Some(remaining) if remaining >= MIN_MARKET_LIQUIDITY => remaining,
+            _ => return false,
+        };
+
+        let yes_removed = self.reserve_share(self.yes_reserve, amount);
+        let no_removed = self.reserve_share(self.no_reserve, amount);
+        self.yes_reserve = self.yes_reserve.saturating_sub(yes_removed);
+        self.no_reserve = self.no_reserve.saturating_sub(no_removed);
+        self.sol_liquidity = remaining;
+        true
+    }
+
+    /// Portion of a reserve corresponding to `amount` of SOL liquidity
+    fn reserve_share(&self, reserve: u64, amount: u64) -> u64 {
+        (reserve as u128 * amount as u128 / self.sol_liquidity as u128) as u64
+    }
+
+    /// Seconds until the market closes (0 once closed)
+    /// Returns None for markets that never expire
+    pub fn time_until_close(&self, now: i64) -> Option<i64> {
+        if self.end_time == NO_END_TIME {
+            return None;
+        }
+        self.end_time.checked_sub(now).map(|remaining| remaining.max(0))
+    }
+
+    /// Resolve the market with a winning side, opening the dispute window
+    /// Returns false if the market is already resolved; the outcome can
+    /// only change through `dispute_resolution`
+    pub fn resolve(&mut self, winning_side: TradeSide, current_time: i64) -> bool {
+        if self.is_resolved {
+            return false;
+        }
         self.is_resolved = true;
-        self.winning_side = winning_side;
+        self.winning_side = Some(winning_side);
+        self.resolved_at = current_time;
+        true
     }
 
-    /// Calculate payout for shares if market is resolved
-    pub fn calculate_payout(&self, side: TradeSide, shares: u64) -> u64 {
-        if !self.is_resolved {
-            return 0;
+    /// Check if the resolved outcome can still be disputed
+    pub fn is_dispute_window_active(&self, current_time: i64) -> bool {
+        self.is_resolved
+            && current_time < self.resolved_at.saturating_add(self.dispute_window_seconds)
+    }
+
+    /// Overturn the winning side during the dispute window
+    /// Returns false if the market is unresolved or the window has closed
+    pub fn dispute_resolution(&mut self, winning_side: TradeSide, current_time: i64) -> bool {
+        if !self.is_dispute_window_active(current_time) {
+            return false;
+        }
+        self.winning_side = Some(winning_side);
+        true
+    }
+
+    /// Payout for redeeming shares once the dispute window has closed
+    pub fn redeem_payout(
+        &self,
+        side: TradeSide,
+        shares: u64,
+        current_time: i64,
+    ) -> std::result::Result<u64, MarketError> {
+        if self.is_dispute_window_active(current_time) {
+            return Err(MarketError::DisputeWindowActive);
         }
+        self.calculate_payout(side, shares)
+            .ok_or(MarketError::MarketUnresolved)
+    }
+
+    /// Calculate payout for shares
+    /// Returns None if the market is unresolved
+    pub fn calculate_payout(&self, side: TradeSide, shares: u64) -> Option<u64> {
+        let winning_side = self.winning_side?;
 
-        if side == self.winning_side {
+        if side == winning_side {
             // Winning side gets full value
-            shares
+            Some(shares)
         } else {
             // Losing side gets nothing
-            0
+            Some(0)
         }
     }
 }
 
+// ============================================
+// Resolved Payouts
+// ============================================
+
+/// Lamport pool shared pro rata across a resolved market's winning shares
+///
+/// Rounding policy: each redemption is rounded down, and the truncated
+/// remainder is carried to the next redemption rather than dropped. The
+/// pool is therefore never over-distributed, at most one lamport of it is
+/// outstanding as dust at any time, and once every winning share has been
+/// redeemed the payouts sum to exactly `pool`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PayoutPool {
+    /// Lamports available to winning shares
+    pub pool: u64,
+
+    /// Winning shares outstanding at resolution
+    pub total_shares: u64,
+
+    /// Winning shares redeemed so far
+    pub redeemed_shares: u64,
+
+    /// Lamports paid out so far
+    pub paid_out: u64,
+
+    /// Carried remainder, in units of 1/`total_shares` lamport
+    pub dust: u64,
+}
+
+impl PayoutPool {
+    /// Create a pool for `total_shares` winning shares
+    pub fn new(pool: u64, total_shares: u64) -> Self {
+        Self {
+            pool,
+            total_shares,
+            ..Default::default()
+        }
+    }
+
+    /// Pay out `shares` of the pool, carrying the rounding remainder forward
+    ///
+    /// Returns None, leaving the pool unchanged, if more shares would be
+    /// redeemed than were outstanding.
+    pub fn redeem(&mut self, shares: u64) -> Option<u64> {
+        let redeemed_shares = self.redeemed_shares.checked_add(shares)?;
+        if self.total_shares == 0 || redeemed_shares > self.total_shares {
+            return None;
+        }
+
+        let owed = self.pool as u128 * shares as u128 + self.dust as u128;
+        let payout = (owed / self.total_shares as u128) as u64;
+
+        self.dust = (owed % self.total_shares as u128) as u64;
+        self.redeemed_shares = redeemed_shares;
+        self.paid_out += payout;
+        Some(payout)
+    }
+
+    /// Lamports of the pool not yet paid out
+    pub fn remaining(&self) -> u64 {
+        self.pool - self.paid_out
+    }
+}
+
 // ============================================
 // CPI Data Building
 // ============================================
@@ -558,6 +1058,16 @@ mod tests {
         assert_eq!(TradeSide::No.opposite(), TradeSide::Yes);
     }
 
+    #[test]
+    fn test_is_valid_price() {
+        assert!(is_valid_price(1));
+        assert!(is_valid_price(500_000));
+        assert!(is_valid_price(MAX_PRICE));
+        assert!(!is_valid_price(0));
+        assert!(!is_valid_price(MAX_PRICE + 1));
+        assert!(!is_valid_price(u64::MAX));
+    }
+
     #[test]
     fn test_trade_params_validation() {
         // Valid market order
@@ -584,6 +1094,8 @@ mod tests {
             order_type: OrderType::Limit,
             limit_price: 0, // Invalid
             max_slippage_bps: 0,
+            min_price_improvement_bps: 0,
+            slippage_size_coefficient_bps: 0,
         };
         assert!(!invalid_limit.validate());
 
@@ -594,8 +1106,15 @@ mod tests {
             order_type: OrderType::Limit,
             limit_price: PRICE_SCALE + 1,
             max_slippage_bps: 0,
+            min_price_improvement_bps: 0,
+            slippage_size_coefficient_bps: 0,
         };
         assert!(!invalid_limit_high.validate());
+
+        // Invalid: improvement that leaves no fill price
+        let invalid_improvement = TradeParams::limit_order(TradeSide::Yes, MIN_TRADE_AMOUNT, 500_000)
+            .with_min_price_improvement(10000);
+        assert!(!invalid_improvement.validate());
     }
 
     #[test]
@@ -614,7 +1133,7 @@ mod tests {
 
         // Buy YES tokens
         let params = TradeParams::market_order(TradeSide::Yes, 100_000_000); // 0.1 SOL
-        let result = market.execute_trade(&params);
+        let result = market.execute_trade(&params).unwrap();
 
         assert!(result.success);
         assert!(result.shares_received > 0);
@@ -628,7 +1147,7 @@ mod tests {
         let mut market = MockMarket::default();
 
         let params = TradeParams::market_order(TradeSide::Yes, 100_000_000);
-        let result = market.execute_trade(&params);
+        let result = market.execute_trade(&params).unwrap();
 
         assert!(result.success);
         // Fee should be 0.3% of 0.1 SOL = 300_000 lamports
@@ -646,7 +1165,7 @@ mod tests {
             100_000, // 10% - way below 50% market price
         );
 
-        let result = market.execute_trade(&params);
+        let result = market.execute_trade(&params).unwrap();
         assert!(!result.success);
     }
 
@@ -655,25 +1174,149 @@ mod tests {
         let mut market = MockMarket::default();
 
         // Resolve in favor of YES
-        market.resolve(TradeSide::Yes);
+        assert!(market.resolve(TradeSide::Yes, 1000));
 
         assert!(market.is_resolved);
-        assert_eq!(market.winning_side, TradeSide::Yes);
+        assert_eq!(market.winning_side, Some(TradeSide::Yes));
 
         // Check payouts
-        assert_eq!(market.calculate_payout(TradeSide::Yes, 100), 100);
-        assert_eq!(market.calculate_payout(TradeSide::No, 100), 0);
+        assert_eq!(market.calculate_payout(TradeSide::Yes, 100), Some(100));
+        assert_eq!(market.calculate_payout(TradeSide::No, 100), Some(0));
+    }
+
+    #[test]
+    fn test_mock_market_no_winner_before_resolution() {
+        let market = MockMarket::default();
+        assert!(!market.is_resolved);
+        assert_eq!(market.winning_side, None);
+        assert_eq!(MockMarket::new(MIN_MARKET_LIQUIDITY, NO_END_TIME).winning_side, None);
+
+        // Neither side is paid out before resolution
+        assert_eq!(market.calculate_payout(TradeSide::Yes, 100), None);
+        assert_eq!(market.calculate_payout(TradeSide::No, 100), None);
+        assert_eq!(
+            market.redeem_payout(TradeSide::Yes, 100, i64::MAX),
+            Err(MarketError::MarketUnresolved)
+        );
+    }
+
+    #[test]
+    fn test_mock_market_resolve_once_only() {
+        let mut market = MockMarket::default();
+        assert!(market.resolve(TradeSide::Yes, 1000));
+
+        // Second resolution is rejected and leaves the outcome untouched
+        assert!(!market.resolve(TradeSide::No, 2000));
+        assert_eq!(market.winning_side, Some(TradeSide::Yes));
+        assert_eq!(market.resolved_at, 1000);
+        assert!(market.is_dispute_window_active(1000 + DEFAULT_DISPUTE_WINDOW_SECONDS - 1));
+    }
+
+    #[test]
+    fn test_mock_market_redeem_gated_by_dispute_window() {
+        let mut market = MockMarket::default();
+        market.resolve(TradeSide::Yes, 1000);
+        let window_end = 1000 + DEFAULT_DISPUTE_WINDOW_SECONDS;
+
+        // Redemption before the window closes fails
+        assert!(market.is_dispute_window_active(1000));
+        assert_eq!(
+            market.redeem_payout(TradeSide::Yes, 100, window_end - 1),
+            Err(MarketError::DisputeWindowActive)
+        );
+
+        // Redemption once the window closes succeeds
+        assert!(!market.is_dispute_window_active(window_end));
+        assert_eq!(market.redeem_payout(TradeSide::Yes, 100, window_end), Ok(100));
+        assert_eq!(market.redeem_payout(TradeSide::No, 100, window_end), Ok(0));
+    }
+
+    #[test]
+    fn test_mock_market_dispute_resolution() {
+        let mut market = MockMarket::default();
+
+        // Cannot dispute an unresolved market
+        assert!(!market.dispute_resolution(TradeSide::No, 0));
+        assert_eq!(
+            market.redeem_payout(TradeSide::Yes, 100, 0),
+            Err(MarketError::MarketUnresolved)
+        );
+
+        market.resolve(TradeSide::Yes, 1000);
+
+        // Flip the outcome during the window
+        assert!(market.dispute_resolution(TradeSide::No, 1500));
+        assert_eq!(market.winning_side, Some(TradeSide::No));
+
+        // Window closed: outcome is final
+        let window_end = 1000 + DEFAULT_DISPUTE_WINDOW_SECONDS;
+        assert!(!market.dispute_resolution(TradeSide::Yes, window_end));
+        assert_eq!(market.winning_side, Some(TradeSide::No));
+        assert_eq!(market.redeem_payout(TradeSide::No, 100, window_end), Ok(100));
     }
 
     #[test]
     fn test_mock_market_no_trade_after_resolution() {
         let mut market = MockMarket::default();
-        market.resolve(TradeSide::Yes);
+        market.resolve(TradeSide::Yes, 1000);
 
         let params = TradeParams::market_order(TradeSide::Yes, MIN_TRADE_AMOUNT);
         let result = market.execute_trade(&params);
 
+        assert_eq!(result.unwrap_err(), MarketError::MarketResolved);
+    }
+
+    #[test]
+    fn test_mock_market_price_band_rejects_extreme_trade() {
+        let mut market = MockMarket::default();
+        let before = market;
+
+        // 100 SOL into a 2 SOL market would push YES above 99%
+        let params = TradeParams::market_order(TradeSide::Yes, 100_000_000_000);
+        let result = market.execute_trade(&params).unwrap();
+
         assert!(!result.success);
+        assert_eq!(market.yes_reserve, before.yes_reserve);
+        assert_eq!(market.no_reserve, before.no_reserve);
+        assert_eq!(market.total_volume, 0);
+
+        // Same for pushing NO above 99%
+        let params = TradeParams::market_order(TradeSide::No, 100_000_000_000);
+        assert!(!market.execute_trade(&params).unwrap().success);
+    }
+
+    #[test]
+    fn test_mock_market_price_band_allows_moderate_trade() {
+        let mut market = MockMarket::default();
+
+        let params = TradeParams::market_order(TradeSide::Yes, 1_000_000_000);
+        let result = market.execute_trade(&params).unwrap();
+
+        assert!(result.success);
+        assert!(market.yes_price() <= market.max_price);
+        assert!(market.no_price() >= market.min_price);
+    }
+
+    #[test]
+    fn test_mock_market_price_band_configurable() {
+        // Tight 40%-60% band
+        let mut market = MockMarket {
+            min_price: 400_000,
+            max_price: 600_000,
+            ..MockMarket::default()
+        };
+
+        assert!(market.is_price_in_bounds(500_000));
+        assert!(!market.is_price_in_bounds(650_000));
+        assert!(!market.is_price_in_bounds(350_000));
+
+        // 1 SOL into 2 SOL of reserves moves price well past 60%
+        let params = TradeParams::market_order(TradeSide::Yes, 1_000_000_000);
+        assert!(!market.execute_trade(&params).unwrap().success);
+
+        // A small trade stays inside the band
+        let params = TradeParams::market_order(TradeSide::Yes, 10_000_000);
+        assert!(market.execute_trade(&params).unwrap().success);
     }
 
     #[test]
@@ -693,6 +1336,126 @@ mod tests {
         assert!(!data.is_within_slippage(TradeSide::Yes, 600_000, 500)); // > 5%
     }
 
+    #[test]
+    fn test_generate_quotes_straddle_mid() {
+        let data = PnpMarketData {
+            yes_price: 600_000,
+            no_price: 400_000,
+            liquidity: 10_000_000_000,
+            ..Default::default()
+        };
+
+        // 2% spread around a 60% mid
+        let (bid, ask) = generate_quotes(&data, 200, MIN_TRADE_AMOUNT, &Inventory::default(), 0).unwrap();
+        let ask_yes_price = PRICE_SCALE - ask.limit_price;
+
+        assert_eq!(bid.side, TradeSide::Yes);
+        assert_eq!(ask.side, TradeSide::No);
+        assert_eq!(bid.order_type, OrderType::Limit);
+        assert_eq!(ask.order_type, OrderType::Limit);
+        assert_eq!(bid.amount, MIN_TRADE_AMOUNT);
+        assert_eq!(ask.amount, MIN_TRADE_AMOUNT);
+        assert_eq!(bid.limit_price, 590_000);
+        assert_eq!(ask_yes_price, 610_000);
+        assert_eq!(600_000 - bid.limit_price, ask_yes_price - 600_000);
+
+        // Mid averages YES with the YES price implied by NO
+        let skewed = PnpMarketData {
+            yes_price: 500_000,
+            no_price: 480_000,
+            ..data
+        };
+        let (bid, ask) = generate_quotes(&skewed, 0, MIN_TRADE_AMOUNT, &Inventory::default(), 0).unwrap();
+        assert_eq!(bid.limit_price, 510_000);
+        assert_eq!(PRICE_SCALE - ask.limit_price, 510_000);
+    }
+
+    #[test]
+    fn test_generate_quotes_inventory_skew() {
+        let data = PnpMarketData {
+            yes_price: 600_000,
+            no_price: 400_000,
+            liquidity: 10_000_000_000,
+            ..Default::default()
+        };
+        let ask_yes = |ask: &TradeParams| PRICE_SCALE - ask.limit_price;
+
+        let balanced = Inventory { yes_shares: 500, no_shares: 500 };
+        let (balanced_bid, balanced_ask) =
+            generate_quotes(&data, 200, MIN_TRADE_AMOUNT, &balanced, 100).unwrap();
+        assert_eq!(balanced_bid.limit_price, 590_000);
+        assert_eq!(ask_yes(&balanced_ask), 610_000);
+
+        // Heavy YES: 3:1 imbalance shifts the mid down by half of 1%
+        let long_yes = Inventory { yes_shares: 1_500, no_shares: 500 };
+        assert_eq!(long_yes.skew(100), 5_000);
+        let (bid, ask) = generate_quotes(&data, 200, MIN_TRADE_AMOUNT, &long_yes, 100).unwrap();
+        assert_eq!(bid.limit_price, 585_000);
+        assert_eq!(ask_yes(&ask), 605_000);
+        assert!(bid.limit_price < balanced_bid.limit_price);
+        assert!(ask_yes(&ask) < ask_yes(&balanced_ask));
+
+        // Spread width is unchanged by skew
+        assert_eq!(ask_yes(&ask) - bid.limit_price, 20_000);
+
+        // Heavy NO shifts the other way
+        let long_no = Inventory { yes_shares: 0, no_shares: 800 };
+        assert_eq!(long_no.skew(100), -10_000);
+        let (bid, ask) = generate_quotes(&data, 200, MIN_TRADE_AMOUNT, &long_no, 100).unwrap();
+        assert!(bid.limit_price > balanced_bid.limit_price);
+        assert!(ask_yes(&ask) > ask_yes(&balanced_ask));
+
+        // No inventory, no skew
+        assert_eq!(Inventory::default().skew(100), 0);
+    }
+
+    #[test]
+    fn test_quote_staleness() {
+        let data = PnpMarketData {
+            yes_price: 600_000,
+            no_price: 400_000,
+            liquidity: 10_000_000_000,
+            ..Default::default()
+        };
+        let quote =
+            Quote::generate(&data, 200, MIN_TRADE_AMOUNT, &Inventory::default(), 0, 1_000).unwrap();
+        assert!(quote.is_valid());
+        assert_eq!(quote.generated_slot, 1_000);
+
+        // Fresh up to and including the max age
+        assert!(!quote.is_stale(1_000, 10));
+        assert!(!quote.is_stale(1_010, 10));
+        assert!(quote.is_stale(1_011, 10));
+
+        // Future slots are stale; 0 disables the check
+        assert!(quote.is_stale(999, 10));
+        assert!(!quote.is_stale(1_000_000, 0));
+
+        // Swapped legs are not a valid quote
+        let swapped = Quote {
+            bid: quote.ask,
+            ask: quote.bid,
+            ..quote
+        };
+        assert!(!swapped.is_valid());
+    }
+
+    #[test]
+    fn test_generate_quotes_rejects_invalid() {
+        let data = PnpMarketData::default();
+
+        // Spread pushes the bid below zero or the ask above 100%
+        assert!(generate_quotes(&data, 10_000, MIN_TRADE_AMOUNT, &Inventory::default(), 0).is_none());
+        assert!(generate_quotes(&data, 20_000, MIN_TRADE_AMOUNT, &Inventory::default(), 0).is_none());
+
+        // Size outside trade bounds
+        assert!(generate_quotes(&data, 200, MIN_TRADE_AMOUNT - 1, &Inventory::default(), 0).is_none());
+
+        // Inactive market
+        let inactive = PnpMarketData { is_active: false, ..data };
+        assert!(generate_quotes(&inactive, 200, MIN_TRADE_AMOUNT, &Inventory::default(), 0).is_none());
+    }
+
     #[test]
     fn test_trade_result_creation() {
         let success = TradeResult::success(100_000, 200_000, 500_000, 300);
@@ -722,11 +1485,11 @@ mod tests {
         assert_eq!(market.total_volume, 0);
 
         let params1 = TradeParams::market_order(TradeSide::Yes, 100_000_000);
-        market.execute_trade(&params1);
+        market.execute_trade(&params1).unwrap();
         assert_eq!(market.total_volume, 100_000_000);
 
         let params2 = TradeParams::market_order(TradeSide::No, 50_000_000);
-        market.execute_trade(&params2);
+        market.execute_trade(&params2).unwrap();
         assert_eq!(market.total_volume, 150_000_000);
     }
 
@@ -741,4 +1504,330 @@ mod tests {
         assert!(data.is_active);
         assert_eq!(data.end_time, 1000000);
     }
+
+    #[test]
+    fn test_mock_market_try_new_rejects_low_liquidity() {
+        assert!(MockMarket::try_new(0, 1000000).is_none());
+        assert!(MockMarket::try_new(MIN_TRADE_AMOUNT, 1000000).is_none());
+        assert!(MockMarket::try_new(MIN_MARKET_LIQUIDITY - 1, 1000000).is_none());
+
+        let market = MockMarket::try_new(MIN_MARKET_LIQUIDITY, 1000000).unwrap();
+        assert_eq!(market.sol_liquidity, MIN_MARKET_LIQUIDITY);
+        assert_eq!(market.get_market_data().yes_price, PRICE_SCALE / 2);
+    }
+
+    #[test]
+    fn test_mock_market_new_clamps_liquidity() {
+        let market = MockMarket::new(0, 1000000);
+        assert_eq!(market.sol_liquidity, MIN_MARKET_LIQUIDITY);
+        assert!(market.yes_reserve > 0);
+        assert!(market.no_reserve > 0);
+    }
+
+    #[test]
+    fn test_mock_market_effective_price() {
+        let market = MockMarket::default();
+        let no_fee_market = MockMarket { fee_bps: 0, ..market };
+        let small = 10_000_000; // 0.01 SOL
+        let large = 500_000_000; // 0.5 SOL
+
+        // All-in price exceeds the fee-exclusive spot price
+        let effective = market.effective_price(TradeSide::Yes, small);
+        assert!(effective > market.get_price(TradeSide::Yes));
+
+        // Fee impact: roughly fee_bps above the fee-free price for the same size
+        let no_fee = no_fee_market.effective_price(TradeSide::Yes, small);
+        let fee_impact_bps = (effective - no_fee) * 10_000 / no_fee;
+        assert!(fee_impact_bps >= market.fee_bps - 1 && fee_impact_bps <= market.fee_bps + 1);
+
+        // Slippage: larger trades pay more per share
+        assert!(market.effective_price(TradeSide::Yes, large) > effective);
+
+        // Matches the execution price of the same trade
+        let mut executed = market;
+        let result = executed.execute_trade(&TradeParams::market_order(TradeSide::Yes, small)).unwrap();
+        assert_eq!(result.execution_price, effective);
+
+        // No shares, no price
+        assert_eq!(market.effective_price(TradeSide::Yes, 0), 0);
+    }
+
+    #[test]
+    fn test_mock_market_time_until_close() {
+        // Default market never expires
+        let market = MockMarket::default();
+        assert_eq!(market.time_until_close(0), None);
+        assert_eq!(market.time_until_close(i64::MIN), None);
+
+        // Finite end time counts down and stops at zero
+        let market = MockMarket::new(2_000_000_000, 1_000_000);
+        assert_eq!(market.time_until_close(400_000), Some(600_000));
+        assert_eq!(market.time_until_close(1_000_000), Some(0));
+        assert_eq!(market.time_until_close(2_000_000), Some(0));
+
+        // Overflowing difference is not reported
+        assert_eq!(market.time_until_close(i64::MIN), None);
+    }
+
+    #[test]
+    fn test_mock_market_add_liquidity_preserves_price() {
+        let mut market = MockMarket::default();
+        market.execute_trade(&TradeParams::market_order(TradeSide::Yes, 200_000_000)).unwrap();
+        let price_before = market.yes_price();
+        let effective_before = market.effective_price(TradeSide::Yes, 100_000_000);
+
+        let liquidity_before = market.sol_liquidity;
+        market.add_liquidity(2_000_000_000);
+
+        assert_eq!(market.sol_liquidity, liquidity_before + 2_000_000_000);
+        assert!(market.yes_price().abs_diff(price_before) <= 1);
+
+        // Deeper market: the same trade moves the price less
+        assert!(market.effective_price(TradeSide::Yes, 100_000_000) < effective_before);
+    }
+
+    #[test]
+    fn test_mock_market_remove_liquidity() {
+        let mut market = MockMarket::default();
+        market.execute_trade(&TradeParams::market_order(TradeSide::No, 200_000_000)).unwrap();
+        let price_before = market.yes_price();
+        let liquidity_before = market.sol_liquidity;
+
+        assert!(market.remove_liquidity(1_000_000_000));
+        assert_eq!(market.sol_liquidity, liquidity_before - 1_000_000_000);
+        assert!(market.yes_price().abs_diff(price_before) <= 1);
+
+        // Cannot drain below the minimum
+        let before = market;
+        let drain = market.sol_liquidity - MIN_MARKET_LIQUIDITY + 1;
+        assert!(!market.remove_liquidity(drain));
+        assert!(!market.remove_liquidity(u64::MAX));
+        assert_eq!(market.sol_liquidity, before.sol_liquidity);
+        assert_eq!(market.yes_reserve, before.yes_reserve);
+    }
+
+    #[test]
+    fn test_mock_market_liquidity_tracks_net_inflows() {
+        let mut market = MockMarket::default();
+        let initial_liquidity = market.sol_liquidity;
+        let mut net_inflow = 0u64;
+
+        let trades = [
+            TradeParams::market_order(TradeSide::Yes, 100_000_000),
+            TradeParams::market_order(TradeSide::No, 250_000_000),
+            TradeParams::market_order(TradeSide::Yes, 40_000_000),
+        ];
+
+        for params in trades.iter() {
+            let reserves_before = market.yes_reserve + market.no_reserve;
+            let result = market.execute_trade(params).unwrap();
+            assert!(result.success);
+
+            let amount_after_fee = params.amount - result.fees_paid;
+            net_inflow += amount_after_fee;
+
+            // Reserves grow by the net input and shrink by the shares paid out
+            assert_eq!(
+                market.yes_reserve + market.no_reserve,
+                reserves_before + amount_after_fee - result.shares_received
+            );
+            assert_eq!(market.sol_liquidity, initial_liquidity + net_inflow);
+        }
+
+        // Rejected trades move nothing
+        let before = market.sol_liquidity;
+        let result = market.execute_trade(&TradeParams::market_order(TradeSide::Yes, 0));
+        assert_eq!(result.unwrap_err(), MarketError::InvalidParams);
+        assert_eq!(market.sol_liquidity, before);
+    }
+
+    #[test]
+    fn test_mock_market_limit_order_min_price_improvement() {
+        // YES is cheap, so a small buy executes well below 100%
+        let market = MockMarket {
+            yes_reserve: 3_000_000_000,
+            no_reserve: 1_000_000_000,
+            ..MockMarket::default()
+        };
+        let amount = 10_000_000;
+        let execution_price = market.effective_price(TradeSide::Yes, amount);
+        assert!(execution_price < PRICE_SCALE / 2);
+
+        // A limit exactly at the execution price fills without an improvement requirement
+        let at_limit = TradeParams::limit_order(TradeSide::Yes, amount, execution_price);
+        assert!(market.clone().execute_trade(&at_limit).unwrap().success);
+
+        // ...but is rejected once the fill must beat the limit
+        let improved = at_limit.with_min_price_improvement(50);
+        assert!(!market.clone().execute_trade(&improved).unwrap().success);
+
+        // A limit 1% above the execution price clears a 0.5% requirement
+        let generous = TradeParams::limit_order(TradeSide::Yes, amount, execution_price * 101 / 100)
+            .with_min_price_improvement(50);
+        assert_eq!(generous.max_fill_price(), execution_price * 101 / 100 * 9950 / 10000);
+        assert!(market.clone().execute_trade(&generous).unwrap().success);
+    }
+
+    #[test]
+    fn test_size_scaled_slippage_tolerance() {
+        let market = MockMarket::default(); // 2 SOL liquidity
+
+        let mut small = TradeParams::market_order(TradeSide::Yes, 10_000_000);
+        small.max_slippage_bps = 200;
+        let mut large = TradeParams::market_order(TradeSide::Yes, 100_000_000);
+        large.max_slippage_bps = 200;
+
+        // Without a coefficient both trades get the flat tolerance
+        assert_eq!(market.effective_slippage_tolerance(&small), 200);
+        assert_eq!(market.effective_slippage_tolerance(&large), 200);
+
+        // 200 bps + 20000 bps * size / liquidity
+        let small = small.with_size_scaled_slippage(20_000);
+        let large = large.with_size_scaled_slippage(20_000);
+        assert_eq!(market.effective_slippage_tolerance(&small), 300);
+        assert_eq!(market.effective_slippage_tolerance(&large), 1200);
+
+        // Tolerance is capped at 100%
+        let huge = TradeParams::market_order(TradeSide::Yes, 1_000_000_000)
+            .with_size_scaled_slippage(u64::MAX);
+        assert_eq!(market.effective_slippage_tolerance(&huge), 10000);
+    }
+
+    #[test]
+    fn test_size_scaled_slippage_execution() {
+        let mut tight = TradeParams::market_order(TradeSide::Yes, 100_000_000)
+            .with_size_scaled_slippage(1);
+        tight.max_slippage_bps = 200;
+        let scaled = tight.with_size_scaled_slippage(20_000);
+
+        // A 0.1 SOL fill costs ~10.3% over the marginal price: too much for a flat 2%
+        let mut market = MockMarket::default();
+        let before = market;
+        assert!(!market.execute_trade(&tight).unwrap().success);
+        assert_eq!(market.yes_reserve, before.yes_reserve);
+        assert_eq!(market.sol_liquidity, before.sol_liquidity);
+
+        // ...but within 2% + 20000 bps * 5% of liquidity = 12%
+        assert!(market.execute_trade(&scaled).unwrap().success);
+
+        // A small trade stays within the flat tolerance
+        let mut market = MockMarket::default();
+        let mut small = TradeParams::market_order(TradeSide::Yes, 10_000_000)
+            .with_size_scaled_slippage(1);
+        small.max_slippage_bps = 200;
+        assert!(market.execute_trade(&small).unwrap().success);
+    }
+
+    #[test]
+    fn test_payout_pool_carries_dust() {
+        // 10 lamports over 3 shares: 3.33.. each
+        let mut pool = PayoutPool::new(10, 3);
+
+        assert_eq!(pool.redeem(1), Some(3));
+        assert_eq!(pool.redeem(1), Some(3));
+        // The carried remainder lifts the last payout
+        assert_eq!(pool.redeem(1), Some(4));
+
+        assert_eq!(pool.paid_out, 10);
+        assert_eq!(pool.dust, 0);
+        assert_eq!(pool.remaining(), 0);
+    }
+
+    #[test]
+    fn test_payout_pool_reconciles_within_dust_bound() {
+        let total_shares = 7_777_777;
+        let mut pool = PayoutPool::new(1_000_000_007, total_shares);
+        let mut naive_total = 0u64;
+
+        let mut left = total_shares;
+        let mut size = 1;
+        while left > 0 {
+            let shares = size.min(left);
+            let payout = pool.redeem(shares).unwrap();
+            naive_total += (1_000_000_007u128 * shares as u128 / total_shares as u128) as u64;
+
+            // Paid out exactly the pro-rata amount so far, rounded down once
+            let exact = 1_000_000_007u128 * pool.redeemed_shares as u128 / total_shares as u128;
+            assert_eq!(pool.paid_out as u128, exact);
+            assert!(payout <= pool.pool);
+
+            left -= shares;
+            size = size * 3 + 1;
+        }
+
+        // Every lamport is distributed; truncation alone would short winners
+        assert_eq!(pool.paid_out, pool.pool);
+        assert!(naive_total < pool.pool);
+    }
+
+    #[test]
+    fn test_payout_pool_rejects_over_redemption() {
+        let mut pool = PayoutPool::new(100, 10);
+        assert_eq!(pool.redeem(10), Some(100));
+
+        let before = pool;
+        assert_eq!(pool.redeem(1), None);
+        assert_eq!(pool, before);
+
+        // A pool with no winning shares pays nothing
+        assert_eq!(PayoutPool::new(100, 0).redeem(0), None);
+    }
+
+    #[test]
+    fn test_depth_price_increases_with_size() {
+        let market = MockMarket::new(2_000_000_000, 1_000_000);
+        let sizes = [10_000_000, 50_000_000, 100_000_000, 500_000_000, 1_000_000_000];
+
+        for side in [TradeSide::Yes, TradeSide::No] {
+            let levels = market.depth(side, &sizes);
+            assert_eq!(levels.len(), sizes.len());
+
+            for (i, &(shares_out, price)) in levels.iter().enumerate() {
+                assert_eq!(market.calculate_shares_out(side, sizes[i]).0, shares_out);
+                assert_eq!(market.effective_price(side, sizes[i]), price);
+            }
+            for pair in levels.windows(2) {
+                assert!(pair[1].0 > pair[0].0);
+                assert!(pair[1].1 > pair[0].1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_depth_does_not_mutate_market() {
+        let market = MockMarket::new(2_000_000_000, 1_000_000);
+        let before = market;
+        let _ = market.depth(TradeSide::Yes, &[100_000_000, 1_000_000_000]);
+        assert_eq!(market.yes_reserve, before.yes_reserve);
+        assert_eq!(market.no_reserve, before.no_reserve);
+        assert_eq!(market.sol_liquidity, before.sol_liquidity);
+        assert!(market.depth(TradeSide::No, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_fill_at_quote() {
+        // 100M at a 0.5 YES quote: 0.3% fee, the rest buys shares at 0.5
+        let params = TradeParams::market_order(TradeSide::Yes, 100_000_000);
+        let result = fill_at_quote(&params, 500_000).unwrap();
+        assert!(result.success);
+        assert_eq!(result.amount_traded, 100_000_000);
+        assert_eq!(result.fees_paid, 300_000);
+        assert_eq!(result.shares_received, 199_400_000);
+        assert_eq!(result.execution_price, 501_504);
+
+        // NO buys at the complement of the YES quote
+        let params = TradeParams::market_order(TradeSide::No, 100_000_000);
+        let result = fill_at_quote(&params, 750_000).unwrap();
+        assert_eq!(result.shares_received, 398_800_000);
+
+        // Quotes outside the price band do not fill
+        assert!(!fill_at_quote(&params, PRICE_SCALE).unwrap().success);
+        let params = TradeParams::market_order(TradeSide::Yes, 100_000_000);
+        assert!(!fill_at_quote(&params, 0).unwrap().success);
+        assert!(!fill_at_quote(&params, MAX_PRICE + 1).unwrap().success);
+
+        // Invalid params are an error, as with the mock market
+        let params = TradeParams::market_order(TradeSide::Yes, 0);
+        assert!(matches!(fill_at_quote(&params, 500_000), Err(MarketError::InvalidParams)));
+    }
 }
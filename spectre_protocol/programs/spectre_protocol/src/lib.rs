@@ -46,10 +46,16 @@ pub mod strategy;
 pub mod utils;
 
 use state::*;
-use strategy::{TradeSignal, StrategyParams, MarketInput, run_inference};
+use strategy::{TradeSignal, StrategyParams, MarketInput, run_inference, risk_adjusted_score};
 use utils::privacy_bridge::{ZkProof, verify_deposit_proof, DepositError};
-use utils::compliance::{RangeAttestation, verify_compliance};
-use cpi::{TradeSide, TradeParams, TradeResult, MockMarket, PRICE_SCALE};
+use utils::compliance::{
+    ComplianceAction, ComplianceResult, ComplianceResultView, RangeAttestation, SplitPayout,
+    verify_compliance, verify_compliance_with_decay, verify_split_compliance, MAX_SPLIT_RECIPIENTS,
+};
+use cpi::{
+    TradeSide, TradeParams, TradeResult, Quote, MockMarket, MarketError, MAX_PRICE, MIN_TRADE_AMOUNT,
+    PRICE_SCALE, fill_at_quote, is_valid_price,
+};
 
 declare_id!("B2at4oGQFPAbuH2wMMpBsFrTvJi71GUvR7jyxny7HaGf");
 
@@ -62,11 +68,82 @@ pub mod spectre_protocol {
     // LAYER 1: THE SHIELD - Funding Instructions
     // ============================================
 
+    /// Create the protocol-wide config with `admin` as its admin
+    ///
+    /// Only the program's upgrade authority may call this, so the config
+    /// cannot be claimed by whoever reaches it first after deployment.
+    /// Until it exists, no protocol-level requirements apply.
+    pub fn initialize_protocol_config(
+        ctx: Context<InitializeProtocolConfig>,
+        admin: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.protocol_config;
+        config.admin = admin;
+        config.require_authority_compliance = false;
+        config.strategy_bounds = StrategyParamBounds::default();
+        config.bump = ctx.bumps.protocol_config;
+
+        msg!("Protocol config initialized");
+        msg!("  Admin: {}", config.admin);
+
+        Ok(())
+    }
+
+    /// Require new vault authorities to pass compliance at `initialize`
+    pub fn set_require_authority_compliance(
+        ctx: Context<UpdateProtocolConfig>,
+        required: bool,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.protocol_config;
+        config.require_authority_compliance = required;
+
+        msg!("Authority compliance requirement updated");
+        msg!("  Required: {}", required);
+
+        Ok(())
+    }
+
+    /// Bound the strategy parameters vaults may configure
+    ///
+    /// Applies to later `initialize_strategy` and `set_strategy_params`
+    /// calls; existing strategies keep their parameters.
+    pub fn set_strategy_param_bounds(
+        ctx: Context<UpdateProtocolConfig>,
+        bounds: StrategyParamBounds,
+    ) -> Result<()> {
+        require!(bounds.is_valid(), SpectreError::InvalidStrategyParams);
+
+        let config = &mut ctx.accounts.protocol_config;
+        config.strategy_bounds = bounds;
+
+        msg!("Strategy parameter bounds updated");
+        msg!("  Volatility cap: {} - {}", bounds.volatility_cap.min, bounds.volatility_cap.max);
+
+        Ok(())
+    }
+
     /// Initialize the SPECTRE vault
-    pub fn initialize(ctx: Context<Initialize>, model_hash: Option<[u8; 32]>) -> Result<()> {
-        let vault = &mut ctx.accounts.vault;
+    ///
+    /// `authority_attestation` screens the authority; it is required when
+    /// the protocol config enables `require_authority_compliance`.
+    pub fn initialize(
+        ctx: Context<Initialize>,
+        model_hash: Option<[u8; 32]>,
+        authority_attestation: Option<RangeAttestation>,
+    ) -> Result<()> {
         let clock = Clock::get()?;
 
+        // Screen the authority before the vault is created
+        let protocol_config = load_protocol_config(&ctx.accounts.protocol_config)?;
+        check_authority_compliance(
+            protocol_config.as_ref(),
+            authority_attestation.as_ref(),
+            &ctx.accounts.authority.key(),
+            clock.slot,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+
         vault.authority = ctx.accounts.authority.key();
         vault.vault_bump = ctx.bumps.vault;
         vault.vault_sol_bump = ctx.bumps.vault_sol;
@@ -81,6 +158,60 @@ pub mod spectre_protocol {
         vault.total_deposits_count = 0;
         vault.total_withdrawals_count = 0;
         vault.total_volume = 0;
+        vault.trading_window_start = 0;
+        vault.trading_window_end = SECONDS_PER_DAY;
+        vault.model_updated_at = clock.unix_timestamp;
+        vault.model_version = 0;
+        vault.total_fees_paid = 0;
+        vault.last_compliance_slot = 0;
+        vault.compliance_recheck_interval_slots = 0;
+        vault.require_deposit_compliance = false;
+        vault.compliance_score_decay = false;
+        vault.compliance_authority = Pubkey::default();
+        vault.keeper = Pubkey::default();
+        vault.model_max_age_seconds = 0;
+        vault.min_verify_interval_slots = 0;
+        vault.max_single_withdrawal = 0;
+        vault.max_proof_age_slots = 0;
+        vault.max_quote_age_slots = 0;
+        vault.require_attestation_nonce = false;
+        vault.last_attestation_nonce = 0;
+        vault.min_holding_period_seconds = 0;
+        vault.max_total_deposited = 0;
+        vault.compliance_cache_ttl_slots = 0;
+        vault.winning_closes = 0;
+        vault.losing_closes = 0;
+        vault.performance_sizing = false;
+        vault.max_position_bps = 0;
+        vault.reserved_for_withdrawal = 0;
+        vault.settlement_mint = NATIVE_SETTLEMENT_MINT;
+        vault.fee_rebate_pool = 0;
+        vault.rebate_index = 0;
+        vault.active_deposit_total = 0;
+        vault.last_loss_slot = 0;
+        vault.loss_cooldown_slots = 0;
+        vault.max_delegation_idle_slots = 0;
+        vault.total_open_exposure = 0;
+        vault.max_total_exposure_bps = 0;
+        vault.cover_recipient_rent = false;
+        vault.operator_funded = 0;
+        vault.max_pending_withdrawals = 0;
+        vault.staged_deposits = 0;
+        vault.position_cost_tolerance_bps = DEFAULT_POSITION_COST_TOLERANCE_BPS;
+        vault.min_age_to_raise_capacity_seconds = 0;
+        vault.profit_reserve_bps = 0;
+        vault.profit_reserve = 0;
+        vault.auto_compound_interval_slots = 0;
+        vault.last_compound_slot = 0;
+        vault.has_strategy = false;
+        vault.max_risk_delta = 0;
+        vault.performance_fee_bps = 0;
+        vault.fee_recipient = Pubkey::default();
+        vault.cumulative_realized_pnl = 0;
+        vault.high_water_mark = 0;
+        vault.high_water_mark_enabled = false;
+        vault.delegated_slot = 0;
+        vault.lifetime_volume = 0;
 
         msg!("SPECTRE Vault initialized");
         msg!("  Authority: {}", vault.authority);
@@ -90,7 +221,15 @@ pub mod spectre_protocol {
     }
 
     /// Fund the agent with a ZK-proven deposit
-    pub fn fund_agent(ctx: Context<FundAgent>, proof: ZkProof) -> Result<()> {
+    ///
+    /// Returns a receipt with the recorded commitment and new vault total.
+    /// `attestation` screens the depositor and is mandatory when the vault
+    /// has `require_deposit_compliance` set.
+    pub fn fund_agent(
+        ctx: Context<FundAgent>,
+        proof: ZkProof,
+        attestation: Option<RangeAttestation>,
+    ) -> Result<DepositReceipt> {
         // 1. Verify the ZK proof
         let verification = verify_deposit_proof(&proof);
 
@@ -110,6 +249,27 @@ pub mod spectre_protocol {
         let commitment = verification.commitment;
         let nullifier_hash = verification.nullifier_hash;
 
+        // 1b. Reject replayed proofs older than the vault allows
+        let clock = Clock::get()?;
+        require!(
+            !proof.is_stale(clock.slot, ctx.accounts.vault.max_proof_age_slots),
+            SpectreError::StaleProof
+        );
+
+        // 1c. Enforce the vault's deposit capacity
+        require!(
+            !ctx.accounts.vault.exceeds_capacity(amount),
+            SpectreError::VaultCapacityExceeded
+        );
+
+        // 1d. Screen the depositor before any SOL moves
+        check_depositor_compliance(
+            &ctx.accounts.vault,
+            attestation.as_ref(),
+            &ctx.accounts.depositor.key(),
+            clock.slot,
+        )?;
+
         // 2. Transfer SOL from depositor to vault (the vault account holds SOL)
         let transfer_cpi_context = CpiContext::new(
             ctx.accounts.system_program.to_account_info(),
@@ -121,7 +281,6 @@ pub mod spectre_protocol {
         system_program::transfer(transfer_cpi_context, amount)?;
 
         // 3. Initialize user deposit record
-        let clock = Clock::get()?;
         let user_deposit = &mut ctx.accounts.user_deposit;
 
         user_deposit.owner = ctx.accounts.depositor.key();
@@ -132,13 +291,19 @@ pub mod spectre_protocol {
         user_deposit.created_at = clock.unix_timestamp;
         user_deposit.is_active = true;
         user_deposit.vault = ctx.accounts.vault.key();
+        user_deposit.rebate_index = ctx.accounts.vault.rebate_index;
+        user_deposit.accrued_rebate = 0;
+        user_deposit.pending_withdrawals = 0;
+        user_deposit.withdrawal_nonce = 0;
         user_deposit.bump = ctx.bumps.user_deposit;
 
         // 4. Update vault totals
         let vault = &mut ctx.accounts.vault;
         vault.total_deposited = vault.total_deposited.checked_add(amount)
             .ok_or(SpectreError::MathOverflow)?;
-        vault.available_balance = vault.available_balance.checked_add(amount)
+        vault.credit_deposit(amount)
+            .ok_or(SpectreError::MathOverflow)?;
+        vault.active_deposit_total = vault.active_deposit_total.checked_add(amount)
             .ok_or(SpectreError::MathOverflow)?;
         vault.total_deposits_count = vault.total_deposits_count.checked_add(1)
             .ok_or(SpectreError::MathOverflow)?;
@@ -147,12 +312,25 @@ pub mod spectre_protocol {
         msg!("  Amount: {} lamports", amount);
         msg!("  Vault total: {} lamports", vault.total_deposited);
 
-        Ok(())
+        Ok(DepositReceipt::new(
+            ctx.accounts.user_deposit.key(),
+            &ctx.accounts.user_deposit,
+            &ctx.accounts.vault,
+        ))
     }
 
     /// Request a withdrawal from the vault
     pub fn request_withdrawal(ctx: Context<RequestWithdrawal>, amount: u64) -> Result<()> {
         require!(amount > 0, SpectreError::InvalidAmount);
+        require!(
+            !ctx.accounts.vault.exceeds_withdrawal_cap(amount),
+            SpectreError::WithdrawalTooLarge
+        );
+        let pending = ctx.accounts.user_deposit.pending_withdrawals;
+        require!(
+            !ctx.accounts.vault.at_pending_withdrawal_cap(pending),
+            SpectreError::TooManyPendingWithdrawals
+        );
 
         let clock = Clock::get()?;
         let withdrawal = &mut ctx.accounts.withdrawal_request;
@@ -167,8 +345,15 @@ pub mod spectre_protocol {
         withdrawal.created_at = clock.unix_timestamp;
         withdrawal.updated_at = clock.unix_timestamp;
         withdrawal.compliance_verified_slot = 0;
+        withdrawal.compliance_flagged = false;
+        withdrawal.verified_risk_score = 0;
+        withdrawal.nonce = ctx.accounts.user_deposit.next_withdrawal_nonce();
         withdrawal.bump = ctx.bumps.withdrawal_request;
 
+        // Keep the requested amount out of trading until it pays out
+        ctx.accounts.vault.reserve_withdrawal(amount);
+        ctx.accounts.user_deposit.pending_withdrawals = pending.saturating_add(1);
+
         msg!("Withdrawal request created");
         msg!("  Amount: {} lamports", amount);
         msg!("  Recipient: {}", withdrawal.recipient);
@@ -176,11 +361,111 @@ pub mod spectre_protocol {
         Ok(())
     }
 
+    /// Request a withdrawal of the deposit's entire current balance
+    pub fn request_full_withdrawal(ctx: Context<RequestFullWithdrawal>) -> Result<()> {
+        let amount = ctx.accounts.user_deposit.amount;
+        require!(amount > 0, SpectreError::InvalidAmount);
+        require!(
+            !ctx.accounts.vault.exceeds_withdrawal_cap(amount),
+            SpectreError::WithdrawalTooLarge
+        );
+        let pending = ctx.accounts.user_deposit.pending_withdrawals;
+        require!(
+            !ctx.accounts.vault.at_pending_withdrawal_cap(pending),
+            SpectreError::TooManyPendingWithdrawals
+        );
+
+        let clock = Clock::get()?;
+        let withdrawal = &mut ctx.accounts.withdrawal_request;
+
+        withdrawal.requester = ctx.accounts.requester.key();
+        withdrawal.deposit = ctx.accounts.user_deposit.key();
+        withdrawal.vault = ctx.accounts.vault.key();
+        withdrawal.amount = amount;
+        withdrawal.recipient = ctx.accounts.recipient.key();
+        withdrawal.status = WithdrawalStatus::Pending;
+        withdrawal.risk_score = 0;
+        withdrawal.created_at = clock.unix_timestamp;
+        withdrawal.updated_at = clock.unix_timestamp;
+        withdrawal.compliance_verified_slot = 0;
+        withdrawal.compliance_flagged = false;
+        withdrawal.verified_risk_score = 0;
+        withdrawal.nonce = ctx.accounts.user_deposit.next_withdrawal_nonce();
+        withdrawal.bump = ctx.bumps.withdrawal_request;
+
+        // Keep the requested amount out of trading until it pays out
+        ctx.accounts.vault.reserve_withdrawal(amount);
+        ctx.accounts.user_deposit.pending_withdrawals = pending.saturating_add(1);
+
+        msg!("Full withdrawal request created");
+        msg!("  Amount: {} lamports", amount);
+        msg!("  Recipient: {}", withdrawal.recipient);
+
+        Ok(())
+    }
+
+    /// Cancel a withdrawal request that has not paid out
+    ///
+    /// Releases the reserved amount and the deposit's pending slot, and
+    /// closes the request account, refunding its rent to the requester.
+    pub fn cancel_withdrawal(ctx: Context<CancelWithdrawal>) -> Result<()> {
+        let amount = ctx.accounts.withdrawal_request.amount;
+
+        ctx.accounts.vault.release_withdrawal(amount);
+        let user_deposit = &mut ctx.accounts.user_deposit;
+        user_deposit.pending_withdrawals = user_deposit.pending_withdrawals.saturating_sub(1);
+
+        msg!("Withdrawal request cancelled");
+        msg!("  Amount: {} lamports", amount);
+        msg!("  Nonce: {}", ctx.accounts.withdrawal_request.nonce);
+
+        Ok(())
+    }
+
+    /// Check whether `recipient` would pass withdrawal compliance
+    ///
+    /// Runs the same verification as `complete_withdrawal`, including the
+    /// vault's score decay setting, without touching any request. The
+    /// attestation nonce is neither checked nor consumed.
+    pub fn simulate_compliance(
+        ctx: Context<SimulateCompliance>,
+        attestation: RangeAttestation,
+        recipient: Pubkey,
+    ) -> Result<ComplianceResultView> {
+        let clock = Clock::get()?;
+        let result = verify_compliance_with_decay(
+            &attestation,
+            &recipient,
+            clock.slot,
+            ctx.accounts.vault.compliance_score_decay,
+        )
+        .view();
+
+        msg!("Compliance simulated");
+        msg!("  Recipient: {}", recipient);
+        msg!("  Passed: {}", result.passed);
+        msg!("  Risk score: {}", result.risk_score);
+
+        Ok(result)
+    }
+
     /// Complete a withdrawal with compliance verification
+    ///
+    /// `attestation` may be omitted when a `compliance_cache` for the
+    /// recipient holds a passing result within the vault's cache TTL and
+    /// before the entry's expiry. A passing attestation refreshes the cache
+    /// when one is provided.
     pub fn complete_withdrawal(
         ctx: Context<CompleteWithdrawal>,
-        attestation: RangeAttestation,
+        attestation: Option<RangeAttestation>,
     ) -> Result<()> {
+        // Only the compliance authority may approve, if one is set
+        let approver = ctx.accounts.compliance_authority.as_ref().map(|signer| signer.key());
+        require!(
+            ctx.accounts.vault.is_compliance_approver(approver.as_ref()),
+            SpectreError::Unauthorized
+        );
+
         let clock = Clock::get()?;
         let current_slot = clock.slot;
         let withdrawal = &mut ctx.accounts.withdrawal_request;
@@ -192,35 +477,98 @@ pub mod spectre_protocol {
             SpectreError::InvalidWithdrawalStatus
         );
 
-        // 2. Verify compliance attestation
-        let compliance_result = verify_compliance(
-            &attestation,
-            &ctx.accounts.recipient.key(),
-            current_slot,
-        );
+        // 2. Verify compliance attestation, or fall back to a cached result
+        let (risk_score, action) = match attestation {
+            Some(attestation) => {
+                // Each attestation may only be used once
+                require!(
+                    ctx.accounts.vault.consume_attestation_nonce(attestation.nonce),
+                    SpectreError::AttestationReused
+                );
+
+                let compliance_result = verify_compliance_with_decay(
+                    &attestation,
+                    &ctx.accounts.recipient.key(),
+                    current_slot,
+                    ctx.accounts.vault.compliance_score_decay,
+                );
+
+                let action = compliance_result.action();
+                if action == ComplianceAction::Block {
+                    withdrawal.status = WithdrawalStatus::Rejected;
+                    msg!("Compliance check failed");
+                    msg!("  Risk score: {}", attestation.risk_score);
+                    return Err(SpectreError::ComplianceCheckFailed.into());
+                }
+
+                if let Some(cache) = ctx.accounts.compliance_cache.as_mut() {
+                    cache.record(attestation.risk_score, current_slot);
+                }
+                (attestation.risk_score, action)
+            }
+            None => {
+                let cache = ctx.accounts.compliance_cache.as_ref()
+                    .ok_or(SpectreError::ComplianceCacheMiss)?;
+                require!(
+                    cache.is_fresh(
+                        current_slot,
+                        ctx.accounts.vault.compliance_cache_ttl_slots,
+                        clock.unix_timestamp,
+                    ),
+                    SpectreError::ComplianceCacheMiss
+                );
+
+                msg!("Using cached compliance result from slot {}", cache.verified_slot);
+                (cache.risk_score, ComplianceResult::pass(cache.risk_score).action())
+            }
+        };
 
-        withdrawal.risk_score = attestation.risk_score;
+        // Reject addresses whose risk rose sharply since approval
+        if withdrawal.is_risk_delta_exceeded(risk_score, ctx.accounts.vault.max_risk_delta) {
+            msg!("Risk profile changed since verification");
+            msg!("  Verified score: {}", withdrawal.verified_risk_score);
+            msg!("  Current score: {}", risk_score);
+            return Err(SpectreError::RiskProfileChanged.into());
+        }
+
+        withdrawal.risk_score = risk_score;
         withdrawal.compliance_verified_slot = current_slot;
         withdrawal.updated_at = clock.unix_timestamp;
+        withdrawal.status = WithdrawalStatus::Approved;
 
-        if !compliance_result.passed {
-            withdrawal.status = WithdrawalStatus::Rejected;
-            msg!("Compliance check failed");
-            msg!("  Risk score: {}", attestation.risk_score);
-            return Err(SpectreError::ComplianceCheckFailed.into());
+        // Elevated-risk withdrawals proceed but are flagged for review
+        if action == ComplianceAction::AllowWithFlag {
+            withdrawal.compliance_flagged = true;
+            emit!(ComplianceFlaggedEvent {
+                vault: ctx.accounts.vault.key(),
+                withdrawal: withdrawal.key(),
+                recipient: ctx.accounts.recipient.key(),
+                risk_score,
+                flagged_at: clock.unix_timestamp,
+            });
+            msg!("Withdrawal flagged for compliance review");
+            msg!("  Risk score: {}", risk_score);
         }
 
-        withdrawal.status = WithdrawalStatus::Approved;
-
         let amount = withdrawal.amount;
 
+        // A brand-new recipient may need rent covered to be created safely
+        let rent_top_up = ctx.accounts.vault.recipient_rent_top_up(
+            ctx.accounts.recipient.lamports(),
+            amount,
+            Rent::get()?.minimum_balance(0),
+        );
+        let payout = amount
+            .checked_add(rent_top_up)
+            .ok_or(SpectreError::MathOverflow)?;
+
         // 3. Verify sufficient balance
         require!(
             ctx.accounts.user_deposit.amount >= amount,
             SpectreError::InsufficientBalance
         );
         require!(
-            ctx.accounts.vault.available_balance >= amount,
+            ctx.accounts.vault.available_balance >= payout,
             SpectreError::InsufficientVaultBalance
         );
 
@@ -232,29 +580,38 @@ pub mod spectre_protocol {
 
             **vault_info.try_borrow_mut_lamports()? = vault_info
                 .lamports()
-                .checked_sub(amount)
+                .checked_sub(payout)
                 .ok_or(SpectreError::MathOverflow)?;
 
             **recipient_info.try_borrow_mut_lamports()? = recipient_info
                 .lamports()
-                .checked_add(amount)
+                .checked_add(payout)
                 .ok_or(SpectreError::MathOverflow)?;
         }
 
+        if rent_top_up > 0 {
+            msg!("Vault covered {} lamports of recipient rent", rent_top_up);
+        }
+
         // 5. Update state
+        let rebate_index = ctx.accounts.vault.rebate_index;
         let user_deposit = &mut ctx.accounts.user_deposit;
+        user_deposit.accrue_rebate(rebate_index);
         user_deposit.amount = user_deposit.amount
             .checked_sub(amount)
             .ok_or(SpectreError::MathOverflow)?;
 
+        user_deposit.pending_withdrawals = user_deposit.pending_withdrawals.saturating_sub(1);
         if user_deposit.amount == 0 {
             user_deposit.is_active = false;
         }
 
         let vault = &mut ctx.accounts.vault;
         vault.available_balance = vault.available_balance
-            .checked_sub(amount)
+            .checked_sub(payout)
             .ok_or(SpectreError::MathOverflow)?;
+        vault.release_withdrawal(amount);
+        vault.active_deposit_total = vault.active_deposit_total.saturating_sub(amount);
         vault.total_withdrawals_count = vault.total_withdrawals_count
             .checked_add(1)
             .ok_or(SpectreError::MathOverflow)?;
@@ -268,22 +625,269 @@ pub mod spectre_protocol {
         Ok(())
     }
 
+    /// Create the compliance cache for a withdrawal recipient
+    ///
+    /// The cache starts empty; it is only filled by `complete_withdrawal`
+    /// after a passing attestation, so anyone may pay to create it.
+    pub fn initialize_compliance_cache(ctx: Context<InitializeComplianceCache>) -> Result<()> {
+        let cache = &mut ctx.accounts.compliance_cache;
+        cache.vault = ctx.accounts.vault.key();
+        cache.recipient = ctx.accounts.recipient.key();
+        cache.risk_score = 0;
+        cache.verified_slot = 0;
+        cache.expires_at = 0;
+        cache.bump = ctx.bumps.compliance_cache;
+
+        msg!("Compliance cache initialized");
+        msg!("  Recipient: {}", cache.recipient);
+
+        Ok(())
+    }
+
+    /// Set a recipient's compliance cache to expire at `expires_at` (admin only)
+    ///
+    /// Once expired the cached result is ignored and withdrawals to the
+    /// recipient need a fresh attestation. The new expiry must be in the future.
+    pub fn refresh_compliance_cache(
+        ctx: Context<RefreshComplianceCache>,
+        expires_at: i64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(expires_at > clock.unix_timestamp, SpectreError::InvalidCacheExpiry);
+
+        let cache = &mut ctx.accounts.compliance_cache;
+        cache.expires_at = expires_at;
+
+        msg!("Compliance cache refreshed");
+        msg!("  Recipient: {}", cache.recipient);
+        msg!("  Expires at: {}", expires_at);
+
+        Ok(())
+    }
+
+    /// Complete a withdrawal split across multiple recipients
+    ///
+    /// Each recipient needs its own passing attestation; if any fails the
+    /// whole withdrawal is rejected. Recipient accounts are passed as
+    /// writable remaining accounts in the same order as `payouts`. When
+    /// attestation nonces are required, the attestations' nonces must
+    /// strictly increase in payout order.
+    pub fn complete_split_withdrawal(
+        ctx: Context<CompleteSplitWithdrawal>,
+        payouts: Vec<SplitPayout>,
+    ) -> Result<()> {
+        // Only the compliance authority may approve, if one is set
+        let approver = ctx.accounts.compliance_authority.as_ref().map(|signer| signer.key());
+        require!(
+            ctx.accounts.vault.is_compliance_approver(approver.as_ref()),
+            SpectreError::Unauthorized
+        );
+
+        let clock = Clock::get()?;
+        let current_slot = clock.slot;
+        let withdrawal = &mut ctx.accounts.withdrawal_request;
+
+        // 1. Verify the withdrawal is in correct state
+        require!(
+            withdrawal.status == WithdrawalStatus::Pending
+                || withdrawal.status == WithdrawalStatus::Approved,
+            SpectreError::InvalidWithdrawalStatus
+        );
+
+        // 2. Verify the split covers exactly the requested amount
+        require!(
+            !payouts.is_empty() && payouts.len() <= MAX_SPLIT_RECIPIENTS,
+            SpectreError::InvalidSplitPayouts
+        );
+        let amount = payouts
+            .iter()
+            .try_fold(0u64, |total, payout| total.checked_add(payout.amount))
+            .ok_or(SpectreError::MathOverflow)?;
+        require!(
+            amount == withdrawal.amount && payouts.iter().all(|p| p.amount > 0),
+            SpectreError::InvalidSplitPayouts
+        );
+
+        let recipients = ctx.remaining_accounts;
+        require!(
+            recipients.len() == payouts.len(),
+            SpectreError::RecipientMismatch
+        );
+        for (recipient_info, payout) in recipients.iter().zip(payouts.iter()) {
+            require!(
+                recipient_info.key() == payout.recipient && recipient_info.is_writable,
+                SpectreError::RecipientMismatch
+            );
+        }
+
+        // 3. Verify compliance for every recipient before moving any funds
+        // Each attestation may only be used once
+        for payout in payouts.iter() {
+            require!(
+                ctx.accounts.vault.consume_attestation_nonce(payout.attestation.nonce),
+                SpectreError::AttestationReused
+            );
+        }
+
+        withdrawal.compliance_verified_slot = current_slot;
+        withdrawal.updated_at = clock.unix_timestamp;
+
+        if let Some((index, result)) = verify_split_compliance(
+            &payouts,
+            current_slot,
+            ctx.accounts.vault.compliance_score_decay,
+        ) {
+            withdrawal.status = WithdrawalStatus::Rejected;
+            withdrawal.risk_score = result.risk_score;
+            msg!("Compliance check failed for split recipient {}", index);
+            msg!("  Risk score: {}", result.risk_score);
+            return Err(SpectreError::ComplianceCheckFailed.into());
+        }
+
+        let risk_score = payouts
+            .iter()
+            .map(|p| p.attestation.risk_score)
+            .max()
+            .unwrap_or(0);
+
+        // Reject if any recipient's risk rose sharply since approval
+        if withdrawal.is_risk_delta_exceeded(risk_score, ctx.accounts.vault.max_risk_delta) {
+            msg!("Risk profile changed since verification");
+            msg!("  Verified score: {}", withdrawal.verified_risk_score);
+            msg!("  Current score: {}", risk_score);
+            return Err(SpectreError::RiskProfileChanged.into());
+        }
+
+        withdrawal.risk_score = risk_score;
+        withdrawal.status = WithdrawalStatus::Approved;
+
+        // Brand-new recipients may need rent covered to be created safely
+        let rent_exempt = Rent::get()?.minimum_balance(0);
+        let rent_top_ups: Vec<u64> = recipients
+            .iter()
+            .zip(payouts.iter())
+            .map(|(recipient_info, payout)| {
+                ctx.accounts.vault.recipient_rent_top_up(
+                    recipient_info.lamports(),
+                    payout.amount,
+                    rent_exempt,
+                )
+            })
+            .collect();
+        let total_rent_top_up = rent_top_ups
+            .iter()
+            .try_fold(0u64, |total, &top_up| total.checked_add(top_up))
+            .ok_or(SpectreError::MathOverflow)?;
+        let total_payout = amount
+            .checked_add(total_rent_top_up)
+            .ok_or(SpectreError::MathOverflow)?;
+
+        // 4. Verify sufficient balance
+        require!(
+            ctx.accounts.user_deposit.amount >= amount,
+            SpectreError::InsufficientBalance
+        );
+        require!(
+            ctx.accounts.vault.available_balance >= total_payout,
+            SpectreError::InsufficientVaultBalance
+        );
+
+        // 5. Transfer each share from vault to its recipient
+        {
+            let vault_info = ctx.accounts.vault.to_account_info();
+
+            for ((recipient_info, payout), &rent_top_up) in
+                recipients.iter().zip(payouts.iter()).zip(rent_top_ups.iter())
+            {
+                let lamports = payout.amount
+                    .checked_add(rent_top_up)
+                    .ok_or(SpectreError::MathOverflow)?;
+
+                **vault_info.try_borrow_mut_lamports()? = vault_info
+                    .lamports()
+                    .checked_sub(lamports)
+                    .ok_or(SpectreError::MathOverflow)?;
+
+                **recipient_info.try_borrow_mut_lamports()? = recipient_info
+                    .lamports()
+                    .checked_add(lamports)
+                    .ok_or(SpectreError::MathOverflow)?;
+            }
+        }
+
+        if total_rent_top_up > 0 {
+            msg!("Vault covered {} lamports of recipient rent", total_rent_top_up);
+        }
+
+        // 6. Update state
+        let rebate_index = ctx.accounts.vault.rebate_index;
+        let user_deposit = &mut ctx.accounts.user_deposit;
+        user_deposit.accrue_rebate(rebate_index);
+        user_deposit.amount = user_deposit.amount
+            .checked_sub(amount)
+            .ok_or(SpectreError::MathOverflow)?;
+
+        user_deposit.pending_withdrawals = user_deposit.pending_withdrawals.saturating_sub(1);
+        if user_deposit.amount == 0 {
+            user_deposit.is_active = false;
+        }
+
+        let vault = &mut ctx.accounts.vault;
+        vault.available_balance = vault.available_balance
+            .checked_sub(total_payout)
+            .ok_or(SpectreError::MathOverflow)?;
+        vault.release_withdrawal(amount);
+        vault.active_deposit_total = vault.active_deposit_total.saturating_sub(amount);
+        vault.total_withdrawals_count = vault.total_withdrawals_count
+            .checked_add(1)
+            .ok_or(SpectreError::MathOverflow)?;
+
+        withdrawal.status = WithdrawalStatus::Completed;
+
+        msg!("Split withdrawal completed successfully");
+        msg!("  Amount: {} lamports", amount);
+        msg!("  Recipients: {}", payouts.len());
+
+        Ok(())
+    }
+
     /// Verify compliance for a pending withdrawal (without completing it)
+    ///
+    /// A failed check is recorded as `Rejected` rather than reverted, so the
+    /// requester can retry later with a fresh attestation.
     pub fn verify_withdrawal_compliance(
         ctx: Context<VerifyWithdrawalCompliance>,
         attestation: RangeAttestation,
     ) -> Result<()> {
+        // Only the compliance authority may approve, if one is set
+        let approver = ctx.accounts.compliance_authority.as_ref().map(|signer| signer.key());
+        require!(
+            ctx.accounts.vault.is_compliance_approver(approver.as_ref()),
+            SpectreError::Unauthorized
+        );
+
         let clock = Clock::get()?;
         let current_slot = clock.slot;
         let withdrawal = &mut ctx.accounts.withdrawal_request;
 
-        let compliance_result = verify_compliance(
+        // Rate-limit attempts so the oracle can't be spammed for a passing result
+        require!(
+            !withdrawal.is_verify_throttled(
+                current_slot,
+                ctx.accounts.vault.min_verify_interval_slots,
+            ),
+            SpectreError::VerifyTooFrequent
+        );
+
+        let compliance_result = verify_compliance_with_decay(
             &attestation,
             &withdrawal.recipient,
             current_slot,
+            ctx.accounts.vault.compliance_score_decay,
         );
 
         withdrawal.risk_score = attestation.risk_score;
+        withdrawal.verified_risk_score = attestation.risk_score;
         withdrawal.compliance_verified_slot = current_slot;
         withdrawal.updated_at = clock.unix_timestamp;
 
@@ -293,7 +897,7 @@ pub mod spectre_protocol {
         } else {
             withdrawal.status = WithdrawalStatus::Rejected;
             msg!("Compliance check failed - withdrawal rejected");
-            return Err(SpectreError::ComplianceCheckFailed.into());
+            msg!("  Risk score: {}", attestation.risk_score);
         }
 
         Ok(())
@@ -312,8 +916,9 @@ pub mod spectre_protocol {
         let config = &mut ctx.accounts.strategy_config;
         let params = params.unwrap_or_default();
 
-        // Validate params
+        // Validate params, including against protocol bounds
         require!(params.validate(), SpectreError::InvalidStrategyParams);
+        check_strategy_bounds(&ctx.accounts.protocol_config, &params)?;
 
         config.vault = ctx.accounts.vault.key();
         config.authority = ctx.accounts.authority.key();
@@ -326,8 +931,26 @@ pub mod spectre_protocol {
         config.last_signal = 0;
         config.last_signal_at = 0;
         config.total_signals = 0;
+        config.actionable_signals = 0;
+        config.hold_signals = 0;
+        config.max_trades_per_day = 0;
+        config.trades_today = 0;
+        config.trade_day_start = 0;
+        config.require_confirmation_for_normal = false;
+        config.pending_signal = None;
+        config.pending_signal_at = 0;
+        config.signal_bias = 0;
+        config.default_signal = params.default_signal;
+        config.signal_overrides = params.signal_overrides;
+        config.last_price = 0;
+        config.max_price_move_bps = 0;
+        config.min_volume_for_signal = params.min_volume_for_signal;
+        config.contrarian = false;
+        config.min_signals_before_trading = 0;
+        config.min_risk_adjusted_score = 0;
         config.bump = ctx.bumps.strategy_config;
         config._reserved = [0u8; 32];
+        ctx.accounts.vault.has_strategy = true;
 
         msg!("Strategy initialized for vault");
         msg!("  Price thresholds: {} - {}", params.price_threshold_low, params.price_threshold_high);
@@ -379,6 +1002,12 @@ pub mod spectre_protocol {
             system_program: &system_program_info,
         };
 
+        // Persist the delegated flag so the TEE session starts with it set,
+        // and start the idle clock from delegation
+        ctx.accounts.vault.is_delegated = true;
+        ctx.accounts.vault.delegated_slot = Clock::get()?.slot;
+        ctx.accounts.vault.exit(&crate::ID)?;
+
         // Configure delegation settings
         let config = DelegateConfig {
             commit_frequency_ms: 3000,
@@ -411,6 +1040,13 @@ pub mod spectre_protocol {
     pub fn undelegate_from_tee(ctx: Context<UndelegateFromTee>) -> Result<()> {
         let vault_key = ctx.accounts.vault.key();
 
+        let mut vault = {
+            let data = ctx.accounts.vault.try_borrow_data()?;
+            SpectreVault::try_deserialize(&mut &data[..])?
+        };
+        require!(vault.can_undelegate(), SpectreError::VaultNotDelegated);
+        settle_undelegated_vault(&ctx.accounts.vault, &mut vault)?;
+
         // Use the SDK's commit_and_undelegate function
         // This commits final state and returns ownership to this program
         // Note: vault is an AccountInfo since it's owned by delegation program during delegation
@@ -428,442 +1064,2323 @@ pub mod spectre_protocol {
         Ok(())
     }
 
+    /// Undelegate a vault that has been idle in the TEE for too long
+    ///
+    /// Permissionless: anyone may call this once `max_delegation_idle_slots`
+    /// have passed since the vault's last trade, so funds are not left in a
+    /// dead TEE session. Like `undelegate_from_tee`, it must be sent to the
+    /// TEE endpoint.
+    pub fn undelegate_if_stale(ctx: Context<UndelegateIfStale>) -> Result<()> {
+        let clock = Clock::get()?;
+        let vault_key = ctx.accounts.vault.key();
+
+        // The vault is owned by the delegation program, so read it by hand
+        let mut vault = {
+            let data = ctx.accounts.vault.try_borrow_data()?;
+            SpectreVault::try_deserialize(&mut &data[..])?
+        };
+        require!(
+            vault.authority == ctx.accounts.vault_authority.key(),
+            SpectreError::Unauthorized
+        );
+        require!(vault.can_undelegate(), SpectreError::VaultNotDelegated);
+        require!(
+            vault.is_delegation_stale(clock.slot),
+            SpectreError::DelegationNotStale
+        );
+        settle_undelegated_vault(&ctx.accounts.vault, &mut vault)?;
+
+        commit_and_undelegate_accounts(
+            &ctx.accounts.payer.to_account_info(),
+            vec![&ctx.accounts.vault],
+            &ctx.accounts.magic_context,
+            &ctx.accounts.magic_program,
+        ).map_err(|_| SpectreError::UndelegationFailed)?;
+
+        msg!("🔓 Idle vault undelegated from MagicBlock TEE enclave");
+        msg!("  Vault: {}", vault_key);
+        msg!("  Idle: {} slots", vault.delegation_idle_slots(clock.slot));
+
+        Ok(())
+    }
+
     /// Update the trading model hash (admin only)
     ///
     /// The model hash is used for attestation - proving which
-    /// model version generated trading signals.
+    /// model version generated trading signals. Each update bumps
+    /// the vault's model version and emits a `ModelUpdatedEvent`.
     pub fn update_model(
         ctx: Context<UpdateModel>,
         model_hash: [u8; 32],
     ) -> Result<()> {
+        let clock = Clock::get()?;
         let vault = &mut ctx.accounts.vault;
 
-        // Update model hash
-        vault.model_hash = model_hash;
+        // Update model hash and provenance
+        vault.set_model(model_hash, clock.unix_timestamp);
+
+        emit!(ModelUpdatedEvent {
+            vault: vault.key(),
+            authority: ctx.accounts.authority.key(),
+            model_hash,
+            model_version: vault.model_version,
+            updated_at: clock.unix_timestamp,
+        });
 
         msg!("Model hash updated");
         msg!("  New hash: {:?}", &model_hash[..8]); // First 8 bytes for logging
+        msg!("  Version: {}", vault.model_version);
 
         Ok(())
     }
 
-    /// Update strategy parameters
-    pub fn set_strategy_params(
-        ctx: Context<SetStrategyParams>,
-        params: StrategyParams,
+    /// Restrict trading to a daily window (seconds-of-day, UTC)
+    ///
+    /// Use 0..86400 for always-on. A start greater than the end
+    /// wraps past midnight (e.g. 22:00-04:00).
+    pub fn set_trading_window(
+        ctx: Context<UpdateVaultConfig>,
+        start: u32,
+        end: u32,
     ) -> Result<()> {
-        let clock = Clock::get()?;
+        require!(is_valid_trading_window(start, end), SpectreError::InvalidTradingWindow);
 
-        // Validate params
-        require!(params.validate(), SpectreError::InvalidStrategyParams);
+        let vault = &mut ctx.accounts.vault;
+        vault.trading_window_start = start;
+        vault.trading_window_end = end;
 
-        let config = &mut ctx.accounts.strategy_config;
+        msg!("Trading window updated");
+        msg!("  Window: {} - {} (seconds of day, UTC)", start, end);
 
-        config.price_threshold_low = params.price_threshold_low;
-        config.price_threshold_high = params.price_threshold_high;
-        config.trend_threshold = params.trend_threshold;
-        config.volatility_cap = params.volatility_cap;
-        config.updated_at = clock.unix_timestamp;
+        Ok(())
+    }
 
-        msg!("Strategy parameters updated");
-        msg!("  Price thresholds: {} - {}", params.price_threshold_low, params.price_threshold_high);
-        msg!("  Trend threshold: {}", params.trend_threshold);
-        msg!("  Volatility cap: {}", params.volatility_cap);
+    /// Require the vault authority to be re-attested every `interval_slots`
+    /// before trading (0 disables)
+    pub fn set_compliance_recheck_interval(
+        ctx: Context<UpdateVaultConfig>,
+        interval_slots: u64,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.compliance_recheck_interval_slots = interval_slots;
+
+        msg!("Compliance re-check interval updated");
+        msg!("  Interval: {} slots", interval_slots);
 
         Ok(())
     }
 
-    /// Generate a trade signal from market data
-    ///
-    /// This runs the decision tree inference inside the TEE (when delegated).
-    /// The signal is logged and stored, but execution happens separately.
-    pub fn generate_trade_signal(
-        ctx: Context<GenerateTradeSignal>,
-        input: MarketInput,
-    ) -> Result<TradeSignal> {
-        let clock = Clock::get()?;
-        let config = &mut ctx.accounts.strategy_config;
-        let vault = &ctx.accounts.vault;
+    /// Reject signals once the model is older than `max_age_seconds` (0 disables)
+    pub fn set_model_max_age(
+        ctx: Context<UpdateVaultConfig>,
+        max_age_seconds: u64,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.model_max_age_seconds = max_age_seconds;
 
-        // Build strategy params from config
-        let params = StrategyParams::new(
-            config.price_threshold_low,
-            config.price_threshold_high,
-            config.trend_threshold,
-            config.volatility_cap,
-        );
+        msg!("Model max age updated");
+        msg!("  Max age: {} seconds", max_age_seconds);
 
-        // Run inference
-        let signal = run_inference(&input, &params);
+        Ok(())
+    }
 
-        // Update stats
-        config.last_signal = match signal {
-            TradeSignal::StrongBuy => 1,
-            TradeSignal::Buy => 2,
-            TradeSignal::Hold => 3,
-            TradeSignal::Sell => 4,
-            TradeSignal::StrongSell => 5,
-        };
-        config.last_signal_at = clock.unix_timestamp;
-        config.total_signals = config.total_signals.saturating_add(1);
+    /// Require `interval_slots` between compliance verifications of the same
+    /// withdrawal (0 disables)
+    pub fn set_min_verify_interval(
+        ctx: Context<UpdateVaultConfig>,
+        interval_slots: u64,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.min_verify_interval_slots = interval_slots;
 
-        msg!("Trade signal generated");
-        msg!("  Signal: {:?}", signal);
-        msg!("  Vault delegated: {}", vault.is_delegated);
-        msg!("  Input: price={}, trend={}, vol={}", input.price, input.trend, input.volatility);
+        msg!("Minimum verify interval updated");
+        msg!("  Interval: {} slots", interval_slots);
 
-        Ok(signal)
+        Ok(())
     }
 
-    // ============================================
-    // LAYER 3: THE HAND - Trading Instructions
-    // ============================================
+    /// Cap the amount of a single withdrawal request (0 disables)
+    pub fn set_max_single_withdrawal(
+        ctx: Context<UpdateVaultConfig>,
+        max_amount: u64,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.max_single_withdrawal = max_amount;
 
-    /// Execute a trade based on market conditions and strategy signal
-    ///
-    /// This is the main entry point for automated trading. It:
-    /// 1. Generates a trade signal from market data
-    /// 2. Calculates position size based on signal strength
-    /// 3. Opens a position if signal is actionable
-    ///
-    /// Note: In Phase 3, we use a mock market for testing.
-    /// Real PNP integration would use CPI to the PNP program.
-    pub fn execute_trade(
-        ctx: Context<ExecuteTrade>,
-        market_input: MarketInput,
-    ) -> Result<TradeResult> {
-        let clock = Clock::get()?;
+        msg!("Max single withdrawal updated");
+        msg!("  Max amount: {} lamports", max_amount);
+
+        Ok(())
+    }
+
+    /// Cap the number of pending withdrawal requests per deposit (0 disables)
+    pub fn set_max_pending_withdrawals(
+        ctx: Context<UpdateVaultConfig>,
+        max_pending: u32,
+    ) -> Result<()> {
         let vault = &mut ctx.accounts.vault;
-        let config = &mut ctx.accounts.strategy_config;
+        vault.max_pending_withdrawals = max_pending;
 
-        // 1. Ensure vault is active and has sufficient balance
-        require!(vault.is_active, SpectreError::VaultInactive);
-        require!(vault.available_balance > 0, SpectreError::InsufficientVaultBalance);
-
-        // 2. Build strategy params and generate signal
-        let params = StrategyParams::new(
-            config.price_threshold_low,
-            config.price_threshold_high,
-            config.trend_threshold,
-            config.volatility_cap,
-        );
+        msg!("Max pending withdrawals updated");
+        msg!("  Max pending: {}", max_pending);
 
-        let signal = run_inference(&market_input, &params);
+        Ok(())
+    }
 
-        // 3. Update strategy stats
-        config.last_signal = match signal {
-            TradeSignal::StrongBuy => 1,
-            TradeSignal::Buy => 2,
-            TradeSignal::Hold => 3,
-            TradeSignal::Sell => 4,
-            TradeSignal::StrongSell => 5,
-        };
-        config.last_signal_at = clock.unix_timestamp;
-        config.total_signals = config.total_signals.saturating_add(1);
+    /// Reject deposit proofs generated more than `max_age_slots` ago (0 disables)
+    pub fn set_max_proof_age(
+        ctx: Context<UpdateVaultConfig>,
+        max_age_slots: u64,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.max_proof_age_slots = max_age_slots;
 
-        // 4. Determine if we should trade
-        let should_trade = signal.is_buy() || signal.is_sell();
+        msg!("Max proof age updated");
+        msg!("  Max age: {} slots", max_age_slots);
 
-        if !should_trade {
-            msg!("Signal is HOLD - no trade executed");
-            return Ok(TradeResult::default());
-        }
+        Ok(())
+    }
+
+    /// Reject market-making quotes generated more than `max_age_slots` ago (0 disables)
+    pub fn set_max_quote_age(
+        ctx: Context<UpdateVaultConfig>,
+        max_age_slots: u64,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.max_quote_age_slots = max_age_slots;
 
-        // 5. Calculate position size (5% for normal, 10% for strong signals)
-        let is_strong = signal.is_strong();
-        let position_size = vault.calculate_position_size(is_strong);
+        msg!("Max quote age updated");
+        msg!("  Max age: {} slots", max_age_slots);
+
+        Ok(())
+    }
+
+    /// Block closing positions held for less than `min_seconds` (0 disables)
+    pub fn set_min_holding_period(
+        ctx: Context<UpdateVaultConfig>,
+        min_seconds: u64,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.min_holding_period_seconds = min_seconds;
+
+        msg!("Min holding period updated");
+        msg!("  Min period: {} seconds", min_seconds);
+
+        Ok(())
+    }
+
+    /// Cap the vault's total deposits at `max_total_deposited` lamports (0 = unlimited)
+    ///
+    /// Raising or removing an existing cap requires the vault to be at least
+    /// `min_age_to_raise_capacity_seconds` old.
+    pub fn set_capacity(
+        ctx: Context<UpdateVaultConfig>,
+        max_total_deposited: u64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let vault = &mut ctx.accounts.vault;
 
-        // Ensure position size is valid
         require!(
-            position_size >= cpi::MIN_TRADE_AMOUNT,
-            SpectreError::InsufficientVaultBalance
+            !vault.is_too_young_to_raise_capacity(max_total_deposited, clock.unix_timestamp),
+            SpectreError::VaultTooYoung
         );
 
-        // 6. Determine trade side
-        let side = if signal.is_buy() {
-            TradeSide::Yes
-        } else {
-            TradeSide::No
-        };
+        vault.max_total_deposited = max_total_deposited;
 
-        // 7. Create trade params
-        let trade_params = TradeParams::market_order(side, position_size);
+        msg!("Vault capacity updated");
+        msg!("  Max total deposited: {} lamports", max_total_deposited);
+        msg!("  Currently deposited: {} lamports", vault.total_deposited);
 
-        // 8. Execute trade on mock market
-        // In production, this would be a CPI to PNP Exchange
-        let mut mock_market = MockMarket::default();
-        let result = mock_market.execute_trade(&trade_params);
+        Ok(())
+    }
 
-        if result.success {
-            // 9. Update vault state
-            vault.available_balance = vault.available_balance
-                .saturating_sub(result.amount_traded);
-            vault.total_volume = vault.total_volume
-                .saturating_add(result.amount_traded);
-            vault.last_trade_slot = clock.slot;
+    /// Require the vault to reach `min_age_seconds` before its capacity can be raised (0 = no minimum)
+    pub fn set_min_age_to_raise_capacity(
+        ctx: Context<UpdateVaultConfig>,
+        min_age_seconds: u64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let vault = &mut ctx.accounts.vault;
+        vault.min_age_to_raise_capacity_seconds = min_age_seconds;
 
-            msg!("Trade executed successfully");
-            msg!("  Signal: {:?}", signal);
-            msg!("  Side: {:?}", side);
-            msg!("  Amount: {} lamports", result.amount_traded);
-            msg!("  Shares: {}", result.shares_received);
-            msg!("  Price: {}", result.execution_price);
-        } else {
-            msg!("Trade execution failed");
-        }
+        msg!("Capacity raise age gate updated");
+        msg!("  Min vault age: {} seconds", min_age_seconds);
+        msg!("  Vault age: {} seconds", vault.age_seconds(clock.unix_timestamp));
 
-        Ok(result)
+        Ok(())
     }
 
-    /// Open a new trading position
+    /// Set the currency the vault accounts in
     ///
-    /// Creates a Position account to track an active market position.
-    /// This is called after a successful trade to record the position.
-    pub fn open_position(
-        ctx: Context<OpenPosition>,
-        market_id: Pubkey,
-        side: TradeSide,
-        shares: u64,
-        entry_price: u64,
-        invested_amount: u64,
+    /// Positions record the vault's settlement mint when opened, and can only
+    /// be closed while it still matches.
+    pub fn set_settlement_mint(
+        ctx: Context<UpdateVaultConfig>,
+        settlement_mint: Pubkey,
     ) -> Result<()> {
-        let clock = Clock::get()?;
+        let vault = &mut ctx.accounts.vault;
+        vault.settlement_mint = settlement_mint;
 
-        // Get vault key before mutable borrow
-        let vault_key = ctx.accounts.vault.key();
+        msg!("Vault settlement currency updated");
+        msg!("  Settlement mint: {}", settlement_mint);
 
-        // Validate inputs
-        require!(shares > 0, SpectreError::InvalidTradeAmount);
-        require!(invested_amount > 0, SpectreError::InvalidTradeAmount);
-        require!(entry_price > 0, SpectreError::InvalidPrice);
+        Ok(())
+    }
 
-        // Ensure vault has sufficient balance
-        require!(
-            ctx.accounts.vault.available_balance >= invested_amount,
-            SpectreError::InsufficientVaultBalance
-        );
+    /// Let cached passing compliance results stand in for an attestation
+    /// for `ttl_slots` (0 disables caching)
+    pub fn set_compliance_cache_ttl(
+        ctx: Context<UpdateVaultConfig>,
+        ttl_slots: u64,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.compliance_cache_ttl_slots = ttl_slots;
 
-        // Initialize position
-        let position = &mut ctx.accounts.position;
-        position.vault = vault_key;
-        position.market_id = market_id;
-        position.side = match side {
-            TradeSide::Yes => Side::Yes,
-            TradeSide::No => Side::No,
-        };
-        position.shares = shares;
-        position.entry_price = entry_price;
-        position.invested_amount = invested_amount;
-        position.status = PositionStatus::Open;
-        position.opened_at = clock.unix_timestamp;
-        position.closed_at = 0;
-        position.exit_price = 0;
-        position.realized_pnl = 0;
-        position.bump = ctx.bumps.position;
+        msg!("Compliance cache TTL updated");
+        msg!("  TTL: {} slots", ttl_slots);
+
+        Ok(())
+    }
+
+    /// Scale position sizes by realized win rate and cap them at
+    /// `max_position_bps` of available balance (0 = no cap)
+    pub fn set_performance_sizing(
+        ctx: Context<UpdateVaultConfig>,
+        enabled: bool,
+        max_position_bps: u16,
+    ) -> Result<()> {
+        require!(max_position_bps <= 10_000, SpectreError::InvalidAmount);
 
-        // Update vault state
         let vault = &mut ctx.accounts.vault;
-        vault.available_balance = vault.available_balance
-            .saturating_sub(invested_amount);
-        vault.active_positions = vault.active_positions
-            .saturating_add(1);
-        vault.total_volume = vault.total_volume
-            .saturating_add(invested_amount);
-        vault.last_trade_slot = clock.slot;
+        vault.performance_sizing = enabled;
+        vault.max_position_bps = max_position_bps;
 
-        msg!("Position opened");
-        msg!("  Market: {}", market_id);
-        msg!("  Side: {:?}", side);
-        msg!("  Shares: {}", shares);
-        msg!("  Entry price: {}", entry_price);
-        msg!("  Invested: {} lamports", invested_amount);
+        msg!("Performance sizing updated");
+        msg!("  Enabled: {}", enabled);
+        msg!("  Max position: {} bps", max_position_bps);
 
         Ok(())
     }
 
-    /// Close an existing trading position
-    ///
-    /// Closes a position and calculates realized PnL.
-    /// Returns funds to the vault's available balance.
-    pub fn close_position(
-        ctx: Context<ClosePosition>,
-        exit_price: u64,
-    ) -> Result<i64> {
-        let clock = Clock::get()?;
+    /// Cap total open exposure at `max_total_exposure_bps` of deposits (0 = no cap)
+    pub fn set_max_total_exposure(
+        ctx: Context<UpdateVaultConfig>,
+        max_total_exposure_bps: u16,
+    ) -> Result<()> {
+        require!(max_total_exposure_bps <= 10_000, SpectreError::InvalidAmount);
+
         let vault = &mut ctx.accounts.vault;
-        let position = &mut ctx.accounts.position;
+        vault.max_total_exposure_bps = max_total_exposure_bps;
 
-        // Verify position is open
-        require!(
-            position.status == PositionStatus::Open,
-            SpectreError::PositionAlreadyClosed
-        );
+        msg!("Total exposure cap updated");
+        msg!("  Max exposure: {} bps", max_total_exposure_bps);
+        msg!("  Open exposure: {} lamports", vault.total_open_exposure);
 
-        // Validate exit price
-        require!(exit_price > 0, SpectreError::InvalidPrice);
+        Ok(())
+    }
 
-        // Calculate position value at exit
-        // value = shares * exit_price / PRICE_SCALE
-        let exit_value = (position.shares as u128)
-            .saturating_mul(exit_price as u128)
-            .saturating_div(PRICE_SCALE as u128) as u64;
+    /// Set how far a position's invested amount may stray from shares * entry price
+    pub fn set_position_cost_tolerance(
+        ctx: Context<UpdateVaultConfig>,
+        tolerance_bps: u16,
+    ) -> Result<()> {
+        require!(tolerance_bps <= 10_000, SpectreError::InvalidAmount);
 
-        // Calculate realized PnL
-        let realized_pnl = (exit_value as i64)
-            .saturating_sub(position.invested_amount as i64);
+        let vault = &mut ctx.accounts.vault;
+        vault.position_cost_tolerance_bps = tolerance_bps;
 
-        // Update position state
-        position.status = PositionStatus::Closed;
-        position.closed_at = clock.unix_timestamp;
-        position.exit_price = exit_price;
-        position.realized_pnl = realized_pnl;
+        msg!("Position cost tolerance updated");
+        msg!("  Tolerance: {} bps", tolerance_bps);
 
-        // Update vault state
-        vault.available_balance = vault.available_balance
-            .saturating_add(exit_value);
-        vault.active_positions = vault.active_positions
-            .saturating_sub(1);
-        vault.last_trade_slot = clock.slot;
+        Ok(())
+    }
 
-        msg!("Position closed");
-        msg!("  Market: {}", position.market_id);
-        msg!("  Side: {:?}", position.side);
-        msg!("  Exit price: {}", exit_price);
-        msg!("  Exit value: {} lamports", exit_value);
-        msg!("  Realized PnL: {} lamports", realized_pnl);
+    /// Pause trading for `cooldown_slots` after a losing close (0 = disabled)
+    pub fn set_loss_cooldown(
+        ctx: Context<UpdateVaultConfig>,
+        cooldown_slots: u64,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.loss_cooldown_slots = cooldown_slots;
 
-        Ok(realized_pnl)
+        msg!("Loss cooldown updated");
+        msg!("  Cooldown: {} slots", cooldown_slots);
+        msg!("  Last loss slot: {}", vault.last_loss_slot);
+
+        Ok(())
     }
 
-    /// Get position information
-    ///
-    /// Returns the current unrealized PnL for an open position
-    /// given the current market price.
-    pub fn get_position_pnl(
-        ctx: Context<GetPositionPnl>,
-        current_price: u64,
-    ) -> Result<i64> {
-        let position = &ctx.accounts.position;
+    /// Let anyone undelegate the vault after `idle_slots` without a trade (0 = disabled)
+    pub fn set_max_delegation_idle(
+        ctx: Context<UpdateVaultConfig>,
+        idle_slots: u64,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.max_delegation_idle_slots = idle_slots;
 
-        // For closed positions, return realized PnL
-        if position.status != PositionStatus::Open {
-            return Ok(position.realized_pnl);
-        }
+        msg!("Delegation idle limit updated");
+        msg!("  Max idle: {} slots", idle_slots);
 
-        // Calculate unrealized PnL
-        let pnl = position.calculate_unrealized_pnl(current_price);
+        Ok(())
+    }
 
-        msg!("Position PnL calculated");
-        msg!("  Current price: {}", current_price);
-        msg!("  Unrealized PnL: {} lamports", pnl);
+    /// Let the vault cover rent for withdrawals to recipients with no lamports
+    pub fn set_cover_recipient_rent(
+        ctx: Context<UpdateVaultConfig>,
+        enabled: bool,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.cover_recipient_rent = enabled;
 
-        Ok(pnl)
+        msg!("Recipient rent cover updated");
+        msg!("  Enabled: {}", enabled);
+
+        Ok(())
     }
-}
 
-// ============================================
-// Account Contexts
-// ============================================
+    /// Require depositors to provide a passing compliance attestation
+    pub fn set_require_deposit_compliance(
+        ctx: Context<UpdateVaultConfig>,
+        required: bool,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.require_deposit_compliance = required;
 
-/// Accounts for initializing the SPECTRE vault
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
+        msg!("Deposit compliance requirement updated");
+        msg!("  Required: {}", required);
+
+        Ok(())
+    }
+
+    /// Require each withdrawal attestation to carry a fresh, increasing nonce
+    pub fn set_require_attestation_nonce(
+        ctx: Context<UpdateVaultConfig>,
+        required: bool,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.require_attestation_nonce = required;
+
+        msg!("Attestation nonce requirement updated");
+        msg!("  Required: {}", required);
+        msg!("  Last nonce: {}", vault.last_attestation_nonce);
+
+        Ok(())
+    }
+
+    /// Enable age-based risk score decay for compliance attestations
+    pub fn set_compliance_score_decay(
+        ctx: Context<UpdateVaultConfig>,
+        enabled: bool,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.compliance_score_decay = enabled;
+
+        msg!("Compliance score decay updated");
+        msg!("  Enabled: {}", enabled);
+
+        Ok(())
+    }
+
+    /// Cap how far a recipient's risk score may rise between withdrawal
+    /// verification and completion (0 = unchecked)
+    pub fn set_max_risk_delta(
+        ctx: Context<UpdateVaultConfig>,
+        max_risk_delta: u8,
+    ) -> Result<()> {
+        require!(max_risk_delta <= 100, SpectreError::InvalidAmount);
+
+        let vault = &mut ctx.accounts.vault;
+        vault.max_risk_delta = max_risk_delta;
+
+        msg!("Max risk delta updated");
+        msg!("  Max delta: {}", max_risk_delta);
+
+        Ok(())
+    }
+
+    /// Set a dedicated compliance authority for withdrawals
+    /// (Pubkey::default() lets any requester verify)
+    pub fn set_compliance_authority(
+        ctx: Context<UpdateVaultConfig>,
+        compliance_authority: Pubkey,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.compliance_authority = compliance_authority;
+
+        msg!("Compliance authority updated");
+        msg!("  Compliance authority: {}", compliance_authority);
+
+        Ok(())
+    }
+
+    /// Set the keeper key allowed to generate signals and trade
+    /// (Pubkey::default() removes the keeper)
+    pub fn set_keeper(ctx: Context<UpdateVaultConfig>, keeper: Pubkey) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.keeper = keeper;
+
+        msg!("Keeper updated");
+        msg!("  Keeper: {}", keeper);
+
+        Ok(())
+    }
+
+    /// Check `available_balance` against the lamports actually held by the vault
+    ///
+    /// Errors with `BalanceDiscrepancy` if the balance plus the rebate pool,
+    /// profit reserve and staged deposits claim more than the vault holds above
+    /// rent, unless `correct` is set, in which case the balance is lowered to
+    /// match. Returns the shortfall found (0 when consistent).
+    pub fn reconcile_vault(ctx: Context<ReconcileVault>, correct: bool) -> Result<u64> {
+        let vault_info = ctx.accounts.vault.to_account_info();
+        let rent_exempt = Rent::get()?.minimum_balance(vault_info.data_len());
+        let backing = vault_info.lamports().saturating_sub(rent_exempt);
+
+        let vault = &mut ctx.accounts.vault;
+        let shortfall = vault.balance_shortfall(backing);
+
+        msg!("Vault reconciliation");
+        msg!("  Backing lamports: {}", backing);
+        msg!("  Available balance: {}", vault.available_balance);
+        msg!("  Held outside balance: {}", vault.held_outside_balance());
+
+        if shortfall > 0 {
+            msg!("  Shortfall: {} lamports", shortfall);
+            require!(correct, SpectreError::BalanceDiscrepancy);
+
+            vault.correct_balance(backing);
+            msg!("  Available balance corrected to {}", vault.available_balance);
+        }
+
+        Ok(shortfall)
+    }
+
+    /// Add authority-provided SOL to the vault's tradable balance
+    ///
+    /// Seeds liquidity without a ZK deposit. The lamports count toward
+    /// `available_balance` and `operator_funded`, not `total_deposited`.
+    pub fn top_up_vault(ctx: Context<TopUpVault>, amount: u64) -> Result<()> {
+        require!(amount > 0, SpectreError::InvalidAmount);
+
+        let transfer_cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.authority.to_account_info(),
+                to: ctx.accounts.vault.to_account_info(),
+            },
+        );
+        system_program::transfer(transfer_cpi_context, amount)?;
+
+        let vault = &mut ctx.accounts.vault;
+        vault.record_operator_top_up(amount)
+            .ok_or(SpectreError::MathOverflow)?;
+
+        msg!("Vault topped up");
+        msg!("  Amount: {} lamports", amount);
+        msg!("  Operator funded: {} lamports", vault.operator_funded);
+        msg!("  Available balance: {} lamports", vault.available_balance);
+
+        Ok(())
+    }
+
+    /// Set the share of each close's realized profit held in the profit reserve (bps)
+    pub fn set_profit_reserve_bps(
+        ctx: Context<UpdateVaultConfig>,
+        profit_reserve_bps: u16,
+    ) -> Result<()> {
+        require!(profit_reserve_bps <= 10_000, SpectreError::InvalidAmount);
+
+        let vault = &mut ctx.accounts.vault;
+        vault.profit_reserve_bps = profit_reserve_bps;
+
+        msg!("Profit reserve updated");
+        msg!("  Reserve share: {} bps", profit_reserve_bps);
+
+        Ok(())
+    }
+
+    /// Set how often the profit reserve may be compounded (0 = disabled)
+    ///
+    /// The first interval starts at the current slot.
+    pub fn set_auto_compound_interval(
+        ctx: Context<UpdateVaultConfig>,
+        interval_slots: u64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let vault = &mut ctx.accounts.vault;
+        vault.auto_compound_interval_slots = interval_slots;
+        vault.last_compound_slot = clock.slot;
+
+        msg!("Auto-compound interval updated");
+        msg!("  Interval: {} slots", interval_slots);
+
+        Ok(())
+    }
+
+    /// Re-inject the profit reserve into the tradable balance
+    ///
+    /// Permissionless: anyone may call this once per
+    /// `auto_compound_interval_slots`. Returns the amount compounded.
+    pub fn compound_profits(ctx: Context<CompoundProfits>) -> Result<u64> {
+        let clock = Clock::get()?;
+        let vault = &mut ctx.accounts.vault;
+
+        require!(vault.is_compound_due(clock.slot), SpectreError::CompoundNotDue);
+
+        let amount = vault
+            .compound_profits(clock.slot)
+            .ok_or(SpectreError::MathOverflow)?;
+
+        msg!("Profits compounded");
+        msg!("  Amount: {} lamports", amount);
+        msg!("  Available balance: {} lamports", vault.available_balance);
+
+        Ok(amount)
+    }
+
+    /// Charge `performance_fee_bps` of each profitable close, paid to `fee_recipient`
+    pub fn set_performance_fee(
+        ctx: Context<UpdateVaultConfig>,
+        performance_fee_bps: u16,
+        fee_recipient: Pubkey,
+    ) -> Result<()> {
+        require!(performance_fee_bps <= MAX_PERFORMANCE_FEE_BPS, SpectreError::InvalidAmount);
+        require!(
+            performance_fee_bps == 0 || fee_recipient != Pubkey::default(),
+            SpectreError::InvalidFeeRecipient
+        );
+
+        let vault = &mut ctx.accounts.vault;
+        vault.performance_fee_bps = performance_fee_bps;
+        vault.fee_recipient = fee_recipient;
+
+        msg!("Performance fee updated");
+        msg!("  Fee: {} bps", performance_fee_bps);
+        msg!("  Recipient: {}", fee_recipient);
+
+        Ok(())
+    }
+
+    /// Only charge performance fees on realized PnL above the high-water mark
+    pub fn set_high_water_mark_enabled(
+        ctx: Context<UpdateVaultConfig>,
+        enabled: bool,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.high_water_mark_enabled = enabled;
+
+        msg!("High-water mark updated");
+        msg!("  Enabled: {}", enabled);
+        msg!("  Cumulative PnL: {} lamports", vault.cumulative_realized_pnl);
+        msg!("  High-water mark: {} lamports", vault.high_water_mark);
+
+        Ok(())
+    }
+
+    /// Set aside `amount` of trading proceeds as a rebate to depositors
+    ///
+    /// The amount leaves the tradable balance and is shared across
+    /// outstanding deposits in proportion to their amounts.
+    pub fn fund_rebate_pool(ctx: Context<UpdateVaultConfig>, amount: u64) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+
+        require!(amount > 0, SpectreError::InvalidAmount);
+        require!(vault.active_deposit_total > 0, SpectreError::NoActiveDeposits);
+        require!(vault.fund_rebate_pool(amount), SpectreError::InsufficientVaultBalance);
+
+        msg!("Rebate pool funded");
+        msg!("  Amount: {} lamports", amount);
+        msg!("  Pool: {} lamports", vault.fee_rebate_pool);
+        msg!("  Active deposits: {} lamports", vault.active_deposit_total);
+
+        Ok(())
+    }
+
+    /// Pay a depositor their accrued share of the rebate pool
+    ///
+    /// Returns the lamports paid (0 if nothing has accrued).
+    pub fn claim_rebate(ctx: Context<ClaimRebate>) -> Result<u64> {
+        let rebate_index = ctx.accounts.vault.rebate_index;
+        let rebate = ctx.accounts.user_deposit.take_rebate(rebate_index);
+
+        if rebate > 0 {
+            let vault_info = ctx.accounts.vault.to_account_info();
+            let owner_info = ctx.accounts.owner.to_account_info();
+
+            **vault_info.try_borrow_mut_lamports()? = vault_info
+                .lamports()
+                .checked_sub(rebate)
+                .ok_or(SpectreError::MathOverflow)?;
+
+            **owner_info.try_borrow_mut_lamports()? = owner_info
+                .lamports()
+                .checked_add(rebate)
+                .ok_or(SpectreError::MathOverflow)?;
+
+            let vault = &mut ctx.accounts.vault;
+            vault.fee_rebate_pool = vault.fee_rebate_pool
+                .checked_sub(rebate)
+                .ok_or(SpectreError::MathOverflow)?;
+        }
+
+        msg!("Rebate claimed");
+        msg!("  Amount: {} lamports", rebate);
+
+        Ok(rebate)
+    }
+
+    /// Read a deposit without exposing its nullifier
+    ///
+    /// Returns the amount, status and creation time together with a short
+    /// commitment fingerprint instead of the full commitment.
+    pub fn get_deposit_info(ctx: Context<GetDepositInfo>) -> Result<DepositInfoView> {
+        let view = ctx.accounts.user_deposit.view();
+
+        msg!("Deposit info read");
+        msg!("  Amount: {} lamports", view.amount);
+        msg!("  Active: {}", view.is_active);
+
+        Ok(view)
+    }
+
+    /// Update strategy parameters
+    pub fn set_strategy_params(
+        ctx: Context<UpdateStrategyParams>,
+        params: StrategyParams,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+
+        // Validate params, including against protocol bounds
+        require!(params.validate(), SpectreError::InvalidStrategyParams);
+        check_strategy_bounds(&ctx.accounts.protocol_config, &params)?;
+
+        let config = &mut ctx.accounts.strategy_config;
+
+        config.price_threshold_low = params.price_threshold_low;
+        config.price_threshold_high = params.price_threshold_high;
+        config.trend_threshold = params.trend_threshold;
+        config.volatility_cap = params.volatility_cap;
+        config.default_signal = params.default_signal;
+        config.signal_overrides = params.signal_overrides;
+        config.min_volume_for_signal = params.min_volume_for_signal;
+        config.updated_at = clock.unix_timestamp;
+
+        msg!("Strategy parameters updated");
+        msg!("  Price thresholds: {} - {}", params.price_threshold_low, params.price_threshold_high);
+        msg!("  Trend threshold: {}", params.trend_threshold);
+        msg!("  Volatility cap: {}", params.volatility_cap);
+        msg!("  Default signal: {:?}", params.default_signal);
+        msg!("  Min volume: {}", params.min_volume_for_signal);
+
+        Ok(())
+    }
+
+    /// Cap the number of trades executed per UTC day (0 = unlimited)
+    pub fn set_max_trades_per_day(
+        ctx: Context<SetStrategyParams>,
+        max_trades_per_day: u32,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let config = &mut ctx.accounts.strategy_config;
+
+        config.max_trades_per_day = max_trades_per_day;
+        config.updated_at = clock.unix_timestamp;
+
+        msg!("Daily trade limit updated");
+        msg!("  Max trades per day: {}", max_trades_per_day);
+
+        Ok(())
+    }
+
+    /// Pause signal generation when the price moves more than `max_price_move_bps`
+    /// between signals (0 = disabled)
+    ///
+    /// Clears the reference price, so the next signal's price becomes the
+    /// new baseline. This is also how a tripped breaker is reset.
+    pub fn set_max_price_move(
+        ctx: Context<SetStrategyParams>,
+        max_price_move_bps: u16,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let config = &mut ctx.accounts.strategy_config;
+
+        config.max_price_move_bps = max_price_move_bps;
+        config.last_price = 0;
+        config.updated_at = clock.unix_timestamp;
+
+        msg!("Price move circuit breaker updated");
+        msg!("  Max price move: {} bps", max_price_move_bps);
+
+        Ok(())
+    }
+
+    /// Invert model signals before trading on them (contrarian strategy)
+    pub fn set_contrarian(
+        ctx: Context<SetStrategyParams>,
+        contrarian: bool,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let config = &mut ctx.accounts.strategy_config;
+
+        config.contrarian = contrarian;
+        config.updated_at = clock.unix_timestamp;
+
+        msg!("Contrarian mode updated");
+        msg!("  Contrarian: {}", contrarian);
+
+        Ok(())
+    }
+
+    /// Set how many signals to generate before trading on them (0 = no warm-up)
+    pub fn set_min_signals_before_trading(
+        ctx: Context<SetStrategyParams>,
+        min_signals: u64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let config = &mut ctx.accounts.strategy_config;
+
+        config.min_signals_before_trading = min_signals;
+        config.updated_at = clock.unix_timestamp;
+
+        msg!("Strategy warm-up updated");
+        msg!("  Min signals before trading: {}", min_signals);
+
+        Ok(())
+    }
+
+    /// Set the risk-adjusted score a signal must exceed to trade (scaled by 1000, 0 = disabled)
+    ///
+    /// The score is the signal confidence divided by `1 + volatility / 1000`.
+    pub fn set_min_risk_adjusted_score(
+        ctx: Context<SetStrategyParams>,
+        min_score: u32,
+    ) -> Result<()> {
+        require!(min_score <= 1000, SpectreError::InvalidStrategyParams);
+
+        let clock = Clock::get()?;
+        let config = &mut ctx.accounts.strategy_config;
+
+        config.min_risk_adjusted_score = min_score;
+        config.updated_at = clock.unix_timestamp;
+
+        msg!("Risk-adjusted gate updated");
+        msg!("  Min risk-adjusted score: {}", min_score);
+
+        Ok(())
+    }
+
+    /// Switch the strategy on or off
+    ///
+    /// Trading instructions fail with `StrategyNotActive` while it is off.
+    pub fn set_strategy_active(ctx: Context<SetStrategyParams>, is_active: bool) -> Result<()> {
+        let clock = Clock::get()?;
+        let config = &mut ctx.accounts.strategy_config;
+
+        config.is_active = is_active;
+        config.updated_at = clock.unix_timestamp;
+
+        msg!("Strategy activation updated");
+        msg!("  Active: {}", is_active);
+
+        Ok(())
+    }
+
+    /// Require manual confirmation before executing normal (non-strong) signals
+    pub fn set_require_confirmation_for_normal(
+        ctx: Context<SetStrategyParams>,
+        required: bool,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let config = &mut ctx.accounts.strategy_config;
+
+        config.require_confirmation_for_normal = required;
+        if !required {
+            config.take_pending_signal();
+        }
+        config.updated_at = clock.unix_timestamp;
+
+        msg!("Normal signal confirmation updated");
+        msg!("  Required: {}", required);
+
+        Ok(())
+    }
+
+    /// Create a signal log retaining the last `capacity` generated signals
+    pub fn initialize_signal_log(ctx: Context<InitializeSignalLog>, capacity: u8) -> Result<()> {
+        require!(
+            SignalLog::is_valid_capacity(capacity),
+            SpectreError::InvalidSignalLogCapacity
+        );
+
+        let signal_log = &mut ctx.accounts.signal_log;
+        signal_log.vault = ctx.accounts.vault.key();
+        signal_log.capacity = capacity;
+        signal_log.len = 0;
+        signal_log.head = 0;
+        signal_log.entries = [SignalLogEntry::default(); MAX_SIGNAL_LOG_ENTRIES];
+        signal_log.bump = ctx.bumps.signal_log;
+
+        msg!("Signal log initialized");
+        msg!("  Capacity: {}", capacity);
+
+        Ok(())
+    }
+
+    /// Generate a trade signal from market data
+    ///
+    /// This runs the decision tree inference inside the TEE (when delegated).
+    /// The signal is logged and stored, but execution happens separately.
+    pub fn generate_trade_signal(
+        ctx: Context<GenerateTradeSignal>,
+        input: MarketInput,
+    ) -> Result<TradeSignal> {
+        let clock = Clock::get()?;
+        let config = &mut ctx.accounts.strategy_config;
+        let vault = &ctx.accounts.vault;
+
+        require!(!vault.is_model_stale(clock.unix_timestamp), SpectreError::StaleModel);
+
+        // Pause on a flash move instead of acting on it
+        require!(
+            !config.is_price_move_too_large(input.price),
+            SpectreError::PriceMoveTooLarge
+        );
+        config.last_price = input.price;
+
+        // Build strategy params from config
+        let params = config.strategy_params();
+
+        // Run inference
+        let signal = run_inference(&input, &params);
+
+        // Update stats
+        config.record_signal(signal, clock.unix_timestamp);
+
+        // Append to the signal log if one is attached
+        if let Some(signal_log) = ctx.accounts.signal_log.as_mut() {
+            signal_log.push(SignalLogEntry {
+                timestamp: clock.unix_timestamp,
+                signal,
+                price: input.price,
+            });
+        }
+
+        msg!("Trade signal generated");
+        msg!("  Signal: {:?}", signal);
+        msg!("  Signal bias: {}", config.signal_bias());
+        msg!("  Vault delegated: {}", vault.is_delegated);
+        msg!("  Input: price={}, trend={}, vol={}", input.price, input.trend, input.volatility);
+
+        Ok(signal)
+    }
+
+    /// Compute a trade signal from explicit params without touching strategy state
+    ///
+    /// Intended for backtesting: works whether or not the strategy is active
+    /// and never records stats or trades.
+    pub fn generate_signal_readonly(
+        _ctx: Context<GenerateSignalReadonly>,
+        params: StrategyParams,
+        input: MarketInput,
+    ) -> Result<TradeSignal> {
+        require!(params.validate(), SpectreError::InvalidStrategyParams);
+
+        let signal = run_inference(&input, &params);
+
+        msg!("Read-only trade signal generated");
+        msg!("  Signal: {:?}", signal);
+        msg!("  Input: price={}, trend={}, vol={}", input.price, input.trend, input.volatility);
+
+        Ok(signal)
+    }
+
+    /// Read the effective strategy configuration
+    ///
+    /// Returns the parameters inference runs with, the last signal decoded
+    /// from its stored code and derived stats such as the signal rate.
+    pub fn get_strategy_config(
+        ctx: Context<GetStrategyConfig>,
+    ) -> Result<StrategyConfigView> {
+        let view = ctx.accounts.strategy_config.view();
+
+        msg!("Strategy config read");
+        msg!("  Active: {}", view.is_active);
+        msg!("  Last signal: {:?}", view.last_signal);
+        msg!("  Signal rate: {} bps", view.signal_rate_bps);
+
+        Ok(view)
+    }
+
+    // ============================================
+    // LAYER 3: THE HAND - Trading Instructions
+    // ============================================
+
+    /// Execute a trade based on market conditions and strategy signal
+    ///
+    /// This is the main entry point for automated trading. It:
+    /// 1. Generates a trade signal from market data
+    /// 2. Calculates position size based on signal strength
+    /// 3. Opens a position if signal is actionable
+    ///
+    /// Note: In Phase 3, trades fill at the `market_input` price without a
+    /// market simulation. Real PNP integration would use CPI to the PNP program.
+    ///
+    /// `attestation` is only needed when the vault's compliance
+    /// re-check interval has elapsed.
+    ///
+    /// This is the hot path: it must stay within `EXECUTE_TRADE_COMPUTE_BUDGET`.
+    /// Per-trade detail logs are only emitted with the opt-in `verbose-logs` feature.
+    pub fn execute_trade(
+        ctx: Context<ExecuteTrade>,
+        market_input: MarketInput,
+        attestation: Option<RangeAttestation>,
+    ) -> Result<TradeResult> {
+        let clock = Clock::get()?;
+        let vault = &mut ctx.accounts.vault;
+        let mut config = load_strategy_config(vault, &ctx.accounts.strategy_config)?;
+
+        let result = trade_on_market_input(
+            vault,
+            &mut config,
+            &market_input,
+            attestation.as_ref(),
+            &clock,
+        )?;
+
+        store_strategy_config(&ctx.accounts.strategy_config, &config)?;
+        Ok(result)
+    }
+
+    /// Execute the trade for a normal signal held for confirmation
+    ///
+    /// `attestation` is only needed when the vault's compliance
+    /// re-check interval has elapsed.
+    pub fn confirm_pending_trade(
+        ctx: Context<ExecuteTrade>,
+        attestation: Option<RangeAttestation>,
+    ) -> Result<TradeResult> {
+        let clock = Clock::get()?;
+        let vault = &mut ctx.accounts.vault;
+        let mut config = load_strategy_config(vault, &ctx.accounts.strategy_config)?;
+
+        check_vault_compliance(vault, attestation.as_ref(), clock.slot)?;
+
+        require!(vault.is_active, SpectreError::VaultInactive);
+        require!(
+            vault.is_within_trading_window(clock.unix_timestamp),
+            SpectreError::OutsideTradingWindow
+        );
+        require!(!vault.is_model_stale(clock.unix_timestamp), SpectreError::StaleModel);
+
+        let signal = config
+            .take_pending_signal()
+            .ok_or(SpectreError::NoPendingSignal)?;
+
+        msg!("Confirming pending signal {:?}", signal);
+
+        let result = execute_signal_trade(vault, &mut config, signal, None, &clock)?;
+
+        store_strategy_config(&ctx.accounts.strategy_config, &config)?;
+        Ok(result)
+    }
+
+    /// Execute a strategy trade and record it as a position in one step
+    ///
+    /// The position is built directly from the trade result, so shares,
+    /// entry price and invested amount always match what was traded. Fails
+    /// (opening nothing) if the strategy is warming up, the signal is HOLD,
+    /// is held for confirmation, or the trade does not execute.
+    pub fn execute_and_open(
+        ctx: Context<ExecuteAndOpen>,
+        market_id: Pubkey,
+        market_input: MarketInput,
+        attestation: Option<RangeAttestation>,
+    ) -> Result<TradeResult> {
+        let clock = Clock::get()?;
+        let vault_key = ctx.accounts.vault.key();
+        let vault = &mut ctx.accounts.vault;
+        let config = &mut ctx.accounts.strategy_config;
+
+        let signal = generate_execution_signal(
+            vault,
+            config,
+            &market_input,
+            attestation.as_ref(),
+            &clock,
+        )?;
+
+        require!(!config.is_warming_up(), SpectreError::StrategyWarmingUp);
+
+        // Only actionable signals that need no confirmation can open a position
+        require!(
+            !signal.is_hold() && !config.requires_confirmation(signal),
+            SpectreError::InvalidTradeSignal
+        );
+
+        let result = execute_signal_trade(
+            vault,
+            config,
+            signal,
+            Some(quoted_yes_price(&market_input)),
+            &clock,
+        )?;
+        require!(result.success, SpectreError::TradeExecutionFailed);
+
+        require!(
+            !vault.exceeds_exposure_cap(result.amount_traded),
+            SpectreError::ExposureCapExceeded
+        );
+
+        // The trade already deducted the invested amount from available balance
+        vault.active_positions = vault.active_positions
+            .saturating_add(1);
+        vault.total_open_exposure = vault.total_open_exposure
+            .saturating_add(result.amount_traded);
+
+        let side = if signal.is_buy() {
+            TradeSide::Yes
+        } else {
+            TradeSide::No
+        };
+        let position = &mut ctx.accounts.position;
+        position.set_inner(Position {
+            settlement_mint: vault.settlement_mint,
+            ..Position::from_trade_result(
+                vault_key,
+                market_id,
+                side,
+                &result,
+                clock.unix_timestamp,
+                ctx.bumps.position,
+            )
+        });
+
+        msg!("Position opened from trade");
+        msg!("  Market: {}", market_id);
+        msg!("  Side: {:?}", side);
+
+        Ok(result)
+    }
+
+    /// Post a two-sided market-making quote
+    ///
+    /// Refuses quotes generated more than the vault's max quote age ago, so
+    /// orders are never placed against stale market data.
+    ///
+    /// Note: In Phase 3 quotes are validated and logged only.
+    /// Real PNP integration would place both legs via CPI.
+    pub fn submit_quote(ctx: Context<SubmitQuote>, quote: Quote) -> Result<()> {
+        let clock = Clock::get()?;
+        let vault = &ctx.accounts.vault;
+
+        require!(
+            !quote.is_stale(clock.slot, vault.max_quote_age_slots),
+            SpectreError::StaleQuote
+        );
+        require!(quote.is_valid(), SpectreError::InvalidPrice);
+
+        let total = quote.bid.amount
+            .checked_add(quote.ask.amount)
+            .ok_or(SpectreError::MathOverflow)?;
+        require!(
+            vault.has_sufficient_balance(total),
+            SpectreError::InsufficientBalance
+        );
+
+        msg!("Quote submitted");
+        msg!("  Bid: {} YES @ {}", quote.bid.amount, quote.bid.limit_price);
+        msg!("  Ask: {} NO @ {}", quote.ask.amount, quote.ask.limit_price);
+        msg!("  Generated at slot: {}", quote.generated_slot);
+
+        Ok(())
+    }
+
+    /// Open a new trading position
+    ///
+    /// Creates a Position account to track an active market position.
+    /// This is called after a successful trade to record the position.
+    pub fn open_position(
+        ctx: Context<OpenPosition>,
+        market_id: Pubkey,
+        side: TradeSide,
+        shares: u64,
+        entry_price: u64,
+        invested_amount: u64,
+        attestation: Option<RangeAttestation>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+
+        // Re-attest the vault authority if a periodic compliance check is due
+        check_vault_compliance(&mut ctx.accounts.vault, attestation.as_ref(), clock.slot)?;
+
+        // Get vault key before mutable borrow
+        let vault_key = ctx.accounts.vault.key();
+
+        // Validate inputs
+        require!(shares > 0, SpectreError::InvalidTradeAmount);
+        require!(invested_amount > 0, SpectreError::InvalidTradeAmount);
+        require!(is_valid_price(entry_price), SpectreError::InvalidPrice);
+
+        // The invested amount must match what the shares cost, up to fees
+        require!(
+            ctx.accounts.vault.is_position_cost_consistent(shares, entry_price, invested_amount),
+            SpectreError::InconsistentPositionData
+        );
+
+        // Ensure vault has sufficient balance outside pending withdrawals
+        require!(
+            ctx.accounts.vault.has_sufficient_balance(invested_amount),
+            SpectreError::InsufficientVaultBalance
+        );
+
+        // Keep total open exposure under the vault's cap
+        require!(
+            !ctx.accounts.vault.exceeds_exposure_cap(invested_amount),
+            SpectreError::ExposureCapExceeded
+        );
+
+        // Initialize position
+        let position = &mut ctx.accounts.position;
+        position.vault = vault_key;
+        position.market_id = market_id;
+        position.side = side.into();
+        position.shares = shares;
+        position.entry_price = entry_price;
+        position.invested_amount = invested_amount;
+        position.entry_fees = 0;
+        position.status = PositionStatus::Open;
+        position.opened_at = clock.unix_timestamp;
+        position.closed_at = 0;
+        position.exit_price = 0;
+        position.realized_pnl = 0;
+        position.settlement_mint = ctx.accounts.vault.settlement_mint;
+        position.last_mark_price = 0;
+        position.last_mark_slot = 0;
+        position.bump = ctx.bumps.position;
+
+        // Update vault state
+        let vault = &mut ctx.accounts.vault;
+        vault.available_balance = vault.available_balance
+            .saturating_sub(invested_amount);
+        vault.active_positions = vault.active_positions
+            .saturating_add(1);
+        vault.total_open_exposure = vault.total_open_exposure
+            .saturating_add(invested_amount);
+        vault.add_volume(invested_amount)
+            .ok_or(SpectreError::MathOverflow)?;
+        vault.last_trade_slot = clock.slot;
+
+        msg!("Position opened");
+        msg!("  Market: {}", market_id);
+        msg!("  Side: {:?}", side);
+        msg!("  Shares: {}", shares);
+        msg!("  Entry price: {}", entry_price);
+        msg!("  Invested: {} lamports", invested_amount);
+
+        Ok(())
+    }
+
+    /// Close an existing trading position
+    ///
+    /// Closes a position and calculates realized PnL.
+    /// Returns funds to the vault's available balance.
+    pub fn close_position(
+        ctx: Context<ClosePosition>,
+        exit_price: u64,
+    ) -> Result<i64> {
+        close_open_position(ctx.accounts, exit_price, false)
+    }
+
+    /// Force-close a position, e.g. on a stop-loss
+    ///
+    /// Same settlement as `close_position`, but not held back by the
+    /// vault's minimum holding period.
+    pub fn force_close_position(
+        ctx: Context<ClosePosition>,
+        exit_price: u64,
+    ) -> Result<i64> {
+        close_open_position(ctx.accounts, exit_price, true)
+    }
+
+    /// Partially exit an open position
+    ///
+    /// Sells `shares` at `exit_price`, returning their value to the vault's
+    /// available balance and accruing the realized PnL on them. The position
+    /// stays open; use `close_position` to exit fully.
+    pub fn decrease_position(
+        ctx: Context<DecreasePosition>,
+        shares: u64,
+        exit_price: u64,
+    ) -> Result<u64> {
+        let clock = Clock::get()?;
+        let vault = &mut ctx.accounts.vault;
+        let position = &mut ctx.accounts.position;
+
+        require!(shares > 0, SpectreError::InvalidAmount);
+        require!(shares <= position.shares, SpectreError::InsufficientBalance);
+        require!(shares != position.shares, SpectreError::DecreaseClosesPosition);
+        require!(is_valid_price(exit_price), SpectreError::InvalidPrice);
+        require!(
+            position.settles_in(&vault.settlement_mint),
+            SpectreError::CurrencyMismatch
+        );
+
+        // Partial exits are held back like full ones
+        require!(
+            !position.is_too_young(clock.unix_timestamp, vault.min_holding_period_seconds, false),
+            SpectreError::PositionTooYoung
+        );
+
+        let invested_before = position.invested_amount;
+        let exit_value = position.decrease(shares, exit_price);
+
+        vault.available_balance = vault.available_balance
+            .saturating_add(exit_value);
+        vault.total_open_exposure = vault.total_open_exposure
+            .saturating_sub(invested_before.saturating_sub(position.invested_amount));
+        vault.last_trade_slot = clock.slot;
+
+        msg!("Position decreased");
+        msg!("  Market: {}", position.market_id);
+        msg!("  Shares sold: {}", shares);
+        msg!("  Shares remaining: {}", position.shares);
+        msg!("  Exit value: {} lamports", exit_value);
+        msg!("  Realized PnL to date: {} lamports", position.realized_pnl);
+
+        Ok(exit_value)
+    }
+
+    /// Get position information
+    ///
+    /// Returns the current unrealized PnL for an open position
+    /// given the current market price.
+    pub fn get_position_pnl(
+        ctx: Context<GetPositionPnl>,
+        current_price: u64,
+    ) -> Result<i64> {
+        let position = &ctx.accounts.position;
+
+        // Reject prices beyond 100%
+        require!(current_price <= MAX_PRICE, SpectreError::InvalidPrice);
+
+        // Unrealized PnL, or realized PnL for closed positions
+        let pnl = position.pnl_at(current_price);
+
+        msg!("Position PnL calculated");
+        msg!("  Current price: {}", current_price);
+        msg!("  Unrealized PnL: {} lamports", pnl);
+
+        Ok(pnl)
+    }
+
+    /// Get the PnL of several positions at once
+    ///
+    /// Positions are passed as remaining accounts with a matching price for
+    /// each. Returns per-position PnL (realized for closed positions) and
+    /// the portfolio total.
+    pub fn batch_get_position_pnl<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchGetPositionPnl<'info>>,
+        prices: Vec<u64>,
+    ) -> Result<PortfolioPnl> {
+        let vault_key = ctx.accounts.vault.key();
+        let position_infos = ctx.remaining_accounts;
+
+        require!(
+            position_infos.len() == prices.len(),
+            SpectreError::PriceCountMismatch
+        );
+        require!(
+            position_infos.len() <= MAX_POSITIONS,
+            SpectreError::MaxPositionsReached
+        );
+
+        let mut position_pnls = Vec::with_capacity(prices.len());
+        for (position_info, &price) in position_infos.iter().zip(prices.iter()) {
+            require!(price <= MAX_PRICE, SpectreError::InvalidPrice);
+
+            let position = Account::<Position>::try_from(position_info)?;
+            require!(position.vault == vault_key, SpectreError::PositionNotFound);
+
+            position_pnls.push(position.pnl_at(price));
+        }
+
+        let portfolio = PortfolioPnl::from_position_pnls(position_pnls)
+            .ok_or(SpectreError::MathOverflow)?;
+
+        msg!("Portfolio PnL calculated");
+        msg!("  Positions: {}", portfolio.position_pnls.len());
+        msg!("  Total PnL: {} lamports", portfolio.total_pnl);
+
+        Ok(portfolio)
+    }
+
+    /// Record an open position's mark-to-market valuation for NAV snapshots
+    ///
+    /// Stores the mark price and slot on the position and emits the
+    /// unrealized PnL at that price. Shares and invested amount are unchanged.
+    pub fn mark_position(ctx: Context<MarkPosition>, mark_price: u64) -> Result<i64> {
+        require!(is_valid_price(mark_price), SpectreError::InvalidPrice);
+
+        let clock = Clock::get()?;
+        let position = &mut ctx.accounts.position;
+        let unrealized_pnl = position.mark(mark_price, clock.slot);
+
+        emit!(PositionMarkedEvent {
+            vault: ctx.accounts.vault.key(),
+            position: position.key(),
+            market_id: position.market_id,
+            mark_price,
+            unrealized_pnl,
+            marked_slot: clock.slot,
+            marked_at: clock.unix_timestamp,
+        });
+
+        msg!("Position marked");
+        msg!("  Mark price: {}", mark_price);
+        msg!("  Unrealized PnL: {} lamports", unrealized_pnl);
+
+        Ok(unrealized_pnl)
+    }
+
+    /// Schedule a TWAP-style entry into a new position
+    ///
+    /// Creates the schedule and an empty position on `market_id`; each
+    /// `execute_entry_tranche` call then buys `total_size / num_tranches`
+    /// lamports of `side`, at most once per `interval_seconds`.
+    pub fn create_entry_schedule(
+        ctx: Context<CreateEntrySchedule>,
+        market_id: Pubkey,
+        side: TradeSide,
+        total_size: u64,
+        num_tranches: u8,
+        interval_seconds: u64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+
+        require!(
+            num_tranches > 0 && num_tranches <= MAX_ENTRY_TRANCHES,
+            SpectreError::InvalidEntrySchedule
+        );
+        require!(
+            total_size / num_tranches as u64 >= MIN_TRADE_AMOUNT,
+            SpectreError::InvalidEntrySchedule
+        );
+        require!(interval_seconds > 0, SpectreError::InvalidEntrySchedule);
+
+        let vault_key = ctx.accounts.vault.key();
+        let position_key = ctx.accounts.position.key();
+
+        // The position starts empty and fills one tranche at a time
+        let position = &mut ctx.accounts.position;
+        position.set_inner(Position {
+            vault: vault_key,
+            market_id,
+            side: side.into(),
+            status: PositionStatus::Open,
+            opened_at: clock.unix_timestamp,
+            settlement_mint: ctx.accounts.vault.settlement_mint,
+            bump: ctx.bumps.position,
+            ..Default::default()
+        });
+
+        let schedule = &mut ctx.accounts.entry_schedule;
+        schedule.set_inner(EntrySchedule {
+            vault: vault_key,
+            position: position_key,
+            market_id,
+            side: side.into(),
+            total_size,
+            num_tranches,
+            tranches_executed: 0,
+            interval_seconds,
+            last_tranche_at: 0,
+            bump: ctx.bumps.entry_schedule,
+        });
+
+        msg!("Entry schedule created");
+        msg!("  Market: {}", market_id);
+        msg!("  Side: {:?}", side);
+        msg!("  Total size: {} lamports", total_size);
+        msg!("  Tranches: {} every {}s", num_tranches, interval_seconds);
+
+        Ok(())
+    }
+
+    /// Execute the next tranche of a scheduled entry
+    ///
+    /// Callable by the vault authority or keeper once the schedule's
+    /// interval has passed since the previous tranche. Tranches are held to
+    /// the same trading gates as `execute_trade`; `attestation` is only
+    /// needed when the vault's compliance re-check interval has elapsed.
+    /// The position counts as active from its first filled tranche.
+    pub fn execute_entry_tranche(
+        ctx: Context<ExecuteEntryTranche>,
+        attestation: Option<RangeAttestation>,
+    ) -> Result<TradeResult> {
+        let clock = Clock::get()?;
+        let vault = &mut ctx.accounts.vault;
+        let schedule = &mut ctx.accounts.entry_schedule;
+
+        check_trading_preconditions(vault, attestation.as_ref(), &clock)?;
+
+        require!(!schedule.is_complete(), SpectreError::EntryScheduleComplete);
+        require!(
+            schedule.is_tranche_due(clock.unix_timestamp),
+            SpectreError::EntryTrancheNotDue
+        );
+
+        let size = schedule.next_tranche_size();
+        require!(
+            vault.has_sufficient_balance(size),
+            SpectreError::InsufficientVaultBalance
+        );
+        require!(
+            !vault.exceeds_exposure_cap(size),
+            SpectreError::ExposureCapExceeded
+        );
+
+        let side = match schedule.side {
+            Side::Yes => TradeSide::Yes,
+            Side::No => TradeSide::No,
+        };
+        let mut mock_market = MockMarket::default();
+        let result = mock_market
+            .execute_trade(&TradeParams::market_order(side, size))
+            .map_err(SpectreError::from)?;
+        require!(result.success, SpectreError::TradeExecutionFailed);
+
+        vault.record_trade(&result, clock.slot)
+            .ok_or(SpectreError::MathOverflow)?;
+        vault.total_open_exposure = vault.total_open_exposure
+            .saturating_add(result.amount_traded);
+
+        let position = &mut ctx.accounts.position;
+        if position.shares == 0 {
+            vault.active_positions = vault.active_positions
+                .saturating_add(1);
+        }
+        position.add_fill(&result);
+        schedule.record_tranche(clock.unix_timestamp);
+
+        msg!("Entry tranche executed");
+        msg!("  Tranche: {}/{}", schedule.tranches_executed, schedule.num_tranches);
+        msg!("  Amount: {} lamports", result.amount_traded);
+        msg!("  Shares: {}", result.shares_received);
+        msg!("  Price: {}", result.execution_price);
+
+        Ok(result)
+    }
+}
+
+// ============================================
+// Instruction Helpers
+// ============================================
+
+/// Settle and close an open position; `forced` closes skip the minimum
+/// holding period
+fn close_open_position(
+    accounts: &mut ClosePosition,
+    exit_price: u64,
+    forced: bool,
+) -> Result<i64> {
+    let clock = Clock::get()?;
+    let vault = &mut accounts.vault;
+    let position = &mut accounts.position;
+
+    // Verify position is open
+    require!(
+        position.status == PositionStatus::Open,
+        SpectreError::PositionAlreadyClosed
+    );
+
+    // Validate exit price
+    require!(is_valid_price(exit_price), SpectreError::InvalidPrice);
+
+    // Proceeds are credited in the vault's currency
+    require!(
+        position.settles_in(&vault.settlement_mint),
+        SpectreError::CurrencyMismatch
+    );
+
+    // Discourage open/close churn
+    require!(
+        !position.is_too_young(clock.unix_timestamp, vault.min_holding_period_seconds, forced),
+        SpectreError::PositionTooYoung
+    );
+
+    // Calculate position value at exit
+    let exit_value = position.current_value(exit_price);
+
+    // Calculate realized PnL, including any accrued from partial decreases
+    let prior_realized_pnl = position.realized_pnl;
+    let realized_pnl = (exit_value as i64)
+        .saturating_sub(position.invested_amount as i64)
+        .saturating_add(prior_realized_pnl);
+
+    // Exits are valued at the exit price without a market trade, so no
+    // exit fees are charged yet
+    let exit_fees = 0u64;
+    let gross_pnl = position.gross_pnl(exit_value);
+
+    // The performance fee comes out of profits only, and never out of the
+    // lamports backing reserves or pending withdrawals
+    let vault_info = vault.to_account_info();
+    let rent_exempt = Rent::get()?.minimum_balance(vault_info.data_len());
+    let backing = vault_info.lamports().saturating_sub(rent_exempt);
+    let performance_fee = vault
+        .performance_fee(realized_pnl)
+        .ok_or(SpectreError::MathOverflow)?
+        .min(vault.fee_payable(backing));
+    if performance_fee > 0 {
+        let recipient_info = accounts.fee_recipient
+            .as_ref()
+            .ok_or(SpectreError::InvalidFeeRecipient)?
+            .to_account_info();
+
+        **vault_info.try_borrow_mut_lamports()? = vault_info
+            .lamports()
+            .checked_sub(performance_fee)
+            .ok_or(SpectreError::InsufficientVaultBalance)?;
+
+        **recipient_info.try_borrow_mut_lamports()? = recipient_info
+            .lamports()
+            .checked_add(performance_fee)
+            .ok_or(SpectreError::MathOverflow)?;
+    }
+
+    // Update position state
+    position.status = PositionStatus::Closed;
+    position.closed_at = clock.unix_timestamp;
+    position.exit_price = exit_price;
+    position.realized_pnl = realized_pnl;
+
+    // Update vault state
+    vault.available_balance = vault.available_balance
+        .saturating_add(exit_value)
+        .checked_sub(performance_fee)
+        .ok_or(SpectreError::InsufficientVaultBalance)?;
+    // A scheduled entry with no filled tranche was never counted
+    if position.shares > 0 {
+        vault.active_positions = vault.active_positions
+            .saturating_sub(1);
+    }
+    vault.total_open_exposure = vault.total_open_exposure
+        .saturating_sub(position.invested_amount);
+    vault.last_trade_slot = clock.slot;
+    vault.record_close(realized_pnl, clock.slot);
+    vault.record_realized_pnl(realized_pnl);
+    let reserved = vault.reserve_profit(realized_pnl.saturating_sub(performance_fee as i64));
+
+    emit!(PositionClosedEvent {
+        vault: vault.key(),
+        position: position.key(),
+        market_id: position.market_id,
+        invested_amount: position.invested_amount,
+        exit_value,
+        entry_fees: position.entry_fees,
+        exit_fees,
+        gross_pnl,
+        prior_realized_pnl,
+        net_pnl: realized_pnl,
+        performance_fee,
+        closed_at: clock.unix_timestamp,
+    });
+
+    msg!("Position closed");
+    msg!("  Market: {}", position.market_id);
+    msg!("  Side: {:?}", position.side);
+    msg!("  Exit price: {}", exit_price);
+    msg!("  Exit value: {} lamports", exit_value);
+    msg!("  Realized PnL: {} lamports", realized_pnl);
+    if performance_fee > 0 {
+        msg!("  Performance fee: {} lamports", performance_fee);
+    }
+    if reserved > 0 {
+        msg!("  Reserved profit: {} lamports", reserved);
+    }
+
+    Ok(realized_pnl)
+}
+
+/// Prepare a delegated vault for undelegation: merge deposits staged during
+/// the TEE session and clear the delegated flag, writing the result back so
+/// it is included in the commit
+fn settle_undelegated_vault(vault_info: &AccountInfo, vault: &mut SpectreVault) -> Result<()> {
+    let merged = vault.settle_staged_deposits()
+        .ok_or(SpectreError::MathOverflow)?;
+    vault.is_delegated = false;
+
+    let mut data = vault_info.try_borrow_mut_data()?;
+    vault.try_serialize(&mut &mut data[..])?;
+
+    if merged > 0 {
+        msg!("Merged {} lamports of staged deposits", merged);
+    }
+    Ok(())
+}
+
+/// Record a fresh authority attestation (if given) and ensure the vault's
+/// periodic compliance re-check is not overdue
+fn check_vault_compliance(
+    vault: &mut SpectreVault,
+    attestation: Option<&RangeAttestation>,
+    current_slot: u64,
+) -> Result<()> {
+    if let Some(attestation) = attestation {
+        let result = verify_compliance_with_decay(
+            attestation,
+            &vault.authority,
+            current_slot,
+            vault.compliance_score_decay,
+        );
+        if !result.passed {
+            msg!("Vault authority compliance check failed");
+            msg!("  Risk score: {}", attestation.risk_score);
+            return Err(SpectreError::ComplianceCheckFailed.into());
+        }
+        vault.last_compliance_slot = current_slot;
+    }
+
+    require!(
+        !vault.needs_compliance_recheck(current_slot),
+        SpectreError::ComplianceRecheckRequired
+    );
+
+    Ok(())
+}
+
+/// Check the vault-level gates shared by every automated trade path:
+/// compliance re-check, balance, trading window, model age and loss cooldown
+fn check_trading_preconditions(
+    vault: &mut SpectreVault,
+    attestation: Option<&RangeAttestation>,
+    clock: &Clock,
+) -> Result<()> {
+    // Re-attest the vault authority if a periodic compliance check is due
+    check_vault_compliance(vault, attestation, clock.slot)?;
+
+    // Ensure vault is active and has sufficient balance
+    require!(vault.is_active, SpectreError::VaultInactive);
+    require!(vault.tradable_balance() > 0, SpectreError::InsufficientVaultBalance);
+    require!(
+        vault.is_within_trading_window(clock.unix_timestamp),
+        SpectreError::OutsideTradingWindow
+    );
+    require!(!vault.is_model_stale(clock.unix_timestamp), SpectreError::StaleModel);
+    require!(
+        !vault.is_in_loss_cooldown(clock.slot),
+        SpectreError::LossCooldownActive
+    );
+
+    Ok(())
+}
+
+/// Check trading preconditions and run the strategy on `input`, recording
+/// the signal in the strategy stats
+fn generate_execution_signal(
+    vault: &mut SpectreVault,
+    config: &mut StrategyConfig,
+    input: &MarketInput,
+    attestation: Option<&RangeAttestation>,
+    clock: &Clock,
+) -> Result<TradeSignal> {
+    check_trading_preconditions(vault, attestation, clock)?;
+
+    // Pause on a flash move instead of trading into it
+    require!(
+        !config.is_price_move_too_large(input.price),
+        SpectreError::PriceMoveTooLarge
+    );
+    config.last_price = input.price;
+
+    let params = config.strategy_params();
+
+    // Contrarian strategies fade the model
+    let signal = config.trading_signal(run_inference(input, &params));
+    config.record_signal(signal, clock.unix_timestamp);
+
+    Ok(signal)
+}
+
+/// Run `execute_trade` against a loaded strategy config: generate the signal,
+/// apply the trading gates and trade if it is actionable
+fn trade_on_market_input(
+    vault: &mut SpectreVault,
+    config: &mut StrategyConfig,
+    market_input: &MarketInput,
+    attestation: Option<&RangeAttestation>,
+    clock: &Clock,
+) -> Result<TradeResult> {
+    // 1-3. Check trading preconditions and generate the signal
+    let signal = generate_execution_signal(
+        vault,
+        config,
+        market_input,
+        attestation,
+        clock,
+    )?;
+
+    // 4. Determine if we should trade
+    let should_trade = signal.is_buy() || signal.is_sell();
+
+    if !should_trade {
+        msg!("Signal is HOLD - no trade executed");
+        return Ok(TradeResult::default());
+    }
+
+    // Signals still count toward warm-up but are not acted on
+    if config.is_warming_up() {
+        msg!(
+            "Strategy warming up ({}/{} signals) - no trade executed",
+            config.total_signals,
+            config.min_signals_before_trading
+        );
+        return Ok(TradeResult::default());
+    }
+
+    // Marginal signals are not traded in choppy markets
+    if config.is_below_risk_adjusted_score(signal, market_input.volatility) {
+        msg!(
+            "Risk-adjusted score {} at or below minimum {} - no trade executed",
+            risk_adjusted_score(signal, market_input.volatility),
+            config.min_risk_adjusted_score
+        );
+        return Ok(TradeResult::default());
+    }
+
+    // Normal signals may wait for manual confirmation
+    if config.requires_confirmation(signal) {
+        config.set_pending_signal(signal, clock.unix_timestamp);
+        msg!("Signal {:?} is pending confirmation - no trade executed", signal);
+        return Ok(TradeResult::default());
+    }
+
+    execute_signal_trade(vault, config, signal, Some(quoted_yes_price(market_input)), clock)
+}
+
+/// YES price of `market_input` rescaled from 0-1000 to PRICE_SCALE
+fn quoted_yes_price(market_input: &MarketInput) -> u64 {
+    market_input.price as u64 * (PRICE_SCALE / 1_000)
+}
+
+/// Size and execute the trade for an actionable signal, updating vault
+/// balance and strategy trade counts
+fn execute_signal_trade(
+    vault: &mut SpectreVault,
+    config: &mut StrategyConfig,
+    signal: TradeSignal,
+    yes_price: Option<u64>,
+    clock: &Clock,
+) -> Result<TradeResult> {
+    // Enforce the daily trade limit
+    config.roll_trade_day(clock.unix_timestamp);
+    require!(config.can_trade_today(), SpectreError::DailyTradeLimitReached);
+
+    // 1. Calculate position size (5% for normal, 10% for strong signals)
+    let is_strong = signal.is_strong();
+    let position_size = vault.calculate_position_size(is_strong);
+
+    // Ensure position size is tradeable
+    require!(position_size > 0, SpectreError::PositionSizeTooSmall);
+
+    // 2. Determine trade side
+    let side = if signal.is_buy() {
+        TradeSide::Yes
+    } else {
+        TradeSide::No
+    };
+
+    // 3. Create trade params
+    let trade_params = TradeParams::market_order(side, position_size);
+
+    // 4. Fill at the quoted price when market data was passed; otherwise
+    // execute on the mock market. In production, this would be a CPI to
+    // PNP Exchange
+    let result = match yes_price {
+        Some(yes_price) => fill_at_quote(&trade_params, yes_price),
+        None => MockMarket::default().execute_trade(&trade_params),
+    }
+    .map_err(SpectreError::from)?;
+
+    if result.success {
+        // 5. Update vault state (balance, volume, fees)
+        vault.record_trade(&result, clock.slot)
+            .ok_or(SpectreError::MathOverflow)?;
+        config.record_trade();
+
+        msg!("Trade executed successfully");
+        #[cfg(feature = "verbose-logs")]
+        {
+            msg!("  Signal: {:?}", signal);
+            msg!("  Side: {:?}", side);
+            msg!("  Amount: {} lamports", result.amount_traded);
+            msg!("  Shares: {}", result.shares_received);
+            msg!("  Price: {}", result.execution_price);
+            msg!("  Fees: {} lamports", result.fees_paid);
+        }
+    } else {
+        msg!("Trade execution failed");
+    }
+
+    Ok(result)
+}
+
+/// Read the strategy config trading instructions run against, telling a vault
+/// that never created one apart from one whose strategy is switched off
+fn load_strategy_config(vault: &Account<SpectreVault>, info: &AccountInfo) -> Result<StrategyConfig> {
+    require!(vault.has_strategy, SpectreError::StrategyNotInitialized);
+    require_keys_eq!(*info.owner, crate::ID, SpectreError::StrategyNotInitialized);
+
+    let config = {
+        let data = info.try_borrow_data()?;
+        StrategyConfig::try_deserialize(&mut &data[..])?
+    };
+    require_keys_eq!(config.vault, vault.key(), SpectreError::Unauthorized);
+    require!(config.is_active, SpectreError::StrategyNotActive);
+
+    Ok(config)
+}
+
+/// Write back a strategy config read by `load_strategy_config`
+fn store_strategy_config(info: &AccountInfo, config: &StrategyConfig) -> Result<()> {
+    let mut data = info.try_borrow_mut_data()?;
+    config.try_serialize(&mut &mut data[..])?;
+    Ok(())
+}
+
+/// Read the protocol config, or None if it has not been created yet
+fn load_protocol_config(info: &AccountInfo) -> Result<Option<ProtocolConfig>> {
+    if info.data_is_empty() {
+        return Ok(None);
+    }
+    require_keys_eq!(*info.owner, crate::ID, SpectreError::Unauthorized);
+    let data = info.try_borrow_data()?;
+    Ok(Some(ProtocolConfig::try_deserialize(&mut &data[..])?))
+}
+
+/// Reject strategy params outside the protocol's bounds, if a protocol config exists
+fn check_strategy_bounds(protocol_config: &AccountInfo, params: &StrategyParams) -> Result<()> {
+    if let Some(protocol_config) = load_protocol_config(protocol_config)? {
+        require!(
+            protocol_config.strategy_bounds.allows(params),
+            SpectreError::InvalidStrategyParams
+        );
+    }
+    Ok(())
+}
+
+/// Screen a vault authority against their attestation (if given), requiring
+/// one when the protocol mandates authority compliance
+fn check_authority_compliance(
+    protocol_config: Option<&ProtocolConfig>,
+    attestation: Option<&RangeAttestation>,
+    authority: &Pubkey,
+    current_slot: u64,
+) -> Result<()> {
+    match attestation {
+        Some(attestation) => {
+            let result = verify_compliance(attestation, authority, current_slot);
+            if !result.passed {
+                msg!("Authority compliance check failed");
+                msg!("  Risk score: {}", attestation.risk_score);
+                return Err(SpectreError::HighRiskAddress.into());
+            }
+        }
+        None => require!(
+            !protocol_config.is_some_and(|config| config.require_authority_compliance),
+            SpectreError::AuthorityAttestationRequired
+        ),
+    }
+
+    Ok(())
+}
+
+/// Screen a depositor against their attestation (if given), requiring one
+/// when the vault mandates deposit compliance
+fn check_depositor_compliance(
+    vault: &SpectreVault,
+    attestation: Option<&RangeAttestation>,
+    depositor: &Pubkey,
+    current_slot: u64,
+) -> Result<()> {
+    match attestation {
+        Some(attestation) => {
+            let result = verify_compliance_with_decay(
+                attestation,
+                depositor,
+                current_slot,
+                vault.compliance_score_decay,
+            );
+            if !result.passed {
+                msg!("Depositor compliance check failed");
+                msg!("  Risk score: {}", attestation.risk_score);
+                return Err(SpectreError::HighRiskAddress.into());
+            }
+        }
+        None => require!(
+            !vault.require_deposit_compliance,
+            SpectreError::DepositAttestationRequired
+        ),
+    }
+
+    Ok(())
+}
+
+// ============================================
+// Account Contexts
+// ============================================
+
+/// Accounts for initializing the SPECTRE vault
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + SpectreVault::INIT_SPACE,
+        seeds = [VAULT_SEED, authority.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, SpectreVault>,
+
+    /// CHECK: PDA that will hold SOL
+    #[account(
+        seeds = [VAULT_SEED, authority.key().as_ref(), b"sol"],
+        bump
+    )]
+    pub vault_sol: AccountInfo<'info>,
+
+    /// CHECK: Protocol config PDA. It may not exist yet, in which case no
+    /// protocol-level requirements apply; otherwise it is deserialized in
+    /// the handler.
+    #[account(
+        seeds = [PROTOCOL_CONFIG_SEED],
+        bump
+    )]
+    pub protocol_config: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for creating the protocol config
+#[derive(Accounts)]
+pub struct InitializeProtocolConfig<'info> {
+    #[account(mut)]
+    pub upgrade_authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = upgrade_authority,
+        space = 8 + ProtocolConfig::INIT_SPACE,
+        seeds = [PROTOCOL_CONFIG_SEED],
+        bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key()) @ SpectreError::Unauthorized
+    )]
+    pub program: Program<'info, crate::program::SpectreProtocol>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(upgrade_authority.key()) @ SpectreError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for updating the protocol config (protocol admin only)
+#[derive(Accounts)]
+pub struct UpdateProtocolConfig<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROTOCOL_CONFIG_SEED],
+        bump = protocol_config.bump,
+        constraint = protocol_config.admin == admin.key() @ SpectreError::Unauthorized
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+}
+
+/// Accounts for funding the agent
+#[derive(Accounts)]
+#[instruction(proof: ZkProof)]
+pub struct FundAgent<'info> {
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.authority.as_ref()],
+        bump = vault.vault_bump,
+        constraint = vault.is_active @ SpectreError::VaultInactive
+    )]
+    pub vault: Account<'info, SpectreVault>,
+
+    #[account(
+        init,
+        payer = depositor,
+        space = 8 + UserDeposit::INIT_SPACE,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), &proof.public_inputs.commitment],
+        bump
+    )]
+    pub user_deposit: Account<'info, UserDeposit>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for requesting a withdrawal
+#[derive(Accounts)]
+#[instruction(amount: u64)]
+pub struct RequestWithdrawal<'info> {
+    #[account(mut)]
+    pub requester: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.authority.as_ref()],
+        bump = vault.vault_bump,
+        constraint = vault.is_active @ SpectreError::VaultInactive
+    )]
+    pub vault: Account<'info, SpectreVault>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), &user_deposit.commitment],
+        bump = user_deposit.bump,
+        constraint = user_deposit.owner == requester.key() @ SpectreError::UnauthorizedWithdrawal,
+        constraint = user_deposit.is_active @ SpectreError::DepositNotActive,
+        constraint = user_deposit.amount >= amount @ SpectreError::InsufficientBalance
+    )]
+    pub user_deposit: Account<'info, UserDeposit>,
+
+    #[account(
+        init,
+        payer = requester,
+        space = 8 + WithdrawalRequest::INIT_SPACE,
+        seeds = [
+            WITHDRAWAL_SEED,
+            vault.key().as_ref(),
+            requester.key().as_ref(),
+            user_deposit.key().as_ref(),
+            &user_deposit.withdrawal_nonce.to_le_bytes()
+        ],
+        bump
+    )]
+    pub withdrawal_request: Account<'info, WithdrawalRequest>,
+
+    /// CHECK: Any valid Solana address can receive funds
+    pub recipient: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for requesting a withdrawal of the full deposit balance
+#[derive(Accounts)]
+pub struct RequestFullWithdrawal<'info> {
+    #[account(mut)]
+    pub requester: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.authority.as_ref()],
+        bump = vault.vault_bump,
+        constraint = vault.is_active @ SpectreError::VaultInactive
+    )]
+    pub vault: Account<'info, SpectreVault>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), &user_deposit.commitment],
+        bump = user_deposit.bump,
+        constraint = user_deposit.owner == requester.key() @ SpectreError::UnauthorizedWithdrawal,
+        constraint = user_deposit.is_active @ SpectreError::DepositNotActive
+    )]
+    pub user_deposit: Account<'info, UserDeposit>,
+
+    #[account(
+        init,
+        payer = requester,
+        space = 8 + WithdrawalRequest::INIT_SPACE,
+        seeds = [
+            WITHDRAWAL_SEED,
+            vault.key().as_ref(),
+            requester.key().as_ref(),
+            user_deposit.key().as_ref(),
+            &user_deposit.withdrawal_nonce.to_le_bytes()
+        ],
+        bump
+    )]
+    pub withdrawal_request: Account<'info, WithdrawalRequest>,
+
+    /// CHECK: Any valid Solana address can receive funds
+    pub recipient: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for cancelling a withdrawal request
+#[derive(Accounts)]
+pub struct CancelWithdrawal<'info> {
+    #[account(mut)]
+    pub requester: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.authority.as_ref()],
+        bump = vault.vault_bump
+    )]
+    pub vault: Account<'info, SpectreVault>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), &user_deposit.commitment],
+        bump = user_deposit.bump,
+        constraint = user_deposit.owner == requester.key() @ SpectreError::UnauthorizedWithdrawal
+    )]
+    pub user_deposit: Account<'info, UserDeposit>,
+
+    #[account(
+        mut,
+        close = requester,
+        seeds = [
+            WITHDRAWAL_SEED,
+            vault.key().as_ref(),
+            requester.key().as_ref(),
+            user_deposit.key().as_ref(),
+            &withdrawal_request.nonce.to_le_bytes()
+        ],
+        bump = withdrawal_request.bump,
+        constraint = withdrawal_request.requester == requester.key() @ SpectreError::UnauthorizedWithdrawal,
+        constraint = withdrawal_request.can_cancel() @ SpectreError::InvalidWithdrawalStatus
+    )]
+    pub withdrawal_request: Account<'info, WithdrawalRequest>,
+}
+
+/// Accounts for simulating withdrawal compliance
+#[derive(Accounts)]
+pub struct SimulateCompliance<'info> {
+    #[account(
+        seeds = [VAULT_SEED, vault.authority.as_ref()],
+        bump = vault.vault_bump
+    )]
+    pub vault: Account<'info, SpectreVault>,
+}
+
+/// Accounts for completing a withdrawal
+#[derive(Accounts)]
+pub struct CompleteWithdrawal<'info> {
+    #[account(mut)]
+    pub requester: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.authority.as_ref()],
+        bump = vault.vault_bump
+    )]
+    pub vault: Account<'info, SpectreVault>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), &user_deposit.commitment],
+        bump = user_deposit.bump,
+        constraint = user_deposit.owner == requester.key() @ SpectreError::UnauthorizedWithdrawal
+    )]
+    pub user_deposit: Account<'info, UserDeposit>,
+
+    #[account(
+        mut,
+        seeds = [
+            WITHDRAWAL_SEED,
+            vault.key().as_ref(),
+            requester.key().as_ref(),
+            user_deposit.key().as_ref(),
+            &withdrawal_request.nonce.to_le_bytes()
+        ],
+        bump = withdrawal_request.bump,
+        constraint = withdrawal_request.requester == requester.key() @ SpectreError::UnauthorizedWithdrawal
+    )]
+    pub withdrawal_request: Account<'info, WithdrawalRequest>,
 
+    /// CHECK: Must match the recipient in the withdrawal request
     #[account(
-        init,
-        payer = authority,
-        space = 8 + SpectreVault::INIT_SPACE,
-        seeds = [VAULT_SEED, authority.key().as_ref()],
-        bump
+        mut,
+        constraint = recipient.key() == withdrawal_request.recipient @ SpectreError::RecipientMismatch
     )]
-    pub vault: Account<'info, SpectreVault>,
+    pub recipient: AccountInfo<'info>,
 
-    /// CHECK: PDA that will hold SOL
+    /// Must sign when the vault has a compliance authority set
+    pub compliance_authority: Option<Signer<'info>>,
+
+    /// Cached compliance result for the recipient
     #[account(
-        seeds = [VAULT_SEED, authority.key().as_ref(), b"sol"],
-        bump
+        mut,
+        seeds = [COMPLIANCE_CACHE_SEED, vault.key().as_ref(), recipient.key().as_ref()],
+        bump = compliance_cache.bump
     )]
-    pub vault_sol: AccountInfo<'info>,
+    pub compliance_cache: Option<Account<'info, ComplianceCache>>,
 
     pub system_program: Program<'info, System>,
 }
 
-/// Accounts for funding the agent
+/// Accounts for creating a recipient's compliance cache
 #[derive(Accounts)]
-#[instruction(proof: ZkProof)]
-pub struct FundAgent<'info> {
+pub struct InitializeComplianceCache<'info> {
     #[account(mut)]
-    pub depositor: Signer<'info>,
+    pub payer: Signer<'info>,
 
     #[account(
-        mut,
         seeds = [VAULT_SEED, vault.authority.as_ref()],
-        bump = vault.vault_bump,
-        constraint = vault.is_active @ SpectreError::VaultInactive
+        bump = vault.vault_bump
     )]
     pub vault: Account<'info, SpectreVault>,
 
+    /// CHECK: Only used as a seed; the cache applies to this address
+    pub recipient: AccountInfo<'info>,
+
     #[account(
         init,
-        payer = depositor,
-        space = 8 + UserDeposit::INIT_SPACE,
-        seeds = [DEPOSIT_SEED, vault.key().as_ref(), &proof.public_inputs.commitment],
+        payer = payer,
+        space = 8 + ComplianceCache::INIT_SPACE,
+        seeds = [COMPLIANCE_CACHE_SEED, vault.key().as_ref(), recipient.key().as_ref()],
         bump
     )]
-    pub user_deposit: Account<'info, UserDeposit>,
+    pub compliance_cache: Account<'info, ComplianceCache>,
 
     pub system_program: Program<'info, System>,
 }
 
-/// Accounts for requesting a withdrawal
+/// Accounts for changing a recipient's compliance cache expiry
 #[derive(Accounts)]
-#[instruction(amount: u64)]
-pub struct RequestWithdrawal<'info> {
-    #[account(mut)]
-    pub requester: Signer<'info>,
+pub struct RefreshComplianceCache<'info> {
+    pub authority: Signer<'info>,
 
     #[account(
-        seeds = [VAULT_SEED, vault.authority.as_ref()],
+        seeds = [VAULT_SEED, authority.key().as_ref()],
         bump = vault.vault_bump,
-        constraint = vault.is_active @ SpectreError::VaultInactive
+        constraint = vault.authority == authority.key() @ SpectreError::Unauthorized
     )]
     pub vault: Account<'info, SpectreVault>,
 
-    #[account(
-        mut,
-        seeds = [DEPOSIT_SEED, vault.key().as_ref(), &user_deposit.commitment],
-        bump = user_deposit.bump,
-        constraint = user_deposit.owner == requester.key() @ SpectreError::UnauthorizedWithdrawal,
-        constraint = user_deposit.is_active @ SpectreError::DepositNotActive,
-        constraint = user_deposit.amount >= amount @ SpectreError::InsufficientBalance
-    )]
-    pub user_deposit: Account<'info, UserDeposit>,
+    /// CHECK: Only used as a seed; the cache applies to this address
+    pub recipient: AccountInfo<'info>,
 
     #[account(
-        init,
-        payer = requester,
-        space = 8 + WithdrawalRequest::INIT_SPACE,
-        seeds = [
-            WITHDRAWAL_SEED,
-            vault.key().as_ref(),
-            requester.key().as_ref(),
-            user_deposit.key().as_ref()
-        ],
-        bump
+        mut,
+        seeds = [COMPLIANCE_CACHE_SEED, vault.key().as_ref(), recipient.key().as_ref()],
+        bump = compliance_cache.bump
     )]
-    pub withdrawal_request: Account<'info, WithdrawalRequest>,
-
-    /// CHECK: Any valid Solana address can receive funds
-    pub recipient: AccountInfo<'info>,
-
-    pub system_program: Program<'info, System>,
+    pub compliance_cache: Account<'info, ComplianceCache>,
 }
 
-/// Accounts for completing a withdrawal
+/// Accounts for completing a multi-recipient withdrawal
+/// Recipients are passed as writable remaining accounts
 #[derive(Accounts)]
-pub struct CompleteWithdrawal<'info> {
+pub struct CompleteSplitWithdrawal<'info> {
     #[account(mut)]
     pub requester: Signer<'info>,
 
@@ -888,19 +3405,16 @@ pub struct CompleteWithdrawal<'info> {
             WITHDRAWAL_SEED,
             vault.key().as_ref(),
             requester.key().as_ref(),
-            user_deposit.key().as_ref()
+            user_deposit.key().as_ref(),
+            &withdrawal_request.nonce.to_le_bytes()
         ],
         bump = withdrawal_request.bump,
         constraint = withdrawal_request.requester == requester.key() @ SpectreError::UnauthorizedWithdrawal
     )]
     pub withdrawal_request: Account<'info, WithdrawalRequest>,
 
-    /// CHECK: Must match the recipient in the withdrawal request
-    #[account(
-        mut,
-        constraint = recipient.key() == withdrawal_request.recipient @ SpectreError::RecipientMismatch
-    )]
-    pub recipient: AccountInfo<'info>,
+    /// Must sign when the vault has a compliance authority set
+    pub compliance_authority: Option<Signer<'info>>,
 
     pub system_program: Program<'info, System>,
 }
@@ -929,13 +3443,17 @@ pub struct VerifyWithdrawalCompliance<'info> {
             WITHDRAWAL_SEED,
             vault.key().as_ref(),
             requester.key().as_ref(),
-            user_deposit.key().as_ref()
+            user_deposit.key().as_ref(),
+            &withdrawal_request.nonce.to_le_bytes()
         ],
         bump = withdrawal_request.bump,
         constraint = withdrawal_request.requester == requester.key() @ SpectreError::UnauthorizedWithdrawal,
-        constraint = withdrawal_request.status == WithdrawalStatus::Pending @ SpectreError::InvalidWithdrawalStatus
+        constraint = withdrawal_request.can_verify() @ SpectreError::InvalidWithdrawalStatus
     )]
     pub withdrawal_request: Account<'info, WithdrawalRequest>,
+
+    /// Must sign when the vault has a compliance authority set
+    pub compliance_authority: Option<Signer<'info>>,
 }
 
 // ============================================
@@ -949,6 +3467,7 @@ pub struct InitializeStrategy<'info> {
     pub authority: Signer<'info>,
 
     #[account(
+        mut,
         seeds = [VAULT_SEED, authority.key().as_ref()],
         bump = vault.vault_bump,
         constraint = vault.authority == authority.key() @ SpectreError::Unauthorized
@@ -964,6 +3483,15 @@ pub struct InitializeStrategy<'info> {
     )]
     pub strategy_config: Account<'info, StrategyConfig>,
 
+    /// CHECK: Protocol config PDA. It may not exist yet, in which case
+    /// strategy params are unbounded; otherwise it is deserialized in the
+    /// handler.
+    #[account(
+        seeds = [PROTOCOL_CONFIG_SEED],
+        bump
+    )]
+    pub protocol_config: UncheckedAccount<'info>,
+
     pub system_program: Program<'info, System>,
 }
 
@@ -1060,6 +3588,34 @@ pub struct UndelegateFromTee<'info> {
     pub magic_program: AccountInfo<'info>,
 }
 
+/// Accounts for permissionlessly undelegating an idle vault
+#[derive(Accounts)]
+pub struct UndelegateIfStale<'info> {
+    /// Anyone may pay for undelegating an idle vault
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Authority the vault PDA is derived from. Checked against the
+    /// vault's stored authority in the handler.
+    pub vault_authority: AccountInfo<'info>,
+
+    /// CHECK: The vault account currently delegated to the TEE.
+    /// Owned by the delegation program, so it is deserialized in the handler.
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault_authority.key().as_ref()],
+        bump
+    )]
+    pub vault: AccountInfo<'info>,
+
+    /// CHECK: Magic context account - required for commit/undelegate
+    #[account(mut)]
+    pub magic_context: AccountInfo<'info>,
+
+    /// CHECK: Magic program - required for commit/undelegate
+    pub magic_program: AccountInfo<'info>,
+}
+
 /// Accounts for updating model hash
 #[derive(Accounts)]
 pub struct UpdateModel<'info> {
@@ -1075,16 +3631,196 @@ pub struct UpdateModel<'info> {
     pub vault: Account<'info, SpectreVault>,
 }
 
-/// Accounts for updating strategy parameters
+/// Accounts for updating vault configuration (admin only)
+#[derive(Accounts)]
+pub struct UpdateVaultConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, authority.key().as_ref()],
+        bump = vault.vault_bump,
+        constraint = vault.authority == authority.key() @ SpectreError::Unauthorized
+    )]
+    pub vault: Account<'info, SpectreVault>,
+}
+
+/// Accounts for an operator top-up of vault liquidity (admin only)
+#[derive(Accounts)]
+pub struct TopUpVault<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, authority.key().as_ref()],
+        bump = vault.vault_bump,
+        constraint = vault.authority == authority.key() @ SpectreError::Unauthorized
+    )]
+    pub vault: Account<'info, SpectreVault>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for permissionlessly compounding the profit reserve
+#[derive(Accounts)]
+pub struct CompoundProfits<'info> {
+    /// Anyone may trigger a due compound
+    pub caller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.authority.as_ref()],
+        bump = vault.vault_bump
+    )]
+    pub vault: Account<'info, SpectreVault>,
+}
+
+/// Accounts for reconciling vault accounting (admin only)
+#[derive(Accounts)]
+pub struct ReconcileVault<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, authority.key().as_ref()],
+        bump = vault.vault_bump,
+        constraint = vault.authority == authority.key() @ SpectreError::Unauthorized
+    )]
+    pub vault: Account<'info, SpectreVault>,
+}
+
+/// Accounts for claiming a deposit's fee rebate
+#[derive(Accounts)]
+pub struct ClaimRebate<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.authority.as_ref()],
+        bump = vault.vault_bump
+    )]
+    pub vault: Account<'info, SpectreVault>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), &user_deposit.commitment],
+        bump = user_deposit.bump,
+        constraint = user_deposit.owner == owner.key() @ SpectreError::UnauthorizedWithdrawal
+    )]
+    pub user_deposit: Account<'info, UserDeposit>,
+}
+
+/// Accounts for reading a deposit's client view
+#[derive(Accounts)]
+pub struct GetDepositInfo<'info> {
+    #[account(
+        seeds = [VAULT_SEED, vault.authority.as_ref()],
+        bump = vault.vault_bump
+    )]
+    pub vault: Account<'info, SpectreVault>,
+
+    #[account(
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), &user_deposit.commitment],
+        bump = user_deposit.bump
+    )]
+    pub user_deposit: Account<'info, UserDeposit>,
+}
+
+/// Accounts for updating strategy parameters
+#[derive(Accounts)]
+pub struct SetStrategyParams<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [VAULT_SEED, authority.key().as_ref()],
+        bump = vault.vault_bump,
+        constraint = vault.authority == authority.key() @ SpectreError::Unauthorized
+    )]
+    pub vault: Account<'info, SpectreVault>,
+
+    #[account(
+        mut,
+        seeds = [STRATEGY_CONFIG_SEED, vault.key().as_ref()],
+        bump = strategy_config.bump,
+        constraint = strategy_config.authority == authority.key() @ SpectreError::Unauthorized
+    )]
+    pub strategy_config: Account<'info, StrategyConfig>,
+}
+
+/// Accounts for replacing strategy parameters, checked against protocol bounds
+#[derive(Accounts)]
+pub struct UpdateStrategyParams<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [VAULT_SEED, authority.key().as_ref()],
+        bump = vault.vault_bump,
+        constraint = vault.authority == authority.key() @ SpectreError::Unauthorized
+    )]
+    pub vault: Account<'info, SpectreVault>,
+
+    #[account(
+        mut,
+        seeds = [STRATEGY_CONFIG_SEED, vault.key().as_ref()],
+        bump = strategy_config.bump,
+        constraint = strategy_config.authority == authority.key() @ SpectreError::Unauthorized
+    )]
+    pub strategy_config: Account<'info, StrategyConfig>,
+
+    /// CHECK: Protocol config PDA. It may not exist yet, in which case
+    /// strategy params are unbounded; otherwise it is deserialized in the
+    /// handler.
+    #[account(
+        seeds = [PROTOCOL_CONFIG_SEED],
+        bump
+    )]
+    pub protocol_config: UncheckedAccount<'info>,
+}
+
+/// Accounts for initializing the signal log
+#[derive(Accounts)]
+pub struct InitializeSignalLog<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [VAULT_SEED, authority.key().as_ref()],
+        bump = vault.vault_bump,
+        constraint = vault.authority == authority.key() @ SpectreError::Unauthorized
+    )]
+    pub vault: Account<'info, SpectreVault>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + SignalLog::INIT_SPACE,
+        seeds = [SIGNAL_LOG_SEED, vault.key().as_ref()],
+        bump
+    )]
+    pub signal_log: Account<'info, SignalLog>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for generating trade signal
 #[derive(Accounts)]
-pub struct SetStrategyParams<'info> {
-    #[account(mut)]
+pub struct GenerateTradeSignal<'info> {
+    /// Vault authority or keeper
+    #[account(
+        mut,
+        constraint = vault.is_trader(&authority.key()) @ SpectreError::Unauthorized
+    )]
     pub authority: Signer<'info>,
 
     #[account(
-        seeds = [VAULT_SEED, authority.key().as_ref()],
+        seeds = [VAULT_SEED, vault.authority.as_ref()],
         bump = vault.vault_bump,
-        constraint = vault.authority == authority.key() @ SpectreError::Unauthorized
+        constraint = vault.is_active @ SpectreError::VaultInactive
     )]
     pub vault: Account<'info, SpectreVault>,
 
@@ -1092,29 +3828,44 @@ pub struct SetStrategyParams<'info> {
         mut,
         seeds = [STRATEGY_CONFIG_SEED, vault.key().as_ref()],
         bump = strategy_config.bump,
-        constraint = strategy_config.authority == authority.key() @ SpectreError::Unauthorized
+        constraint = strategy_config.is_active @ SpectreError::StrategyNotActive
     )]
     pub strategy_config: Account<'info, StrategyConfig>,
+
+    /// Optional ring buffer recording recent signals
+    #[account(
+        mut,
+        seeds = [SIGNAL_LOG_SEED, vault.key().as_ref()],
+        bump = signal_log.bump
+    )]
+    pub signal_log: Option<Account<'info, SignalLog>>,
 }
 
-/// Accounts for generating trade signal
+/// Accounts for generating a read-only trade signal
 #[derive(Accounts)]
-pub struct GenerateTradeSignal<'info> {
-    #[account(mut)]
+pub struct GenerateSignalReadonly<'info> {
     pub authority: Signer<'info>,
 
     #[account(
-        seeds = [VAULT_SEED, vault.authority.as_ref()],
+        seeds = [VAULT_SEED, authority.key().as_ref()],
         bump = vault.vault_bump,
-        constraint = vault.is_active @ SpectreError::VaultInactive
+        constraint = vault.authority == authority.key() @ SpectreError::Unauthorized
+    )]
+    pub vault: Account<'info, SpectreVault>,
+}
+
+/// Accounts for reading the strategy configuration
+#[derive(Accounts)]
+pub struct GetStrategyConfig<'info> {
+    #[account(
+        seeds = [VAULT_SEED, vault.authority.as_ref()],
+        bump = vault.vault_bump
     )]
     pub vault: Account<'info, SpectreVault>,
 
     #[account(
-        mut,
         seeds = [STRATEGY_CONFIG_SEED, vault.key().as_ref()],
-        bump = strategy_config.bump,
-        constraint = strategy_config.is_active @ SpectreError::StrategyNotActive
+        bump = strategy_config.bump
     )]
     pub strategy_config: Account<'info, StrategyConfig>,
 }
@@ -1126,7 +3877,11 @@ pub struct GenerateTradeSignal<'info> {
 /// Accounts for executing a trade
 #[derive(Accounts)]
 pub struct ExecuteTrade<'info> {
-    #[account(mut)]
+    /// Vault authority or keeper
+    #[account(
+        mut,
+        constraint = vault.is_trader(&authority.key()) @ SpectreError::Unauthorized
+    )]
     pub authority: Signer<'info>,
 
     #[account(
@@ -1137,6 +3892,35 @@ pub struct ExecuteTrade<'info> {
     )]
     pub vault: Account<'info, SpectreVault>,
 
+    /// CHECK: The vault's strategy config. Loaded in the handler so a vault
+    /// without a strategy fails with `StrategyNotInitialized` rather than a
+    /// generic deserialization error; its `vault` field is checked there.
+    #[account(mut)]
+    pub strategy_config: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for executing a trade and opening its position
+#[derive(Accounts)]
+#[instruction(market_id: Pubkey)]
+pub struct ExecuteAndOpen<'info> {
+    /// Vault authority or keeper (pays for the position account)
+    #[account(
+        mut,
+        constraint = vault.is_trader(&authority.key()) @ SpectreError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.authority.as_ref()],
+        bump = vault.vault_bump,
+        constraint = vault.is_active @ SpectreError::VaultInactive,
+        constraint = vault.active_positions < MAX_POSITIONS as u32 @ SpectreError::MaxPositionsReached
+    )]
+    pub vault: Account<'info, SpectreVault>,
+
     #[account(
         mut,
         seeds = [STRATEGY_CONFIG_SEED, vault.key().as_ref()],
@@ -1145,9 +3929,35 @@ pub struct ExecuteTrade<'info> {
     )]
     pub strategy_config: Account<'info, StrategyConfig>,
 
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [POSITION_SEED, vault.key().as_ref(), market_id.as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+
     pub system_program: Program<'info, System>,
 }
 
+/// Accounts for submitting a market-making quote
+#[derive(Accounts)]
+pub struct SubmitQuote<'info> {
+    /// Vault authority or keeper
+    #[account(
+        constraint = vault.is_trader(&authority.key()) @ SpectreError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [VAULT_SEED, vault.authority.as_ref()],
+        bump = vault.vault_bump,
+        constraint = vault.is_active @ SpectreError::VaultInactive
+    )]
+    pub vault: Account<'info, SpectreVault>,
+}
+
 /// Accounts for opening a position
 #[derive(Accounts)]
 #[instruction(market_id: Pubkey, side: TradeSide, shares: u64, entry_price: u64, invested_amount: u64)]
@@ -1200,6 +4010,40 @@ pub struct ClosePosition<'info> {
     )]
     pub position: Account<'info, Position>,
 
+    /// CHECK: Receives the performance fee. Only required for a profitable
+    /// close while a fee is configured; must be the vault's `fee_recipient`.
+    #[account(
+        mut,
+        address = vault.fee_recipient @ SpectreError::InvalidFeeRecipient
+    )]
+    pub fee_recipient: Option<UncheckedAccount<'info>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for partially exiting a position
+#[derive(Accounts)]
+pub struct DecreasePosition<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, authority.key().as_ref()],
+        bump = vault.vault_bump,
+        constraint = vault.authority == authority.key() @ SpectreError::Unauthorized
+    )]
+    pub vault: Account<'info, SpectreVault>,
+
+    #[account(
+        mut,
+        seeds = [POSITION_SEED, vault.key().as_ref(), position.market_id.as_ref()],
+        bump = position.bump,
+        constraint = position.vault == vault.key() @ SpectreError::PositionNotFound,
+        constraint = position.status == PositionStatus::Open @ SpectreError::PositionAlreadyClosed
+    )]
+    pub position: Account<'info, Position>,
+
     pub system_program: Program<'info, System>,
 }
 
@@ -1222,6 +4066,171 @@ pub struct GetPositionPnl<'info> {
     pub position: Account<'info, Position>,
 }
 
+/// Accounts for valuing several positions; the positions are passed as
+/// remaining accounts
+#[derive(Accounts)]
+pub struct BatchGetPositionPnl<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [VAULT_SEED, vault.authority.as_ref()],
+        bump = vault.vault_bump
+    )]
+    pub vault: Account<'info, SpectreVault>,
+}
+
+/// Accounts for marking an open position to market (admin only)
+#[derive(Accounts)]
+pub struct MarkPosition<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [VAULT_SEED, authority.key().as_ref()],
+        bump = vault.vault_bump,
+        constraint = vault.authority == authority.key() @ SpectreError::Unauthorized
+    )]
+    pub vault: Account<'info, SpectreVault>,
+
+    #[account(
+        mut,
+        seeds = [POSITION_SEED, vault.key().as_ref(), position.market_id.as_ref()],
+        bump = position.bump,
+        constraint = position.vault == vault.key() @ SpectreError::PositionNotFound,
+        constraint = position.status == PositionStatus::Open @ SpectreError::PositionAlreadyClosed
+    )]
+    pub position: Account<'info, Position>,
+}
+
+/// Accounts for scheduling a tranched position entry (admin only)
+#[derive(Accounts)]
+#[instruction(market_id: Pubkey)]
+pub struct CreateEntrySchedule<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, authority.key().as_ref()],
+        bump = vault.vault_bump,
+        constraint = vault.authority == authority.key() @ SpectreError::Unauthorized,
+        constraint = vault.is_active @ SpectreError::VaultInactive,
+        constraint = vault.active_positions < MAX_POSITIONS as u32 @ SpectreError::MaxPositionsReached
+    )]
+    pub vault: Account<'info, SpectreVault>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [POSITION_SEED, vault.key().as_ref(), market_id.as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + EntrySchedule::INIT_SPACE,
+        seeds = [ENTRY_SCHEDULE_SEED, vault.key().as_ref(), market_id.as_ref()],
+        bump
+    )]
+    pub entry_schedule: Account<'info, EntrySchedule>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for executing one tranche of a scheduled entry
+#[derive(Accounts)]
+pub struct ExecuteEntryTranche<'info> {
+    /// Vault authority or keeper
+    #[account(
+        constraint = vault.is_trader(&authority.key()) @ SpectreError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.authority.as_ref()],
+        bump = vault.vault_bump,
+        constraint = vault.is_active @ SpectreError::VaultInactive
+    )]
+    pub vault: Account<'info, SpectreVault>,
+
+    #[account(
+        mut,
+        seeds = [ENTRY_SCHEDULE_SEED, vault.key().as_ref(), entry_schedule.market_id.as_ref()],
+        bump = entry_schedule.bump,
+        constraint = entry_schedule.vault == vault.key() @ SpectreError::Unauthorized
+    )]
+    pub entry_schedule: Account<'info, EntrySchedule>,
+
+    #[account(
+        mut,
+        address = entry_schedule.position @ SpectreError::PositionNotFound,
+        constraint = position.status == PositionStatus::Open @ SpectreError::PositionAlreadyClosed
+    )]
+    pub position: Account<'info, Position>,
+}
+
+// ============================================
+// Events
+// ============================================
+
+/// Emitted when the vault's trading model hash changes
+#[event]
+pub struct ModelUpdatedEvent {
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    pub model_hash: [u8; 32],
+    pub model_version: u32,
+    pub updated_at: i64,
+}
+
+/// Emitted when a withdrawal passes compliance with elevated risk
+#[event]
+pub struct ComplianceFlaggedEvent {
+    pub vault: Pubkey,
+    pub withdrawal: Pubkey,
+    pub recipient: Pubkey,
+    pub risk_score: u8,
+    pub flagged_at: i64,
+}
+
+/// Emitted when a position is closed, with a full fee breakdown
+///
+/// `gross_pnl - entry_fees - exit_fees + prior_realized_pnl == net_pnl`
+///
+/// `gross_pnl` and `entry_fees` cover the shares held at close;
+/// `prior_realized_pnl` is what partial decreases already realized, net of
+/// their share of the entry fees.
+#[event]
+pub struct PositionClosedEvent {
+    pub vault: Pubkey,
+    pub position: Pubkey,
+    pub market_id: Pubkey,
+    pub invested_amount: u64,
+    pub exit_value: u64,
+    pub entry_fees: u64,
+    pub exit_fees: u64,
+    pub gross_pnl: i64,
+    pub prior_realized_pnl: i64,
+    pub net_pnl: i64,
+    pub performance_fee: u64,
+    pub closed_at: i64,
+}
+
+/// Emitted when an open position is marked to market
+#[event]
+pub struct PositionMarkedEvent {
+    pub vault: Pubkey,
+    pub position: Pubkey,
+    pub market_id: Pubkey,
+    pub mark_price: u64,
+    pub unrealized_pnl: i64,
+    pub marked_slot: u64,
+    pub marked_at: i64,
+}
+
 // ============================================
 // Error Definitions
 // ============================================
@@ -1246,9 +4255,36 @@ pub enum SpectreError {
     #[msg("Failed to undelegate vault from TEE enclave")]
     UndelegationFailed,
 
+    #[msg("Vault has not been idle long enough to undelegate")]
+    DelegationNotStale,
+
     #[msg("Insufficient balance in vault")]
     InsufficientVaultBalance,
 
+    #[msg("Trading is not allowed outside the vault's trading window")]
+    OutsideTradingWindow,
+
+    #[msg("Invalid trading window")]
+    InvalidTradingWindow,
+
+    #[msg("Vault available balance exceeds the lamports it holds")]
+    BalanceDiscrepancy,
+
+    #[msg("Deposit would exceed the vault's capacity")]
+    VaultCapacityExceeded,
+
+    #[msg("Vault is too young to raise its capacity")]
+    VaultTooYoung,
+
+    #[msg("Profit compounding is disabled or not yet due")]
+    CompoundNotDue,
+
+    #[msg("Fee recipient is missing or does not match the vault's")]
+    InvalidFeeRecipient,
+
+    #[msg("Vault has no active deposits to share rebates with")]
+    NoActiveDeposits,
+
     // ============================================
     // Deposit Errors
     // ============================================
@@ -1273,6 +4309,9 @@ pub enum SpectreError {
     #[msg("Deposit is not active")]
     DepositNotActive,
 
+    #[msg("Deposit proof is too old")]
+    StaleProof,
+
     // ============================================
     // Withdrawal Errors
     // ============================================
@@ -1291,6 +4330,15 @@ pub enum SpectreError {
     #[msg("Recipient does not match withdrawal request")]
     RecipientMismatch,
 
+    #[msg("Split payouts must be non-empty, within the recipient limit, and sum to the withdrawal amount")]
+    InvalidSplitPayouts,
+
+    #[msg("Withdrawal amount exceeds the per-request cap - split into multiple requests")]
+    WithdrawalTooLarge,
+
+    #[msg("Deposit has too many pending withdrawal requests")]
+    TooManyPendingWithdrawals,
+
     // ============================================
     // Compliance Errors
     // ============================================
@@ -1303,9 +4351,33 @@ pub enum SpectreError {
     #[msg("Address is flagged as high risk")]
     HighRiskAddress,
 
+    #[msg("Recipient risk score rose too far since verification")]
+    RiskProfileChanged,
+
     #[msg("Invalid oracle signature on attestation")]
     InvalidOracleSignature,
 
+    #[msg("Vault authority compliance re-check is due - provide a fresh attestation")]
+    ComplianceRecheckRequired,
+
+    #[msg("This vault requires a compliance attestation for deposits")]
+    DepositAttestationRequired,
+
+    #[msg("The protocol requires a compliance attestation for new vault authorities")]
+    AuthorityAttestationRequired,
+
+    #[msg("Compliance was verified too recently for this withdrawal")]
+    VerifyTooFrequent,
+
+    #[msg("Compliance attestation has already been used")]
+    AttestationReused,
+
+    #[msg("No fresh cached compliance result; an attestation is required")]
+    ComplianceCacheMiss,
+
+    #[msg("Compliance cache expiry must be in the future")]
+    InvalidCacheExpiry,
+
     // ============================================
     // Trading Errors (Phase 3)
     // ============================================
@@ -1315,6 +4387,39 @@ pub enum SpectreError {
     #[msg("Position is already closed")]
     PositionAlreadyClosed,
 
+    #[msg("Decrease would sell every share; use close_position instead")]
+    DecreaseClosesPosition,
+
+    #[msg("Position has not been held for the minimum holding period")]
+    PositionTooYoung,
+
+    #[msg("Position settles in a different currency than the vault")]
+    CurrencyMismatch,
+
+    #[msg("Trading is paused after a recent losing close")]
+    LossCooldownActive,
+
+    #[msg("Number of prices does not match number of positions")]
+    PriceCountMismatch,
+
+    #[msg("Position would push total open exposure above the vault's cap")]
+    ExposureCapExceeded,
+
+    #[msg("Strategy has not generated enough signals to start trading")]
+    StrategyWarmingUp,
+
+    #[msg("Invested amount does not match shares at the entry price")]
+    InconsistentPositionData,
+
+    #[msg("Entry schedule needs 1 to 32 tranches of at least the minimum trade size and a non-zero interval")]
+    InvalidEntrySchedule,
+
+    #[msg("Next entry tranche is not due yet")]
+    EntryTrancheNotDue,
+
+    #[msg("Every tranche of the entry schedule has been executed")]
+    EntryScheduleComplete,
+
     #[msg("Invalid trade signal")]
     InvalidTradeSignal,
 
@@ -1339,6 +4444,18 @@ pub enum SpectreError {
     #[msg("Market is not active")]
     MarketNotActive,
 
+    #[msg("Market has resolved and no longer accepts trades")]
+    MarketResolved,
+
+    #[msg("Market has not been resolved")]
+    MarketNotResolved,
+
+    #[msg("Market resolution is still within its dispute window")]
+    DisputeWindowActive,
+
+    #[msg("Quote is too old")]
+    StaleQuote,
+
     #[msg("Insufficient liquidity")]
     InsufficientLiquidity,
 
@@ -1351,9 +4468,30 @@ pub enum SpectreError {
     #[msg("Strategy is not active")]
     StrategyNotActive,
 
+    #[msg("Vault has no strategy - call initialize_strategy first")]
+    StrategyNotInitialized,
+
     #[msg("Invalid market input data")]
     InvalidMarketInput,
 
+    #[msg("Model attestation is too old; update the model before trading")]
+    StaleModel,
+
+    #[msg("Daily trade limit reached")]
+    DailyTradeLimitReached,
+
+    #[msg("Price moved too far since the last signal")]
+    PriceMoveTooLarge,
+
+    #[msg("Position size is below the minimum trade amount")]
+    PositionSizeTooSmall,
+
+    #[msg("No signal is pending confirmation")]
+    NoPendingSignal,
+
+    #[msg("Signal log capacity must be between 1 and the maximum entries")]
+    InvalidSignalLogCapacity,
+
     // ============================================
     // Authorization Errors
     // ============================================
@@ -1370,6 +4508,17 @@ pub enum SpectreError {
     MathUnderflow,
 }
 
+impl From<MarketError> for SpectreError {
+    fn from(err: MarketError) -> Self {
+        match err {
+            MarketError::MarketResolved => SpectreError::MarketResolved,
+            MarketError::InvalidParams => SpectreError::InvalidTradeAmount,
+            MarketError::MarketUnresolved => SpectreError::MarketNotResolved,
+            MarketError::DisputeWindowActive => SpectreError::DisputeWindowActive,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
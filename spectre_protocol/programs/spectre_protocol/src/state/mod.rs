@@ -8,23 +8,83 @@
 
 use anchor_lang::prelude::*;
 
+use crate::cpi::{TradeResult, TradeSide, MIN_TRADE_AMOUNT, PRICE_SCALE};
+use crate::strategy::{risk_adjusted_score, StrategyParams, TradeSignal, NUM_INPUT_BUCKETS};
+
 /// Seeds for PDA derivation
 pub const VAULT_SEED: &[u8] = b"spectre_vault";
 pub const DEPOSIT_SEED: &[u8] = b"user_deposit";
 pub const WITHDRAWAL_SEED: &[u8] = b"withdrawal";
 pub const POSITION_SEED: &[u8] = b"position";
 pub const STRATEGY_CONFIG_SEED: &[u8] = b"strategy_config";
+pub const SIGNAL_LOG_SEED: &[u8] = b"signal_log";
+pub const COMPLIANCE_CACHE_SEED: &[u8] = b"compliance_cache";
+pub const ENTRY_SCHEDULE_SEED: &[u8] = b"entry_schedule";
+pub const PROTOCOL_CONFIG_SEED: &[u8] = b"protocol_config";
+
+/// Maximum number of entries a signal log can hold
+pub const MAX_SIGNAL_LOG_ENTRIES: usize = 32;
 
 /// Maximum number of active positions per vault
 pub const MAX_POSITIONS: usize = 100;
 
+/// Maximum number of tranches in a scheduled position entry
+pub const MAX_ENTRY_TRANCHES: u8 = 32;
+
+/// Number of seconds in a UTC day (trading window bounds are seconds-of-day)
+pub const SECONDS_PER_DAY: u32 = 86_400;
+
+/// Full-scale signal bias (every recent signal a strong buy)
+pub const MAX_SIGNAL_BIAS: i32 = 1_000;
+
+/// Smoothing divisor for the signal bias EWMA (each signal moves the bias 1/8
+/// of the way toward its own weight)
+pub const SIGNAL_BIAS_SMOOTHING: i32 = 8;
+
+/// Closed positions needed before performance sizing adjusts position size
+pub const MIN_PERFORMANCE_SAMPLE: u64 = 5;
+
+/// Win rate (bps) above which performance sizing scales positions up
+pub const HIGH_WIN_RATE_BPS: u64 = 6_000;
+
+/// Win rate (bps) below which performance sizing scales positions down
+pub const LOW_WIN_RATE_BPS: u64 = 4_000;
+
+/// Position size multiplier (bps) at a high win rate (1.25x)
+pub const HIGH_PERFORMANCE_MULTIPLIER_BPS: u64 = 12_500;
+
+/// Position size multiplier (bps) at a low win rate (0.75x)
+pub const LOW_PERFORMANCE_MULTIPLIER_BPS: u64 = 7_500;
+
+/// Fixed-point scale of the per-lamport fee rebate index
+pub const REBATE_INDEX_SCALE: u128 = 1_000_000_000_000;
+
+/// Leading commitment bytes exposed by `get_deposit_info`
+pub const COMMITMENT_FINGERPRINT_LEN: usize = 8;
+
+/// Default allowed gap between a position's invested amount and its
+/// shares * entry price, covering trading fees (2%)
+pub const DEFAULT_POSITION_COST_TOLERANCE_BPS: u16 = 200;
+
+/// Highest performance fee a vault may charge (30% of profits)
+pub const MAX_PERFORMANCE_FEE_BPS: u16 = 3_000;
+
+/// Compute units `execute_trade` is budgeted to stay within; clients should
+/// request at least this much. This is an estimate, not yet a measured figure:
+/// the integration test logs consumed units and asserts against it, but has
+/// not been run to calibrate it since trades stopped simulating a MockMarket
+pub const EXECUTE_TRADE_COMPUTE_BUDGET: u32 = 100_000;
+
+/// Settlement mint marking native SOL (lamport) accounting
+pub const NATIVE_SETTLEMENT_MINT: Pubkey = Pubkey::new_from_array([0u8; 32]);
+
 /// Delegation program ID for MagicBlock TEE (placeholder)
 /// In production, this would be the actual delegation program
 pub const DELEGATION_PROGRAM_ID: &str = "DELegateXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX";
 
 /// Main vault account that holds shielded funds and manages trading state
 #[account]
-#[derive(InitSpace)]
+#[derive(InitSpace, Default)]
 pub struct SpectreVault {
     /// Authority that controls the vault (TEE-controlled keypair in production)
     pub authority: Pubkey,
@@ -65,24 +125,518 @@ pub struct SpectreVault {
     /// Total number of withdrawals completed
     pub total_withdrawals_count: u64,
 
-    /// Total trading volume (lamports)
+    /// Total trading volume (lamports), saturating at u64::MAX; see `lifetime_volume`
     pub total_volume: u64,
+
+    /// Start of the daily trading window (seconds-of-day, UTC)
+    pub trading_window_start: u32,
+
+    /// End of the daily trading window (seconds-of-day, UTC, exclusive)
+    /// A window of 0..86400 means always-on; start > end wraps past midnight
+    pub trading_window_end: u32,
+
+    /// Unix timestamp of the last model hash update
+    pub model_updated_at: i64,
+
+    /// Model version, incremented on each model hash update
+    pub model_version: u32,
+
+    /// Total trading fees paid across all trades (lamports)
+    pub total_fees_paid: u64,
+
+    /// Slot of the last passing compliance attestation for the authority
+    pub last_compliance_slot: u64,
+
+    /// Slots after which trading requires a fresh authority attestation (0 = disabled)
+    pub compliance_recheck_interval_slots: u64,
+
+    /// Whether depositors must pass compliance screening to fund the vault
+    pub require_deposit_compliance: bool,
+
+    /// Whether attestation risk scores increase with age during compliance checks
+    pub compliance_score_decay: bool,
+
+    /// Dedicated key that must sign withdrawal compliance (default = any requester)
+    pub compliance_authority: Pubkey,
+
+    /// Hot key allowed to generate signals and trade (default = none)
+    pub keeper: Pubkey,
+
+    /// Seconds after a model update before signals are rejected (0 = disabled)
+    pub model_max_age_seconds: u64,
+
+    /// Minimum slots between compliance verifications of one withdrawal (0 = disabled)
+    pub min_verify_interval_slots: u64,
+
+    /// Largest amount a single withdrawal request may ask for (0 = no cap)
+    pub max_single_withdrawal: u64,
+
+    /// Oldest deposit proof accepted, in slots since generation (0 = disabled)
+    pub max_proof_age_slots: u64,
+    /// Oldest market-making quote accepted, in slots since generation (0 = disabled)
+    pub max_quote_age_slots: u64,
+    /// Whether withdrawal attestations must carry a nonce above `last_attestation_nonce`
+    pub require_attestation_nonce: bool,
+
+    /// Highest attestation nonce consumed by a withdrawal
+    pub last_attestation_nonce: u64,
+    /// Seconds a position must be held before it can be closed (0 = disabled)
+    pub min_holding_period_seconds: u64,
+    /// Cap on `total_deposited` across all deposits (0 = unlimited)
+    pub max_total_deposited: u64,
+    /// Slots a cached passing compliance result stays valid (0 = caching disabled)
+    pub compliance_cache_ttl_slots: u64,
+    /// Positions closed with a positive realized PnL
+    pub winning_closes: u64,
+
+    /// Positions closed with a negative realized PnL
+    pub losing_closes: u64,
+
+    /// Whether position size scales with the realized win rate
+    pub performance_sizing: bool,
+
+    /// Largest position as a share of available balance in bps (0 = no cap)
+    pub max_position_bps: u16,
+    /// Part of `available_balance` promised to pending withdrawals and
+    /// not available for trading
+    pub reserved_for_withdrawal: u64,
+    /// Currency the vault accounts in; positions must settle in it
+    pub settlement_mint: Pubkey,
+    /// Lamports set aside for depositor rebates, outside `available_balance`
+    pub fee_rebate_pool: u64,
+    /// Cumulative rebate per deposited lamport (scaled by REBATE_INDEX_SCALE)
+    pub rebate_index: u128,
+    /// Sum of outstanding deposit amounts that share in rebates
+    pub active_deposit_total: u64,
+    /// Slot of the most recent losing close (0 = none)
+    pub last_loss_slot: u64,
+    /// Slots to pause trading after a losing close (0 = disabled)
+    pub loss_cooldown_slots: u64,
+    /// Idle slots after which anyone may undelegate the vault (0 = disabled)
+    pub max_delegation_idle_slots: u64,
+    /// Sum of `invested_amount` across open positions
+    pub total_open_exposure: u64,
+    /// Cap on `total_open_exposure` as bps of `total_deposited` (0 = no cap)
+    pub max_total_exposure_bps: u16,
+    /// Whether the vault covers rent for withdrawals to unfunded recipients
+    pub cover_recipient_rent: bool,
+    /// Lamports added by the authority via `top_up_vault`, outside `total_deposited`
+    pub operator_funded: u64,
+    /// Cap on pending withdrawal requests per deposit (0 = unlimited)
+    pub max_pending_withdrawals: u32,
+    /// Deposits received while delegated, merged into `available_balance` on undelegation
+    pub staged_deposits: u64,
+    /// Allowed gap between a position's invested amount and shares * entry price (bps)
+    pub position_cost_tolerance_bps: u16,
+    /// Vault age required before its capacity may be raised (seconds, 0 = no minimum)
+    pub min_age_to_raise_capacity_seconds: u64,
+    /// Share of each close's realized profit set aside in `profit_reserve` (bps, 0 = none)
+    pub profit_reserve_bps: u16,
+    /// Realized profit held out of `available_balance` until compounded
+    pub profit_reserve: u64,
+    /// Slots between permissionless `compound_profits` calls (0 = disabled)
+    pub auto_compound_interval_slots: u64,
+    /// Slot the compounding interval was last started from
+    pub last_compound_slot: u64,
+    /// Whether `initialize_strategy` has created this vault's strategy config
+    pub has_strategy: bool,
+    /// Largest risk score increase between verification and completion (0 = unchecked)
+    pub max_risk_delta: u8,
+    /// Share of each profitable close's realized PnL paid to `fee_recipient` (bps)
+    pub performance_fee_bps: u16,
+    /// Account receiving performance fees
+    pub fee_recipient: Pubkey,
+    /// Sum of realized PnL across all closed positions
+    pub cumulative_realized_pnl: i64,
+    /// Highest `cumulative_realized_pnl` reached so far
+    pub high_water_mark: i64,
+    /// Whether performance fees only apply above the high-water mark
+    pub high_water_mark_enabled: bool,
+    /// Slot the vault was last delegated to the TEE (0 = never)
+    pub delegated_slot: u64,
+    /// Total trading volume (lamports) as a u128, so a long-lived vault never
+    /// saturates it; appended rather than widening `total_volume` in place to
+    /// keep the offsets of existing fields
+    pub lifetime_volume: u128,
 }
 
 impl SpectreVault {
     /// Check if the vault has sufficient balance for a trade
     pub fn has_sufficient_balance(&self, amount: u64) -> bool {
-        self.available_balance >= amount
+        self.tradable_balance() >= amount
+    }
+
+    /// Available balance not reserved for pending withdrawals
+    pub fn tradable_balance(&self) -> u64 {
+        self.available_balance.saturating_sub(self.reserved_for_withdrawal)
+    }
+
+    /// Hold back `amount` of the available balance for a requested withdrawal
+    pub fn reserve_withdrawal(&mut self, amount: u64) {
+        self.reserved_for_withdrawal = self.reserved_for_withdrawal.saturating_add(amount);
+    }
+
+    /// Release a reservation once its withdrawal has paid out
+    pub fn release_withdrawal(&mut self, amount: u64) {
+        self.reserved_for_withdrawal = self.reserved_for_withdrawal.saturating_sub(amount);
+    }
+
+    /// Move `amount` of the tradable balance into the rebate pool, shared
+    /// across outstanding deposits in proportion to their amounts
+    ///
+    /// Returns false, leaving the vault unchanged, if there are no deposits
+    /// to share it or the tradable balance is too small.
+    pub fn fund_rebate_pool(&mut self, amount: u64) -> bool {
+        if self.active_deposit_total == 0 || !self.has_sufficient_balance(amount) {
+            return false;
+        }
+        self.available_balance -= amount;
+        self.fee_rebate_pool = self.fee_rebate_pool.saturating_add(amount);
+        self.rebate_index = self.rebate_index.saturating_add(
+            amount as u128 * REBATE_INDEX_SCALE / self.active_deposit_total as u128,
+        );
+        true
     }
 
     /// Calculate position size based on signal strength
+    ///
+    /// With `performance_sizing` on, the size is scaled by
+    /// `performance_multiplier_bps`. The result is capped at
+    /// `max_position_bps` of the tradable balance when set.
+    /// Returns 0 if the size would fall below `MIN_TRADE_AMOUNT`
     pub fn calculate_position_size(&self, is_strong_signal: bool) -> u64 {
-        let base_size = self.available_balance / 20; // 5% per trade
-        if is_strong_signal {
+        let tradable = self.tradable_balance();
+        let base_size = tradable / 20; // 5% per trade
+        let mut size = if is_strong_signal {
             base_size.saturating_mul(2) // 10% for strong signals
         } else {
             base_size
+        };
+
+        if self.performance_sizing {
+            size = (size as u128 * self.performance_multiplier_bps() as u128 / 10_000) as u64;
+        }
+        if self.max_position_bps != 0 {
+            let cap = tradable as u128 * self.max_position_bps as u128 / 10_000;
+            size = size.min(cap as u64);
+        }
+
+        // Too small to trade
+        if size < MIN_TRADE_AMOUNT {
+            return 0;
+        }
+        size
+    }
+
+    /// Share of closed positions that were profitable, in bps
+    /// None until `MIN_PERFORMANCE_SAMPLE` positions have closed with a gain or loss
+    pub fn win_rate_bps(&self) -> Option<u64> {
+        let total = self.winning_closes.saturating_add(self.losing_closes);
+        if total < MIN_PERFORMANCE_SAMPLE {
+            return None;
+        }
+        Some((self.winning_closes as u128 * 10_000 / total as u128) as u64)
+    }
+
+    /// Position size multiplier (bps) derived from the realized win rate
+    /// 1.25x above 60% wins, 0.75x below 40%, 1x otherwise or without enough history
+    pub fn performance_multiplier_bps(&self) -> u64 {
+        match self.win_rate_bps() {
+            Some(rate) if rate > HIGH_WIN_RATE_BPS => HIGH_PERFORMANCE_MULTIPLIER_BPS,
+            Some(rate) if rate < LOW_WIN_RATE_BPS => LOW_PERFORMANCE_MULTIPLIER_BPS,
+            _ => 10_000,
+        }
+    }
+
+    /// Check if opening `amount` more would push open exposure above the cap
+    pub fn exceeds_exposure_cap(&self, amount: u64) -> bool {
+        if self.max_total_exposure_bps == 0 {
+            return false;
+        }
+        let cap = self.total_deposited as u128 * self.max_total_exposure_bps as u128 / 10_000;
+        self.total_open_exposure as u128 + amount as u128 > cap
+    }
+
+    /// Extra lamports the vault adds so a payout of `amount` leaves a brand-new
+    /// recipient rent-exempt
+    ///
+    /// 0 unless rent cover is enabled and the recipient holds no lamports yet.
+    pub fn recipient_rent_top_up(&self, recipient_lamports: u64, amount: u64, rent_exempt: u64) -> u64 {
+        if !self.cover_recipient_rent || recipient_lamports != 0 {
+            return 0;
+        }
+        rent_exempt.saturating_sub(amount)
+    }
+
+    /// Count a closed position towards the win rate (break-even closes are ignored)
+    /// and start the loss cooldown on a losing close
+    pub fn record_close(&mut self, realized_pnl: i64, slot: u64) {
+        if realized_pnl > 0 {
+            self.winning_closes = self.winning_closes.saturating_add(1);
+        } else if realized_pnl < 0 {
+            self.losing_closes = self.losing_closes.saturating_add(1);
+            self.last_loss_slot = slot;
+        }
+    }
+
+    /// Part of a close's realized PnL that performance fees apply to
+    ///
+    /// With the high-water mark enabled, only the part that lifts
+    /// `cumulative_realized_pnl` above `high_water_mark` counts, so gains
+    /// that merely recover earlier losses are not charged.
+    pub fn fee_eligible_profit(&self, realized_pnl: i64) -> u64 {
+        if realized_pnl <= 0 {
+            return 0;
+        }
+        if !self.high_water_mark_enabled {
+            return realized_pnl as u64;
+        }
+        let equity = self.cumulative_realized_pnl as i128 + realized_pnl as i128;
+        (equity - self.high_water_mark as i128).clamp(0, realized_pnl as i128) as u64
+    }
+
+    /// Performance fee owed on a close: `performance_fee_bps` of its
+    /// fee-eligible profit, never charged on principal or losses. None on overflow.
+    pub fn performance_fee(&self, realized_pnl: i64) -> Option<u64> {
+        let profit = self.fee_eligible_profit(realized_pnl);
+        let fee = (profit as u128).checked_mul(self.performance_fee_bps as u128)? / 10_000;
+        u64::try_from(fee).ok()
+    }
+
+    /// Lamports a performance fee may be paid from: the backing above rent,
+    /// less the reserves and the balance set aside for pending withdrawals
+    pub fn fee_payable(&self, backing_lamports: u64) -> u64 {
+        backing_lamports
+            .saturating_sub(self.held_outside_balance())
+            .saturating_sub(self.reserved_for_withdrawal)
+    }
+
+    /// Add a close's realized PnL to the running total and raise the
+    /// high-water mark if it was exceeded
+    pub fn record_realized_pnl(&mut self, realized_pnl: i64) {
+        self.cumulative_realized_pnl = self.cumulative_realized_pnl.saturating_add(realized_pnl);
+        self.high_water_mark = self.high_water_mark.max(self.cumulative_realized_pnl);
+    }
+
+    /// Move `profit_reserve_bps` of a profitable close out of `available_balance`
+    /// into `profit_reserve`, returning the amount reserved
+    pub fn reserve_profit(&mut self, realized_pnl: i64) -> u64 {
+        if realized_pnl <= 0 {
+            return 0;
+        }
+        let reserved = (realized_pnl as u128 * self.profit_reserve_bps as u128 / 10_000) as u64;
+        let reserved = reserved.min(self.available_balance);
+        self.available_balance -= reserved;
+        self.profit_reserve = self.profit_reserve.saturating_add(reserved);
+        reserved
+    }
+
+    /// Check if a compounding interval has elapsed since the last compound
+    pub fn is_compound_due(&self, current_slot: u64) -> bool {
+        self.auto_compound_interval_slots != 0
+            && current_slot >= self.last_compound_slot.saturating_add(self.auto_compound_interval_slots)
+    }
+
+    /// Move the whole profit reserve back into `available_balance` and restart
+    /// the interval, returning the amount compounded
+    pub fn compound_profits(&mut self, current_slot: u64) -> Option<u64> {
+        let amount = self.profit_reserve;
+        self.available_balance = self.available_balance.checked_add(amount)?;
+        self.profit_reserve = 0;
+        self.last_compound_slot = current_slot;
+        Some(amount)
+    }
+
+    /// Check if trading is paused after a recent losing close
+    pub fn is_in_loss_cooldown(&self, current_slot: u64) -> bool {
+        self.loss_cooldown_slots != 0
+            && self.last_loss_slot != 0
+            && current_slot < self.last_loss_slot.saturating_add(self.loss_cooldown_slots)
+    }
+
+    /// Slots since the last trade, or since delegation if that is more recent
+    pub fn delegation_idle_slots(&self, current_slot: u64) -> u64 {
+        current_slot.saturating_sub(self.last_trade_slot.max(self.delegated_slot))
+    }
+
+    /// Check if the vault has gone too long without a trade to stay delegated
+    pub fn is_delegation_stale(&self, current_slot: u64) -> bool {
+        self.max_delegation_idle_slots != 0
+            && self.delegation_idle_slots(current_slot) > self.max_delegation_idle_slots
+    }
+
+    /// Check if the authority must be re-attested before trading
+    pub fn needs_compliance_recheck(&self, current_slot: u64) -> bool {
+        self.compliance_recheck_interval_slots != 0
+            && current_slot.saturating_sub(self.last_compliance_slot)
+                > self.compliance_recheck_interval_slots
+    }
+
+    /// Check if a signer may generate signals and execute trades
+    pub fn is_trader(&self, signer: &Pubkey) -> bool {
+        *signer == self.authority
+            || (self.keeper != Pubkey::default() && *signer == self.keeper)
+    }
+
+    /// Check if a signer may approve withdrawal compliance
+    /// Any requester may when no compliance authority is set
+    pub fn is_compliance_approver(&self, signer: Option<&Pubkey>) -> bool {
+        self.compliance_authority == Pubkey::default()
+            || signer == Some(&self.compliance_authority)
+    }
+
+    /// Consume an attestation nonce, rejecting replays
+    ///
+    /// Nonces must strictly increase, so each attestation can be used once.
+    /// Always succeeds when `require_attestation_nonce` is off.
+    pub fn consume_attestation_nonce(&mut self, nonce: u64) -> bool {
+        if !self.require_attestation_nonce {
+            return true;
+        }
+        if nonce <= self.last_attestation_nonce {
+            return false;
+        }
+        self.last_attestation_nonce = nonce;
+        true
+    }
+
+    /// Add traded lamports to the lifetime volume
+    ///
+    /// Returns None, leaving the volume unchanged, if it would overflow.
+    pub fn add_volume(&mut self, amount: u64) -> Option<u128> {
+        self.lifetime_volume = self.lifetime_volume.checked_add(amount as u128)?;
+        self.total_volume = self.total_volume.saturating_add(amount);
+        Some(self.lifetime_volume)
+    }
+
+    /// Apply a successful trade to the vault's balance and trading stats
+    ///
+    /// Returns None, leaving the vault unchanged, if the volume would overflow.
+    pub fn record_trade(&mut self, result: &TradeResult, slot: u64) -> Option<()> {
+        self.add_volume(result.amount_traded)?;
+        self.available_balance = self.available_balance
+            .saturating_sub(result.amount_traded);
+        self.total_fees_paid = self.total_fees_paid
+            .saturating_add(result.fees_paid);
+        self.last_trade_slot = slot;
+        Some(())
+    }
+
+    /// Replace the model hash, recording when it changed and bumping the version
+    pub fn set_model(&mut self, model_hash: [u8; 32], timestamp: i64) {
+        self.model_hash = model_hash;
+        self.model_updated_at = timestamp;
+        self.model_version = self.model_version.saturating_add(1);
+    }
+
+    /// Lamports the vault holds outside `available_balance`: the rebate pool,
+    /// the profit reserve and deposits staged during delegation
+    pub fn held_outside_balance(&self) -> u64 {
+        self.fee_rebate_pool
+            .saturating_add(self.profit_reserve)
+            .saturating_add(self.staged_deposits)
+    }
+
+    /// Amount by which `available_balance` and the reserves exceed the lamports
+    /// backing them
+    ///
+    /// Trades and open positions only reduce `available_balance` without moving
+    /// lamports, so backing may exceed it; any shortfall indicates an accounting bug.
+    pub fn balance_shortfall(&self, backing_lamports: u64) -> u64 {
+        self.available_balance
+            .saturating_add(self.held_outside_balance())
+            .saturating_sub(backing_lamports)
+    }
+
+    /// Lower `available_balance` to what the backing covers after the reserves
+    pub fn correct_balance(&mut self, backing_lamports: u64) {
+        self.available_balance = backing_lamports.saturating_sub(self.held_outside_balance());
+    }
+
+    /// Check that `invested_amount` matches `shares * entry_price / PRICE_SCALE`
+    /// to within `position_cost_tolerance_bps` of that notional
+    pub fn is_position_cost_consistent(&self, shares: u64, entry_price: u64, invested_amount: u64) -> bool {
+        let notional = shares as u128 * entry_price as u128 / PRICE_SCALE as u128;
+        let tolerance = notional * self.position_cost_tolerance_bps as u128 / 10_000;
+        (invested_amount as u128).abs_diff(notional) <= tolerance
+    }
+
+    /// Credit a new deposit to the vault's balance
+    ///
+    /// While delegated, the TEE owns the tradable balance, so the deposit is
+    /// held in `staged_deposits` until undelegation. Returns None, leaving
+    /// the vault unchanged, on overflow.
+    pub fn credit_deposit(&mut self, amount: u64) -> Option<()> {
+        if self.is_delegated {
+            self.staged_deposits = self.staged_deposits.checked_add(amount)?;
+        } else {
+            self.available_balance = self.available_balance.checked_add(amount)?;
         }
+        Some(())
+    }
+
+    /// Merge staged deposits into the available balance, returning the amount merged
+    ///
+    /// Returns None, leaving the vault unchanged, on overflow.
+    pub fn settle_staged_deposits(&mut self) -> Option<u64> {
+        let staged = self.staged_deposits;
+        self.available_balance = self.available_balance.checked_add(staged)?;
+        self.staged_deposits = 0;
+        Some(staged)
+    }
+
+    /// Credit an operator top-up to the tradable balance
+    ///
+    /// Tracked in `operator_funded` rather than `total_deposited`, so deposit
+    /// accounting only reflects user deposits. Returns None, leaving the vault
+    /// unchanged, on overflow.
+    pub fn record_operator_top_up(&mut self, amount: u64) -> Option<()> {
+        let available_balance = self.available_balance.checked_add(amount)?;
+        let operator_funded = self.operator_funded.checked_add(amount)?;
+        self.available_balance = available_balance;
+        self.operator_funded = operator_funded;
+        Some(())
+    }
+
+    /// Check if a deposit would push `total_deposited` above the vault's capacity
+    pub fn exceeds_capacity(&self, amount: u64) -> bool {
+        self.max_total_deposited != 0
+            && self.total_deposited.saturating_add(amount) > self.max_total_deposited
+    }
+
+    /// Check if a withdrawal amount is above the single-request cap
+    pub fn exceeds_withdrawal_cap(&self, amount: u64) -> bool {
+        self.max_single_withdrawal != 0 && amount > self.max_single_withdrawal
+    }
+
+    /// Check if a deposit already holds the maximum number of pending requests
+    pub fn at_pending_withdrawal_cap(&self, pending: u32) -> bool {
+        self.max_pending_withdrawals != 0 && pending >= self.max_pending_withdrawals
+    }
+
+    /// Seconds since the vault was created (0 if `now` predates creation)
+    pub fn age_seconds(&self, now: i64) -> i64 {
+        now.saturating_sub(self.created_at).max(0)
+    }
+
+    /// Check if `max_total_deposited` would allow more deposits than the current cap
+    pub fn is_capacity_increase(&self, max_total_deposited: u64) -> bool {
+        self.max_total_deposited != 0
+            && (max_total_deposited == 0 || max_total_deposited > self.max_total_deposited)
+    }
+
+    /// Check if the vault is too young to raise its capacity to `max_total_deposited`
+    pub fn is_too_young_to_raise_capacity(&self, max_total_deposited: u64, now: i64) -> bool {
+        self.min_age_to_raise_capacity_seconds != 0
+            && self.is_capacity_increase(max_total_deposited)
+            && (self.age_seconds(now) as u64) < self.min_age_to_raise_capacity_seconds
+    }
+
+    /// Check if the model is too old to trade on
+    pub fn is_model_stale(&self, now: i64) -> bool {
+        self.model_max_age_seconds != 0
+            && now.saturating_sub(self.model_updated_at)
+                > i64::try_from(self.model_max_age_seconds).unwrap_or(i64::MAX)
     }
 
     /// Check if vault can be delegated
@@ -94,12 +648,106 @@ impl SpectreVault {
     pub fn can_undelegate(&self) -> bool {
         self.is_active && self.is_delegated
     }
+
+    /// Check if trading is allowed at the given unix timestamp
+    ///
+    /// Windows with start > end wrap around midnight (e.g. 22:00-04:00).
+    pub fn is_within_trading_window(&self, unix_timestamp: i64) -> bool {
+        let time_of_day = unix_timestamp.rem_euclid(SECONDS_PER_DAY as i64) as u32;
+        let start = self.trading_window_start;
+        let end = self.trading_window_end;
+
+        if start <= end {
+            time_of_day >= start && time_of_day < end
+        } else {
+            time_of_day >= start || time_of_day < end
+        }
+    }
+}
+
+/// Validate trading window bounds (both within a day, non-empty)
+pub fn is_valid_trading_window(start: u32, end: u32) -> bool {
+    start < SECONDS_PER_DAY && end <= SECONDS_PER_DAY && start != end
+}
+
+/// Protocol-wide settings shared by every vault
+#[account]
+#[derive(InitSpace, Default)]
+pub struct ProtocolConfig {
+    /// Key allowed to change protocol settings
+    pub admin: Pubkey,
+
+    /// Whether `initialize` requires a passing attestation for the vault authority
+    pub require_authority_compliance: bool,
+
+    /// Ranges vault strategy parameters must fall within
+    pub strategy_bounds: StrategyParamBounds,
+
+    /// Bump seed for the protocol config PDA
+    pub bump: u8,
+}
+
+/// Inclusive range a strategy parameter must fall within
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub struct ParamRange {
+    pub min: u64,
+    pub max: u64,
+}
+
+impl ParamRange {
+    /// Range allowing any value
+    pub const UNBOUNDED: Self = Self { min: 0, max: u64::MAX };
+
+    /// Check if `value` lies within the range
+    pub fn contains(&self, value: u64) -> bool {
+        self.min <= value && value <= self.max
+    }
+}
+
+impl Default for ParamRange {
+    fn default() -> Self {
+        Self::UNBOUNDED
+    }
+}
+
+/// Protocol-wide bounds on the strategy parameters vaults may configure
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default, InitSpace)]
+pub struct StrategyParamBounds {
+    pub price_threshold_low: ParamRange,
+    pub price_threshold_high: ParamRange,
+    pub trend_threshold: ParamRange,
+    pub volatility_cap: ParamRange,
+    pub min_volume_for_signal: ParamRange,
+}
+
+impl StrategyParamBounds {
+    /// Check that every range is non-empty
+    pub fn is_valid(&self) -> bool {
+        [
+            self.price_threshold_low,
+            self.price_threshold_high,
+            self.trend_threshold,
+            self.volatility_cap,
+            self.min_volume_for_signal,
+        ]
+        .iter()
+        .all(|range| range.min <= range.max)
+    }
+
+    /// Check if every parameter in `params` is within its range
+    pub fn allows(&self, params: &StrategyParams) -> bool {
+        self.price_threshold_low.contains(params.price_threshold_low as u64)
+            && self.price_threshold_high.contains(params.price_threshold_high as u64)
+            && self.trend_threshold.contains(params.trend_threshold as u64)
+            && self.volatility_cap.contains(params.volatility_cap as u64)
+            && self.min_volume_for_signal.contains(params.min_volume_for_signal)
+    }
 }
 
 /// Strategy configuration stored on-chain
 /// Allows updating strategy parameters without recompiling
 #[account]
-#[derive(InitSpace)]
+#[derive(InitSpace, Default)]
 pub struct StrategyConfig {
     /// Associated vault
     pub vault: Pubkey,
@@ -134,6 +782,58 @@ pub struct StrategyConfig {
     /// Total signals generated
     pub total_signals: u64,
 
+    /// Buy/sell signals generated
+    pub actionable_signals: u64,
+
+    /// Hold signals generated
+    pub hold_signals: u64,
+
+    /// Maximum trades executed per UTC day (0 = unlimited)
+    pub max_trades_per_day: u32,
+
+    /// Trades executed since `trade_day_start`
+    pub trades_today: u32,
+
+    /// Unix timestamp of the start of the current trading day
+    pub trade_day_start: i64,
+
+    /// Whether normal (non-strong) signals wait for manual confirmation
+    pub require_confirmation_for_normal: bool,
+
+    /// Normal signal awaiting `confirm_pending_trade`
+    pub pending_signal: Option<TradeSignal>,
+
+    /// Timestamp when the pending signal was recorded
+    pub pending_signal_at: i64,
+
+    /// Recency-weighted directional lean of recent signals
+    /// (+MAX_SIGNAL_BIAS = all strong buys, -MAX_SIGNAL_BIAS = all strong sells)
+    pub signal_bias: i32,
+
+    /// Signal used when market input is missing data
+    pub default_signal: TradeSignal,
+
+    /// Forced signal per input bucket, consulted before the decision tree
+    pub signal_overrides: [Option<TradeSignal>; NUM_INPUT_BUCKETS],
+
+    /// Market price seen by the last generated signal (scaled by 1000, 0 = none yet)
+    pub last_price: u32,
+
+    /// Largest price move between signals before trading pauses (bps, 0 = disabled)
+    pub max_price_move_bps: u16,
+
+    /// Provided market volume below which inference holds (0 = disabled)
+    pub min_volume_for_signal: u64,
+
+    /// Whether trading fades the model by inverting its signals
+    pub contrarian: bool,
+
+    /// Signals to generate before any are traded on (0 = no warm-up)
+    pub min_signals_before_trading: u64,
+
+    /// Risk-adjusted score a signal must exceed to be traded (scaled by 1000, 0 = disabled)
+    pub min_risk_adjusted_score: u32,
+
     /// Bump seed
     pub bump: u8,
 
@@ -141,10 +841,182 @@ pub struct StrategyConfig {
     pub _reserved: [u8; 32],
 }
 
+impl StrategyConfig {
+    /// Strategy params for inference, built from the stored thresholds
+    pub fn strategy_params(&self) -> StrategyParams {
+        StrategyParams {
+            signal_overrides: self.signal_overrides,
+            ..StrategyParams::new(
+                self.price_threshold_low,
+                self.price_threshold_high,
+                self.trend_threshold,
+                self.volatility_cap,
+            )
+            .with_default_signal(self.default_signal)
+            .with_min_volume(self.min_volume_for_signal)
+        }
+    }
+
+    /// Share of generated signals that were actionable (basis points)
+    pub fn signal_rate_bps(&self) -> u16 {
+        if self.total_signals == 0 {
+            return 0;
+        }
+        (self.actionable_signals as u128 * 10_000 / self.total_signals as u128) as u16
+    }
+
+    /// Client-facing view of the effective strategy
+    pub fn view(&self) -> StrategyConfigView {
+        StrategyConfigView {
+            params: self.strategy_params(),
+            is_active: self.is_active,
+            last_signal: TradeSignal::from_code(self.last_signal),
+            last_signal_at: self.last_signal_at,
+            total_signals: self.total_signals,
+            actionable_signals: self.actionable_signals,
+            hold_signals: self.hold_signals,
+            signal_rate_bps: self.signal_rate_bps(),
+            signal_bias: self.signal_bias,
+            updated_at: self.updated_at,
+        }
+    }
+
+    /// Check if `price` moved more than `max_price_move_bps` away from the
+    /// price seen by the last signal
+    pub fn is_price_move_too_large(&self, price: u32) -> bool {
+        if self.max_price_move_bps == 0 || self.last_price == 0 {
+            return false;
+        }
+        let moved = price.abs_diff(self.last_price) as u64;
+        moved * 10_000 > self.last_price as u64 * self.max_price_move_bps as u64
+    }
+
+    /// Check if the strategy is still warming up
+    ///
+    /// Signals generated during warm-up update the stats and smoothing
+    /// state but are not traded on.
+    pub fn is_warming_up(&self) -> bool {
+        self.total_signals < self.min_signals_before_trading
+    }
+
+    /// Check if `signal` is too weak for the market's volatility to trade
+    pub fn is_below_risk_adjusted_score(&self, signal: TradeSignal, volatility: u32) -> bool {
+        self.min_risk_adjusted_score > 0
+            && risk_adjusted_score(signal, volatility) <= self.min_risk_adjusted_score
+    }
+
+    /// Update signal stats with a newly generated signal
+    pub fn record_signal(&mut self, signal: TradeSignal, timestamp: i64) {
+        self.last_signal = signal.code();
+        self.last_signal_at = timestamp;
+        self.total_signals = self.total_signals.saturating_add(1);
+
+        if signal.is_hold() {
+            self.hold_signals = self.hold_signals.saturating_add(1);
+        } else {
+            self.actionable_signals = self.actionable_signals.saturating_add(1);
+        }
+
+        // Exponentially-weighted lean: strong signals pull twice as hard
+        let weight = match signal {
+            TradeSignal::StrongBuy => MAX_SIGNAL_BIAS,
+            TradeSignal::Buy => MAX_SIGNAL_BIAS / 2,
+            TradeSignal::Hold => 0,
+            TradeSignal::Sell => -MAX_SIGNAL_BIAS / 2,
+            TradeSignal::StrongSell => -MAX_SIGNAL_BIAS,
+        };
+        self.signal_bias += (weight - self.signal_bias) / SIGNAL_BIAS_SMOOTHING;
+    }
+
+    /// Current directional lean: positive leans buy, negative leans sell
+    pub fn signal_bias(&self) -> i32 {
+        self.signal_bias
+    }
+
+    /// Reset the daily trade counter if a new UTC day has started
+    pub fn roll_trade_day(&mut self, unix_timestamp: i64) {
+        let day_start = unix_timestamp - unix_timestamp.rem_euclid(SECONDS_PER_DAY as i64);
+        if day_start != self.trade_day_start {
+            self.trade_day_start = day_start;
+            self.trades_today = 0;
+        }
+    }
+
+    /// Check if another trade is allowed today
+    pub fn can_trade_today(&self) -> bool {
+        self.max_trades_per_day == 0 || self.trades_today < self.max_trades_per_day
+    }
+
+    /// Record an executed trade against today's count
+    pub fn record_trade(&mut self) {
+        self.trades_today = self.trades_today.saturating_add(1);
+    }
+
+    /// Signal to act on for a model output, inverted for contrarian strategies
+    pub fn trading_signal(&self, model_signal: TradeSignal) -> TradeSignal {
+        if self.contrarian {
+            model_signal.invert()
+        } else {
+            model_signal
+        }
+    }
+
+    /// Check if an actionable signal must wait for manual confirmation
+    pub fn requires_confirmation(&self, signal: TradeSignal) -> bool {
+        self.require_confirmation_for_normal && !signal.is_strong() && !signal.is_hold()
+    }
+
+    /// Store a signal for later confirmation, replacing any earlier one
+    pub fn set_pending_signal(&mut self, signal: TradeSignal, timestamp: i64) {
+        self.pending_signal = Some(signal);
+        self.pending_signal_at = timestamp;
+    }
+
+    /// Remove and return the pending signal, if any
+    pub fn take_pending_signal(&mut self) -> Option<TradeSignal> {
+        self.pending_signal_at = 0;
+        self.pending_signal.take()
+    }
+}
+
+/// Effective strategy returned by `get_strategy_config`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct StrategyConfigView {
+    /// Parameters inference currently runs with
+    pub params: StrategyParams,
+
+    /// Whether the strategy is active
+    pub is_active: bool,
+
+    /// Last signal generated (None if no signal yet)
+    pub last_signal: Option<TradeSignal>,
+
+    /// Last signal timestamp
+    pub last_signal_at: i64,
+
+    /// Total signals generated
+    pub total_signals: u64,
+
+    /// Buy/sell signals generated
+    pub actionable_signals: u64,
+
+    /// Hold signals generated
+    pub hold_signals: u64,
+
+    /// Share of signals that were actionable (basis points)
+    pub signal_rate_bps: u16,
+
+    /// Recency-weighted directional lean of recent signals
+    pub signal_bias: i32,
+
+    /// Last time parameters were updated
+    pub updated_at: i64,
+}
+
 /// Individual user deposit with ZK commitment
 /// Links a Privacy Cash commitment to the SPECTRE vault
 #[account]
-#[derive(InitSpace)]
+#[derive(InitSpace, Default)]
 pub struct UserDeposit {
     /// The user's wallet (for administrative purposes only, not linked on-chain)
     pub owner: Pubkey,
@@ -170,6 +1042,18 @@ pub struct UserDeposit {
     /// Associated vault
     pub vault: Pubkey,
 
+    /// Vault rebate index when rebates were last accrued
+    pub rebate_index: u128,
+
+    /// Rebates accrued but not yet claimed (lamports)
+    pub accrued_rebate: u64,
+
+    /// Withdrawal requests against this deposit that have not completed
+    pub pending_withdrawals: u32,
+
+    /// Nonce of the next withdrawal request, part of its PDA seeds
+    pub withdrawal_nonce: u64,
+
     /// Bump seed for this deposit PDA
     pub bump: u8,
 }
@@ -179,12 +1063,176 @@ impl UserDeposit {
     pub fn can_withdraw(&self, amount: u64) -> bool {
         self.is_active && self.amount >= amount
     }
+
+    /// Claim the nonce for a new withdrawal request, so each request
+    /// against this deposit gets its own PDA
+    pub fn next_withdrawal_nonce(&mut self) -> u64 {
+        let nonce = self.withdrawal_nonce;
+        self.withdrawal_nonce = nonce.saturating_add(1);
+        nonce
+    }
+
+    /// Accrue rebates earned by the current amount up to `vault_index`
+    ///
+    /// Must run before `amount` changes so each lamport earns only for the
+    /// time it was deposited.
+    pub fn accrue_rebate(&mut self, vault_index: u128) {
+        let earned = (self.amount as u128)
+            .saturating_mul(vault_index.saturating_sub(self.rebate_index))
+            / REBATE_INDEX_SCALE;
+        self.accrued_rebate = self
+            .accrued_rebate
+            .saturating_add(u64::try_from(earned).unwrap_or(u64::MAX));
+        self.rebate_index = vault_index;
+    }
+
+    /// Accrue and take all unclaimed rebates
+    pub fn take_rebate(&mut self, vault_index: u128) -> u64 {
+        self.accrue_rebate(vault_index);
+        std::mem::take(&mut self.accrued_rebate)
+    }
+
+    /// Client view that leaves out the nullifier and most of the commitment
+    pub fn view(&self) -> DepositInfoView {
+        let mut commitment_fingerprint = [0u8; COMMITMENT_FINGERPRINT_LEN];
+        commitment_fingerprint.copy_from_slice(&self.commitment[..COMMITMENT_FINGERPRINT_LEN]);
+
+        DepositInfoView {
+            amount: self.amount,
+            is_active: self.is_active,
+            created_at: self.created_at,
+            commitment_fingerprint,
+        }
+    }
 }
 
-/// Withdrawal request status
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
-pub enum WithdrawalStatus {
-    /// Request submitted, pending compliance check
+/// Privacy-respecting deposit returned by `get_deposit_info`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DepositInfoView {
+    /// Amount deposited (lamports)
+    pub amount: u64,
+
+    /// Whether this deposit is active (not withdrawn)
+    pub is_active: bool,
+
+    /// Unix timestamp of deposit
+    pub created_at: i64,
+
+    /// Leading bytes of the commitment, enough to tell deposits apart
+    pub commitment_fingerprint: [u8; COMMITMENT_FINGERPRINT_LEN],
+}
+
+/// Receipt returned by `fund_agent`
+/// Lets clients read the recorded deposit without a separate fetch
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DepositReceipt {
+    /// Commitment recorded for the deposit
+    pub commitment: [u8; 32],
+
+    /// Amount deposited (lamports)
+    pub amount: u64,
+
+    /// Vault total deposited after this deposit (lamports)
+    pub vault_total: u64,
+
+    /// Address of the UserDeposit account
+    pub deposit_pubkey: Pubkey,
+}
+
+impl DepositReceipt {
+    /// Build a receipt from the recorded deposit and updated vault
+    pub fn new(deposit_pubkey: Pubkey, deposit: &UserDeposit, vault: &SpectreVault) -> Self {
+        Self {
+            commitment: deposit.commitment,
+            amount: deposit.amount,
+            vault_total: vault.total_deposited,
+            deposit_pubkey,
+        }
+    }
+}
+
+/// A single recorded trade signal
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug, Default)]
+pub struct SignalLogEntry {
+    /// Unix timestamp when the signal was generated
+    pub timestamp: i64,
+
+    /// Signal produced by the strategy
+    pub signal: TradeSignal,
+
+    /// Market price input (scaled by 1000)
+    pub price: u32,
+}
+
+/// Ring buffer of the most recent trade signals for analytics
+#[account]
+#[derive(InitSpace)]
+pub struct SignalLog {
+    /// Associated vault
+    pub vault: Pubkey,
+
+    /// Number of entries retained before the oldest is evicted
+    pub capacity: u8,
+
+    /// Number of valid entries (at most `capacity`)
+    pub len: u8,
+
+    /// Index where the next entry will be written
+    pub head: u8,
+
+    /// Entry storage; only the first `capacity` slots are used
+    pub entries: [SignalLogEntry; MAX_SIGNAL_LOG_ENTRIES],
+
+    /// Bump seed for this PDA
+    pub bump: u8,
+}
+
+impl Default for SignalLog {
+    fn default() -> Self {
+        Self {
+            vault: Pubkey::default(),
+            capacity: 0,
+            len: 0,
+            head: 0,
+            entries: [SignalLogEntry::default(); MAX_SIGNAL_LOG_ENTRIES],
+            bump: 0,
+        }
+    }
+}
+
+impl SignalLog {
+    /// Check if a capacity can be stored in the log
+    pub fn is_valid_capacity(capacity: u8) -> bool {
+        capacity > 0 && capacity as usize <= MAX_SIGNAL_LOG_ENTRIES
+    }
+
+    /// Append an entry, evicting the oldest once the log is full
+    pub fn push(&mut self, entry: SignalLogEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.entries[self.head as usize] = entry;
+        self.head = (self.head + 1) % self.capacity;
+        if self.len < self.capacity {
+            self.len += 1;
+        }
+    }
+
+    /// Retained entries ordered from newest to oldest
+    pub fn newest_first(&self) -> Vec<SignalLogEntry> {
+        (1..=self.len)
+            .map(|offset| {
+                let index = (self.head + self.capacity - offset) % self.capacity;
+                self.entries[index as usize]
+            })
+            .collect()
+    }
+}
+
+/// Withdrawal request status
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum WithdrawalStatus {
+    /// Request submitted, pending compliance check
     Pending,
     /// Compliance check passed, ready for completion
     Approved,
@@ -204,7 +1252,7 @@ impl Default for WithdrawalStatus {
 
 /// Pending withdrawal request
 #[account]
-#[derive(InitSpace)]
+#[derive(InitSpace, Default)]
 pub struct WithdrawalRequest {
     /// User requesting withdrawal
     pub requester: Pubkey,
@@ -236,6 +1284,15 @@ pub struct WithdrawalRequest {
     /// Slot when compliance was verified
     pub compliance_verified_slot: u64,
 
+    /// Whether compliance passed with elevated risk and needs review
+    pub compliance_flagged: bool,
+
+    /// Risk score seen by `verify_withdrawal_compliance`
+    pub verified_risk_score: u8,
+
+    /// Per-deposit request nonce, part of the PDA seeds
+    pub nonce: u64,
+
     /// Bump seed for this withdrawal PDA
     pub bump: u8,
 }
@@ -246,6 +1303,37 @@ impl WithdrawalRequest {
         self.status == WithdrawalStatus::Approved
     }
 
+    /// Check if compliance can be (re-)verified for this request
+    /// Rejected requests may retry with a fresh attestation
+    pub fn can_verify(&self) -> bool {
+        matches!(self.status, WithdrawalStatus::Pending | WithdrawalStatus::Rejected)
+    }
+
+    /// Check if the requester may still cancel this request
+    pub fn can_cancel(&self) -> bool {
+        matches!(
+            self.status,
+            WithdrawalStatus::Pending | WithdrawalStatus::Approved | WithdrawalStatus::Rejected
+        )
+    }
+
+    /// Check if a completion-time `risk_score` rose more than `max_delta`
+    /// above the score this request was approved at (0 = unchecked)
+    ///
+    /// Only approved requests carry a verification-time score to compare.
+    pub fn is_risk_delta_exceeded(&self, risk_score: u8, max_delta: u8) -> bool {
+        max_delta != 0
+            && self.status == WithdrawalStatus::Approved
+            && risk_score.saturating_sub(self.verified_risk_score) > max_delta
+    }
+
+    /// Check if a re-verification comes too soon after the last one
+    pub fn is_verify_throttled(&self, current_slot: u64, min_interval: u64) -> bool {
+        min_interval != 0
+            && self.compliance_verified_slot != 0
+            && current_slot.saturating_sub(self.compliance_verified_slot) < min_interval
+    }
+
     /// Check if the compliance attestation is still fresh
     pub fn is_attestation_fresh(&self, current_slot: u64, max_age: u64) -> bool {
         if self.compliance_verified_slot == 0 {
@@ -255,6 +1343,52 @@ impl WithdrawalRequest {
     }
 }
 
+/// Cached passing compliance result for one withdrawal recipient
+/// Lets repeat withdrawals to the same address skip a fresh attestation
+#[account]
+#[derive(InitSpace, Default)]
+pub struct ComplianceCache {
+    /// Associated vault
+    pub vault: Pubkey,
+
+    /// Recipient the result applies to
+    pub recipient: Pubkey,
+
+    /// Risk score of the cached attestation
+    pub risk_score: u8,
+
+    /// Slot the result was cached at (0 = empty)
+    pub verified_slot: u64,
+
+    /// Unix timestamp after which the entry is ignored (0 = no expiry)
+    pub expires_at: i64,
+
+    /// Bump seed for this PDA
+    pub bump: u8,
+}
+
+impl ComplianceCache {
+    /// Check if the cached result can stand in for an attestation
+    /// Never fresh when empty, expired, or when caching is disabled (`ttl_slots` = 0)
+    pub fn is_fresh(&self, current_slot: u64, ttl_slots: u64, now: i64) -> bool {
+        ttl_slots != 0
+            && self.verified_slot != 0
+            && current_slot.saturating_sub(self.verified_slot) <= ttl_slots
+            && !self.is_expired(now)
+    }
+
+    /// Check if the entry has passed its expiry
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at != 0 && now >= self.expires_at
+    }
+
+    /// Cache a passing compliance result
+    pub fn record(&mut self, risk_score: u8, slot: u64) {
+        self.risk_score = risk_score;
+        self.verified_slot = slot;
+    }
+}
+
 /// Trading side for prediction markets
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
 pub enum Side {
@@ -270,6 +1404,15 @@ impl Default for Side {
     }
 }
 
+impl From<TradeSide> for Side {
+    fn from(side: TradeSide) -> Self {
+        match side {
+            TradeSide::Yes => Side::Yes,
+            TradeSide::No => Side::No,
+        }
+    }
+}
+
 /// Position status
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
 pub enum PositionStatus {
@@ -289,7 +1432,7 @@ impl Default for PositionStatus {
 
 /// Active trading position on a prediction market
 #[account]
-#[derive(InitSpace)]
+#[derive(InitSpace, Default)]
 pub struct Position {
     /// Associated vault
     pub vault: Pubkey,
@@ -309,6 +1452,9 @@ pub struct Position {
     /// Amount invested (lamports)
     pub invested_amount: u64,
 
+    /// Trading fees paid on entry (lamports, included in `invested_amount`)
+    pub entry_fees: u64,
+
     /// Current status
     pub status: PositionStatus,
 
@@ -321,31 +1467,317 @@ pub struct Position {
     /// Exit price (0 if still open)
     pub exit_price: u64,
 
-    /// Realized PnL (0 if still open)
+    /// Realized PnL, accrued by partial decreases while open
     pub realized_pnl: i64,
 
+    /// Currency the position settles in
+    pub settlement_mint: Pubkey,
+
+    /// Price at the last mark-to-market (0 if never marked)
+    pub last_mark_price: u64,
+
+    /// Slot of the last mark-to-market (0 if never marked)
+    pub last_mark_slot: u64,
+
     /// Bump seed for this position PDA
     pub bump: u8,
 }
 
 impl Position {
+    /// Build an open position from an executed trade
+    ///
+    /// The invested amount is the full amount traded (fees included), which is
+    /// what the trade deducted from the vault's available balance.
+    pub fn from_trade_result(
+        vault: Pubkey,
+        market_id: Pubkey,
+        side: TradeSide,
+        result: &TradeResult,
+        opened_at: i64,
+        bump: u8,
+    ) -> Self {
+        Self {
+            vault,
+            market_id,
+            side: side.into(),
+            shares: result.shares_received,
+            entry_price: result.execution_price,
+            invested_amount: result.amount_traded,
+            entry_fees: result.fees_paid,
+            status: PositionStatus::Open,
+            opened_at,
+            closed_at: 0,
+            exit_price: 0,
+            realized_pnl: 0,
+            settlement_mint: NATIVE_SETTLEMENT_MINT,
+            last_mark_price: 0,
+            last_mark_slot: 0,
+            bump,
+        }
+    }
+
+    /// Average cost per share (scaled by PRICE_SCALE), 0 if no shares are held
+    pub fn cost_basis_per_share(&self) -> u64 {
+        if self.shares == 0 {
+            return 0;
+        }
+        let cost = (self.invested_amount as u128)
+            .saturating_mul(PRICE_SCALE as u128)
+            / self.shares as u128;
+        u64::try_from(cost).unwrap_or(u64::MAX)
+    }
+
+    /// PnL at a given exit value before any trading fees
+    ///
+    /// Measured against the invested amount net of entry fees, so
+    /// `gross_pnl - entry_fees - exit_fees` is the net PnL.
+    pub fn gross_pnl(&self, exit_value: u64) -> i64 {
+        let cost = self.invested_amount.saturating_sub(self.entry_fees);
+        (exit_value as i64).saturating_sub(cost as i64)
+    }
+
+    /// Value of the held shares at a given price (lamports)
+    pub fn current_value(&self, price: u64) -> u64 {
+        let value = (self.shares as u128)
+            .saturating_mul(price as u128)
+            .saturating_div(PRICE_SCALE as u128);
+        u64::try_from(value).unwrap_or(u64::MAX)
+    }
+
     /// Calculate unrealized PnL given current price
     pub fn calculate_unrealized_pnl(&self, current_price: u64) -> i64 {
         if self.status != PositionStatus::Open {
             return 0;
         }
 
-        let current_value = (self.shares as u128)
-            .saturating_mul(current_price as u128)
-            .saturating_div(1_000_000) as u64;
+        (self.current_value(current_price) as i64).saturating_sub(self.invested_amount as i64)
+    }
+
+    /// PnL reported at `current_price`: unrealized while open, realized once closed
+    pub fn pnl_at(&self, current_price: u64) -> i64 {
+        if self.status != PositionStatus::Open {
+            return self.realized_pnl;
+        }
+        self.calculate_unrealized_pnl(current_price)
+    }
+
+    /// Record a mark-to-market at `price`, returning the unrealized PnL there
+    ///
+    /// Only the mark fields change; shares and invested amount are untouched.
+    pub fn mark(&mut self, price: u64, slot: u64) -> i64 {
+        self.last_mark_price = price;
+        self.last_mark_slot = slot;
+        self.calculate_unrealized_pnl(price)
+    }
+
+    /// Check if the position has been held for less than `min_holding_seconds`
+    ///
+    /// Forced closes (stop-loss, liquidation) are never held back.
+    pub fn is_too_young(&self, now: i64, min_holding_seconds: u64, forced: bool) -> bool {
+        !forced
+            && min_holding_seconds != 0
+            && now.saturating_sub(self.opened_at)
+                < i64::try_from(min_holding_seconds).unwrap_or(i64::MAX)
+    }
 
-        (current_value as i64).saturating_sub(self.invested_amount as i64)
+    /// Check if the position settles in the given currency
+    pub fn settles_in(&self, mint: &Pubkey) -> bool {
+        self.settlement_mint == *mint
     }
 
     /// Check if the position is profitable at current price
     pub fn is_profitable(&self, current_price: u64) -> bool {
         self.calculate_unrealized_pnl(current_price) > 0
     }
+
+    /// Offset this position against an opposite-side position on the same market
+    ///
+    /// Each matched YES/NO share pair redeems for exactly one lamport regardless
+    /// of outcome, so the matched shares are removed from both positions along
+    /// with their proportional cost basis. Returns the freed capital (lamports),
+    /// or 0 if the positions cannot be netted.
+    pub fn net_against(&mut self, other: &mut Position) -> u64 {
+        if self.market_id != other.market_id
+            || self.side == other.side
+            || self.status != PositionStatus::Open
+            || other.status != PositionStatus::Open
+        {
+            return 0;
+        }
+
+        let matched = self.shares.min(other.shares);
+        self.remove_shares(matched);
+        other.remove_shares(matched);
+        matched
+    }
+
+    /// Sell part of the position at `exit_price` without closing it
+    ///
+    /// The shares leave with their proportional cost basis, and the PnL on
+    /// them accrues to `realized_pnl`. Returns the exit value (lamports) to
+    /// credit back to the vault. Callers must ensure `shares` is less than
+    /// the shares held; full exits go through `close_position`.
+    pub fn decrease(&mut self, shares: u64, exit_price: u64) -> u64 {
+        let invested_before = self.invested_amount;
+        let exit_value = u64::try_from(
+            (shares as u128).saturating_mul(exit_price as u128) / PRICE_SCALE as u128,
+        )
+        .unwrap_or(u64::MAX);
+
+        self.remove_shares(shares);
+        let cost = invested_before.saturating_sub(self.invested_amount);
+        self.realized_pnl = self
+            .realized_pnl
+            .saturating_add((exit_value as i64).saturating_sub(cost as i64));
+        exit_value
+    }
+
+    /// Add a filled trade to the position
+    ///
+    /// The entry price becomes the share-weighted average of the existing
+    /// entry price and the fill's execution price.
+    pub fn add_fill(&mut self, result: &TradeResult) {
+        let shares = self.shares.saturating_add(result.shares_received);
+        if shares > 0 {
+            let weighted = self.entry_price as u128 * self.shares as u128
+                + result.execution_price as u128 * result.shares_received as u128;
+            self.entry_price = (weighted / shares as u128) as u64;
+        }
+        self.shares = shares;
+        self.invested_amount = self.invested_amount.saturating_add(result.amount_traded);
+        self.entry_fees = self.entry_fees.saturating_add(result.fees_paid);
+    }
+
+    /// Remove shares along with their proportional share of the invested amount
+    /// and entry fees
+    fn remove_shares(&mut self, shares: u64) {
+        if self.shares == 0 {
+            return;
+        }
+        let cost = (self.invested_amount as u128 * shares as u128 / self.shares as u128) as u64;
+        self.invested_amount = self.invested_amount.saturating_sub(cost);
+        let fees = (self.entry_fees as u128 * shares as u128 / self.shares as u128) as u64;
+        self.entry_fees = self.entry_fees.saturating_sub(fees);
+        self.shares = self.shares.saturating_sub(shares);
+    }
+}
+
+/// Net directional exposure across open positions on one market
+///
+/// YES and NO shares on the same market hedge each other one-for-one.
+/// Returns the dominant side and its residual shares, or None if the
+/// market has no open exposure or the sides fully offset.
+pub fn net_market_exposure(market_id: &Pubkey, positions: &[Position]) -> Option<(Side, u64)> {
+    let (yes, no) = positions
+        .iter()
+        .filter(|p| p.market_id == *market_id && p.status == PositionStatus::Open)
+        .fold((0u64, 0u64), |(yes, no), p| match p.side {
+            Side::Yes => (yes.saturating_add(p.shares), no),
+            Side::No => (yes, no.saturating_add(p.shares)),
+        });
+
+    match yes.cmp(&no) {
+        std::cmp::Ordering::Greater => Some((Side::Yes, yes - no)),
+        std::cmp::Ordering::Less => Some((Side::No, no - yes)),
+        std::cmp::Ordering::Equal => None,
+    }
+}
+
+/// Per-position and aggregate PnL returned by `batch_get_position_pnl`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PortfolioPnl {
+    /// PnL of each position, in the order the positions were passed
+    pub position_pnls: Vec<i64>,
+
+    /// Sum of `position_pnls`
+    pub total_pnl: i64,
+}
+
+impl PortfolioPnl {
+    /// Total the per-position PnL
+    ///
+    /// Returns None if the total overflows.
+    pub fn from_position_pnls(position_pnls: Vec<i64>) -> Option<Self> {
+        let total_pnl = position_pnls
+            .iter()
+            .try_fold(0i64, |total, pnl| total.checked_add(*pnl))?;
+        Some(Self { position_pnls, total_pnl })
+    }
+}
+
+/// TWAP-style entry into a position, split into equal tranches executed
+/// at most once per interval
+#[account]
+#[derive(InitSpace, Default)]
+pub struct EntrySchedule {
+    /// Associated vault
+    pub vault: Pubkey,
+
+    /// Position the tranches are added to
+    pub position: Pubkey,
+
+    /// Market ID on PNP Exchange
+    pub market_id: Pubkey,
+
+    /// Side every tranche buys
+    pub side: Side,
+
+    /// Total lamports to invest across all tranches
+    pub total_size: u64,
+
+    /// Number of tranches the entry is split into
+    pub num_tranches: u8,
+
+    /// Tranches executed so far
+    pub tranches_executed: u8,
+
+    /// Minimum seconds between tranches
+    pub interval_seconds: u64,
+
+    /// Unix timestamp of the last executed tranche (0 if none yet)
+    pub last_tranche_at: i64,
+
+    /// Bump seed for this schedule PDA
+    pub bump: u8,
+}
+
+impl EntrySchedule {
+    /// Check if every tranche has been executed
+    pub fn is_complete(&self) -> bool {
+        self.tranches_executed >= self.num_tranches
+    }
+
+    /// Check if the next tranche may execute at `now`
+    ///
+    /// The first tranche is due immediately; later ones wait out the interval.
+    pub fn is_tranche_due(&self, now: i64) -> bool {
+        !self.is_complete()
+            && (self.tranches_executed == 0
+                || now.saturating_sub(self.last_tranche_at)
+                    >= i64::try_from(self.interval_seconds).unwrap_or(i64::MAX))
+    }
+
+    /// Lamports to invest in the next tranche
+    ///
+    /// Tranches are equal, with the division remainder added to the last one
+    /// so the tranches sum to `total_size`.
+    pub fn next_tranche_size(&self) -> u64 {
+        if self.is_complete() {
+            return 0;
+        }
+        let base = self.total_size / self.num_tranches as u64;
+        if self.tranches_executed + 1 == self.num_tranches {
+            base + self.total_size % self.num_tranches as u64
+        } else {
+            base
+        }
+    }
+
+    /// Record an executed tranche
+    pub fn record_tranche(&mut self, now: i64) {
+        self.tranches_executed = self.tranches_executed.saturating_add(1);
+        self.last_tranche_at = now;
+    }
 }
 
 #[cfg(test)]
@@ -369,6 +1801,7 @@ mod tests {
             total_deposits_count: 0,
             total_withdrawals_count: 0,
             total_volume: 0,
+            ..Default::default()
         };
 
         assert!(vault.has_sufficient_balance(100_000_000));
@@ -376,6 +1809,32 @@ mod tests {
         assert!(!vault.has_sufficient_balance(600_000_000));
     }
 
+    #[test]
+    fn test_vault_reserved_withdrawal_reduces_tradable() {
+        let mut vault = SpectreVault {
+            available_balance: 1_000_000_000,
+            ..Default::default()
+        };
+        assert_eq!(vault.calculate_position_size(false), 50_000_000);
+
+        // A pending withdrawal of 0.6 SOL leaves 0.4 SOL to trade
+        vault.reserve_withdrawal(600_000_000);
+        assert_eq!(vault.tradable_balance(), 400_000_000);
+        assert_eq!(vault.calculate_position_size(false), 20_000_000);
+        assert!(!vault.has_sufficient_balance(400_000_001));
+
+        // Completing the withdrawal removes both the balance and the reservation
+        vault.available_balance -= 600_000_000;
+        vault.release_withdrawal(600_000_000);
+        assert_eq!(vault.reserved_for_withdrawal, 0);
+        assert_eq!(vault.tradable_balance(), 400_000_000);
+
+        // Reservations above the available balance leave nothing to trade
+        vault.reserve_withdrawal(500_000_000);
+        assert_eq!(vault.tradable_balance(), 0);
+        assert_eq!(vault.calculate_position_size(true), 0);
+    }
+
     #[test]
     fn test_vault_calculate_position_size() {
         let vault = SpectreVault {
@@ -393,6 +1852,7 @@ mod tests {
             total_deposits_count: 0,
             total_withdrawals_count: 0,
             total_volume: 0,
+            ..Default::default()
         };
 
         // Normal signal: 5% = 50_000_000 lamports
@@ -403,75 +1863,1637 @@ mod tests {
     }
 
     #[test]
-    fn test_user_deposit_can_withdraw() {
-        let deposit = UserDeposit {
-            owner: Pubkey::default(),
-            commitment: [0u8; 32],
-            nullifier_hash: [0u8; 32],
-            amount: 100_000_000,
-            delegated: false,
-            created_at: 0,
-            is_active: true,
-            vault: Pubkey::default(),
-            bump: 0,
+    fn test_vault_position_size_below_minimum() {
+        // 5% of 0.015 SOL is below MIN_TRADE_AMOUNT, 10% is not
+        let vault = SpectreVault {
+            available_balance: 15_000_000,
+            ..Default::default()
         };
+        assert_eq!(vault.calculate_position_size(false), 0);
+        assert_eq!(vault.calculate_position_size(true), 1_500_000);
 
-        assert!(deposit.can_withdraw(50_000_000));
-        assert!(deposit.can_withdraw(100_000_000));
-        assert!(!deposit.can_withdraw(150_000_000));
+        // Exactly at the minimum
+        let vault = SpectreVault {
+            available_balance: MIN_TRADE_AMOUNT * 20,
+            ..Default::default()
+        };
+        assert_eq!(vault.calculate_position_size(false), MIN_TRADE_AMOUNT);
     }
 
     #[test]
-    fn test_withdrawal_request_can_complete() {
-        let mut request = WithdrawalRequest {
-            requester: Pubkey::default(),
-            deposit: Pubkey::default(),
-            vault: Pubkey::default(),
-            amount: 100_000_000,
-            recipient: Pubkey::default(),
-            status: WithdrawalStatus::Pending,
-            risk_score: 0,
-            created_at: 0,
-            updated_at: 0,
-            compliance_verified_slot: 0,
-            bump: 0,
+    fn test_vault_performance_sizing() {
+        let mut vault = SpectreVault {
+            available_balance: 1_000_000_000, // 1 SOL
+            performance_sizing: true,
+            ..Default::default()
         };
 
-        assert!(!request.can_complete());
+        // Not enough history: unscaled
+        vault.record_close(10, 0);
+        assert_eq!(vault.win_rate_bps(), None);
+        assert_eq!(vault.calculate_position_size(false), 50_000_000);
 
-        request.status = WithdrawalStatus::Approved;
-        assert!(request.can_complete());
+        // 7 of 10 winners: 1.25x
+        vault.winning_closes = 7;
+        vault.losing_closes = 3;
+        assert_eq!(vault.win_rate_bps(), Some(7_000));
+        let winning_size = vault.calculate_position_size(false);
+        assert_eq!(winning_size, 62_500_000);
+
+        // 3 of 10 winners: 0.75x
+        vault.winning_closes = 3;
+        vault.losing_closes = 7;
+        let losing_size = vault.calculate_position_size(false);
+        assert_eq!(losing_size, 37_500_000);
+        assert!(winning_size > losing_size);
+
+        // Exactly 40-60%: unscaled
+        vault.winning_closes = 5;
+        vault.losing_closes = 5;
+        assert_eq!(vault.calculate_position_size(false), 50_000_000);
 
-        request.status = WithdrawalStatus::Rejected;
-        assert!(!request.can_complete());
+        // Scaled size is bounded by max_position_bps
+        vault.winning_closes = 9;
+        vault.losing_closes = 1;
+        vault.max_position_bps = 1_000; // 10%
+        assert_eq!(vault.calculate_position_size(true), 100_000_000);
+
+        // Disabled: win rate is ignored
+        vault.performance_sizing = false;
+        vault.max_position_bps = 0;
+        assert_eq!(vault.calculate_position_size(false), 50_000_000);
     }
 
     #[test]
-    fn test_position_calculate_pnl() {
-        let position = Position {
-            vault: Pubkey::default(),
-            market_id: Pubkey::default(),
-            side: Side::Yes,
-            shares: 100_000_000, // 100 shares
-            entry_price: 500_000, // 0.5 per share
-            invested_amount: 50_000_000, // 0.05 SOL invested
-            status: PositionStatus::Open,
-            opened_at: 0,
-            closed_at: 0,
-            exit_price: 0,
-            realized_pnl: 0,
-            bump: 0,
+    fn test_vault_record_close() {
+        let mut vault = SpectreVault::default();
+        vault.record_close(100, 10);
+        vault.record_close(-5, 20);
+        vault.record_close(0, 30);
+        assert_eq!(vault.winning_closes, 1);
+        assert_eq!(vault.losing_closes, 1);
+        assert_eq!(vault.last_loss_slot, 20);
+    }
+
+    #[test]
+    fn test_strategy_config_confirmation_for_normal_signals() {
+        let mut config = StrategyConfig::default();
+
+        // Disabled: every actionable signal auto-executes
+        assert!(!config.requires_confirmation(TradeSignal::Buy));
+
+        config.require_confirmation_for_normal = true;
+        assert!(config.requires_confirmation(TradeSignal::Buy));
+        assert!(config.requires_confirmation(TradeSignal::Sell));
+        assert!(!config.requires_confirmation(TradeSignal::StrongBuy));
+        assert!(!config.requires_confirmation(TradeSignal::StrongSell));
+        assert!(!config.requires_confirmation(TradeSignal::Hold));
+
+        // Pending signal is consumed exactly once
+        config.set_pending_signal(TradeSignal::Sell, 1_000);
+        assert_eq!(config.pending_signal_at, 1_000);
+        assert_eq!(config.take_pending_signal(), Some(TradeSignal::Sell));
+        assert_eq!(config.take_pending_signal(), None);
+        assert_eq!(config.pending_signal_at, 0);
+    }
+
+    #[test]
+    fn test_vault_balance_shortfall() {
+        let mut vault = SpectreVault {
+            available_balance: 1_000,
+            ..Default::default()
         };
 
-        // Price went up to 0.7: profit
-        let pnl = position.calculate_unrealized_pnl(700_000);
-        assert_eq!(pnl, 20_000_000); // 0.02 SOL profit
+        // Backing covers the balance (surplus is funds locked in trades)
+        assert_eq!(vault.balance_shortfall(1_000), 0);
+        assert_eq!(vault.balance_shortfall(5_000), 0);
+
+        // Corrupted balance overstating the vault's lamports
+        vault.available_balance = 7_500;
+        assert_eq!(vault.balance_shortfall(5_000), 2_500);
+
+        // Reserves are backed by the same lamports
+        vault.available_balance = 4_000;
+        vault.fee_rebate_pool = 500;
+        vault.profit_reserve = 300;
+        vault.staged_deposits = 200;
+        assert_eq!(vault.balance_shortfall(5_000), 0);
+        assert_eq!(vault.balance_shortfall(4_600), 400);
+
+        // Correcting leaves the reserves intact
+        vault.correct_balance(4_600);
+        assert_eq!(vault.available_balance, 3_600);
+        assert_eq!(vault.balance_shortfall(4_600), 0);
+
+        // Backing below the reserves alone zeroes the balance
+        vault.correct_balance(800);
+        assert_eq!(vault.available_balance, 0);
+    }
 
-        // Price went down to 0.3: loss
-        let pnl = position.calculate_unrealized_pnl(300_000);
-        assert_eq!(pnl, -20_000_000); // 0.02 SOL loss
+    #[test]
+    fn test_vault_exceeds_withdrawal_cap() {
+        let mut vault = SpectreVault::default();
 
-        assert!(position.is_profitable(700_000));
-        assert!(!position.is_profitable(300_000));
+        // No cap by default
+        assert!(!vault.exceeds_withdrawal_cap(u64::MAX));
+
+        vault.max_single_withdrawal = 1_000;
+        assert!(!vault.exceeds_withdrawal_cap(999));
+        assert!(!vault.exceeds_withdrawal_cap(1_000));
+        assert!(vault.exceeds_withdrawal_cap(1_001));
+    }
+
+    #[test]
+    fn test_vault_is_model_stale() {
+        let mut vault = SpectreVault {
+            model_updated_at: 1_000,
+            ..Default::default()
+        };
+
+        // Disabled by default
+        assert!(!vault.is_model_stale(i64::MAX));
+
+        vault.model_max_age_seconds = 3_600;
+        assert!(!vault.is_model_stale(4_600));
+        assert!(vault.is_model_stale(4_601));
+
+        // Re-attesting the model resets the age
+        vault.set_model([1u8; 32], 4_601);
+        assert!(!vault.is_model_stale(4_601));
+    }
+
+    #[test]
+    fn test_vault_is_trader() {
+        let authority = Pubkey::new_unique();
+        let keeper = Pubkey::new_unique();
+        let mut vault = SpectreVault {
+            authority,
+            ..Default::default()
+        };
+
+        // No keeper: authority only
+        assert!(vault.is_trader(&authority));
+        assert!(!vault.is_trader(&keeper));
+        assert!(!vault.is_trader(&Pubkey::default()));
+
+        vault.keeper = keeper;
+        assert!(vault.is_trader(&authority));
+        assert!(vault.is_trader(&keeper));
+        assert!(!vault.is_trader(&Pubkey::new_unique()));
+    }
+
+    #[test]
+    fn test_vault_compliance_approver() {
+        let officer = Pubkey::new_unique();
+        let mut vault = SpectreVault::default();
+
+        // Unset: anyone may approve
+        assert!(vault.is_compliance_approver(None));
+        assert!(vault.is_compliance_approver(Some(&Pubkey::new_unique())));
+
+        // Set: only the compliance authority
+        vault.compliance_authority = officer;
+        assert!(vault.is_compliance_approver(Some(&officer)));
+        assert!(!vault.is_compliance_approver(Some(&Pubkey::new_unique())));
+        assert!(!vault.is_compliance_approver(None));
+    }
+
+    #[test]
+    fn test_vault_compliance_recheck() {
+        let mut vault = SpectreVault {
+            compliance_recheck_interval_slots: 1_000,
+            ..Default::default()
+        };
+
+        // Never attested: blocked once past the interval
+        assert!(!vault.needs_compliance_recheck(500));
+        assert!(vault.needs_compliance_recheck(1_001));
+
+        // Re-attested: allowed until the interval elapses again
+        vault.last_compliance_slot = 5_000;
+        assert!(!vault.needs_compliance_recheck(5_000));
+        assert!(!vault.needs_compliance_recheck(6_000));
+        assert!(vault.needs_compliance_recheck(6_001));
+
+        // Disabled
+        vault.compliance_recheck_interval_slots = 0;
+        assert!(!vault.needs_compliance_recheck(u64::MAX));
+    }
+
+    #[test]
+    fn test_vault_record_trade_accumulates_fees() {
+        let mut vault = SpectreVault {
+            available_balance: 1_000_000_000,
+            ..Default::default()
+        };
+
+        vault.record_trade(&TradeResult::success(100_000_000, 180_000_000, 555_000, 300_000), 10).unwrap();
+        assert_eq!(vault.total_fees_paid, 300_000);
+        assert_eq!(vault.available_balance, 900_000_000);
+        assert_eq!(vault.total_volume, 100_000_000);
+        assert_eq!(vault.last_trade_slot, 10);
+
+        vault.record_trade(&TradeResult::success(50_000_000, 90_000_000, 555_000, 150_000), 12).unwrap();
+        assert_eq!(vault.total_fees_paid, 450_000);
+        assert_eq!(vault.available_balance, 850_000_000);
+        assert_eq!(vault.total_volume, 150_000_000);
+        assert_eq!(vault.lifetime_volume, 150_000_000);
+        assert_eq!(vault.last_trade_slot, 12);
+    }
+
+    #[test]
+    fn test_vault_set_model_increments_version() {
+        let mut vault = SpectreVault::default();
+        assert_eq!(vault.model_version, 0);
+
+        vault.set_model([1u8; 32], 1_000);
+        assert_eq!(vault.model_hash, [1u8; 32]);
+        assert_eq!(vault.model_version, 1);
+        assert_eq!(vault.model_updated_at, 1_000);
+
+        vault.set_model([2u8; 32], 2_000);
+        assert_eq!(vault.model_hash, [2u8; 32]);
+        assert_eq!(vault.model_version, 2);
+        assert_eq!(vault.model_updated_at, 2_000);
+    }
+
+    #[test]
+    fn test_vault_trading_window_always_on() {
+        let vault = SpectreVault {
+            trading_window_start: 0,
+            trading_window_end: SECONDS_PER_DAY,
+            ..Default::default()
+        };
+
+        assert!(vault.is_within_trading_window(0));
+        assert!(vault.is_within_trading_window(43_200));
+        assert!(vault.is_within_trading_window(86_399));
+        assert!(vault.is_within_trading_window(1_700_000_000));
+    }
+
+    #[test]
+    fn test_vault_trading_window_daytime() {
+        // 09:00 - 17:00 UTC
+        let vault = SpectreVault {
+            trading_window_start: 9 * 3600,
+            trading_window_end: 17 * 3600,
+            ..Default::default()
+        };
+        let day = 19_000 * SECONDS_PER_DAY as i64;
+
+        assert!(vault.is_within_trading_window(day + 9 * 3600));
+        assert!(vault.is_within_trading_window(day + 12 * 3600));
+        assert!(!vault.is_within_trading_window(day + 17 * 3600));
+        assert!(!vault.is_within_trading_window(day + 8 * 3600));
+        assert!(!vault.is_within_trading_window(day + 23 * 3600));
+    }
+
+    #[test]
+    fn test_vault_trading_window_wrap_around() {
+        // 22:00 - 04:00 UTC
+        let vault = SpectreVault {
+            trading_window_start: 22 * 3600,
+            trading_window_end: 4 * 3600,
+            ..Default::default()
+        };
+        let day = 19_000 * SECONDS_PER_DAY as i64;
+
+        assert!(vault.is_within_trading_window(day + 23 * 3600));
+        assert!(vault.is_within_trading_window(day));
+        assert!(vault.is_within_trading_window(day + 3 * 3600));
+        assert!(!vault.is_within_trading_window(day + 4 * 3600));
+        assert!(!vault.is_within_trading_window(day + 12 * 3600));
+        assert!(!vault.is_within_trading_window(day + 21 * 3600));
+    }
+
+    #[test]
+    fn test_is_valid_trading_window() {
+        assert!(is_valid_trading_window(0, SECONDS_PER_DAY));
+        assert!(is_valid_trading_window(22 * 3600, 4 * 3600));
+        assert!(!is_valid_trading_window(3600, 3600));
+        assert!(!is_valid_trading_window(SECONDS_PER_DAY, 3600));
+        assert!(!is_valid_trading_window(0, SECONDS_PER_DAY + 1));
+    }
+
+    #[test]
+    fn test_strategy_record_signal_counters() {
+        let mut config = StrategyConfig::default();
+        let signals = [
+            TradeSignal::Hold,
+            TradeSignal::Buy,
+            TradeSignal::Hold,
+            TradeSignal::StrongSell,
+            TradeSignal::Hold,
+            TradeSignal::StrongBuy,
+        ];
+
+        for (i, signal) in signals.iter().enumerate() {
+            config.record_signal(*signal, 1000 + i as i64);
+        }
+
+        assert_eq!(config.total_signals, 6);
+        assert_eq!(config.hold_signals, 3);
+        assert_eq!(config.actionable_signals, 3);
+        assert_eq!(config.last_signal, 1); // StrongBuy
+        assert_eq!(config.last_signal_at, 1005);
+    }
+
+    #[test]
+    fn test_strategy_signal_bias_tracks_recent_lean() {
+        let mut config = StrategyConfig::default();
+        assert_eq!(config.signal_bias(), 0);
+
+        // Buy-heavy sequence leans positive
+        for (i, signal) in [
+            TradeSignal::Buy,
+            TradeSignal::StrongBuy,
+            TradeSignal::Hold,
+            TradeSignal::Sell,
+            TradeSignal::Buy,
+            TradeSignal::StrongBuy,
+        ]
+        .iter()
+        .enumerate()
+        {
+            config.record_signal(*signal, i as i64);
+        }
+        let buy_lean = config.signal_bias();
+        assert!(buy_lean > 0);
+
+        // Recent sells outweigh older buys
+        for i in 0..10 {
+            config.record_signal(TradeSignal::StrongSell, 100 + i);
+        }
+        assert!(config.signal_bias() < 0);
+        assert!(config.signal_bias() >= -MAX_SIGNAL_BIAS);
+
+        // Holds decay the lean back toward neutral
+        let sell_lean = config.signal_bias();
+        config.record_signal(TradeSignal::Hold, 200);
+        assert!(config.signal_bias() > sell_lean);
+    }
+
+    #[test]
+    fn test_strategy_daily_trade_limit() {
+        let mut config = StrategyConfig {
+            max_trades_per_day: 3,
+            ..Default::default()
+        };
+        let day = 19_000 * SECONDS_PER_DAY as i64;
+
+        // Exhaust the daily limit
+        for hour in 0..3 {
+            config.roll_trade_day(day + hour * 3600);
+            assert!(config.can_trade_today());
+            config.record_trade();
+        }
+        config.roll_trade_day(day + 20 * 3600);
+        assert_eq!(config.trades_today, 3);
+        assert!(!config.can_trade_today());
+
+        // Next day resets the counter
+        config.roll_trade_day(day + SECONDS_PER_DAY as i64 + 60);
+        assert_eq!(config.trades_today, 0);
+        assert_eq!(config.trade_day_start, day + SECONDS_PER_DAY as i64);
+        assert!(config.can_trade_today());
+    }
+
+    #[test]
+    fn test_strategy_daily_trade_limit_disabled() {
+        let mut config = StrategyConfig::default();
+
+        for _ in 0..1000 {
+            config.record_trade();
+        }
+        assert!(config.can_trade_today());
+    }
+
+    #[test]
+    fn test_user_deposit_can_withdraw() {
+        let deposit = UserDeposit {
+            owner: Pubkey::default(),
+            commitment: [0u8; 32],
+            nullifier_hash: [0u8; 32],
+            amount: 100_000_000,
+            delegated: false,
+            created_at: 0,
+            is_active: true,
+            vault: Pubkey::default(),
+            rebate_index: 0,
+            accrued_rebate: 0,
+            pending_withdrawals: 0,
+            withdrawal_nonce: 0,
+            bump: 0,
+        };
+
+        assert!(deposit.can_withdraw(50_000_000));
+        assert!(deposit.can_withdraw(100_000_000));
+        assert!(!deposit.can_withdraw(150_000_000));
+    }
+
+    #[test]
+    fn test_deposit_receipt_matches_state() {
+        let deposit_pubkey = Pubkey::new_unique();
+        let deposit = UserDeposit {
+            commitment: [9u8; 32],
+            amount: 250_000_000,
+            is_active: true,
+            ..Default::default()
+        };
+        let vault = SpectreVault {
+            total_deposited: 1_250_000_000,
+            available_balance: 1_250_000_000,
+            ..Default::default()
+        };
+
+        let receipt = DepositReceipt::new(deposit_pubkey, &deposit, &vault);
+
+        assert_eq!(receipt.commitment, deposit.commitment);
+        assert_eq!(receipt.amount, deposit.amount);
+        assert_eq!(receipt.vault_total, vault.total_deposited);
+        assert_eq!(receipt.deposit_pubkey, deposit_pubkey);
+    }
+
+    #[test]
+    fn test_withdrawal_request_can_complete() {
+        let mut request = WithdrawalRequest {
+            requester: Pubkey::default(),
+            deposit: Pubkey::default(),
+            vault: Pubkey::default(),
+            amount: 100_000_000,
+            recipient: Pubkey::default(),
+            status: WithdrawalStatus::Pending,
+            risk_score: 0,
+            created_at: 0,
+            updated_at: 0,
+            compliance_verified_slot: 0,
+            compliance_flagged: false,
+            verified_risk_score: 0,
+            nonce: 0,
+            bump: 0,
+        };
+
+        assert!(!request.can_complete());
+
+        request.status = WithdrawalStatus::Approved;
+        assert!(request.can_complete());
+
+        request.status = WithdrawalStatus::Rejected;
+        assert!(!request.can_complete());
+    }
+
+    #[test]
+    fn test_withdrawal_request_can_verify() {
+        let mut request = WithdrawalRequest {
+            status: WithdrawalStatus::Pending,
+            ..Default::default()
+        };
+        assert!(request.can_verify());
+
+        // A rejected request may retry
+        request.status = WithdrawalStatus::Rejected;
+        assert!(request.can_verify());
+
+        request.status = WithdrawalStatus::Approved;
+        assert!(!request.can_verify());
+
+        request.status = WithdrawalStatus::Completed;
+        assert!(!request.can_verify());
+    }
+
+    #[test]
+    fn test_withdrawal_request_verify_throttle() {
+        let mut request = WithdrawalRequest::default();
+
+        // Never verified: no throttle
+        assert!(!request.is_verify_throttled(100, 10));
+
+        request.compliance_verified_slot = 100;
+        assert!(request.is_verify_throttled(100, 10));
+        assert!(request.is_verify_throttled(109, 10));
+        assert!(!request.is_verify_throttled(110, 10));
+
+        // Disabled
+        assert!(!request.is_verify_throttled(100, 0));
+    }
+
+    #[test]
+    fn test_position_calculate_pnl() {
+        let position = Position {
+            vault: Pubkey::default(),
+            market_id: Pubkey::default(),
+            side: Side::Yes,
+            shares: 100_000_000, // 100 shares
+            entry_price: 500_000, // 0.5 per share
+            invested_amount: 50_000_000, // 0.05 SOL invested
+            entry_fees: 0,
+            status: PositionStatus::Open,
+            opened_at: 0,
+            closed_at: 0,
+            exit_price: 0,
+            realized_pnl: 0,
+            settlement_mint: NATIVE_SETTLEMENT_MINT,
+            last_mark_price: 0,
+            last_mark_slot: 0,
+            bump: 0,
+        };
+
+        // Price went up to 0.7: profit
+        let pnl = position.calculate_unrealized_pnl(700_000);
+        assert_eq!(pnl, 20_000_000); // 0.02 SOL profit
+
+        // Price went down to 0.3: loss
+        let pnl = position.calculate_unrealized_pnl(300_000);
+        assert_eq!(pnl, -20_000_000); // 0.02 SOL loss
+
+        assert!(position.is_profitable(700_000));
+        assert!(!position.is_profitable(300_000));
+    }
+
+    #[test]
+    fn test_position_cost_basis_and_current_value() {
+        let position = Position {
+            shares: 100_000_000, // 100 shares
+            entry_price: 400_000,
+            invested_amount: 40_000_000, // 0.04 SOL invested
+            status: PositionStatus::Open,
+            ..Default::default()
+        };
+
+        assert_eq!(position.cost_basis_per_share(), 400_000);
+        assert_eq!(position.current_value(650_000), 65_000_000);
+
+        for price in [0, 250_000, 400_000, 999_999, PRICE_SCALE] {
+            let expected = position.current_value(price) as i64 - position.invested_amount as i64;
+            assert_eq!(position.calculate_unrealized_pnl(price), expected);
+        }
+
+        // No shares: no cost basis, no value
+        let empty = Position::default();
+        assert_eq!(empty.cost_basis_per_share(), 0);
+        assert_eq!(empty.current_value(PRICE_SCALE), 0);
+    }
+
+    #[test]
+    fn test_position_from_trade_result() {
+        let vault = Pubkey::new_unique();
+        let market_id = Pubkey::new_unique();
+        let result = TradeResult::success(50_000_000, 99_400_000, 500_000, 300_000);
+
+        let position = Position::from_trade_result(vault, market_id, TradeSide::No, &result, 1_000, 7);
+
+        assert_eq!(position.vault, vault);
+        assert_eq!(position.market_id, market_id);
+        assert_eq!(position.side, Side::No);
+        assert_eq!(position.shares, result.shares_received);
+        assert_eq!(position.entry_price, result.execution_price);
+        assert_eq!(position.invested_amount, result.amount_traded);
+        assert_eq!(position.status, PositionStatus::Open);
+        assert_eq!(position.opened_at, 1_000);
+        assert_eq!(position.closed_at, 0);
+        assert_eq!(position.realized_pnl, 0);
+        assert_eq!(position.bump, 7);
+    }
+
+    #[test]
+    fn test_net_market_exposure_and_netting() {
+        let market_id = Pubkey::new_unique();
+        let mut yes = Position {
+            market_id,
+            side: Side::Yes,
+            shares: 1_000,
+            invested_amount: 400,
+            status: PositionStatus::Open,
+            ..Default::default()
+        };
+        let mut no = Position {
+            market_id,
+            side: Side::No,
+            shares: 600,
+            invested_amount: 360,
+            status: PositionStatus::Open,
+            ..Default::default()
+        };
+
+        // Net exposure is the YES residual
+        let positions = [yes.clone(), no.clone()];
+        assert_eq!(net_market_exposure(&market_id, &positions), Some((Side::Yes, 400)));
+        assert_eq!(net_market_exposure(&Pubkey::new_unique(), &positions), None);
+
+        // Netting frees one lamport per matched pair and keeps the residual
+        assert_eq!(yes.net_against(&mut no), 600);
+        assert_eq!(yes.shares, 400);
+        assert_eq!(yes.invested_amount, 160);
+        assert_eq!(no.shares, 0);
+        assert_eq!(no.invested_amount, 0);
+        assert_eq!(net_market_exposure(&market_id, &[yes.clone(), no.clone()]), Some((Side::Yes, 400)));
+
+        // Same side or different markets cannot be netted
+        let mut other_yes = Position { shares: 100, ..yes };
+        assert_eq!(yes.net_against(&mut other_yes), 0);
+        let mut elsewhere = Position { market_id: Pubkey::new_unique(), side: Side::No, ..yes };
+        assert_eq!(yes.net_against(&mut elsewhere), 0);
+    }
+
+    #[test]
+    fn test_signal_log_keeps_newest_entries() {
+        let mut log = SignalLog {
+            capacity: 4,
+            ..Default::default()
+        };
+        assert!(log.newest_first().is_empty());
+
+        // N + 3 signals into a log of capacity N
+        for i in 0..7u32 {
+            log.push(SignalLogEntry {
+                timestamp: i as i64,
+                signal: TradeSignal::Buy,
+                price: 300 + i,
+            });
+        }
+
+        assert_eq!(log.len, 4);
+        let timestamps: Vec<i64> = log.newest_first().iter().map(|e| e.timestamp).collect();
+        assert_eq!(timestamps, vec![6, 5, 4, 3]);
+        assert_eq!(log.newest_first()[0].price, 306);
+    }
+
+    #[test]
+    fn test_signal_log_partial_fill_and_capacity() {
+        let mut log = SignalLog {
+            capacity: 8,
+            ..Default::default()
+        };
+        log.push(SignalLogEntry { timestamp: 1, signal: TradeSignal::Sell, price: 700 });
+        log.push(SignalLogEntry { timestamp: 2, signal: TradeSignal::Hold, price: 500 });
+
+        let entries = log.newest_first();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].signal, TradeSignal::Hold);
+        assert_eq!(entries[1].signal, TradeSignal::Sell);
+
+        assert!(!SignalLog::is_valid_capacity(0));
+        assert!(SignalLog::is_valid_capacity(MAX_SIGNAL_LOG_ENTRIES as u8));
+        assert!(!SignalLog::is_valid_capacity(MAX_SIGNAL_LOG_ENTRIES as u8 + 1));
+    }
+
+    #[test]
+    fn test_vault_consume_attestation_nonce() {
+        // Not required: any nonce (including reuse) is accepted
+        let mut vault = SpectreVault::default();
+        assert!(vault.consume_attestation_nonce(0));
+        assert!(vault.consume_attestation_nonce(0));
+        assert_eq!(vault.last_attestation_nonce, 0);
+
+        vault.require_attestation_nonce = true;
+        assert!(vault.consume_attestation_nonce(5));
+        assert_eq!(vault.last_attestation_nonce, 5);
+
+        // Replayed and older nonces are rejected without changing state
+        assert!(!vault.consume_attestation_nonce(5));
+        assert!(!vault.consume_attestation_nonce(3));
+        assert_eq!(vault.last_attestation_nonce, 5);
+
+        assert!(vault.consume_attestation_nonce(6));
+    }
+
+    #[test]
+    fn test_position_gross_pnl_reconciles_with_fees() {
+        let result = TradeResult::success(50_000_000, 100_000_000, 500_000, 150_000);
+        let position = Position::from_trade_result(
+            Pubkey::default(),
+            Pubkey::default(),
+            TradeSide::Yes,
+            &result,
+            0,
+            0,
+        );
+        assert_eq!(position.entry_fees, 150_000);
+
+        let exit_value = position.current_value(700_000);
+        let exit_fees = 0;
+        let gross = position.gross_pnl(exit_value);
+        let net = gross - position.entry_fees as i64 - exit_fees;
+
+        assert_eq!(gross, 20_150_000);
+        assert_eq!(net, position.calculate_unrealized_pnl(700_000));
+
+        // Netting removes entry fees with the shares
+        let mut half = position.clone();
+        half.remove_shares(50_000_000);
+        assert_eq!(half.entry_fees, 75_000);
+    }
+
+    #[test]
+    fn test_position_min_holding_period() {
+        let position = Position {
+            opened_at: 1_000,
+            status: PositionStatus::Open,
+            ..Default::default()
+        };
+
+        // Closing before the period has elapsed is blocked
+        assert!(position.is_too_young(1_000, 60, false));
+        assert!(position.is_too_young(1_059, 60, false));
+        assert!(!position.is_too_young(1_060, 60, false));
+
+        // Forced closes and a disabled minimum are never blocked
+        assert!(!position.is_too_young(1_000, 60, true));
+        assert!(!position.is_too_young(1_000, 0, false));
+    }
+
+    #[test]
+    fn test_vault_exceeds_capacity() {
+        let mut vault = SpectreVault {
+            total_deposited: 800_000_000,
+            ..Default::default()
+        };
+
+        // Unlimited by default
+        assert!(!vault.exceeds_capacity(u64::MAX));
+
+        vault.max_total_deposited = 1_000_000_000;
+        assert!(!vault.exceeds_capacity(200_000_000));
+        assert!(vault.exceeds_capacity(200_000_001));
+    }
+
+    #[test]
+    fn test_compliance_cache_ttl() {
+        let mut cache = ComplianceCache::default();
+
+        // Empty cache never hits
+        assert!(!cache.is_fresh(100, 50, 0));
+
+        cache.record(12, 100);
+        assert_eq!(cache.risk_score, 12);
+
+        // Hit within TTL, miss after expiry
+        assert!(cache.is_fresh(100, 50, 0));
+        assert!(cache.is_fresh(150, 50, 0));
+        assert!(!cache.is_fresh(151, 50, 0));
+
+        // Disabled TTL never hits
+        assert!(!cache.is_fresh(100, 0, 0));
+    }
+
+    #[test]
+    fn test_strategy_config_carries_signal_overrides() {
+        let mut signal_overrides = [None; NUM_INPUT_BUCKETS];
+        signal_overrides[2] = Some(TradeSignal::Hold);
+        let mut config = StrategyConfig {
+            price_threshold_low: 350,
+            price_threshold_high: 650,
+            trend_threshold: 100,
+            volatility_cap: 400,
+            signal_overrides,
+            ..Default::default()
+        };
+
+        let params = config.strategy_params();
+        assert_eq!(params.signal_overrides[2], Some(TradeSignal::Hold));
+        assert_eq!(params.signal_overrides.iter().filter(|o| o.is_some()).count(), 1);
+        assert_eq!(params.min_volume_for_signal, 0);
+
+        config.min_volume_for_signal = 5_000;
+        assert_eq!(config.strategy_params().min_volume_for_signal, 5_000);
+    }
+
+    #[test]
+    fn test_position_decrease_by_thirty_percent() {
+        let mut position = Position {
+            shares: 100_000_000,
+            entry_price: 500_000,
+            invested_amount: 50_000_000,
+            entry_fees: 1_000_000,
+            status: PositionStatus::Open,
+            ..Default::default()
+        };
+
+        // Sell 30% at 0.60
+        let exit_value = position.decrease(30_000_000, 600_000);
+        assert_eq!(exit_value, 18_000_000);
+        assert_eq!(position.shares, 70_000_000);
+        assert_eq!(position.invested_amount, 35_000_000);
+        assert_eq!(position.entry_fees, 700_000);
+        // 18M exit value against 15M of cost basis
+        assert_eq!(position.realized_pnl, 3_000_000);
+        assert_eq!(position.status, PositionStatus::Open);
+
+        // A losing decrease accrues negative PnL
+        let exit_value = position.decrease(35_000_000, 400_000);
+        assert_eq!(exit_value, 14_000_000);
+        assert_eq!(position.shares, 35_000_000);
+        assert_eq!(position.realized_pnl, 3_000_000 - 3_500_000);
+    }
+
+    #[test]
+    fn test_strategy_config_view_decodes_last_signal() {
+        let mut config = StrategyConfig::default();
+        let view = config.view();
+        assert_eq!(view.last_signal, None);
+        assert_eq!(view.signal_rate_bps, 0);
+
+        config.record_signal(TradeSignal::Buy, 100);
+        config.record_signal(TradeSignal::Hold, 101);
+        config.record_signal(TradeSignal::Hold, 102);
+        config.record_signal(TradeSignal::StrongSell, 103);
+
+        let view = config.view();
+        assert_eq!(view.last_signal, Some(TradeSignal::StrongSell));
+        assert_eq!(view.last_signal_at, 103);
+        assert_eq!(view.total_signals, 4);
+        assert_eq!(view.signal_rate_bps, 5_000);
+    }
+
+    #[test]
+    fn test_vault_volume_counts_past_u64_max() {
+        let mut vault = SpectreVault {
+            total_volume: u64::MAX - 10,
+            lifetime_volume: u64::MAX as u128 - 10,
+            ..Default::default()
+        };
+
+        // The u64 counter saturates here; the lifetime counter keeps going
+        assert_eq!(vault.add_volume(100), Some(u64::MAX as u128 + 90));
+        assert_eq!(vault.add_volume(u64::MAX), Some(2 * u64::MAX as u128 + 90));
+        assert_eq!(vault.total_volume, u64::MAX);
+        assert!(vault.lifetime_volume > u64::MAX as u128);
+
+        // Only the u128 ceiling is an error, and it leaves both counters untouched
+        vault.lifetime_volume = u128::MAX - 1;
+        assert_eq!(vault.add_volume(2), None);
+        assert_eq!(vault.lifetime_volume, u128::MAX - 1);
+        assert_eq!(vault.total_volume, u64::MAX);
+    }
+
+    #[test]
+    fn test_strategy_price_move_circuit_breaker() {
+        let mut config = StrategyConfig {
+            max_price_move_bps: 1_000, // 10%
+            ..Default::default()
+        };
+
+        // No reference price yet
+        assert!(!config.is_price_move_too_large(500));
+
+        config.last_price = 500;
+        // 30% jump in either direction trips the breaker
+        assert!(config.is_price_move_too_large(650));
+        assert!(config.is_price_move_too_large(350));
+        // Exactly 10% is allowed
+        assert!(!config.is_price_move_too_large(550));
+        assert!(!config.is_price_move_too_large(450));
+
+        // Disabled breaker never trips
+        config.max_price_move_bps = 0;
+        assert!(!config.is_price_move_too_large(650));
+    }
+
+    #[test]
+    fn test_position_settlement_currency() {
+        let vault = SpectreVault::default();
+        let result = TradeResult::success(50_000_000, 99_400_000, 500_000, 300_000);
+        let position = Position::from_trade_result(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            TradeSide::Yes,
+            &result,
+            0,
+            0,
+        );
+
+        // Defaults to native SOL, matching a fresh vault
+        assert_eq!(vault.settlement_mint, NATIVE_SETTLEMENT_MINT);
+        assert!(position.settles_in(&vault.settlement_mint));
+
+        let other_mint = Pubkey::new_unique();
+        assert!(!position.settles_in(&other_mint));
+    }
+
+    #[test]
+    fn test_fee_rebates_split_by_deposit_amount() {
+        let mut vault = SpectreVault {
+            available_balance: 4_000_000_000,
+            active_deposit_total: 4_000_000_000,
+            ..Default::default()
+        };
+        let mut small = UserDeposit {
+            amount: 1_000_000_000,
+            is_active: true,
+            ..Default::default()
+        };
+        let mut large = UserDeposit {
+            amount: 3_000_000_000,
+            is_active: true,
+            ..Default::default()
+        };
+
+        assert!(vault.fund_rebate_pool(40_000_000));
+        assert_eq!(vault.fee_rebate_pool, 40_000_000);
+        assert_eq!(vault.available_balance, 3_960_000_000);
+
+        // 1:3 deposits claim 1:3 of the pool
+        assert_eq!(small.take_rebate(vault.rebate_index), 10_000_000);
+        assert_eq!(large.take_rebate(vault.rebate_index), 30_000_000);
+
+        // Nothing more until the pool is funded again
+        assert_eq!(small.take_rebate(vault.rebate_index), 0);
+    }
+
+    #[test]
+    fn test_fee_rebate_accrual_tracks_amount_changes() {
+        let mut vault = SpectreVault {
+            available_balance: 2_000_000_000,
+            active_deposit_total: 2_000_000_000,
+            ..Default::default()
+        };
+        let mut deposit = UserDeposit {
+            amount: 1_000_000_000,
+            is_active: true,
+            ..Default::default()
+        };
+
+        assert!(vault.fund_rebate_pool(20_000_000));
+
+        // Accrue at the old amount before withdrawing half
+        deposit.accrue_rebate(vault.rebate_index);
+        deposit.amount = 500_000_000;
+        vault.active_deposit_total = 1_500_000_000;
+
+        assert!(vault.fund_rebate_pool(15_000_000));
+        // 10M from the first round + 5M (1/3 of 15M) from the second
+        assert_eq!(deposit.take_rebate(vault.rebate_index), 15_000_000);
+
+        // Nothing to share with, or nothing to share
+        vault.active_deposit_total = 0;
+        assert!(!vault.fund_rebate_pool(1));
+        vault.active_deposit_total = 1;
+        assert!(!vault.fund_rebate_pool(u64::MAX));
+    }
+
+    #[test]
+    fn test_vault_loss_cooldown() {
+        let mut vault = SpectreVault {
+            loss_cooldown_slots: 100,
+            ..Default::default()
+        };
+
+        // No loss yet
+        assert!(!vault.is_in_loss_cooldown(1_000));
+
+        // Winning closes don't start a cooldown
+        vault.record_close(50, 1_000);
+        assert!(!vault.is_in_loss_cooldown(1_001));
+
+        vault.record_close(-50, 1_000);
+        assert!(vault.is_in_loss_cooldown(1_000));
+        assert!(vault.is_in_loss_cooldown(1_099));
+        assert!(!vault.is_in_loss_cooldown(1_100));
+
+        // Disabled
+        vault.loss_cooldown_slots = 0;
+        assert!(!vault.is_in_loss_cooldown(1_000));
+    }
+
+    #[test]
+    fn test_deposit_info_view_omits_nullifier() {
+        let mut commitment = [0u8; 32];
+        for (i, byte) in commitment.iter_mut().enumerate() {
+            *byte = i as u8 + 1;
+        }
+        let deposit = UserDeposit {
+            commitment,
+            nullifier_hash: [0xAB; 32],
+            amount: 1_000_000_000,
+            created_at: 1_700_000_000,
+            is_active: true,
+            ..Default::default()
+        };
+
+        // Exhaustive destructure: the view carries no nullifier or full commitment
+        let DepositInfoView {
+            amount,
+            is_active,
+            created_at,
+            commitment_fingerprint,
+        } = deposit.view();
+        assert_eq!(amount, 1_000_000_000);
+        assert!(is_active);
+        assert_eq!(created_at, 1_700_000_000);
+        assert_eq!(commitment_fingerprint, [1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn test_vault_delegation_staleness() {
+        let mut vault = SpectreVault {
+            last_trade_slot: 1_000,
+            max_delegation_idle_slots: 500,
+            ..Default::default()
+        };
+
+        assert!(!vault.is_delegation_stale(1_000));
+        assert!(!vault.is_delegation_stale(1_500));
+        assert!(vault.is_delegation_stale(1_501));
+
+        // A trade resets the idle clock
+        vault.last_trade_slot = 1_400;
+        assert!(!vault.is_delegation_stale(1_501));
+
+        // Disabled
+        vault.max_delegation_idle_slots = 0;
+        assert!(!vault.is_delegation_stale(u64::MAX));
+    }
+
+    #[test]
+    fn test_fresh_delegation_is_not_stale() {
+        // Never traded, delegated long after creation
+        let vault = SpectreVault {
+            last_trade_slot: 0,
+            delegated_slot: 10_000,
+            max_delegation_idle_slots: 500,
+            ..Default::default()
+        };
+
+        assert_eq!(vault.delegation_idle_slots(10_400), 400);
+        assert!(!vault.is_delegation_stale(10_500));
+        assert!(vault.is_delegation_stale(10_501));
+    }
+
+    #[test]
+    fn test_portfolio_pnl_totals_positions() {
+        let winner = Position {
+            shares: 100_000_000,
+            invested_amount: 50_000_000,
+            status: PositionStatus::Open,
+            ..Default::default()
+        };
+        let loser = Position {
+            shares: 200_000_000,
+            invested_amount: 80_000_000,
+            status: PositionStatus::Open,
+            ..Default::default()
+        };
+        let closed = Position {
+            shares: 0,
+            realized_pnl: 5_000_000,
+            status: PositionStatus::Closed,
+            ..Default::default()
+        };
+
+        let pnls = vec![
+            winner.pnl_at(700_000), // 70M - 50M
+            loser.pnl_at(300_000),  // 60M - 80M
+            closed.pnl_at(900_000), // realized, price ignored
+        ];
+        let portfolio = PortfolioPnl::from_position_pnls(pnls).unwrap();
+        assert_eq!(portfolio.position_pnls, vec![20_000_000, -20_000_000, 5_000_000]);
+        assert_eq!(portfolio.total_pnl, 5_000_000);
+
+        assert_eq!(PortfolioPnl::from_position_pnls(vec![]).unwrap().total_pnl, 0);
+        assert!(PortfolioPnl::from_position_pnls(vec![i64::MAX, 1]).is_none());
+    }
+
+    #[test]
+    fn test_vault_total_exposure_cap() {
+        let mut vault = SpectreVault {
+            total_deposited: 10_000_000_000,
+            max_position_bps: 2_500, // 25% per position
+            max_total_exposure_bps: 5_000, // 50% across positions
+            ..Default::default()
+        };
+
+        // Two 2 SOL positions fit under the 5 SOL cap
+        assert!(!vault.exceeds_exposure_cap(2_000_000_000));
+        vault.total_open_exposure = 4_000_000_000;
+
+        // A third is within the per-position limit but breaches the aggregate cap
+        assert!(!vault.exceeds_exposure_cap(1_000_000_000));
+        assert!(vault.exceeds_exposure_cap(2_000_000_000));
+
+        // Closing a position frees room again
+        vault.total_open_exposure = 2_000_000_000;
+        assert!(!vault.exceeds_exposure_cap(2_000_000_000));
+
+        // No cap
+        vault.max_total_exposure_bps = 0;
+        assert!(!vault.exceeds_exposure_cap(u64::MAX));
+    }
+
+    #[test]
+    fn test_compliance_cache_expiry() {
+        let mut cache = ComplianceCache::default();
+        cache.record(12, 100);
+
+        // No expiry set: only the TTL applies
+        assert!(!cache.is_expired(i64::MAX));
+        assert!(cache.is_fresh(120, 50, 1_700_000_000));
+
+        // An expired entry is ignored even within the TTL
+        cache.expires_at = 1_700_000_000;
+        assert!(cache.is_fresh(120, 50, 1_699_999_999));
+        assert!(cache.is_expired(1_700_000_000));
+        assert!(!cache.is_fresh(120, 50, 1_700_000_000));
+
+        // Refreshing the expiry restores the entry
+        cache.expires_at = 1_700_086_400;
+        assert!(cache.is_fresh(120, 50, 1_700_000_000));
+    }
+
+    #[test]
+    fn test_contrarian_config_inverts_signals() {
+        let mut config = StrategyConfig::default();
+        assert_eq!(config.trading_signal(TradeSignal::Buy), TradeSignal::Buy);
+
+        config.contrarian = true;
+        assert_eq!(config.trading_signal(TradeSignal::Buy), TradeSignal::Sell);
+        assert_eq!(config.trading_signal(TradeSignal::StrongSell), TradeSignal::StrongBuy);
+        assert_eq!(config.trading_signal(TradeSignal::Hold), TradeSignal::Hold);
+    }
+
+    #[test]
+    fn test_vault_recipient_rent_top_up() {
+        let rent_exempt = 890_880;
+        let mut vault = SpectreVault::default();
+
+        // Disabled
+        assert_eq!(vault.recipient_rent_top_up(0, 100_000, rent_exempt), 0);
+
+        vault.cover_recipient_rent = true;
+
+        // A fresh recipient is topped up to the rent-exempt minimum
+        assert_eq!(vault.recipient_rent_top_up(0, 100_000, rent_exempt), 790_880);
+
+        // Payouts already above the minimum need nothing extra
+        assert_eq!(vault.recipient_rent_top_up(0, 1_000_000, rent_exempt), 0);
+
+        // Existing accounts are never topped up
+        assert_eq!(vault.recipient_rent_top_up(1, 100_000, rent_exempt), 0);
+    }
+
+    #[test]
+    fn test_vault_operator_top_up() {
+        let mut vault = SpectreVault {
+            total_deposited: 1_000_000_000,
+            available_balance: 1_000_000_000,
+            ..Default::default()
+        };
+
+        assert_eq!(vault.record_operator_top_up(500_000_000), Some(()));
+        assert_eq!(vault.available_balance, 1_500_000_000);
+        assert_eq!(vault.tradable_balance(), 1_500_000_000);
+        assert_eq!(vault.operator_funded, 500_000_000);
+        // User deposit accounting is untouched
+        assert_eq!(vault.total_deposited, 1_000_000_000);
+
+        // Overflow leaves the vault unchanged
+        assert_eq!(vault.record_operator_top_up(u64::MAX), None);
+        assert_eq!(vault.available_balance, 1_500_000_000);
+        assert_eq!(vault.operator_funded, 500_000_000);
+    }
+
+    #[test]
+    fn test_strategy_warm_up() {
+        let mut config = StrategyConfig::default();
+        assert!(!config.is_warming_up());
+
+        config.min_signals_before_trading = 3;
+        assert!(config.is_warming_up());
+
+        config.record_signal(TradeSignal::Buy, 1);
+        config.record_signal(TradeSignal::Hold, 2);
+        assert!(config.is_warming_up());
+
+        // The third signal completes the warm-up and may be traded
+        config.record_signal(TradeSignal::Buy, 3);
+        assert!(!config.is_warming_up());
+    }
+
+    #[test]
+    fn test_vault_pending_withdrawal_cap() {
+        let mut vault = SpectreVault::default();
+
+        // No cap
+        assert!(!vault.at_pending_withdrawal_cap(u32::MAX));
+
+        vault.max_pending_withdrawals = 2;
+        let mut deposit = UserDeposit::default();
+        assert!(!vault.at_pending_withdrawal_cap(deposit.pending_withdrawals));
+
+        deposit.pending_withdrawals += 1;
+        assert!(!vault.at_pending_withdrawal_cap(deposit.pending_withdrawals));
+
+        // Cap exhausted until a request completes
+        deposit.pending_withdrawals += 1;
+        assert!(vault.at_pending_withdrawal_cap(deposit.pending_withdrawals));
+
+        deposit.pending_withdrawals -= 1;
+        assert!(!vault.at_pending_withdrawal_cap(deposit.pending_withdrawals));
+    }
+
+    #[test]
+    fn test_user_deposit_withdrawal_nonce() {
+        let mut deposit = UserDeposit::default();
+
+        // Each request claims the next nonce
+        assert_eq!(deposit.next_withdrawal_nonce(), 0);
+        assert_eq!(deposit.next_withdrawal_nonce(), 1);
+        assert_eq!(deposit.withdrawal_nonce, 2);
+    }
+
+    #[test]
+    fn test_withdrawal_request_can_cancel() {
+        let mut request = WithdrawalRequest::default();
+        assert!(request.can_cancel());
+
+        request.status = WithdrawalStatus::Approved;
+        assert!(request.can_cancel());
+        request.status = WithdrawalStatus::Rejected;
+        assert!(request.can_cancel());
+
+        // Finished requests stay as they are
+        request.status = WithdrawalStatus::Completed;
+        assert!(!request.can_cancel());
+        request.status = WithdrawalStatus::Cancelled;
+        assert!(!request.can_cancel());
+    }
+
+    #[test]
+    fn test_position_mark() {
+        let mut position = Position {
+            shares: 100_000_000,
+            invested_amount: 50_000_000,
+            status: PositionStatus::Open,
+            ..Default::default()
+        };
+
+        assert_eq!(position.mark(700_000, 42), 20_000_000);
+        assert_eq!(position.last_mark_price, 700_000);
+        assert_eq!(position.last_mark_slot, 42);
+        assert_eq!(position.shares, 100_000_000);
+        assert_eq!(position.invested_amount, 50_000_000);
+
+        // A later mark replaces the earlier one
+        assert_eq!(position.mark(400_000, 50), -10_000_000);
+        assert_eq!(position.last_mark_price, 400_000);
+        assert_eq!(position.last_mark_slot, 50);
+    }
+
+    #[test]
+    fn test_vault_stages_deposits_while_delegated() {
+        let mut vault = SpectreVault {
+            available_balance: 1_000,
+            ..Default::default()
+        };
+
+        vault.credit_deposit(500).unwrap();
+        assert_eq!(vault.available_balance, 1_500);
+        assert_eq!(vault.staged_deposits, 0);
+
+        // Delegated deposits wait outside the TEE-controlled balance
+        vault.is_delegated = true;
+        vault.credit_deposit(200).unwrap();
+        vault.credit_deposit(300).unwrap();
+        assert_eq!(vault.available_balance, 1_500);
+        assert_eq!(vault.staged_deposits, 500);
+
+        // Undelegation merges them
+        assert_eq!(vault.settle_staged_deposits(), Some(500));
+        assert_eq!(vault.available_balance, 2_000);
+        assert_eq!(vault.staged_deposits, 0);
+        assert_eq!(vault.settle_staged_deposits(), Some(0));
+
+        // Overflow leaves the vault unchanged
+        vault.staged_deposits = u64::MAX;
+        assert_eq!(vault.settle_staged_deposits(), None);
+        assert_eq!(vault.available_balance, 2_000);
+        assert_eq!(vault.staged_deposits, u64::MAX);
+    }
+
+    #[test]
+    fn test_vault_position_cost_consistency() {
+        let mut vault = SpectreVault {
+            position_cost_tolerance_bps: DEFAULT_POSITION_COST_TOLERANCE_BPS,
+            ..Default::default()
+        };
+
+        // 100 shares at 0.5 = 0.05 SOL
+        assert!(vault.is_position_cost_consistent(100_000_000, 500_000, 50_000_000));
+
+        // Fees within the 2% tolerance either way
+        assert!(vault.is_position_cost_consistent(100_000_000, 500_000, 51_000_000));
+        assert!(vault.is_position_cost_consistent(100_000_000, 500_000, 49_000_000));
+        assert!(!vault.is_position_cost_consistent(100_000_000, 500_000, 51_000_001));
+
+        // Claiming to invest half the shares' worth
+        assert!(!vault.is_position_cost_consistent(200_000_000, 500_000, 50_000_000));
+
+        // Zero tolerance requires an exact match
+        vault.position_cost_tolerance_bps = 0;
+        assert!(vault.is_position_cost_consistent(100_000_000, 500_000, 50_000_000));
+        assert!(!vault.is_position_cost_consistent(100_000_000, 500_000, 50_000_001));
+    }
+
+    #[test]
+    fn test_entry_schedule_tranches() {
+        let mut schedule = EntrySchedule {
+            total_size: 100_000_001,
+            num_tranches: 3,
+            interval_seconds: 60,
+            ..Default::default()
+        };
+
+        // The first tranche is due immediately
+        assert!(schedule.is_tranche_due(1_000));
+        assert_eq!(schedule.next_tranche_size(), 33_333_333);
+        schedule.record_tranche(1_000);
+
+        // Later tranches wait out the interval
+        assert!(!schedule.is_tranche_due(1_059));
+        assert!(schedule.is_tranche_due(1_060));
+        assert_eq!(schedule.next_tranche_size(), 33_333_333);
+        schedule.record_tranche(1_060);
+
+        // The last tranche takes the remainder
+        assert_eq!(schedule.next_tranche_size(), 33_333_335);
+        schedule.record_tranche(1_120);
+
+        assert!(schedule.is_complete());
+        assert!(!schedule.is_tranche_due(i64::MAX));
+        assert_eq!(schedule.next_tranche_size(), 0);
+    }
+
+    #[test]
+    fn test_position_add_fill_averages_entry_price() {
+        let mut position = Position {
+            status: PositionStatus::Open,
+            ..Default::default()
+        };
+
+        position.add_fill(&TradeResult {
+            success: true,
+            amount_traded: 50_000_000,
+            shares_received: 100_000_000,
+            execution_price: 500_000,
+            fees_paid: 150_000,
+        });
+        assert_eq!(position.entry_price, 500_000);
+
+        position.add_fill(&TradeResult {
+            success: true,
+            amount_traded: 60_000_000,
+            shares_received: 100_000_000,
+            execution_price: 600_000,
+            fees_paid: 180_000,
+        });
+        assert_eq!(position.shares, 200_000_000);
+        assert_eq!(position.entry_price, 550_000);
+        assert_eq!(position.invested_amount, 110_000_000);
+        assert_eq!(position.entry_fees, 330_000);
+    }
+
+    #[test]
+    fn test_strategy_risk_adjusted_gate() {
+        let mut config = StrategyConfig::default();
+
+        // Disabled by default
+        assert!(!config.is_below_risk_adjusted_score(TradeSignal::Buy, 1000));
+
+        config.min_risk_adjusted_score = 700;
+
+        // High confidence in a calm market trades
+        assert!(!config.is_below_risk_adjusted_score(TradeSignal::StrongBuy, 100));
+        // High confidence in a choppy market is gated
+        assert!(config.is_below_risk_adjusted_score(TradeSignal::StrongBuy, 900));
+        // Normal signals never reach the threshold
+        assert!(config.is_below_risk_adjusted_score(TradeSignal::Sell, 0));
+    }
+
+    #[test]
+    fn test_vault_age_seconds() {
+        let vault = SpectreVault {
+            created_at: 1_000,
+            ..Default::default()
+        };
+        assert_eq!(vault.age_seconds(1_000), 0);
+        assert_eq!(vault.age_seconds(87_400), 86_400);
+
+        // Clock skew never yields a negative age
+        assert_eq!(vault.age_seconds(500), 0);
+        assert_eq!(vault.age_seconds(i64::MIN), 0);
+    }
+
+    #[test]
+    fn test_vault_capacity_increase_age_gate() {
+        let mut vault = SpectreVault {
+            created_at: 1_000,
+            max_total_deposited: 5_000,
+            ..Default::default()
+        };
+
+        // No minimum age
+        assert!(!vault.is_too_young_to_raise_capacity(10_000, 1_000));
+
+        vault.min_age_to_raise_capacity_seconds = SECONDS_PER_DAY as u64;
+        let young = 1_000 + SECONDS_PER_DAY as i64 - 1;
+        let old = 1_000 + SECONDS_PER_DAY as i64;
+
+        // Raising or removing the cap waits for the vault to age
+        assert!(vault.is_too_young_to_raise_capacity(10_000, young));
+        assert!(vault.is_too_young_to_raise_capacity(0, young));
+        assert!(!vault.is_too_young_to_raise_capacity(10_000, old));
+        assert!(!vault.is_too_young_to_raise_capacity(0, old));
+
+        // Keeping or lowering the cap is always allowed
+        assert!(!vault.is_too_young_to_raise_capacity(5_000, young));
+        assert!(!vault.is_too_young_to_raise_capacity(1_000, young));
+
+        // Capping an unlimited vault only restricts it
+        vault.max_total_deposited = 0;
+        assert!(!vault.is_too_young_to_raise_capacity(10_000, young));
+    }
+
+    #[test]
+    fn test_vault_reserve_profit() {
+        let mut vault = SpectreVault {
+            available_balance: 1_000_000,
+            ..Default::default()
+        };
+
+        // Nothing is reserved by default
+        assert_eq!(vault.reserve_profit(100_000), 0);
+        assert_eq!(vault.available_balance, 1_000_000);
+
+        vault.profit_reserve_bps = 2_500;
+        assert_eq!(vault.reserve_profit(100_000), 25_000);
+        assert_eq!(vault.available_balance, 975_000);
+        assert_eq!(vault.profit_reserve, 25_000);
+
+        // Losses and break-even closes reserve nothing
+        assert_eq!(vault.reserve_profit(-100_000), 0);
+        assert_eq!(vault.reserve_profit(0), 0);
+        assert_eq!(vault.profit_reserve, 25_000);
+
+        // Never reserves more than is available
+        vault.available_balance = 10;
+        assert_eq!(vault.reserve_profit(100_000), 10);
+        assert_eq!(vault.available_balance, 0);
+        assert_eq!(vault.profit_reserve, 25_010);
+    }
+
+    #[test]
+    fn test_vault_compound_profits_interval() {
+        let mut vault = SpectreVault {
+            available_balance: 1_000,
+            profit_reserve: 500,
+            last_compound_slot: 100,
+            ..Default::default()
+        };
+
+        // Disabled until an interval is configured
+        assert!(!vault.is_compound_due(u64::MAX));
+
+        vault.auto_compound_interval_slots = 50;
+        assert!(!vault.is_compound_due(149));
+        assert!(vault.is_compound_due(150));
+
+        assert_eq!(vault.compound_profits(150), Some(500));
+        assert_eq!(vault.available_balance, 1_500);
+        assert_eq!(vault.profit_reserve, 0);
+
+        // The next interval starts from the compound
+        assert!(!vault.is_compound_due(199));
+        assert!(vault.is_compound_due(200));
+
+        // Overflow leaves the vault unchanged
+        vault.profit_reserve = u64::MAX;
+        assert_eq!(vault.compound_profits(200), None);
+        assert_eq!(vault.available_balance, 1_500);
+        assert_eq!(vault.last_compound_slot, 150);
+    }
+
+    #[test]
+    fn test_withdrawal_risk_delta() {
+        let mut request = WithdrawalRequest {
+            status: WithdrawalStatus::Approved,
+            verified_risk_score: 10,
+            ..Default::default()
+        };
+
+        // Unchecked by default
+        assert!(!request.is_risk_delta_exceeded(28, 0));
+
+        // An acceptable increase, a decrease and an excessive increase
+        assert!(!request.is_risk_delta_exceeded(25, 15));
+        assert!(!request.is_risk_delta_exceeded(0, 15));
+        assert!(request.is_risk_delta_exceeded(28, 15));
+
+        // Never-verified requests have nothing to compare against
+        request.status = WithdrawalStatus::Pending;
+        assert!(!request.is_risk_delta_exceeded(28, 15));
+    }
+
+    #[test]
+    fn test_vault_performance_fee() {
+        let mut vault = SpectreVault::default();
+
+        // No fee configured
+        assert_eq!(vault.performance_fee(1_000_000), Some(0));
+
+        // 20% of a profit
+        vault.performance_fee_bps = 2_000;
+        assert_eq!(vault.performance_fee(1_000_000), Some(200_000));
+        assert_eq!(vault.performance_fee(1), Some(0));
+
+        // Nothing from a loss or a break-even close
+        assert_eq!(vault.performance_fee(-1_000_000), Some(0));
+        assert_eq!(vault.performance_fee(0), Some(0));
+
+        // Large profits do not overflow
+        vault.performance_fee_bps = 10_000;
+        assert_eq!(vault.performance_fee(i64::MAX), Some(i64::MAX as u64));
+    }
+
+    #[test]
+    fn test_vault_fee_payable() {
+        let mut vault = SpectreVault {
+            performance_fee_bps: 2_000,
+            ..Default::default()
+        };
+        assert_eq!(vault.fee_payable(1_000_000), 1_000_000);
+
+        // Reserves and pending withdrawals are not available for fees
+        vault.fee_rebate_pool = 100_000;
+        vault.profit_reserve = 200_000;
+        vault.staged_deposits = 300_000;
+        vault.reserved_for_withdrawal = 250_000;
+        assert_eq!(vault.fee_payable(1_000_000), 150_000);
+
+        // A fee larger than the free lamports is cut down to them
+        let fee = vault.performance_fee(1_000_000).unwrap().min(vault.fee_payable(1_000_000));
+        assert_eq!(fee, 150_000);
+
+        // Nothing is payable from a vault holding only its reserves
+        assert_eq!(vault.fee_payable(500_000), 0);
+    }
+
+    #[test]
+    fn test_vault_high_water_mark_fee() {
+        let mut vault = SpectreVault {
+            performance_fee_bps: 2_000,
+            high_water_mark_enabled: true,
+            ..Default::default()
+        };
+
+        // New highs are charged in full
+        assert_eq!(vault.performance_fee(1_000_000), Some(200_000));
+        vault.record_realized_pnl(1_000_000);
+        assert_eq!(vault.high_water_mark, 1_000_000);
+
+        // A drawdown leaves the mark in place
+        assert_eq!(vault.performance_fee(-600_000), Some(0));
+        vault.record_realized_pnl(-600_000);
+        assert_eq!(vault.cumulative_realized_pnl, 400_000);
+        assert_eq!(vault.high_water_mark, 1_000_000);
+
+        // Recovering towards the mark is free
+        assert_eq!(vault.performance_fee(400_000), Some(0));
+        vault.record_realized_pnl(400_000);
+
+        // Only the part above the mark is charged
+        assert_eq!(vault.fee_eligible_profit(500_000), 300_000);
+        assert_eq!(vault.performance_fee(500_000), Some(60_000));
+        vault.record_realized_pnl(500_000);
+        assert_eq!(vault.high_water_mark, 1_300_000);
+
+        // Without the mark every profit is charged
+        vault.high_water_mark_enabled = false;
+        vault.record_realized_pnl(-1_000_000);
+        assert_eq!(vault.performance_fee(500_000), Some(100_000));
+    }
+
+    #[test]
+    fn test_strategy_param_bounds() {
+        let params = StrategyParams::default();
+
+        // Unbounded by default
+        let mut bounds = StrategyParamBounds::default();
+        assert!(bounds.is_valid());
+        assert!(bounds.allows(&params));
+        assert!(ProtocolConfig::default().strategy_bounds.allows(&StrategyParams::aggressive()));
+
+        // No volatility cap below 300
+        bounds.volatility_cap = ParamRange { min: 300, max: 1000 };
+        assert!(bounds.allows(&params));
+        assert!(bounds.allows(&StrategyParams::conservative()));
+        assert!(!bounds.allows(&StrategyParams { volatility_cap: 299, ..params }));
+
+        // Each parameter is checked
+        bounds.min_volume_for_signal = ParamRange { min: 1, max: u64::MAX };
+        assert!(!bounds.allows(&params));
+        assert!(bounds.allows(&params.with_min_volume(1)));
+
+        // Empty ranges are rejected
+        bounds.trend_threshold = ParamRange { min: 200, max: 100 };
+        assert!(!bounds.is_valid());
     }
 }
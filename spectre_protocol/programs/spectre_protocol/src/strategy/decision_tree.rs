@@ -27,6 +27,8 @@
 
 use anchor_lang::prelude::*;
 
+use crate::cpi::{PnpMarketData, PRICE_SCALE};
+
 /// Trade signal generated by the strategy
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
 pub enum TradeSignal {
@@ -74,8 +76,69 @@ impl TradeSignal {
     pub fn position_multiplier(&self) -> u64 {
         if self.is_strong() { 2 } else { 1 }
     }
+
+    /// Confidence in the signal scaled by 1000 (strong = 1000, normal = 500, hold = 0)
+    pub fn confidence(&self) -> u32 {
+        match self {
+            TradeSignal::StrongBuy | TradeSignal::StrongSell => 1000,
+            TradeSignal::Buy | TradeSignal::Sell => 500,
+            TradeSignal::Hold => 0,
+        }
+    }
+
+    /// Directional vote in weighted aggregation (strong = ±2, normal = ±1, hold = 0)
+    pub fn vote(&self) -> i64 {
+        match self {
+            TradeSignal::StrongBuy => 2,
+            TradeSignal::Buy => 1,
+            TradeSignal::Hold => 0,
+            TradeSignal::Sell => -1,
+            TradeSignal::StrongSell => -2,
+        }
+    }
+
+    /// Opposite signal of the same strength (Hold stays Hold)
+    pub fn invert(&self) -> Self {
+        match self {
+            TradeSignal::StrongBuy => TradeSignal::StrongSell,
+            TradeSignal::Buy => TradeSignal::Sell,
+            TradeSignal::Hold => TradeSignal::Hold,
+            TradeSignal::Sell => TradeSignal::Buy,
+            TradeSignal::StrongSell => TradeSignal::StrongBuy,
+        }
+    }
+
+    /// Numeric code stored in `StrategyConfig::last_signal` (1-5)
+    pub fn code(&self) -> u8 {
+        match self {
+            TradeSignal::StrongBuy => 1,
+            TradeSignal::Buy => 2,
+            TradeSignal::Hold => 3,
+            TradeSignal::Sell => 4,
+            TradeSignal::StrongSell => 5,
+        }
+    }
+
+    /// Decode a stored signal code (None for 0 = no signal yet, or unknown codes)
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            1 => Some(TradeSignal::StrongBuy),
+            2 => Some(TradeSignal::Buy),
+            3 => Some(TradeSignal::Hold),
+            4 => Some(TradeSignal::Sell),
+            5 => Some(TradeSignal::StrongSell),
+            _ => None,
+        }
+    }
 }
 
+/// Number of input buckets addressable by signal overrides
+///
+/// Buckets are `price_zone * 3 + trend_direction`, where the price zone is
+/// 0 (below low), 1 (between thresholds) or 2 (above high) and the trend
+/// direction is 0 (negative), 1 (flat) or 2 (positive).
+pub const NUM_INPUT_BUCKETS: usize = 9;
+
 /// Strategy configuration parameters
 ///
 /// All thresholds use fixed-point representation scaled by 1000
@@ -98,6 +161,18 @@ pub struct StrategyParams {
     /// Default: 400 (0.4)
     pub volatility_cap: u32,
 
+    /// Signal returned when market input is missing data
+    /// Default: Hold
+    pub default_signal: TradeSignal,
+
+    /// Forced signal per input bucket, consulted before the decision tree
+    /// Default: no overrides
+    pub signal_overrides: [Option<TradeSignal>; NUM_INPUT_BUCKETS],
+
+    /// Provided market volume below which inference always holds
+    /// Default: 0 (disabled)
+    pub min_volume_for_signal: u64,
+
     /// Reserved for future parameters
     pub _reserved: [u8; 16],
 }
@@ -109,6 +184,9 @@ impl Default for StrategyParams {
             price_threshold_high: 650,  // 0.65
             trend_threshold: 100,       // 0.1
             volatility_cap: 400,        // 0.4
+            default_signal: TradeSignal::Hold,
+            signal_overrides: [None; NUM_INPUT_BUCKETS],
+            min_volume_for_signal: 0,
             _reserved: [0u8; 16],
         }
     }
@@ -127,10 +205,35 @@ impl StrategyParams {
             price_threshold_high,
             trend_threshold,
             volatility_cap,
+            default_signal: TradeSignal::Hold,
+            signal_overrides: [None; NUM_INPUT_BUCKETS],
+            min_volume_for_signal: 0,
             _reserved: [0u8; 16],
         }
     }
 
+    /// Set the signal returned when market input is missing data
+    pub fn with_default_signal(mut self, default_signal: TradeSignal) -> Self {
+        self.default_signal = default_signal;
+        self
+    }
+
+    /// Force `signal` whenever input falls into `bucket`
+    ///
+    /// Buckets outside `NUM_INPUT_BUCKETS` are ignored.
+    pub fn with_signal_override(mut self, bucket: usize, signal: TradeSignal) -> Self {
+        if let Some(slot) = self.signal_overrides.get_mut(bucket) {
+            *slot = Some(signal);
+        }
+        self
+    }
+
+    /// Hold whenever the input's provided volume is below `min_volume`
+    pub fn with_min_volume(mut self, min_volume: u64) -> Self {
+        self.min_volume_for_signal = min_volume;
+        self
+    }
+
     /// Validate that parameters are sensible
     pub fn validate(&self) -> bool {
         // Low threshold should be less than high threshold
@@ -163,6 +266,9 @@ impl StrategyParams {
             price_threshold_high: 600,  // 0.60
             trend_threshold: 50,        // 0.05
             volatility_cap: 500,        // 0.5
+            default_signal: TradeSignal::Hold,
+            signal_overrides: [None; NUM_INPUT_BUCKETS],
+            min_volume_for_signal: 0,
             _reserved: [0u8; 16],
         }
     }
@@ -174,11 +280,20 @@ impl StrategyParams {
             price_threshold_high: 700,  // 0.70
             trend_threshold: 150,       // 0.15
             volatility_cap: 300,        // 0.3
+            default_signal: TradeSignal::Hold,
+            signal_overrides: [None; NUM_INPUT_BUCKETS],
+            min_volume_for_signal: 0,
             _reserved: [0u8; 16],
         }
     }
 }
 
+/// `MarketInput::trend` value marking a trend the oracle could not provide
+pub const MISSING_TREND: i32 = i32::MIN;
+
+/// `MarketInput::volatility` value marking volatility the oracle could not provide
+pub const MISSING_VOLATILITY: u32 = u32::MAX;
+
 /// Market data input for strategy inference
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, InitSpace)]
 pub struct MarketInput {
@@ -187,13 +302,18 @@ pub struct MarketInput {
 
     /// Price trend (current - previous) scaled by 1000
     /// Range: -1000 to +1000 (representing -1 to +1)
+    /// `MISSING_TREND` if unavailable
     pub trend: i32,
 
     /// Market volatility scaled by 1000 (0-1000 representing 0-1)
+    /// `MISSING_VOLATILITY` if unavailable
     pub volatility: u32,
 
     /// Timestamp of the market data
     pub timestamp: i64,
+
+    /// Trading volume behind the price, if the feed provides it
+    pub volume: Option<u64>,
 }
 
 impl MarketInput {
@@ -212,9 +332,51 @@ impl MarketInput {
             trend,
             volatility,
             timestamp,
+            volume: None,
         })
     }
 
+    /// Check if the oracle failed to provide trend or volatility
+    pub fn has_missing_data(&self) -> bool {
+        self.trend == MISSING_TREND || self.volatility == MISSING_VOLATILITY
+    }
+
+    /// Attach the trading volume reported by the feed
+    pub fn with_volume(mut self, volume: u64) -> Self {
+        self.volume = Some(volume);
+        self
+    }
+
+    /// Check if a provided volume is below `min_volume` (0 = no minimum)
+    pub fn is_below_volume(&self, min_volume: u64) -> bool {
+        min_volume != 0 && self.volume.is_some_and(|volume| volume < min_volume)
+    }
+
+    /// Build strategy input from PNP market data
+    ///
+    /// - `price`: the YES price rescaled from PRICE_SCALE to 0-1000
+    /// - `trend`: change in YES price since `prev_price`, on the same 0-1000 scale as `price`
+    /// - `volatility`: proxied by the spread, i.e. how far YES + NO prices
+    ///   deviate from a fair total of 1
+    ///
+    /// Values outside the input ranges are clamped.
+    pub fn from_market_data(data: &PnpMarketData, prev_price: u64, timestamp: i64) -> Self {
+        let to_input_scale =
+            |value: u64| (value as u128 * 1000 / PRICE_SCALE as u128).min(1000) as i64;
+
+        let price = to_input_scale(data.yes_price);
+        let trend = price - to_input_scale(prev_price);
+        let spread = data.yes_price.saturating_add(data.no_price).abs_diff(PRICE_SCALE);
+
+        Self {
+            price: price as u32,
+            trend: trend as i32,
+            volatility: to_input_scale(spread) as u32,
+            timestamp,
+            volume: Some(data.volume_24h),
+        }
+    }
+
     /// Create from floating point values (for testing/convenience)
     pub fn from_floats(price: f32, trend: f32, volatility: f32, timestamp: i64) -> Option<Self> {
         if price < 0.0 || price > 1.0 || volatility < 0.0 || volatility > 1.0 {
@@ -229,10 +391,111 @@ impl MarketInput {
             trend: (trend * 1000.0) as i32,
             volatility: (volatility * 1000.0) as u32,
             timestamp,
+            volume: None,
         })
     }
 }
 
+/// Which decision tree conditions held for a given input
+///
+/// Each flag corresponds to one comparison made by `run_inference`,
+/// so the flags fully determine the resulting signal. Useful for
+/// debugging and tuning strategy parameters off-chain.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default, InitSpace)]
+pub struct SignalExplanation {
+    /// volatility > volatility_cap
+    pub volatility_above_cap: bool,
+
+    /// volatility < volatility_cap / 2
+    pub volatility_below_half_cap: bool,
+
+    /// price < price_threshold_low
+    pub price_below_low: bool,
+
+    /// price > price_threshold_high
+    pub price_above_high: bool,
+
+    /// trend > 0
+    pub trend_positive: bool,
+
+    /// trend < 0
+    pub trend_negative: bool,
+
+    /// trend > trend_threshold
+    pub trend_above_threshold: bool,
+
+    /// trend < -trend_threshold
+    pub trend_below_neg_threshold: bool,
+}
+
+impl SignalExplanation {
+    /// Evaluate every decision tree condition for the given input
+    pub fn evaluate(input: &MarketInput, params: &StrategyParams) -> Self {
+        let trend_threshold = params.trend_threshold as i32;
+
+        Self {
+            volatility_above_cap: input.volatility > params.volatility_cap,
+            volatility_below_half_cap: input.volatility < params.volatility_cap / 2,
+            price_below_low: input.price < params.price_threshold_low,
+            price_above_high: input.price > params.price_threshold_high,
+            trend_positive: input.trend > 0,
+            trend_negative: input.trend < 0,
+            trend_above_threshold: input.trend > trend_threshold,
+            trend_below_neg_threshold: input.trend < -trend_threshold,
+        }
+    }
+
+    /// Input bucket used to look up signal overrides
+    pub fn bucket(&self) -> usize {
+        let price_zone = if self.price_below_low {
+            0
+        } else if self.price_above_high {
+            2
+        } else {
+            1
+        };
+        let trend_direction = if self.trend_negative {
+            0
+        } else if self.trend_positive {
+            2
+        } else {
+            1
+        };
+        price_zone * 3 + trend_direction
+    }
+
+    /// Walk the decision tree using the evaluated conditions
+    pub fn signal(&self) -> TradeSignal {
+        // 1. High volatility = be cautious
+        if self.volatility_above_cap {
+            return TradeSignal::Hold;
+        }
+
+        // 2. Strong buy: underpriced + positive trend + low volatility
+        if self.price_below_low && self.trend_above_threshold && self.volatility_below_half_cap {
+            return TradeSignal::StrongBuy;
+        }
+
+        // 3. Buy: underpriced + any positive trend
+        if self.price_below_low && self.trend_positive {
+            return TradeSignal::Buy;
+        }
+
+        // 4. Strong sell: overpriced + negative trend + low volatility
+        if self.price_above_high && self.trend_below_neg_threshold && self.volatility_below_half_cap {
+            return TradeSignal::StrongSell;
+        }
+
+        // 5. Sell: overpriced + any negative trend
+        if self.price_above_high && self.trend_negative {
+            return TradeSignal::Sell;
+        }
+
+        // 6. Default: Hold
+        TradeSignal::Hold
+    }
+}
+
 /// Run the decision tree inference algorithm
 ///
 /// # Arguments
@@ -244,6 +507,9 @@ impl MarketInput {
 ///
 /// # Algorithm
 ///
+/// 0. **Missing Data**: If trend or volatility is missing → `default_signal`
+///    **Low Volume**: If a provided volume is below `min_volume_for_signal` → Hold
+///    **Override**: If the input bucket has a signal override → that signal
 /// 1. **Volatility Check**: If volatility > cap → Hold
 /// 2. **Strong Buy**: price < low_threshold AND trend > trend_threshold AND volatility < cap/2
 /// 3. **Buy**: price < low_threshold AND trend > 0
@@ -251,46 +517,74 @@ impl MarketInput {
 /// 5. **Sell**: price > high_threshold AND trend < 0
 /// 6. **Default**: Hold
 pub fn run_inference(input: &MarketInput, params: &StrategyParams) -> TradeSignal {
-    let price = input.price;
-    let trend = input.trend;
-    let volatility = input.volatility;
+    run_inference_explained(input, params).0
+}
 
-    // 1. High volatility = be cautious
-    if volatility > params.volatility_cap {
-        return TradeSignal::Hold;
+/// Run inference and also return which conditions led to the signal
+///
+/// Produces exactly the same signal as `run_inference`. When input data is
+/// missing no conditions are evaluated and the explanation is empty. A
+/// low-volume Hold or an overridden signal still carries the explanation
+/// for its bucket.
+pub fn run_inference_explained(
+    input: &MarketInput,
+    params: &StrategyParams,
+) -> (TradeSignal, SignalExplanation) {
+    // Never trade on a partial feed
+    if input.has_missing_data() {
+        return (params.default_signal, SignalExplanation::default());
     }
 
-    let half_vol_cap = params.volatility_cap / 2;
-    let trend_threshold = params.trend_threshold as i32;
+    let explanation = SignalExplanation::evaluate(input, params);
 
-    // 2. Strong buy: underpriced + positive trend + low volatility
-    if price < params.price_threshold_low
-        && trend > trend_threshold
-        && volatility < half_vol_cap
-    {
-        return TradeSignal::StrongBuy;
+    // Price and trend are unreliable on an illiquid market
+    if input.is_below_volume(params.min_volume_for_signal) {
+        return (TradeSignal::Hold, explanation);
     }
 
-    // 3. Buy: underpriced + any positive trend
-    if price < params.price_threshold_low && trend > 0 {
-        return TradeSignal::Buy;
-    }
+    let signal = params.signal_overrides[explanation.bucket()]
+        .unwrap_or_else(|| explanation.signal());
+    (signal, explanation)
+}
 
-    // 4. Strong sell: overpriced + negative trend + low volatility
-    if price > params.price_threshold_high
-        && trend < -trend_threshold
-        && volatility < half_vol_cap
-    {
-        return TradeSignal::StrongSell;
-    }
+/// Signal confidence discounted by market volatility, scaled by 1000
+///
+/// `confidence / (1 + volatility_factor)` where the volatility factor is
+/// `volatility / 1000`. Missing volatility counts as the maximum.
+pub fn risk_adjusted_score(signal: TradeSignal, volatility: u32) -> u32 {
+    let volatility = volatility.min(1000) as u64;
+    (signal.confidence() as u64 * 1000 / (1000 + volatility)) as u32
+}
 
-    // 5. Sell: overpriced + any negative trend
-    if price > params.price_threshold_high && trend < 0 {
-        return TradeSignal::Sell;
+/// Combine per-market signals into one portfolio-level signal by weighted vote
+///
+/// `weights` are basis points and must pair one-to-one with `signals` and sum
+/// to 10000; otherwise returns None. The sign of the weighted vote picks the
+/// side, and a weighted average vote of at least 1.5 makes it strong. An exact
+/// tie is Hold.
+pub fn aggregate_signals(signals: &[TradeSignal], weights: &[u16]) -> Option<TradeSignal> {
+    if signals.is_empty() || signals.len() != weights.len() {
+        return None;
+    }
+    let total_weight: u64 = weights.iter().map(|&w| w as u64).sum();
+    if total_weight != 10_000 {
+        return None;
     }
 
-    // 6. Default: Hold
-    TradeSignal::Hold
+    // In bps of a single normal vote: ±20000 when every market is strong
+    let net: i64 = signals
+        .iter()
+        .zip(weights)
+        .map(|(signal, &weight)| signal.vote() * weight as i64)
+        .sum();
+
+    Some(match net {
+        n if n >= 15_000 => TradeSignal::StrongBuy,
+        n if n > 0 => TradeSignal::Buy,
+        n if n <= -15_000 => TradeSignal::StrongSell,
+        n if n < 0 => TradeSignal::Sell,
+        _ => TradeSignal::Hold,
+    })
 }
 
 /// Convenience function using floating point inputs
@@ -450,6 +744,50 @@ mod tests {
         assert_eq!(input.volatility, 200);
     }
 
+    #[test]
+    fn test_market_input_from_market_data() {
+        let data = PnpMarketData {
+            yes_price: 300_000, // 30%
+            no_price: 720_000,  // 72% (2% overround)
+            volume_24h: 1_000_000_000,
+            liquidity: 10_000_000_000,
+            end_time: i64::MAX,
+            is_active: true,
+        };
+
+        let input = MarketInput::from_market_data(&data, 250_000, 12345);
+        assert_eq!(input.price, 300);
+        assert_eq!(input.trend, 50);
+        assert_eq!(input.volatility, 20);
+        assert_eq!(input.timestamp, 12345);
+
+        // Cheap and rising: the default strategy buys
+        assert_eq!(run_inference(&input, &StrategyParams::default()), TradeSignal::Buy);
+
+        // Falling price, tight book
+        let data = PnpMarketData {
+            yes_price: 700_000,
+            no_price: 300_000,
+            ..data
+        };
+        let input = MarketInput::from_market_data(&data, 800_000, 0);
+        assert_eq!(input.price, 700);
+        assert_eq!(input.trend, -100);
+        assert_eq!(input.volatility, 0);
+
+        // Out-of-range prices clamp to valid input
+        let data = PnpMarketData {
+            yes_price: 3 * PRICE_SCALE,
+            no_price: 0,
+            ..data
+        };
+        let input = MarketInput::from_market_data(&data, 0, 0);
+        assert_eq!(input.price, 1000);
+        assert_eq!(input.trend, 1000);
+        assert_eq!(input.volatility, 1000);
+        assert!(MarketInput::new(input.price, input.trend, input.volatility, 0).is_some());
+    }
+
     // ==========================================
     // Decision Tree Inference Tests
     // ==========================================
@@ -611,4 +949,251 @@ mod tests {
         let signal = run_inference_f32(0.0, 1.0, 1.0, &params);
         assert_eq!(signal, TradeSignal::Hold);
     }
+
+    // ==========================================
+    // Signal Explanation Tests
+    // ==========================================
+
+    #[test]
+    fn test_explained_strong_buy() {
+        let params = StrategyParams::default();
+        let input = MarketInput::new(250, 150, 100, 0).unwrap();
+        let (signal, explanation) = run_inference_explained(&input, &params);
+
+        assert_eq!(signal, TradeSignal::StrongBuy);
+        assert!(explanation.price_below_low);
+        assert!(explanation.trend_positive);
+        assert!(explanation.trend_above_threshold);
+        assert!(explanation.volatility_below_half_cap);
+        assert!(!explanation.volatility_above_cap);
+        assert!(!explanation.price_above_high);
+        assert!(!explanation.trend_negative);
+    }
+
+    #[test]
+    fn test_explained_buy_without_strong_trend() {
+        let params = StrategyParams::default();
+        let input = MarketInput::new(300, 50, 250, 0).unwrap();
+        let (signal, explanation) = run_inference_explained(&input, &params);
+
+        assert_eq!(signal, TradeSignal::Buy);
+        assert!(explanation.price_below_low);
+        assert!(explanation.trend_positive);
+        assert!(!explanation.trend_above_threshold);
+        assert!(!explanation.volatility_below_half_cap);
+    }
+
+    #[test]
+    fn test_explained_sell_paths() {
+        let params = StrategyParams::default();
+
+        let input = MarketInput::new(750, -150, 100, 0).unwrap();
+        let (signal, explanation) = run_inference_explained(&input, &params);
+        assert_eq!(signal, TradeSignal::StrongSell);
+        assert!(explanation.price_above_high);
+        assert!(explanation.trend_negative);
+        assert!(explanation.trend_below_neg_threshold);
+        assert!(explanation.volatility_below_half_cap);
+
+        let input = MarketInput::new(700, -50, 200, 0).unwrap();
+        let (signal, explanation) = run_inference_explained(&input, &params);
+        assert_eq!(signal, TradeSignal::Sell);
+        assert!(explanation.price_above_high);
+        assert!(explanation.trend_negative);
+        assert!(!explanation.trend_below_neg_threshold);
+    }
+
+    #[test]
+    fn test_explained_hold_reasons() {
+        let params = StrategyParams::default();
+
+        // Volatility cap overrides an otherwise strong buy
+        let input = MarketInput::new(250, 150, 500, 0).unwrap();
+        let (signal, explanation) = run_inference_explained(&input, &params);
+        assert_eq!(signal, TradeSignal::Hold);
+        assert!(explanation.volatility_above_cap);
+        assert!(explanation.price_below_low);
+        assert!(explanation.trend_above_threshold);
+
+        // Neutral price zone
+        let input = MarketInput::new(500, 50, 200, 0).unwrap();
+        let (signal, explanation) = run_inference_explained(&input, &params);
+        assert_eq!(signal, TradeSignal::Hold);
+        assert!(!explanation.price_below_low);
+        assert!(!explanation.price_above_high);
+    }
+
+    #[test]
+    fn test_missing_inputs_use_default_signal() {
+        // Cheap and strongly rising: a strong buy with complete data
+        let params = StrategyParams::default();
+        let input = MarketInput::new(250, 150, 100, 0).unwrap();
+        assert!(!input.has_missing_data());
+        assert_eq!(run_inference(&input, &params), TradeSignal::StrongBuy);
+
+        let missing_trend = MarketInput { trend: MISSING_TREND, ..input };
+        let missing_volatility = MarketInput { volatility: MISSING_VOLATILITY, ..input };
+        for input in [missing_trend, missing_volatility] {
+            assert!(input.has_missing_data());
+            let (signal, explanation) = run_inference_explained(&input, &params);
+            assert_eq!(signal, TradeSignal::Hold);
+            assert_eq!(explanation, SignalExplanation::default());
+
+            // A configured fallback is returned as-is
+            let sell_params = params.with_default_signal(TradeSignal::Sell);
+            assert_eq!(run_inference(&input, &sell_params), TradeSignal::Sell);
+        }
+    }
+
+    #[test]
+    fn test_signal_override_forces_hold() {
+        // Underpriced with a positive trend: bucket 2 (below low, positive)
+        let input = MarketInput::new(300, 50, 100, 0).unwrap();
+        let params = StrategyParams::default();
+        assert_eq!(run_inference(&input, &params), TradeSignal::Buy);
+
+        let overridden = params.with_signal_override(2, TradeSignal::Hold);
+        let (signal, explanation) = run_inference_explained(&input, &overridden);
+        assert_eq!(signal, TradeSignal::Hold);
+        assert_eq!(explanation.bucket(), 2);
+        assert_eq!(explanation.signal(), TradeSignal::Buy);
+    }
+
+    #[test]
+    fn test_signal_override_only_affects_its_bucket() {
+        let params = StrategyParams::default().with_signal_override(2, TradeSignal::Hold);
+
+        // Overpriced with a negative trend (bucket 6) is untouched
+        let sell = MarketInput::new(700, -50, 100, 0).unwrap();
+        assert_eq!(run_inference(&sell, &params), TradeSignal::Sell);
+
+        // Out-of-range buckets are ignored
+        let ignored = StrategyParams::default().with_signal_override(NUM_INPUT_BUCKETS, TradeSignal::Sell);
+        assert!(ignored.signal_overrides.iter().all(|o| o.is_none()));
+    }
+
+    #[test]
+    fn test_input_buckets() {
+        let params = StrategyParams::default();
+        let bucket = |price, trend| {
+            let input = MarketInput::new(price, trend, 100, 0).unwrap();
+            SignalExplanation::evaluate(&input, &params).bucket()
+        };
+        assert_eq!(bucket(300, -10), 0);
+        assert_eq!(bucket(300, 0), 1);
+        assert_eq!(bucket(500, 0), 4);
+        assert_eq!(bucket(700, 10), 8);
+    }
+
+    #[test]
+    fn test_signal_code_roundtrip() {
+        for signal in [
+            TradeSignal::StrongBuy,
+            TradeSignal::Buy,
+            TradeSignal::Hold,
+            TradeSignal::Sell,
+            TradeSignal::StrongSell,
+        ] {
+            assert_eq!(TradeSignal::from_code(signal.code()), Some(signal));
+        }
+        assert_eq!(TradeSignal::from_code(0), None);
+        assert_eq!(TradeSignal::from_code(6), None);
+    }
+
+    #[test]
+    fn test_low_volume_forces_hold() {
+        // Cheap and strongly rising: a strong buy on a liquid market
+        let params = StrategyParams::default().with_min_volume(1_000_000_000);
+        let input = MarketInput::new(250, 150, 100, 0).unwrap();
+
+        // No volume provided: the minimum doesn't apply
+        assert_eq!(run_inference(&input, &params), TradeSignal::StrongBuy);
+
+        // Enough volume
+        let liquid = input.with_volume(1_000_000_000);
+        assert_eq!(run_inference(&liquid, &params), TradeSignal::StrongBuy);
+
+        // Too little volume, including none at all
+        for volume in [0, 999_999_999] {
+            let illiquid = input.with_volume(volume);
+            let (signal, explanation) = run_inference_explained(&illiquid, &params);
+            assert_eq!(signal, TradeSignal::Hold);
+            assert!(explanation.price_below_low);
+        }
+
+        // Overrides don't bypass the volume check
+        let overridden = params.with_signal_override(2, TradeSignal::Buy);
+        assert_eq!(run_inference(&input.with_volume(0), &overridden), TradeSignal::Hold);
+
+        // Disabled
+        let disabled = StrategyParams::default();
+        assert_eq!(run_inference(&input.with_volume(0), &disabled), TradeSignal::StrongBuy);
+    }
+
+    #[test]
+    fn test_signal_invert() {
+        assert_eq!(TradeSignal::StrongBuy.invert(), TradeSignal::StrongSell);
+        assert_eq!(TradeSignal::Buy.invert(), TradeSignal::Sell);
+        assert_eq!(TradeSignal::Hold.invert(), TradeSignal::Hold);
+        assert_eq!(TradeSignal::Sell.invert(), TradeSignal::Buy);
+        assert_eq!(TradeSignal::StrongSell.invert(), TradeSignal::StrongBuy);
+
+        for code in 1..=5 {
+            let signal = TradeSignal::from_code(code).unwrap();
+            assert_eq!(signal.invert().invert(), signal);
+            assert_eq!(signal.invert().is_strong(), signal.is_strong());
+        }
+    }
+
+    #[test]
+    fn test_risk_adjusted_score() {
+        // No volatility leaves confidence unchanged
+        assert_eq!(risk_adjusted_score(TradeSignal::StrongBuy, 0), 1000);
+        assert_eq!(risk_adjusted_score(TradeSignal::Sell, 0), 500);
+        assert_eq!(risk_adjusted_score(TradeSignal::Hold, 0), 0);
+
+        // Maximum volatility halves it
+        assert_eq!(risk_adjusted_score(TradeSignal::StrongSell, 1000), 500);
+        assert_eq!(risk_adjusted_score(TradeSignal::Buy, 1000), 250);
+
+        // Missing volatility is treated as maximum
+        assert_eq!(risk_adjusted_score(TradeSignal::StrongBuy, MISSING_VOLATILITY), 500);
+
+        // Higher volatility never raises the score
+        assert!(risk_adjusted_score(TradeSignal::StrongBuy, 100) > risk_adjusted_score(TradeSignal::StrongBuy, 800));
+    }
+
+    #[test]
+    fn test_aggregate_signals_weighted_vote() {
+        use TradeSignal::*;
+
+        // Two buys outweigh one heavier sell
+        assert_eq!(aggregate_signals(&[Buy, Buy, Sell], &[3_000, 3_000, 4_000]), Some(Buy));
+        // ...but not a sell carrying most of the weight
+        assert_eq!(aggregate_signals(&[Buy, Buy, Sell], &[2_000, 2_000, 6_000]), Some(Sell));
+        // Strength counts: one strong sell cancels two buys of equal weight
+        assert_eq!(
+            aggregate_signals(&[Buy, Buy, StrongSell, Hold], &[2_500, 2_500, 2_500, 2_500]),
+            Some(Hold)
+        );
+
+        // Strong only when the weighted average vote reaches 1.5
+        assert_eq!(aggregate_signals(&[StrongBuy, Buy], &[5_000, 5_000]), Some(StrongBuy));
+        assert_eq!(aggregate_signals(&[StrongBuy, Hold], &[7_500, 2_500]), Some(StrongBuy));
+        assert_eq!(aggregate_signals(&[StrongBuy, Hold], &[7_000, 3_000]), Some(Buy));
+        assert_eq!(aggregate_signals(&[StrongSell, StrongSell], &[5_000, 5_000]), Some(StrongSell));
+
+        // A single market passes through
+        assert_eq!(aggregate_signals(&[Sell], &[10_000]), Some(Sell));
+    }
+
+    #[test]
+    fn test_aggregate_signals_rejects_invalid_weights() {
+        use TradeSignal::*;
+
+        assert_eq!(aggregate_signals(&[], &[]), None);
+        assert_eq!(aggregate_signals(&[Buy, Buy], &[10_000]), None);
+        assert_eq!(aggregate_signals(&[Buy, Buy], &[5_000, 4_999]), None);
+        assert_eq!(aggregate_signals(&[Buy, Buy], &[u16::MAX, 10_000]), None);
+    }
 }
@@ -17,26 +17,39 @@ use anchor_lang::prelude::*;
 /// Addresses with risk > 30 are blocked
 pub const MAX_RISK_SCORE: u8 = 30;
 
+/// Highest risk score on the on-chain 0-100 scale
+pub const MAX_SCALED_RISK_SCORE: u8 = 100;
+
 /// Maximum age of compliance attestation in slots
 /// At ~400ms per slot, 50 slots ≈ 20 seconds
 pub const MAX_ATTESTATION_AGE_SLOTS: u64 = 50;
 
+/// Slots of attestation age per point of added risk when decay is enabled
+/// A 50-slot-old attestation carries +5 risk
+pub const RISK_DECAY_SLOTS_PER_POINT: u64 = 10;
+
 /// Risk levels as reported by Range Protocol
+///
+/// Bounds are given on the 0-100 on-chain scale, with the equivalent whole
+/// Range API score (0-10) in brackets.
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
 pub enum RiskLevel {
-    /// Low risk (score 0-2)
+    /// Low risk: 0-20 [0-2]
     Low,
-    /// Medium risk (score 3-5)
+    /// Medium risk: 21-50 [3-5]
     Medium,
-    /// High risk (score 6-8)
+    /// High risk: 51-80 [6-8]
     High,
-    /// Critical risk (score 9-10) - OFAC sanctioned, etc.
+    /// Critical risk: 81-100 [9-10] - OFAC sanctioned, etc.
     Critical,
 }
 
 impl RiskLevel {
-    /// Convert from raw risk score (0-100 scaled)
-    pub fn from_score(score: u8) -> Self {
+    /// Classify a score on the 0-100 on-chain scale
+    ///
+    /// Each bound is inclusive of its upper end (20 is Low, 21 is Medium).
+    /// Scores above 100 are Critical.
+    pub fn from_score_100(score: u8) -> Self {
         match score {
             0..=20 => RiskLevel::Low,
             21..=50 => RiskLevel::Medium,
@@ -45,10 +58,42 @@ impl RiskLevel {
         }
     }
 
+    /// Classify a whole score on the 0-10 Range API scale
+    ///
+    /// Equivalent to `from_score_100(score * 10)`; scores above 10 are Critical.
+    pub fn from_score_10(score: u8) -> Self {
+        Self::from_score_100(score.saturating_mul(10))
+    }
+
     /// Check if this risk level is acceptable for withdrawal
     pub fn is_acceptable(&self) -> bool {
         matches!(self, RiskLevel::Low | RiskLevel::Medium)
     }
+
+    /// Convert a Range API score (0-10, in tenths) to the 0-100 on-chain scale
+    ///
+    /// One tenth of an API point is exactly one on-chain point, so no rounding
+    /// occurs (2.9/10 = 29 tenths -> 29). Scores above 10.0 clamp to 100.
+    pub fn scale_from_api(api_score_tenths: u16) -> u8 {
+        api_score_tenths.min(MAX_SCALED_RISK_SCORE as u16) as u8
+    }
+
+    /// Convert a 0-100 on-chain score back to Range API tenths (inverse of
+    /// `scale_from_api`). Scores above 100 clamp to 10.0.
+    pub fn scale_to_api(score: u8) -> u16 {
+        score.min(MAX_SCALED_RISK_SCORE) as u16
+    }
+}
+
+/// How a withdrawal should be handled after compliance verification
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ComplianceAction {
+    /// Low risk - proceed without further action
+    Allow,
+    /// Passed, but risk is elevated - proceed and record a flag for review
+    AllowWithFlag,
+    /// Failed compliance - reject the withdrawal
+    Block,
 }
 
 /// Range Protocol attestation data
@@ -58,7 +103,7 @@ pub struct RangeAttestation {
     /// Address being checked
     pub address: Pubkey,
 
-    /// Risk score (0-100, scaled from Range API's 0-10)
+    /// Risk score (0-100, see `RiskLevel::scale_from_api`)
     pub risk_score: u8,
 
     /// Risk level classification
@@ -76,6 +121,9 @@ pub struct RangeAttestation {
 
     /// Whether any malicious addresses were found in the path
     pub has_malicious_connections: bool,
+
+    /// Oracle-assigned nonce binding the attestation to a single use
+    pub nonce: u64,
 }
 
 impl RangeAttestation {
@@ -90,14 +138,46 @@ impl RangeAttestation {
         Self {
             address,
             risk_score,
-            risk_level: RiskLevel::from_score(risk_score),
+            risk_level: RiskLevel::from_score_100(risk_score),
             attestation_slot,
             num_hops,
             oracle_signature: [0u8; 64], // Mock signature
             has_malicious_connections,
+            nonce: 0,
         }
     }
 
+    /// Set the attestation nonce
+    pub fn with_nonce(mut self, nonce: u64) -> Self {
+        self.nonce = nonce;
+        self
+    }
+
+    /// Create an attestation from a Range API score (0-10, in tenths)
+    pub fn from_api_score(
+        address: Pubkey,
+        api_score_tenths: u16,
+        attestation_slot: u64,
+        num_hops: u8,
+        has_malicious_connections: bool,
+    ) -> Self {
+        Self::new(
+            address,
+            RiskLevel::scale_from_api(api_score_tenths),
+            attestation_slot,
+            num_hops,
+            has_malicious_connections,
+        )
+    }
+
+    /// Risk score adjusted for attestation age (+1 per `RISK_DECAY_SLOTS_PER_POINT`)
+    /// Models confidence decaying as the attestation gets older
+    pub fn effective_risk_score(&self, current_slot: u64) -> u8 {
+        let age = current_slot.saturating_sub(self.attestation_slot);
+        let decay = (age / RISK_DECAY_SLOTS_PER_POINT).min(MAX_SCALED_RISK_SCORE as u64) as u8;
+        self.risk_score.saturating_add(decay).min(MAX_SCALED_RISK_SCORE)
+    }
+
     /// Create a clean attestation for testing
     pub fn clean(address: Pubkey, slot: u64) -> Self {
         Self::new(address, 0, slot, 0, false)
@@ -110,7 +190,7 @@ impl RangeAttestation {
 }
 
 /// Error returned when compliance check fails
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ComplianceError {
     /// Attestation is too old
     StaleAttestation,
@@ -146,7 +226,7 @@ impl ComplianceResult {
         Self {
             passed: true,
             risk_score,
-            risk_level: RiskLevel::from_score(risk_score),
+            risk_level: RiskLevel::from_score_100(risk_score),
             error: None,
         }
     }
@@ -156,10 +236,49 @@ impl ComplianceResult {
         Self {
             passed: false,
             risk_score,
-            risk_level: RiskLevel::from_score(risk_score),
+            risk_level: RiskLevel::from_score_100(risk_score),
             error: Some(error),
         }
     }
+
+    /// Action to take for this result, tiered by risk level
+    ///
+    /// Failures block; passing results only proceed silently at `RiskLevel::Low`.
+    pub fn action(&self) -> ComplianceAction {
+        if !self.passed {
+            ComplianceAction::Block
+        } else if self.risk_level == RiskLevel::Low {
+            ComplianceAction::Allow
+        } else {
+            ComplianceAction::AllowWithFlag
+        }
+    }
+
+    /// Client-facing copy of the result
+    pub fn view(&self) -> ComplianceResultView {
+        ComplianceResultView {
+            passed: self.passed,
+            risk_score: self.risk_score,
+            risk_level: self.risk_level,
+            reason: self.error,
+        }
+    }
+}
+
+/// Compliance result returned by `simulate_compliance`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ComplianceResultView {
+    /// Whether the address would pass compliance
+    pub passed: bool,
+
+    /// Risk score the check was decided on
+    pub risk_score: u8,
+
+    /// Risk level classification
+    pub risk_level: RiskLevel,
+
+    /// Why the check would fail (None when passing)
+    pub reason: Option<ComplianceError>,
 }
 
 /// Verify compliance of an address for withdrawal
@@ -175,6 +294,20 @@ pub fn verify_compliance(
     attestation: &RangeAttestation,
     expected_address: &Pubkey,
     current_slot: u64,
+) -> ComplianceResult {
+    verify_compliance_with_decay(attestation, expected_address, current_slot, false)
+}
+
+/// Verify compliance, optionally applying age-based risk score decay
+///
+/// With `apply_decay` set, the risk threshold is checked against
+/// `effective_risk_score`, so a borderline attestation can fail as it ages
+/// even while still within `MAX_ATTESTATION_AGE_SLOTS`.
+pub fn verify_compliance_with_decay(
+    attestation: &RangeAttestation,
+    expected_address: &Pubkey,
+    current_slot: u64,
+    apply_decay: bool,
 ) -> ComplianceResult {
     // 1. Verify address matches
     if attestation.address != *expected_address {
@@ -196,18 +329,23 @@ pub fn verify_compliance(
     }
 
     // 4. Check risk score threshold
-    if attestation.risk_score > MAX_RISK_SCORE {
-        return ComplianceResult::fail(attestation.risk_score, ComplianceError::HighRiskAddress);
+    let risk_score = if apply_decay {
+        attestation.effective_risk_score(current_slot)
+    } else {
+        attestation.risk_score
+    };
+    if risk_score > MAX_RISK_SCORE {
+        return ComplianceResult::fail(risk_score, ComplianceError::HighRiskAddress);
     }
 
     // 5. Verify oracle signature
     // In production, this would verify against Switchboard oracle
     // For Phase 1, we use mock verification
     if !verify_oracle_signature(attestation) {
-        return ComplianceResult::fail(attestation.risk_score, ComplianceError::InvalidSignature);
+        return ComplianceResult::fail(risk_score, ComplianceError::InvalidSignature);
     }
 
-    ComplianceResult::pass(attestation.risk_score)
+    ComplianceResult::pass(risk_score)
 }
 
 /// Verify the oracle signature on an attestation
@@ -227,8 +365,63 @@ fn verify_oracle_signature(attestation: &RangeAttestation) -> bool {
         || attestation.oracle_signature.iter().any(|&b| b != 0)
 }
 
+/// Maximum number of recipients in a split withdrawal
+pub const MAX_SPLIT_RECIPIENTS: usize = 4;
+
+/// One recipient's share of a multi-recipient withdrawal
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct SplitPayout {
+    /// Address receiving this share
+    pub recipient: Pubkey,
+
+    /// Amount to send to this recipient (lamports)
+    pub amount: u64,
+
+    /// Range Protocol attestation for this recipient
+    pub attestation: RangeAttestation,
+}
+
+/// Verify compliance for every recipient of a split withdrawal
+///
+/// # Returns
+/// * `None` if all recipients pass
+/// * `Some((index, result))` for the first recipient that fails
+pub fn verify_split_compliance(
+    payouts: &[SplitPayout],
+    current_slot: u64,
+    apply_decay: bool,
+) -> Option<(usize, ComplianceResult)> {
+    payouts.iter().enumerate().find_map(|(i, payout)| {
+        let result = verify_compliance_with_decay(
+            &payout.attestation,
+            &payout.recipient,
+            current_slot,
+            apply_decay,
+        );
+        if result.passed {
+            None
+        } else {
+            Some((i, result))
+        }
+    })
+}
+
 /// Serialize attestation data for signing
 /// Used to verify oracle signatures
+///
+/// This is the signing contract with the oracle, so the layout must not
+/// change. Canonical order (51 bytes, integers little-endian):
+///
+/// | Offset | Size | Field                                  |
+/// |--------|------|----------------------------------------|
+/// | 0      | 32   | `address`                              |
+/// | 32     | 1    | `risk_score`                           |
+/// | 33     | 8    | `attestation_slot`                     |
+/// | 41     | 1    | `num_hops`                             |
+/// | 42     | 1    | `has_malicious_connections` (0 or 1)   |
+/// | 43     | 8    | `nonce`                                |
+///
+/// `risk_level` and `oracle_signature` are not part of the message.
 pub fn serialize_attestation_data(attestation: &RangeAttestation) -> Vec<u8> {
     let mut data = Vec::with_capacity(64);
     data.extend_from_slice(attestation.address.as_ref());
@@ -240,6 +433,7 @@ pub fn serialize_attestation_data(attestation: &RangeAttestation) -> Vec<u8> {
     } else {
         0
     });
+    data.extend_from_slice(&attestation.nonce.to_le_bytes());
     data
 }
 
@@ -248,15 +442,36 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_risk_level_from_score() {
-        assert_eq!(RiskLevel::from_score(0), RiskLevel::Low);
-        assert_eq!(RiskLevel::from_score(20), RiskLevel::Low);
-        assert_eq!(RiskLevel::from_score(21), RiskLevel::Medium);
-        assert_eq!(RiskLevel::from_score(50), RiskLevel::Medium);
-        assert_eq!(RiskLevel::from_score(51), RiskLevel::High);
-        assert_eq!(RiskLevel::from_score(80), RiskLevel::High);
-        assert_eq!(RiskLevel::from_score(81), RiskLevel::Critical);
-        assert_eq!(RiskLevel::from_score(100), RiskLevel::Critical);
+    fn test_risk_level_from_score_100() {
+        assert_eq!(RiskLevel::from_score_100(0), RiskLevel::Low);
+        assert_eq!(RiskLevel::from_score_100(20), RiskLevel::Low);
+        assert_eq!(RiskLevel::from_score_100(21), RiskLevel::Medium);
+        assert_eq!(RiskLevel::from_score_100(50), RiskLevel::Medium);
+        assert_eq!(RiskLevel::from_score_100(51), RiskLevel::High);
+        assert_eq!(RiskLevel::from_score_100(80), RiskLevel::High);
+        assert_eq!(RiskLevel::from_score_100(81), RiskLevel::Critical);
+        assert_eq!(RiskLevel::from_score_100(100), RiskLevel::Critical);
+        assert_eq!(RiskLevel::from_score_100(u8::MAX), RiskLevel::Critical);
+    }
+
+    #[test]
+    fn test_risk_level_from_score_10() {
+        assert_eq!(RiskLevel::from_score_10(0), RiskLevel::Low);
+        assert_eq!(RiskLevel::from_score_10(2), RiskLevel::Low);
+        assert_eq!(RiskLevel::from_score_10(3), RiskLevel::Medium);
+        assert_eq!(RiskLevel::from_score_10(5), RiskLevel::Medium);
+        assert_eq!(RiskLevel::from_score_10(6), RiskLevel::High);
+        assert_eq!(RiskLevel::from_score_10(8), RiskLevel::High);
+        assert_eq!(RiskLevel::from_score_10(9), RiskLevel::Critical);
+        assert_eq!(RiskLevel::from_score_10(10), RiskLevel::Critical);
+        assert_eq!(RiskLevel::from_score_10(u8::MAX), RiskLevel::Critical);
+    }
+
+    #[test]
+    fn test_risk_level_scales_agree() {
+        for score in 0..=10u8 {
+            assert_eq!(RiskLevel::from_score_10(score), RiskLevel::from_score_100(score * 10));
+        }
     }
 
     #[test]
@@ -267,6 +482,43 @@ mod tests {
         assert!(!RiskLevel::Critical.is_acceptable());
     }
 
+    #[test]
+    fn test_risk_scale_conversion() {
+        // 2.9/10 and 3.0/10 are within MAX_RISK_SCORE, 3.1/10 is not
+        assert_eq!(RiskLevel::scale_from_api(29), 29);
+        assert_eq!(RiskLevel::scale_from_api(30), MAX_RISK_SCORE);
+        assert!(RiskLevel::scale_from_api(31) > MAX_RISK_SCORE);
+
+        // Level boundaries line up with API tenths
+        assert_eq!(RiskLevel::from_score_100(RiskLevel::scale_from_api(20)), RiskLevel::Low);
+        assert_eq!(RiskLevel::from_score_100(RiskLevel::scale_from_api(21)), RiskLevel::Medium);
+
+        // Extremes and clamping
+        assert_eq!(RiskLevel::scale_from_api(0), 0);
+        assert_eq!(RiskLevel::scale_from_api(100), 100);
+        assert_eq!(RiskLevel::scale_from_api(u16::MAX), 100);
+        assert_eq!(RiskLevel::scale_to_api(255), 100);
+
+        // Round trip is exact
+        for tenths in 0..=100u16 {
+            assert_eq!(RiskLevel::scale_to_api(RiskLevel::scale_from_api(tenths)), tenths);
+        }
+    }
+
+    #[test]
+    fn test_attestation_from_api_score() {
+        let address = Pubkey::new_unique();
+        let current_slot = 100;
+
+        let attestation = RangeAttestation::from_api_score(address, 30, current_slot, 0, false);
+        assert_eq!(attestation.risk_score, 30);
+        assert!(verify_compliance(&attestation, &address, current_slot).passed);
+
+        let attestation = RangeAttestation::from_api_score(address, 31, current_slot, 0, false);
+        assert_eq!(attestation.risk_level, RiskLevel::Medium);
+        assert!(!verify_compliance(&attestation, &address, current_slot).passed);
+    }
+
     #[test]
     fn test_verify_compliance_passes_for_clean_address() {
         let address = Pubkey::new_unique();
@@ -334,7 +586,7 @@ mod tests {
 
         // One above MAX_RISK_SCORE should fail
         attestation.risk_score = MAX_RISK_SCORE + 1;
-        attestation.risk_level = RiskLevel::from_score(attestation.risk_score);
+        attestation.risk_level = RiskLevel::from_score_100(attestation.risk_score);
         let result = verify_compliance(&attestation, &address, current_slot);
         assert!(!result.passed);
         assert_eq!(result.error, Some(ComplianceError::HighRiskAddress));
@@ -351,18 +603,197 @@ mod tests {
         assert_eq!(result.error, Some(ComplianceError::StaleAttestation));
     }
 
+    #[test]
+    fn test_effective_risk_score_decays_with_age() {
+        let address = Pubkey::new_unique();
+        let attestation = RangeAttestation::new(address, 28, 100, 0, false);
+
+        assert_eq!(attestation.effective_risk_score(100), 28);
+        assert_eq!(attestation.effective_risk_score(109), 28);
+        assert_eq!(attestation.effective_risk_score(110), 29);
+        assert_eq!(attestation.effective_risk_score(150), 33);
+
+        // Clamped to the top of the scale
+        let critical = RangeAttestation::new(address, 99, 0, 0, false);
+        assert_eq!(critical.effective_risk_score(u64::MAX), MAX_SCALED_RISK_SCORE);
+    }
+
+    #[test]
+    fn test_verify_compliance_decay_tips_old_borderline_attestation() {
+        let address = Pubkey::new_unique();
+        let current_slot = 1_000;
+
+        // Borderline score, fresh: passes with decay
+        let fresh = RangeAttestation::new(address, MAX_RISK_SCORE - 1, current_slot, 0, false);
+        let result = verify_compliance_with_decay(&fresh, &address, current_slot, true);
+        assert!(result.passed);
+        assert_eq!(result.risk_score, MAX_RISK_SCORE - 1);
+
+        // Same score, 20 slots old: +2 decay pushes it over the threshold
+        let old = RangeAttestation::new(address, MAX_RISK_SCORE - 1, current_slot - 20, 0, false);
+        let result = verify_compliance_with_decay(&old, &address, current_slot, true);
+        assert!(!result.passed);
+        assert_eq!(result.risk_score, MAX_RISK_SCORE + 1);
+        assert_eq!(result.error, Some(ComplianceError::HighRiskAddress));
+
+        // Without decay the old attestation still passes
+        assert!(verify_compliance(&old, &address, current_slot).passed);
+    }
+
     #[test]
     fn test_serialize_attestation_data() {
         let address = Pubkey::new_unique();
-        let attestation = RangeAttestation::new(address, 15, 12345, 2, true);
+        let attestation = RangeAttestation::new(address, 15, 12345, 2, true).with_nonce(7);
 
         let data = serialize_attestation_data(&attestation);
 
         // Should contain: address (32) + risk_score (1) + slot (8) + num_hops (1) + malicious (1)
-        assert_eq!(data.len(), 43);
+        // + nonce (8)
+        assert_eq!(data.len(), 51);
         assert_eq!(&data[0..32], address.as_ref());
         assert_eq!(data[32], 15); // risk_score
         assert_eq!(data[41], 2); // num_hops
         assert_eq!(data[42], 1); // has_malicious_connections = true
+        assert_eq!(&data[43..51], &7u64.to_le_bytes()); // nonce
+    }
+
+    #[test]
+    fn test_verify_split_compliance_all_clean() {
+        let current_slot = 100;
+        let payouts: Vec<SplitPayout> = (0..3)
+            .map(|_| {
+                let recipient = Pubkey::new_unique();
+                SplitPayout {
+                    recipient,
+                    amount: 1_000,
+                    attestation: RangeAttestation::clean(recipient, current_slot - 5),
+                }
+            })
+            .collect();
+
+        assert!(verify_split_compliance(&payouts, current_slot, false).is_none());
+    }
+
+    #[test]
+    fn test_verify_split_compliance_one_bad_recipient_fails_batch() {
+        let current_slot = 100;
+        let fee_recipient = Pubkey::new_unique();
+        let principal_recipient = Pubkey::new_unique();
+        let payouts = vec![
+            SplitPayout {
+                recipient: fee_recipient,
+                amount: 1_000,
+                attestation: RangeAttestation::clean(fee_recipient, current_slot - 5),
+            },
+            SplitPayout {
+                recipient: principal_recipient,
+                amount: 99_000,
+                attestation: RangeAttestation::high_risk(principal_recipient, current_slot - 5),
+            },
+        ];
+
+        let (index, result) = verify_split_compliance(&payouts, current_slot, false).unwrap();
+        assert_eq!(index, 1);
+        assert!(!result.passed);
+        assert_eq!(result.error, Some(ComplianceError::MaliciousConnections));
+    }
+
+    #[test]
+    fn test_verify_split_compliance_rejects_attestation_for_other_recipient() {
+        let current_slot = 100;
+        let recipient = Pubkey::new_unique();
+        let payouts = vec![SplitPayout {
+            recipient,
+            amount: 1_000,
+            attestation: RangeAttestation::clean(Pubkey::new_unique(), current_slot),
+        }];
+
+        let (index, result) = verify_split_compliance(&payouts, current_slot, false).unwrap();
+        assert_eq!(index, 0);
+        assert_eq!(result.error, Some(ComplianceError::AddressMismatch));
+    }
+
+    #[test]
+    fn test_compliance_result_view() {
+        let address = Pubkey::new_unique();
+        let slot = 1_000;
+
+        let clean = verify_compliance(&RangeAttestation::clean(address, slot), &address, slot).view();
+        assert!(clean.passed);
+        assert_eq!(clean.reason, None);
+
+        let risky = verify_compliance(&RangeAttestation::high_risk(address, slot), &address, slot).view();
+        assert!(!risky.passed);
+        assert!(risky.reason.is_some());
+        assert_eq!(risky.risk_level, RiskLevel::from_score_100(risky.risk_score));
+    }
+
+    #[test]
+    fn test_serialize_attestation_data_golden_vector() {
+        let mut address = [0u8; 32];
+        for (i, byte) in address.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        let attestation = RangeAttestation::new(
+            Pubkey::new_from_array(address),
+            42,
+            0x0102_0304_0506_0708,
+            3,
+            true,
+        )
+        .with_nonce(0x1122_3344_5566_7788);
+
+        #[rustfmt::skip]
+        let expected: [u8; 51] = [
+            // address
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+            0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+            0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17,
+            0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f,
+            // risk_score
+            0x2a,
+            // attestation_slot (LE)
+            0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01,
+            // num_hops
+            0x03,
+            // has_malicious_connections
+            0x01,
+            // nonce (LE)
+            0x88, 0x77, 0x66, 0x55, 0x44, 0x33, 0x22, 0x11,
+        ];
+        assert_eq!(serialize_attestation_data(&attestation), expected.to_vec());
+
+        // Clean connections serialize as 0 in the same position
+        let mut clean = attestation;
+        clean.has_malicious_connections = false;
+        let mut expected_clean = expected;
+        expected_clean[42] = 0x00;
+        assert_eq!(serialize_attestation_data(&clean), expected_clean.to_vec());
+    }
+
+    #[test]
+    fn test_compliance_action_tiers() {
+        let address = Pubkey::new_unique();
+        let slot = 1_000;
+
+        let low = RangeAttestation::new(address, 10, slot, 0, false);
+        assert_eq!(verify_compliance(&low, &address, slot).action(), ComplianceAction::Allow);
+
+        // Passes the threshold but sits in the Medium tier
+        let elevated = RangeAttestation::new(address, 25, slot, 0, false);
+        let result = verify_compliance(&elevated, &address, slot);
+        assert!(result.passed);
+        assert_eq!(result.action(), ComplianceAction::AllowWithFlag);
+
+        let risky = RangeAttestation::high_risk(address, slot);
+        assert_eq!(verify_compliance(&risky, &address, slot).action(), ComplianceAction::Block);
+
+        // Boundary between the Low and Medium tiers
+        assert_eq!(ComplianceResult::pass(20).action(), ComplianceAction::Allow);
+        assert_eq!(ComplianceResult::pass(21).action(), ComplianceAction::AllowWithFlag);
+        assert_eq!(
+            ComplianceResult::fail(0, ComplianceError::StaleAttestation).action(),
+            ComplianceAction::Block
+        );
     }
 }
@@ -26,6 +26,10 @@ pub const NULLIFIER_SIZE: usize = 32;
 /// Size of a ZK proof (variable, but we use fixed buffer)
 pub const PROOF_SIZE: usize = 256;
 
+/// Proof byte that, filling the whole proof, marks it as explicitly invalid
+/// Lets tests exercise the mock verifier's rejection path
+pub const INVALID_PROOF_BYTE: u8 = 0xFF;
+
 /// Minimum deposit amount (0.001 SOL)
 pub const MIN_DEPOSIT_AMOUNT: u64 = 1_000_000;
 
@@ -41,6 +45,9 @@ pub struct ZkProof {
 
     /// Public inputs for the proof
     pub public_inputs: ZkPublicInputs,
+
+    /// Slot at which the proof was generated
+    pub proof_slot: u64,
 }
 
 impl ZkProof {
@@ -54,6 +61,19 @@ impl ZkProof {
                 amount,
                 merkle_root: [0u8; 32],
             },
+            proof_slot: 0,
+        }
+    }
+
+    /// Check if the proof is older than `max_age_slots` (0 disables the check)
+    /// Proofs claiming a future slot are treated as stale
+    pub fn is_stale(&self, current_slot: u64, max_age_slots: u64) -> bool {
+        if max_age_slots == 0 {
+            return false;
+        }
+        match current_slot.checked_sub(self.proof_slot) {
+            Some(age) => age > max_age_slots,
+            None => true,
         }
     }
 }
@@ -74,6 +94,31 @@ pub struct ZkPublicInputs {
     pub merkle_root: [u8; 32],
 }
 
+impl ZkPublicInputs {
+    /// Check amount bounds and that commitment and nullifier are non-zero
+    pub fn validate(&self) -> std::result::Result<(), DepositError> {
+        // 1. Validate amount bounds
+        if self.amount < MIN_DEPOSIT_AMOUNT {
+            return Err(DepositError::AmountTooLow);
+        }
+        if self.amount > MAX_DEPOSIT_AMOUNT {
+            return Err(DepositError::AmountTooHigh);
+        }
+
+        // 2. Validate commitment is not zero
+        if self.commitment.iter().all(|&b| b == 0) {
+            return Err(DepositError::InvalidCommitment);
+        }
+
+        // 3. Validate nullifier is not zero
+        if self.nullifier_hash.iter().all(|&b| b == 0) {
+            return Err(DepositError::InvalidCommitment);
+        }
+
+        Ok(())
+    }
+}
+
 /// Result of deposit proof verification
 #[derive(Debug, Clone)]
 pub struct DepositVerification {
@@ -147,29 +192,15 @@ impl DepositVerification {
 pub fn verify_deposit_proof(proof: &ZkProof) -> DepositVerification {
     let inputs = &proof.public_inputs;
 
-    // 1. Validate amount bounds
-    if inputs.amount < MIN_DEPOSIT_AMOUNT {
-        return DepositVerification::failure(DepositError::AmountTooLow);
-    }
-    if inputs.amount > MAX_DEPOSIT_AMOUNT {
-        return DepositVerification::failure(DepositError::AmountTooHigh);
+    // 1. Validate public inputs (amount bounds, commitment, nullifier)
+    if let Err(error) = inputs.validate() {
+        return DepositVerification::failure(error);
     }
 
-    // 2. Validate commitment is not zero
-    if inputs.commitment.iter().all(|&b| b == 0) {
-        return DepositVerification::failure(DepositError::InvalidCommitment);
-    }
-
-    // 3. Validate nullifier is not zero
-    if inputs.nullifier_hash.iter().all(|&b| b == 0) {
-        return DepositVerification::failure(DepositError::InvalidCommitment);
-    }
-
-    // 4. Mock proof verification
+    // 2. Mock proof verification
     // In production, this would verify the actual groth16/plonk proof
-    // For Phase 1, we accept any non-zero proof
-    let is_mock_valid = proof.proof_data.iter().any(|&b| b != 0)
-        || proof.proof_data.iter().all(|&b| b == 0); // Accept mock (all zeros)
+    // For Phase 1, we accept any proof except the all-0xFF sentinel
+    let is_mock_valid = !proof.proof_data.iter().all(|&b| b == INVALID_PROOF_BYTE);
 
     if !is_mock_valid {
         return DepositVerification::failure(DepositError::InvalidProof);
@@ -223,6 +254,14 @@ pub fn generate_nullifier_hash(nullifier: &[u8; 32]) -> [u8; 32] {
     hash
 }
 
+/// Check that a nullifier hash was derived from the given raw nullifier
+///
+/// The proof's public inputs only carry the hash, so this can only be
+/// checked when the depositor also supplies the raw nullifier (mock mode).
+pub fn validate_nullifier_consistency(nullifier_hash: &[u8; 32], nullifier: &[u8; 32]) -> bool {
+    generate_nullifier_hash(nullifier) == *nullifier_hash
+}
+
 /// Represents a delegation of a note to a TEE agent
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
 pub struct NoteDelegation {
@@ -343,6 +382,51 @@ mod tests {
         assert_eq!(result.error, Some(DepositError::InvalidCommitment));
     }
 
+    #[test]
+    fn test_public_inputs_validate() {
+        let valid = ZkProof::mock([1u8; 32], [2u8; 32], 100_000_000).public_inputs;
+        assert_eq!(valid.validate(), Ok(()));
+
+        let mut inputs = valid.clone();
+        inputs.amount = MIN_DEPOSIT_AMOUNT - 1;
+        assert_eq!(inputs.validate(), Err(DepositError::AmountTooLow));
+
+        let mut inputs = valid.clone();
+        inputs.amount = MAX_DEPOSIT_AMOUNT + 1;
+        assert_eq!(inputs.validate(), Err(DepositError::AmountTooHigh));
+
+        let mut inputs = valid.clone();
+        inputs.commitment = [0u8; 32];
+        assert_eq!(inputs.validate(), Err(DepositError::InvalidCommitment));
+
+        let mut inputs = valid.clone();
+        inputs.nullifier_hash = [0u8; 32];
+        assert_eq!(inputs.validate(), Err(DepositError::InvalidCommitment));
+
+        // Bounds are inclusive
+        let mut inputs = valid;
+        inputs.amount = MIN_DEPOSIT_AMOUNT;
+        assert_eq!(inputs.validate(), Ok(()));
+        inputs.amount = MAX_DEPOSIT_AMOUNT;
+        assert_eq!(inputs.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_proof_is_stale() {
+        let mut proof = ZkProof::mock([1u8; 32], [2u8; 32], MIN_DEPOSIT_AMOUNT);
+        proof.proof_slot = 1_000;
+
+        // Disabled
+        assert!(!proof.is_stale(u64::MAX, 0));
+
+        assert!(!proof.is_stale(1_000, 100));
+        assert!(!proof.is_stale(1_100, 100));
+        assert!(proof.is_stale(1_101, 100));
+
+        // Future proof slots are rejected
+        assert!(proof.is_stale(999, 100));
+    }
+
     #[test]
     fn test_generate_commitment() {
         let secret = [1u8; 32];
@@ -377,6 +461,21 @@ mod tests {
         assert_ne!(hash1, hash3);
     }
 
+    #[test]
+    fn test_validate_nullifier_consistency() {
+        let nullifier = [7u8; 32];
+        let nullifier_hash = generate_nullifier_hash(&nullifier);
+
+        assert!(validate_nullifier_consistency(&nullifier_hash, &nullifier));
+
+        // Hash from a different nullifier
+        let other_hash = generate_nullifier_hash(&[8u8; 32]);
+        assert!(!validate_nullifier_consistency(&other_hash, &nullifier));
+
+        // Raw nullifier passed as its own hash
+        assert!(!validate_nullifier_consistency(&nullifier, &nullifier));
+    }
+
     #[test]
     fn test_delegate_note_to_agent() {
         let commitment = [1u8; 32];
@@ -410,4 +509,18 @@ mod tests {
         let proof_above_max = ZkProof::mock(commitment, nullifier, MAX_DEPOSIT_AMOUNT + 1);
         assert!(!verify_deposit_proof(&proof_above_max).valid);
     }
+
+    #[test]
+    fn test_verify_deposit_proof_explicitly_invalid() {
+        let mut proof = ZkProof::mock([1u8; 32], [2u8; 32], 100_000_000);
+        proof.proof_data = [INVALID_PROOF_BYTE; PROOF_SIZE];
+
+        let result = verify_deposit_proof(&proof);
+        assert!(!result.valid);
+        assert_eq!(result.error, Some(DepositError::InvalidProof));
+
+        // A single differing byte is no longer the sentinel
+        proof.proof_data[0] = 0;
+        assert!(verify_deposit_proof(&proof).valid);
+    }
 }
@@ -70,7 +70,7 @@ describe("SPECTRE Protocol - Devnet Integration", () => {
 
         const modelHash = new Array(32).fill(42);
         const tx = await program.methods
-          .initialize(modelHash)
+          .initialize(modelHash, null)
           .accounts({
             authority: authority.publicKey,
             vault: vaultPda,
@@ -115,10 +115,11 @@ describe("SPECTRE Protocol - Devnet Integration", () => {
           amount: new anchor.BN(depositAmount),
           merkleRoot: new Array(32).fill(0),
         },
+        proofSlot: new anchor.BN(0),
       };
 
       const tx = await program.methods
-        .fundAgent(proof)
+        .fundAgent(proof, null)
         .accounts({
           depositor: authority.publicKey,
           vault: vaultPda,
@@ -255,7 +256,7 @@ describe("SPECTRE Protocol - Devnet Integration", () => {
       };
 
       const tx = await program.methods
-        .executeTrade(marketInput)
+        .executeTrade(marketInput, null)
         .accounts({
           authority: authority.publicKey,
           vault: vaultPda,
@@ -277,7 +278,8 @@ describe("SPECTRE Protocol - Devnet Integration", () => {
           { yes: {} },
           new anchor.BN(10_000_000), // 10M shares
           new anchor.BN(500_000), // 0.5 price
-          new anchor.BN(5_000_000) // 0.005 SOL invested
+          new anchor.BN(5_000_000), // 0.005 SOL invested
+          null
         )
         .accounts({
           authority: authority.publicKey,
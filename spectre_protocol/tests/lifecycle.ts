@@ -0,0 +1,356 @@
+/**
+ * SPECTRE Lifecycle Integration Test
+ *
+ * Runs a single vault through the full flow and checks that vault
+ * accounting stays consistent across instructions:
+ *   initialize -> fund_agent -> initialize_strategy -> execute_trade
+ *   -> open_position -> close_position -> request_withdrawal
+ *   -> complete_withdrawal
+ *
+ * All inputs are fixed so every expected balance can be derived exactly.
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { SpectreProtocol } from "../target/types/spectre_protocol";
+import { PublicKey, SystemProgram, LAMPORTS_PER_SOL, Keypair } from "@solana/web3.js";
+import assert from "assert";
+
+describe("SPECTRE Protocol - Lifecycle", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.SpectreProtocol as Program<SpectreProtocol>;
+
+  const VAULT_SEED = Buffer.from("spectre_vault");
+  const DEPOSIT_SEED = Buffer.from("user_deposit");
+  const WITHDRAWAL_SEED = Buffer.from("withdrawal");
+  const STRATEGY_CONFIG_SEED = Buffer.from("strategy_config");
+  const POSITION_SEED = Buffer.from("position");
+
+  // Fixtures
+  const depositAmount = 1 * LAMPORTS_PER_SOL;
+  const commitment = new Array(32).fill(200);
+  const nullifier = new Array(32).fill(201);
+  const positionShares = 200_000_000; // 200 shares
+  const positionEntryPrice = 500_000; // 0.50
+  const positionInvested = 100_000_000; // 0.1 SOL
+  const positionExitPrice = 600_000; // 0.60
+  const positionExitValue = (positionShares * positionExitPrice) / 1_000_000;
+  const withdrawAmount = 0.5 * LAMPORTS_PER_SOL;
+
+  let authority: Keypair;
+  let depositor: Keypair;
+  let recipient: Keypair;
+  let marketId: Keypair;
+  let vaultPda: PublicKey;
+  let userDepositPda: PublicKey;
+  let strategyConfigPda: PublicKey;
+  let positionPda: PublicKey;
+  let withdrawalRequestPda: PublicKey;
+
+  // Running expectations, updated as each step is applied
+  let expectedAvailable = 0;
+  let expectedVolume = 0;
+  let vaultLamportsAtInit = 0;
+
+  const fetchVault = () => program.account.spectreVault.fetch(vaultPda);
+
+  const assertVaultAccounting = async () => {
+    const vault = await fetchVault();
+    assert.strictEqual(vault.availableBalance.toNumber(), expectedAvailable);
+    assert.strictEqual(vault.totalVolume.toNumber(), expectedVolume);
+    return vault;
+  };
+
+  before(async () => {
+    authority = Keypair.generate();
+    depositor = Keypair.generate();
+    recipient = Keypair.generate();
+    marketId = Keypair.generate();
+
+    for (const [keypair, amount] of [
+      [authority, 2 * LAMPORTS_PER_SOL],
+      [depositor, 2 * LAMPORTS_PER_SOL],
+      [recipient, 0.1 * LAMPORTS_PER_SOL],
+    ] as [Keypair, number][]) {
+      const sig = await provider.connection.requestAirdrop(keypair.publicKey, amount);
+      await provider.connection.confirmTransaction(sig);
+    }
+
+    [vaultPda] = PublicKey.findProgramAddressSync(
+      [VAULT_SEED, authority.publicKey.toBuffer()],
+      program.programId
+    );
+    [userDepositPda] = PublicKey.findProgramAddressSync(
+      [DEPOSIT_SEED, vaultPda.toBuffer(), Buffer.from(commitment)],
+      program.programId
+    );
+    [strategyConfigPda] = PublicKey.findProgramAddressSync(
+      [STRATEGY_CONFIG_SEED, vaultPda.toBuffer()],
+      program.programId
+    );
+    [positionPda] = PublicKey.findProgramAddressSync(
+      [POSITION_SEED, vaultPda.toBuffer(), marketId.publicKey.toBuffer()],
+      program.programId
+    );
+  });
+
+  it("1. initializes an empty vault", async () => {
+    await program.methods
+      .initialize(new Array(32).fill(42), null)
+      .accounts({
+        authority: authority.publicKey,
+        vault: vaultPda,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const vault = await assertVaultAccounting();
+    assert.strictEqual(vault.totalDeposited.toNumber(), 0);
+    assert.strictEqual(vault.activePositions, 0);
+
+    vaultLamportsAtInit = await provider.connection.getBalance(vaultPda);
+  });
+
+  it("2. funds the vault with a ZK-proven deposit", async () => {
+    const proof = {
+      proofData: new Array(256).fill(0),
+      publicInputs: {
+        commitment,
+        nullifierHash: nullifier,
+        amount: new anchor.BN(depositAmount),
+        merkleRoot: new Array(32).fill(0),
+      },
+      proofSlot: new anchor.BN(0),
+    };
+
+    await program.methods
+      .fundAgent(proof, null)
+      .accounts({
+        depositor: depositor.publicKey,
+        vault: vaultPda,
+        userDeposit: userDepositPda,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([depositor])
+      .rpc();
+
+    expectedAvailable += depositAmount;
+
+    const vault = await assertVaultAccounting();
+    assert.strictEqual(vault.totalDeposited.toNumber(), depositAmount);
+    assert.strictEqual(vault.totalDepositsCount.toNumber(), 1);
+
+    const vaultLamports = await provider.connection.getBalance(vaultPda);
+    assert.strictEqual(vaultLamports - vaultLamportsAtInit, depositAmount);
+  });
+
+  it("3. initializes the strategy", async () => {
+    await program.methods
+      .initializeStrategy(null)
+      .accounts({
+        authority: authority.publicKey,
+        vault: vaultPda,
+        strategyConfig: strategyConfigPda,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const config = await program.account.strategyConfig.fetch(strategyConfigPda);
+    assert.strictEqual(config.isActive, true);
+    assert.strictEqual(config.totalSignals.toNumber(), 0);
+
+    await assertVaultAccounting();
+  });
+
+  it("4. executes a BUY trade sized from the available balance", async () => {
+    await program.methods
+      .executeTrade(
+        {
+          price: 300,
+          trend: 50,
+          volatility: 200,
+          timestamp: new anchor.BN(Date.now() / 1000),
+        },
+        null
+      )
+      .accounts({
+        authority: authority.publicKey,
+        vault: vaultPda,
+        strategyConfig: strategyConfigPda,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const vault = await fetchVault();
+    const traded = expectedAvailable - vault.availableBalance.toNumber();
+
+    // 5% of available for a normal signal, 10% for a strong one
+    assert.ok(
+      traded === expectedAvailable / 20 || traded === expectedAvailable / 10,
+      `unexpected trade size ${traded}`
+    );
+    assert.ok(vault.totalFeesPaid.toNumber() > 0);
+    assert.ok(vault.totalFeesPaid.toNumber() < traded);
+
+    expectedAvailable -= traded;
+    expectedVolume += traded;
+    await assertVaultAccounting();
+
+    const config = await program.account.strategyConfig.fetch(strategyConfigPda);
+    assert.strictEqual(config.actionableSignals.toNumber(), 1);
+    assert.strictEqual(config.tradesToday, 1);
+
+    // Trades are accounting-only; no lamports leave the vault
+    const vaultLamports = await provider.connection.getBalance(vaultPda);
+    assert.strictEqual(vaultLamports - vaultLamportsAtInit, depositAmount);
+  });
+
+  it("5. opens a position", async () => {
+    await program.methods
+      .openPosition(
+        marketId.publicKey,
+        { yes: {} },
+        new anchor.BN(positionShares),
+        new anchor.BN(positionEntryPrice),
+        new anchor.BN(positionInvested),
+        null
+      )
+      .accounts({
+        authority: authority.publicKey,
+        vault: vaultPda,
+        position: positionPda,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    expectedAvailable -= positionInvested;
+    expectedVolume += positionInvested;
+
+    const vault = await assertVaultAccounting();
+    assert.strictEqual(vault.activePositions, 1);
+
+    const position = await program.account.position.fetch(positionPda);
+    assert.deepStrictEqual(position.status, { open: {} });
+    assert.strictEqual(position.investedAmount.toNumber(), positionInvested);
+  });
+
+  it("6. closes the position at a profit", async () => {
+    await program.methods
+      .closePosition(new anchor.BN(positionExitPrice))
+      .accounts({
+        authority: authority.publicKey,
+        vault: vaultPda,
+        position: positionPda,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    expectedAvailable += positionExitValue;
+
+    const vault = await assertVaultAccounting();
+    assert.strictEqual(vault.activePositions, 0);
+
+    const position = await program.account.position.fetch(positionPda);
+    assert.deepStrictEqual(position.status, { closed: {} });
+    assert.strictEqual(position.realizedPnl.toNumber(), positionExitValue - positionInvested);
+  });
+
+  it("7. requests a withdrawal without moving funds", async () => {
+    const depositBefore = await program.account.userDeposit.fetch(userDepositPda);
+    [withdrawalRequestPda] = PublicKey.findProgramAddressSync(
+      [
+        WITHDRAWAL_SEED,
+        vaultPda.toBuffer(),
+        depositor.publicKey.toBuffer(),
+        userDepositPda.toBuffer(),
+        depositBefore.withdrawalNonce.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+
+    await program.methods
+      .requestWithdrawal(new anchor.BN(withdrawAmount))
+      .accounts({
+        requester: depositor.publicKey,
+        vault: vaultPda,
+        userDeposit: userDepositPda,
+        withdrawalRequest: withdrawalRequestPda,
+        recipient: recipient.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([depositor])
+      .rpc();
+
+    const withdrawal = await program.account.withdrawalRequest.fetch(withdrawalRequestPda);
+    assert.deepStrictEqual(withdrawal.status, { pending: {} });
+    assert.strictEqual(withdrawal.amount.toNumber(), withdrawAmount);
+
+    await assertVaultAccounting();
+  });
+
+  it("8. completes the withdrawal with a clean attestation", async () => {
+    const recipientBefore = await provider.connection.getBalance(recipient.publicKey);
+    const slot = await provider.connection.getSlot();
+
+    await program.methods
+      .completeWithdrawal({
+        address: recipient.publicKey,
+        riskScore: 0,
+        riskLevel: { low: {} },
+        attestationSlot: new anchor.BN(slot - 5),
+        numHops: 0,
+        oracleSignature: new Array(64).fill(0),
+        hasMaliciousConnections: false,
+        nonce: new anchor.BN(0),
+      })
+      .accounts({
+        requester: depositor.publicKey,
+        vault: vaultPda,
+        userDeposit: userDepositPda,
+        withdrawalRequest: withdrawalRequestPda,
+        recipient: recipient.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([depositor])
+      .rpc();
+
+    expectedAvailable -= withdrawAmount;
+
+    const vault = await assertVaultAccounting();
+    assert.strictEqual(vault.totalWithdrawalsCount.toNumber(), 1);
+    assert.strictEqual(vault.totalDeposited.toNumber(), depositAmount);
+
+    const deposit = await program.account.userDeposit.fetch(userDepositPda);
+    assert.strictEqual(deposit.amount.toNumber(), depositAmount - withdrawAmount);
+    assert.strictEqual(deposit.isActive, true);
+
+    const recipientAfter = await provider.connection.getBalance(recipient.publicKey);
+    assert.strictEqual(recipientAfter - recipientBefore, withdrawAmount);
+
+    const withdrawal = await program.account.withdrawalRequest.fetch(withdrawalRequestPda);
+    assert.deepStrictEqual(withdrawal.status, { completed: {} });
+  });
+
+  it("9. ends with consistent vault accounting", async () => {
+    const vault = await fetchVault();
+
+    // Only deposits and withdrawals move lamports
+    const vaultLamports = await provider.connection.getBalance(vaultPda);
+    assert.strictEqual(vaultLamports - vaultLamportsAtInit, depositAmount - withdrawAmount);
+
+    // Available balance reflects every step applied above
+    const trade = expectedVolume - positionInvested;
+    assert.strictEqual(
+      vault.availableBalance.toNumber(),
+      depositAmount - trade - positionInvested + positionExitValue - withdrawAmount
+    );
+    assert.strictEqual(vault.activePositions, 0);
+  });
+});
@@ -15,6 +15,13 @@ describe("SPECTRE Protocol - Phase 1", () => {
   const VAULT_SEED = Buffer.from("spectre_vault");
   const DEPOSIT_SEED = Buffer.from("user_deposit");
   const WITHDRAWAL_SEED = Buffer.from("withdrawal");
+  // Upgradeable program data account, whose upgrade authority may create the protocol config
+  const [programDataPda] = PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+  );
+  // Withdrawal requests are numbered per deposit, starting at 0
+  const withdrawalNonceSeed = (nonce: number) => new anchor.BN(nonce).toArrayLike(Buffer, "le", 8);
 
   // Test accounts
   let authority: Keypair;
@@ -65,7 +72,7 @@ describe("SPECTRE Protocol - Phase 1", () => {
       const modelHash = new Array(32).fill(42); // Test model hash
 
       const tx = await program.methods
-        .initialize(modelHash)
+        .initialize(modelHash, null)
         .accounts({
           authority: authority.publicKey,
           vault: vaultPda,
@@ -91,7 +98,7 @@ describe("SPECTRE Protocol - Phase 1", () => {
     it("should reject duplicate initialization", async () => {
       try {
         await program.methods
-          .initialize(null)
+          .initialize(null, null)
           .accounts({
             authority: authority.publicKey,
             vault: vaultPda,
@@ -130,10 +137,11 @@ describe("SPECTRE Protocol - Phase 1", () => {
           amount: new anchor.BN(depositAmount),
           merkleRoot: new Array(32).fill(0),
         },
+        proofSlot: new anchor.BN(0),
       };
 
       const tx = await program.methods
-        .fundAgent(proof)
+        .fundAgent(proof, null)
         .accounts({
           depositor: depositor.publicKey,
           vault: vaultPda,
@@ -180,11 +188,12 @@ describe("SPECTRE Protocol - Phase 1", () => {
           amount: new anchor.BN(tooLowAmount),
           merkleRoot: new Array(32).fill(0),
         },
+        proofSlot: new anchor.BN(0),
       };
 
       try {
         await program.methods
-          .fundAgent(proof)
+          .fundAgent(proof, null)
           .accounts({
             depositor: depositor.publicKey,
             vault: vaultPda,
@@ -215,11 +224,12 @@ describe("SPECTRE Protocol - Phase 1", () => {
           amount: new anchor.BN(depositAmount),
           merkleRoot: new Array(32).fill(0),
         },
+        proofSlot: new anchor.BN(0),
       };
 
       try {
         await program.methods
-          .fundAgent(proof)
+          .fundAgent(proof, null)
           .accounts({
             depositor: depositor.publicKey,
             vault: vaultPda,
@@ -252,6 +262,7 @@ describe("SPECTRE Protocol - Phase 1", () => {
           vaultPda.toBuffer(),
           depositor.publicKey.toBuffer(),
           userDepositPda.toBuffer(),
+          withdrawalNonceSeed(0),
         ],
         program.programId
       );
@@ -326,6 +337,7 @@ describe("SPECTRE Protocol - Phase 1", () => {
           vaultPda.toBuffer(),
           depositor.publicKey.toBuffer(),
           userDepositPda.toBuffer(),
+          withdrawalNonceSeed(0),
         ],
         program.programId
       );
@@ -348,6 +360,7 @@ describe("SPECTRE Protocol - Phase 1", () => {
         numHops: 0,
         oracleSignature: new Array(64).fill(0),
         hasMaliciousConnections: false,
+        nonce: new anchor.BN(0),
       };
 
       const tx = await program.methods
@@ -422,6 +435,7 @@ describe("SPECTRE Protocol - Phase 1", () => {
           vaultPda.toBuffer(),
           newDepositor.publicKey.toBuffer(),
           newUserDepositPda.toBuffer(),
+          withdrawalNonceSeed(0),
         ],
         program.programId
       );
@@ -435,10 +449,11 @@ describe("SPECTRE Protocol - Phase 1", () => {
           amount: new anchor.BN(newDepositAmount),
           merkleRoot: new Array(32).fill(0),
         },
+        proofSlot: new anchor.BN(0),
       };
 
       await program.methods
-        .fundAgent(proof)
+        .fundAgent(proof, null)
         .accounts({
           depositor: newDepositor.publicKey,
           vault: vaultPda,
@@ -475,6 +490,7 @@ describe("SPECTRE Protocol - Phase 1", () => {
         numHops: 2,
         oracleSignature: new Array(64).fill(0),
         hasMaliciousConnections: true,
+        nonce: new anchor.BN(0),
       };
 
       try {
@@ -525,7 +541,7 @@ describe("SPECTRE Protocol - Phase 1", () => {
       );
 
       await program.methods
-        .initialize(null) // No model hash
+        .initialize(null, null) // No model hash
         .accounts({
           authority: newAuthority.publicKey,
           vault: newVaultPda,
@@ -547,6 +563,141 @@ describe("SPECTRE Protocol - Phase 1", () => {
     });
   });
 
+  describe("Edge Cases - Protocol Config", () => {
+    const PROTOCOL_CONFIG_SEED = Buffer.from("protocol_config");
+    const [protocolConfigPda] = PublicKey.findProgramAddressSync(
+      [PROTOCOL_CONFIG_SEED],
+      program.programId
+    );
+
+    it("should reject protocol config creation by a non-upgrade-authority", async () => {
+      const stranger = Keypair.generate();
+      const airdrop = await provider.connection.requestAirdrop(stranger.publicKey, LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(airdrop);
+
+      try {
+        await program.methods
+          .initializeProtocolConfig(stranger.publicKey)
+          .accounts({
+            upgradeAuthority: stranger.publicKey,
+            protocolConfig: protocolConfigPda,
+            program: program.programId,
+            programData: programDataPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([stranger])
+          .rpc();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("Unauthorized"));
+      }
+
+      assert.strictEqual(await provider.connection.getAccountInfo(protocolConfigPda), null);
+    });
+  });
+
+  describe("Edge Cases - Authority Compliance", () => {
+    const PROTOCOL_CONFIG_SEED = Buffer.from("protocol_config");
+    const [protocolConfigPda] = PublicKey.findProgramAddressSync(
+      [PROTOCOL_CONFIG_SEED],
+      program.programId
+    );
+
+    const setRequireAuthorityCompliance = async (required: boolean) => {
+      await program.methods
+        .setRequireAuthorityCompliance(required)
+        .accounts({
+          admin: authority.publicKey,
+          protocolConfig: protocolConfigPda,
+        })
+        .signers([authority])
+        .rpc();
+    };
+
+    const initializeVault = async (newAuthority: Keypair, riskScore: number | null) => {
+      const airdrop = await provider.connection.requestAirdrop(newAuthority.publicKey, LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(airdrop);
+
+      const [newVaultPda] = PublicKey.findProgramAddressSync(
+        [VAULT_SEED, newAuthority.publicKey.toBuffer()],
+        program.programId
+      );
+      const slot = await provider.connection.getSlot();
+      const attestation =
+        riskScore === null
+          ? null
+          : {
+              address: newAuthority.publicKey,
+              riskScore,
+              riskLevel: riskScore > 80 ? { critical: {} } : { low: {} },
+              attestationSlot: new anchor.BN(slot - 5),
+              numHops: 0,
+              oracleSignature: new Array(64).fill(0),
+              hasMaliciousConnections: riskScore > 80,
+              nonce: new anchor.BN(0),
+            };
+
+      await program.methods
+        .initialize(null, attestation)
+        .accounts({
+          authority: newAuthority.publicKey,
+          vault: newVaultPda,
+          protocolConfig: protocolConfigPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([newAuthority])
+        .rpc();
+
+      return newVaultPda;
+    };
+
+    before(async () => {
+      // The local validator deploys with the provider wallet as upgrade authority
+      await program.methods
+        .initializeProtocolConfig(authority.publicKey)
+        .accounts({
+          upgradeAuthority: provider.wallet.publicKey,
+          protocolConfig: protocolConfigPda,
+          program: program.programId,
+          programData: programDataPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await setRequireAuthorityCompliance(true);
+    });
+
+    after(async () => {
+      await setRequireAuthorityCompliance(false);
+    });
+
+    it("should create a vault for a clean authority", async () => {
+      const newAuthority = Keypair.generate();
+      const newVaultPda = await initializeVault(newAuthority, 10);
+
+      const vault = await program.account.spectreVault.fetch(newVaultPda);
+      assert.ok(vault.authority.equals(newAuthority.publicKey));
+    });
+
+    it("should block a high-risk authority", async () => {
+      try {
+        await initializeVault(Keypair.generate(), 85);
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("HighRiskAddress"));
+      }
+    });
+
+    it("should require an attestation while enforcement is on", async () => {
+      try {
+        await initializeVault(Keypair.generate(), null);
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("AuthorityAttestationRequired"));
+      }
+    });
+  });
+
   describe("Edge Cases - Deposits", () => {
     it("should accept deposit at exact minimum amount", async () => {
       const minAmount = 1_000_000; // MIN_DEPOSIT_AMOUNT from privacy_bridge.rs
@@ -572,10 +723,11 @@ describe("SPECTRE Protocol - Phase 1", () => {
           amount: new anchor.BN(minAmount),
           merkleRoot: new Array(32).fill(0),
         },
+        proofSlot: new anchor.BN(0),
       };
 
       await program.methods
-        .fundAgent(proof)
+        .fundAgent(proof, null)
         .accounts({
           depositor: minDepositor.publicKey,
           vault: vaultPda,
@@ -605,11 +757,12 @@ describe("SPECTRE Protocol - Phase 1", () => {
           amount: new anchor.BN(depositAmount),
           merkleRoot: new Array(32).fill(0),
         },
+        proofSlot: new anchor.BN(0),
       };
 
       try {
         await program.methods
-          .fundAgent(proof)
+          .fundAgent(proof, null)
           .accounts({
             depositor: depositor.publicKey,
             vault: vaultPda,
@@ -624,6 +777,42 @@ describe("SPECTRE Protocol - Phase 1", () => {
       }
     });
 
+    it("should reject an explicitly invalid (all 0xFF) proof", async () => {
+      const invalidProofCommitment = new Array(32).fill(119);
+
+      const [invalidPda] = PublicKey.findProgramAddressSync(
+        [DEPOSIT_SEED, vaultPda.toBuffer(), Buffer.from(invalidProofCommitment)],
+        program.programId
+      );
+
+      const proof = {
+        proofData: new Array(256).fill(0xff), // INVALID_PROOF_BYTE sentinel
+        publicInputs: {
+          commitment: invalidProofCommitment,
+          nullifierHash: new Array(32).fill(120),
+          amount: new anchor.BN(depositAmount),
+          merkleRoot: new Array(32).fill(0),
+        },
+        proofSlot: new anchor.BN(0),
+      };
+
+      try {
+        await program.methods
+          .fundAgent(proof, null)
+          .accounts({
+            depositor: depositor.publicKey,
+            vault: vaultPda,
+            userDeposit: invalidPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([depositor])
+          .rpc();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("InvalidZkProof"));
+      }
+    });
+
     it("should reject duplicate commitment (same deposit twice)", async () => {
       // Try to create deposit with same commitment as existing one
       const [existingDepositPda] = PublicKey.findProgramAddressSync(
@@ -639,11 +828,12 @@ describe("SPECTRE Protocol - Phase 1", () => {
           amount: new anchor.BN(depositAmount),
           merkleRoot: new Array(32).fill(0),
         },
+        proofSlot: new anchor.BN(0),
       };
 
       try {
         await program.methods
-          .fundAgent(proof)
+          .fundAgent(proof, null)
           .accounts({
             depositor: depositor.publicKey,
             vault: vaultPda,
@@ -702,6 +892,7 @@ describe("SPECTRE Protocol - Phase 1", () => {
           vaultPda.toBuffer(),
           edgeDepositor.publicKey.toBuffer(),
           edgeDepositPda.toBuffer(),
+          withdrawalNonceSeed(0),
         ],
         program.programId
       );
@@ -715,10 +906,11 @@ describe("SPECTRE Protocol - Phase 1", () => {
           amount: new anchor.BN(edgeDepositAmount),
           merkleRoot: new Array(32).fill(0),
         },
+        proofSlot: new anchor.BN(0),
       };
 
       await program.methods
-        .fundAgent(proof)
+        .fundAgent(proof, null)
         .accounts({
           depositor: edgeDepositor.publicKey,
           vault: vaultPda,
@@ -743,6 +935,7 @@ describe("SPECTRE Protocol - Phase 1", () => {
           vaultPda.toBuffer(),
           unauthorized.publicKey.toBuffer(),
           edgeDepositPda.toBuffer(),
+          withdrawalNonceSeed(0),
         ],
         program.programId
       );
@@ -812,6 +1005,7 @@ describe("SPECTRE Protocol - Phase 1", () => {
         numHops: 0,
         oracleSignature: new Array(64).fill(0),
         hasMaliciousConnections: false,
+        nonce: new anchor.BN(0),
       };
 
       try {
@@ -854,6 +1048,7 @@ describe("SPECTRE Protocol - Phase 1", () => {
         numHops: 0,
         oracleSignature: new Array(64).fill(0),
         hasMaliciousConnections: false,
+        nonce: new anchor.BN(0),
       };
 
       try {
@@ -917,6 +1112,7 @@ describe("SPECTRE Protocol - Phase 1", () => {
           vaultPda.toBuffer(),
           boundaryDepositor.publicKey.toBuffer(),
           boundaryDepositPda.toBuffer(),
+          withdrawalNonceSeed(0),
         ],
         program.programId
       );
@@ -930,10 +1126,11 @@ describe("SPECTRE Protocol - Phase 1", () => {
           amount: new anchor.BN(0.1 * LAMPORTS_PER_SOL),
           merkleRoot: new Array(32).fill(0),
         },
+        proofSlot: new anchor.BN(0),
       };
 
       await program.methods
-        .fundAgent(proof)
+        .fundAgent(proof, null)
         .accounts({
           depositor: boundaryDepositor.publicKey,
           vault: vaultPda,
@@ -969,6 +1166,7 @@ describe("SPECTRE Protocol - Phase 1", () => {
         numHops: 1,
         oracleSignature: new Array(64).fill(0),
         hasMaliciousConnections: false,
+        nonce: new anchor.BN(0),
       };
 
       // This should succeed
@@ -1029,6 +1227,7 @@ describe("SPECTRE Protocol - Phase 1", () => {
           vaultPda.toBuffer(),
           aboveDepositor.publicKey.toBuffer(),
           aboveDepositPda.toBuffer(),
+          withdrawalNonceSeed(0),
         ],
         program.programId
       );
@@ -1042,10 +1241,11 @@ describe("SPECTRE Protocol - Phase 1", () => {
           amount: new anchor.BN(0.1 * LAMPORTS_PER_SOL),
           merkleRoot: new Array(32).fill(0),
         },
+        proofSlot: new anchor.BN(0),
       };
 
       await program.methods
-        .fundAgent(proof)
+        .fundAgent(proof, null)
         .accounts({
           depositor: aboveDepositor.publicKey,
           vault: vaultPda,
@@ -1081,6 +1281,7 @@ describe("SPECTRE Protocol - Phase 1", () => {
         numHops: 1,
         oracleSignature: new Array(64).fill(0),
         hasMaliciousConnections: false,
+        nonce: new anchor.BN(0),
       };
 
       try {
@@ -1119,309 +1320,5289 @@ describe("SPECTRE Protocol - Phase 1", () => {
     });
   });
 
-  describe("Final State Verification", () => {
-    it("should have consistent vault state", async () => {
-      const vault = await program.account.spectreVault.fetch(vaultPda);
+  describe("Request Full Withdrawal", () => {
+    let fullDepositor: Keypair;
+    let fullRecipient: Keypair;
+    let fullUserDepositPda: PublicKey;
+    let fullWithdrawalPda: PublicKey;
+    const fullCommitment = new Array(32).fill(80);
+    const fullNullifier = new Array(32).fill(81);
+    const fullDepositAmount = 0.07 * LAMPORTS_PER_SOL;
 
-      console.log("Final vault state:");
-      console.log("  Total deposited:", vault.totalDeposited.toNumber() / LAMPORTS_PER_SOL, "SOL");
-      console.log("  Available balance:", vault.availableBalance.toNumber() / LAMPORTS_PER_SOL, "SOL");
-      console.log("  Total deposits count:", vault.totalDepositsCount.toNumber());
-      console.log("  Total withdrawals count:", vault.totalWithdrawalsCount.toNumber());
-      console.log("  Is active:", vault.isActive);
+    before(async () => {
+      fullDepositor = Keypair.generate();
+      fullRecipient = Keypair.generate();
 
-      // Verify invariants
-      assert.ok(vault.availableBalance.toNumber() <= vault.totalDeposited.toNumber());
-      assert.ok(vault.totalDepositsCount.toNumber() >= 1);
-      assert.ok(vault.totalWithdrawalsCount.toNumber() >= 1);
-      assert.strictEqual(vault.isActive, true);
+      const airdrop = await provider.connection.requestAirdrop(
+        fullDepositor.publicKey,
+        2 * LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdrop);
+
+      [fullUserDepositPda] = PublicKey.findProgramAddressSync(
+        [DEPOSIT_SEED, vaultPda.toBuffer(), Buffer.from(fullCommitment)],
+        program.programId
+      );
+
+      [fullWithdrawalPda] = PublicKey.findProgramAddressSync(
+        [
+          WITHDRAWAL_SEED,
+          vaultPda.toBuffer(),
+          fullDepositor.publicKey.toBuffer(),
+          fullUserDepositPda.toBuffer(),
+          withdrawalNonceSeed(0),
+        ],
+        program.programId
+      );
+
+      const proof = {
+        proofData: new Array(256).fill(0),
+        publicInputs: {
+          commitment: fullCommitment,
+          nullifierHash: fullNullifier,
+          amount: new anchor.BN(fullDepositAmount),
+          merkleRoot: new Array(32).fill(0),
+        },
+        proofSlot: new anchor.BN(0),
+      };
+
+      await program.methods
+        .fundAgent(proof, null)
+        .accounts({
+          depositor: fullDepositor.publicKey,
+          vault: vaultPda,
+          userDeposit: fullUserDepositPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([fullDepositor])
+        .rpc();
     });
 
-    it("should have correct vault lamport balance", async () => {
-      const vaultBalance = await provider.connection.getBalance(vaultPda);
-      const vault = await program.account.spectreVault.fetch(vaultPda);
+    it("should request the entire deposit balance", async () => {
+      await program.methods
+        .requestFullWithdrawal()
+        .accounts({
+          requester: fullDepositor.publicKey,
+          vault: vaultPda,
+          userDeposit: fullUserDepositPda,
+          withdrawalRequest: fullWithdrawalPda,
+          recipient: fullRecipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([fullDepositor])
+        .rpc();
 
-      // Vault lamport balance should be at least the available balance
-      // (plus rent-exempt minimum for the account)
-      console.log("  Vault lamport balance:", vaultBalance / LAMPORTS_PER_SOL, "SOL");
-      assert.ok(vaultBalance > 0);
+      const deposit = await program.account.userDeposit.fetch(fullUserDepositPda);
+      const withdrawal = await program.account.withdrawalRequest.fetch(fullWithdrawalPda);
+
+      assert.strictEqual(withdrawal.amount.toNumber(), deposit.amount.toNumber());
+      assert.strictEqual(withdrawal.amount.toNumber(), fullDepositAmount);
+      assert.ok(withdrawal.recipient.equals(fullRecipient.publicKey));
+      assert.deepStrictEqual(withdrawal.status, { pending: {} });
     });
   });
 
-  // ============================================
-  // PHASE 2: THE BRAIN - TEE & Strategy Tests
-  // ============================================
+  describe("Deposit Compliance", () => {
+    let screenedDepositor: Keypair;
+
+    const depositProof = (commitmentByte: number) => ({
+      proofData: new Array(256).fill(0),
+      publicInputs: {
+        commitment: new Array(32).fill(commitmentByte),
+        nullifierHash: new Array(32).fill(commitmentByte + 1),
+        amount: new anchor.BN(0.05 * LAMPORTS_PER_SOL),
+        merkleRoot: new Array(32).fill(0),
+      },
+      proofSlot: new anchor.BN(0),
+    });
 
-  describe("Phase 2 - Initialize Strategy", () => {
-    const STRATEGY_CONFIG_SEED = Buffer.from("strategy_config");
-    let strategyConfigPda: PublicKey;
+    const depositPdaFor = (commitmentByte: number) =>
+      PublicKey.findProgramAddressSync(
+        [DEPOSIT_SEED, vaultPda.toBuffer(), Buffer.from(new Array(32).fill(commitmentByte))],
+        program.programId
+      )[0];
 
     before(async () => {
-      [strategyConfigPda] = PublicKey.findProgramAddressSync(
-        [STRATEGY_CONFIG_SEED, vaultPda.toBuffer()],
-        program.programId
+      screenedDepositor = Keypair.generate();
+
+      const airdrop = await provider.connection.requestAirdrop(
+        screenedDepositor.publicKey,
+        2 * LAMPORTS_PER_SOL
       );
-    });
+      await provider.connection.confirmTransaction(airdrop);
 
-    it("should initialize strategy with default params", async () => {
-      const tx = await program.methods
-        .initializeStrategy(null) // Use default params
+      await program.methods
+        .setRequireDepositCompliance(true)
         .accounts({
           authority: authority.publicKey,
           vault: vaultPda,
-          strategyConfig: strategyConfigPda,
-          systemProgram: SystemProgram.programId,
         })
         .signers([authority])
         .rpc();
-
-      console.log("Initialize strategy transaction:", tx);
-
-      // Verify strategy config state
-      const config = await program.account.strategyConfig.fetch(strategyConfigPda);
-      assert.strictEqual(config.vault.toString(), vaultPda.toString());
-      assert.strictEqual(config.authority.toString(), authority.publicKey.toString());
-      assert.strictEqual(config.priceThresholdLow, 350);
-      assert.strictEqual(config.priceThresholdHigh, 650);
-      assert.strictEqual(config.trendThreshold, 100);
-      assert.strictEqual(config.volatilityCap, 400);
-      assert.strictEqual(config.isActive, true);
-      assert.strictEqual(config.totalSignals.toNumber(), 0);
     });
 
-    it("should reject duplicate strategy initialization", async () => {
-      try {
-        await program.methods
-          .initializeStrategy(null)
-          .accounts({
-            authority: authority.publicKey,
-            vault: vaultPda,
-            strategyConfig: strategyConfigPda,
-            systemProgram: SystemProgram.programId,
-          })
-          .signers([authority])
-          .rpc();
-        assert.fail("Should have thrown an error");
-      } catch (err) {
-        assert.ok(err.toString().includes("already in use"));
-      }
+    after(async () => {
+      await program.methods
+        .setRequireDepositCompliance(false)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .rpc();
     });
-  });
 
-  describe("Phase 2 - Delegate to TEE", () => {
-    it("should delegate vault to TEE successfully", async () => {
-      // Verify vault is not delegated before
-      let vault = await program.account.spectreVault.fetch(vaultPda);
-      assert.strictEqual(vault.isDelegated, false);
+    it("should accept a deposit from a clean depositor", async () => {
+      const slot = await provider.connection.getSlot();
+      const attestation = {
+        address: screenedDepositor.publicKey,
+        riskScore: 10,
+        riskLevel: { low: {} },
+        attestationSlot: new anchor.BN(slot - 5),
+        numHops: 0,
+        oracleSignature: new Array(64).fill(0),
+        hasMaliciousConnections: false,
+        nonce: new anchor.BN(0),
+      };
 
-      const tx = await program.methods
-        .delegateToTee()
+      await program.methods
+        .fundAgent(depositProof(82), attestation)
         .accounts({
-          authority: authority.publicKey,
+          depositor: screenedDepositor.publicKey,
           vault: vaultPda,
-          delegationProgram: null, // No actual TEE in local testing
+          userDeposit: depositPdaFor(82),
           systemProgram: SystemProgram.programId,
         })
-        .signers([authority])
+        .signers([screenedDepositor])
         .rpc();
 
-      console.log("Delegate to TEE transaction:", tx);
-
-      // Verify vault is now delegated
-      vault = await program.account.spectreVault.fetch(vaultPda);
-      assert.strictEqual(vault.isDelegated, true);
+      const deposit = await program.account.userDeposit.fetch(depositPdaFor(82));
+      assert.ok(deposit.owner.equals(screenedDepositor.publicKey));
+      assert.strictEqual(deposit.isActive, true);
     });
 
-    it("should reject double delegation", async () => {
+    it("should block a deposit from a high-risk depositor", async () => {
+      const slot = await provider.connection.getSlot();
+      const attestation = {
+        address: screenedDepositor.publicKey,
+        riskScore: 85,
+        riskLevel: { critical: {} },
+        attestationSlot: new anchor.BN(slot - 5),
+        numHops: 2,
+        oracleSignature: new Array(64).fill(0),
+        hasMaliciousConnections: true,
+        nonce: new anchor.BN(0),
+      };
+
       try {
         await program.methods
-          .delegateToTee()
+          .fundAgent(depositProof(84), attestation)
           .accounts({
-            authority: authority.publicKey,
+            depositor: screenedDepositor.publicKey,
             vault: vaultPda,
-            delegationProgram: null,
+            userDeposit: depositPdaFor(84),
             systemProgram: SystemProgram.programId,
           })
-          .signers([authority])
+          .signers([screenedDepositor])
           .rpc();
         assert.fail("Should have thrown an error");
       } catch (err) {
-        assert.ok(err.toString().includes("VaultAlreadyDelegated"));
+        assert.ok(err.toString().includes("HighRiskAddress"));
       }
     });
 
-    it("should reject delegation from non-authority", async () => {
-      const notAuthority = Keypair.generate();
+    it("should not move a rejected depositor's SOL", async () => {
+      const slot = await provider.connection.getSlot();
+      const attestation = {
+        address: screenedDepositor.publicKey,
+        riskScore: 85,
+        riskLevel: { critical: {} },
+        attestationSlot: new anchor.BN(slot - 5),
+        numHops: 2,
+        oracleSignature: new Array(64).fill(0),
+        hasMaliciousConnections: true,
+        nonce: new anchor.BN(0),
+      };
+
+      const depositorBefore = await provider.connection.getBalance(screenedDepositor.publicKey);
+      const vaultLamportsBefore = await provider.connection.getBalance(vaultPda);
+
+      try {
+        await program.methods
+          .fundAgent(depositProof(118), attestation)
+          .accounts({
+            depositor: screenedDepositor.publicKey,
+            vault: vaultPda,
+            userDeposit: depositPdaFor(118),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([screenedDepositor])
+          .rpc();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("HighRiskAddress"));
+      }
+
+      // Screening runs before the transfer, so nothing moved and no record exists
+      assert.strictEqual(
+        await provider.connection.getBalance(screenedDepositor.publicKey),
+        depositorBefore
+      );
+      assert.strictEqual(await provider.connection.getBalance(vaultPda), vaultLamportsBefore);
+      assert.strictEqual(await provider.connection.getAccountInfo(depositPdaFor(118)), null);
+    });
+
+    it("should require an attestation when deposit compliance is enabled", async () => {
+      try {
+        await program.methods
+          .fundAgent(depositProof(86), null)
+          .accounts({
+            depositor: screenedDepositor.publicKey,
+            vault: vaultPda,
+            userDeposit: depositPdaFor(86),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([screenedDepositor])
+          .rpc();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("DepositAttestationRequired"));
+      }
+    });
+  });
+
+  describe("Compliance Retry After Rejection", () => {
+    let retryDepositor: Keypair;
+    let retryRecipient: Keypair;
+    let retryUserDepositPda: PublicKey;
+    let retryWithdrawalPda: PublicKey;
+    const retryCommitment = new Array(32).fill(90);
+    const retryNullifier = new Array(32).fill(91);
+    const retryDepositAmount = 0.05 * LAMPORTS_PER_SOL;
+
+    const attestationFor = (address: PublicKey, slot: number, highRisk: boolean) => ({
+      address,
+      riskScore: highRisk ? 85 : 10,
+      riskLevel: highRisk ? { critical: {} } : { low: {} },
+      attestationSlot: new anchor.BN(slot - 5),
+      numHops: highRisk ? 2 : 0,
+      oracleSignature: new Array(64).fill(0),
+      hasMaliciousConnections: highRisk,
+      nonce: new anchor.BN(0),
+    });
+
+    before(async () => {
+      retryDepositor = Keypair.generate();
+      retryRecipient = Keypair.generate();
+
       const airdrop = await provider.connection.requestAirdrop(
-        notAuthority.publicKey,
-        1 * LAMPORTS_PER_SOL
+        retryDepositor.publicKey,
+        2 * LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdrop);
+
+      [retryUserDepositPda] = PublicKey.findProgramAddressSync(
+        [DEPOSIT_SEED, vaultPda.toBuffer(), Buffer.from(retryCommitment)],
+        program.programId
+      );
+
+      [retryWithdrawalPda] = PublicKey.findProgramAddressSync(
+        [
+          WITHDRAWAL_SEED,
+          vaultPda.toBuffer(),
+          retryDepositor.publicKey.toBuffer(),
+          retryUserDepositPda.toBuffer(),
+          withdrawalNonceSeed(0),
+        ],
+        program.programId
+      );
+
+      const proof = {
+        proofData: new Array(256).fill(0),
+        publicInputs: {
+          commitment: retryCommitment,
+          nullifierHash: retryNullifier,
+          amount: new anchor.BN(retryDepositAmount),
+          merkleRoot: new Array(32).fill(0),
+        },
+        proofSlot: new anchor.BN(0),
+      };
+
+      await program.methods
+        .fundAgent(proof, null)
+        .accounts({
+          depositor: retryDepositor.publicKey,
+          vault: vaultPda,
+          userDeposit: retryUserDepositPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([retryDepositor])
+        .rpc();
+
+      await program.methods
+        .requestWithdrawal(new anchor.BN(retryDepositAmount))
+        .accounts({
+          requester: retryDepositor.publicKey,
+          vault: vaultPda,
+          userDeposit: retryUserDepositPda,
+          withdrawalRequest: retryWithdrawalPda,
+          recipient: retryRecipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([retryDepositor])
+        .rpc();
+    });
+
+    it("should approve a retry with a fresh attestation after rejection", async () => {
+      const accounts = {
+        requester: retryDepositor.publicKey,
+        vault: vaultPda,
+        userDeposit: retryUserDepositPda,
+        withdrawalRequest: retryWithdrawalPda,
+      };
+
+      // First attempt fails compliance and is recorded as rejected
+      let slot = await provider.connection.getSlot();
+      await program.methods
+        .verifyWithdrawalCompliance(attestationFor(retryRecipient.publicKey, slot, true))
+        .accounts(accounts)
+        .signers([retryDepositor])
+        .rpc();
+
+      let withdrawal = await program.account.withdrawalRequest.fetch(retryWithdrawalPda);
+      assert.deepStrictEqual(withdrawal.status, { rejected: {} });
+      assert.strictEqual(withdrawal.riskScore, 85);
+
+      // Retry with a clean attestation
+      slot = await provider.connection.getSlot();
+      await program.methods
+        .verifyWithdrawalCompliance(attestationFor(retryRecipient.publicKey, slot, false))
+        .accounts(accounts)
+        .signers([retryDepositor])
+        .rpc();
+
+      withdrawal = await program.account.withdrawalRequest.fetch(retryWithdrawalPda);
+      assert.deepStrictEqual(withdrawal.status, { approved: {} });
+      assert.strictEqual(withdrawal.riskScore, 10);
+    });
+  });
+
+  describe("Compliance Authority", () => {
+    let officer: Keypair;
+    let officerDepositor: Keypair;
+    let officerRecipient: Keypair;
+    let officerUserDepositPda: PublicKey;
+    let officerWithdrawalPda: PublicKey;
+    const officerCommitment = new Array(32).fill(92);
+    const officerNullifier = new Array(32).fill(93);
+    const officerDepositAmount = 0.05 * LAMPORTS_PER_SOL;
+
+    const cleanAttestation = async () => {
+      const slot = await provider.connection.getSlot();
+      return {
+        address: officerRecipient.publicKey,
+        riskScore: 10,
+        riskLevel: { low: {} },
+        attestationSlot: new anchor.BN(slot - 5),
+        numHops: 0,
+        oracleSignature: new Array(64).fill(0),
+        hasMaliciousConnections: false,
+        nonce: new anchor.BN(0),
+      };
+    };
+
+    before(async () => {
+      officer = Keypair.generate();
+      officerDepositor = Keypair.generate();
+      officerRecipient = Keypair.generate();
+
+      const airdrop = await provider.connection.requestAirdrop(
+        officerDepositor.publicKey,
+        2 * LAMPORTS_PER_SOL
       );
       await provider.connection.confirmTransaction(airdrop);
 
+      [officerUserDepositPda] = PublicKey.findProgramAddressSync(
+        [DEPOSIT_SEED, vaultPda.toBuffer(), Buffer.from(officerCommitment)],
+        program.programId
+      );
+
+      [officerWithdrawalPda] = PublicKey.findProgramAddressSync(
+        [
+          WITHDRAWAL_SEED,
+          vaultPda.toBuffer(),
+          officerDepositor.publicKey.toBuffer(),
+          officerUserDepositPda.toBuffer(),
+          withdrawalNonceSeed(0),
+        ],
+        program.programId
+      );
+
+      const proof = {
+        proofData: new Array(256).fill(0),
+        publicInputs: {
+          commitment: officerCommitment,
+          nullifierHash: officerNullifier,
+          amount: new anchor.BN(officerDepositAmount),
+          merkleRoot: new Array(32).fill(0),
+        },
+        proofSlot: new anchor.BN(0),
+      };
+
+      await program.methods
+        .fundAgent(proof, null)
+        .accounts({
+          depositor: officerDepositor.publicKey,
+          vault: vaultPda,
+          userDeposit: officerUserDepositPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([officerDepositor])
+        .rpc();
+
+      await program.methods
+        .requestWithdrawal(new anchor.BN(officerDepositAmount))
+        .accounts({
+          requester: officerDepositor.publicKey,
+          vault: vaultPda,
+          userDeposit: officerUserDepositPda,
+          withdrawalRequest: officerWithdrawalPda,
+          recipient: officerRecipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([officerDepositor])
+        .rpc();
+
+      await program.methods
+        .setComplianceAuthority(officer.publicKey)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .rpc();
+    });
+
+    after(async () => {
+      await program.methods
+        .setComplianceAuthority(PublicKey.default)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .rpc();
+    });
+
+    it("should reject compliance verification by a random signer", async () => {
+      const impostor = Keypair.generate();
+
       try {
         await program.methods
-          .delegateToTee()
+          .verifyWithdrawalCompliance(await cleanAttestation())
           .accounts({
-            authority: notAuthority.publicKey,
+            requester: officerDepositor.publicKey,
             vault: vaultPda,
-            delegationProgram: null,
-            systemProgram: SystemProgram.programId,
+            userDeposit: officerUserDepositPda,
+            withdrawalRequest: officerWithdrawalPda,
+            complianceAuthority: impostor.publicKey,
           })
-          .signers([notAuthority])
+          .signers([officerDepositor, impostor])
+          .rpc();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("Unauthorized"));
+      }
+
+      const withdrawal = await program.account.withdrawalRequest.fetch(officerWithdrawalPda);
+      assert.deepStrictEqual(withdrawal.status, { pending: {} });
+    });
+
+    it("should approve compliance signed by the compliance authority", async () => {
+      await program.methods
+        .verifyWithdrawalCompliance(await cleanAttestation())
+        .accounts({
+          requester: officerDepositor.publicKey,
+          vault: vaultPda,
+          userDeposit: officerUserDepositPda,
+          withdrawalRequest: officerWithdrawalPda,
+          complianceAuthority: officer.publicKey,
+        })
+        .signers([officerDepositor, officer])
+        .rpc();
+
+      const withdrawal = await program.account.withdrawalRequest.fetch(officerWithdrawalPda);
+      assert.deepStrictEqual(withdrawal.status, { approved: {} });
+    });
+  });
+
+  describe("Compliance Verify Throttle", () => {
+    let throttleDepositor: Keypair;
+    let throttleRecipient: Keypair;
+    let throttleUserDepositPda: PublicKey;
+    let throttleWithdrawalPda: PublicKey;
+    const throttleCommitment = new Array(32).fill(94);
+    const throttleNullifier = new Array(32).fill(95);
+    const throttleDepositAmount = 0.05 * LAMPORTS_PER_SOL;
+
+    const attestationFor = (address: PublicKey, slot: number, highRisk: boolean) => ({
+      address,
+      riskScore: highRisk ? 85 : 10,
+      riskLevel: highRisk ? { critical: {} } : { low: {} },
+      attestationSlot: new anchor.BN(slot - 5),
+      numHops: highRisk ? 2 : 0,
+      oracleSignature: new Array(64).fill(0),
+      hasMaliciousConnections: highRisk,
+      nonce: new anchor.BN(0),
+    });
+
+    const setMinVerifyInterval = async (intervalSlots: number) => {
+      await program.methods
+        .setMinVerifyInterval(new anchor.BN(intervalSlots))
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .rpc();
+    };
+
+    before(async () => {
+      throttleDepositor = Keypair.generate();
+      throttleRecipient = Keypair.generate();
+
+      const airdrop = await provider.connection.requestAirdrop(
+        throttleDepositor.publicKey,
+        2 * LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdrop);
+
+      [throttleUserDepositPda] = PublicKey.findProgramAddressSync(
+        [DEPOSIT_SEED, vaultPda.toBuffer(), Buffer.from(throttleCommitment)],
+        program.programId
+      );
+
+      [throttleWithdrawalPda] = PublicKey.findProgramAddressSync(
+        [
+          WITHDRAWAL_SEED,
+          vaultPda.toBuffer(),
+          throttleDepositor.publicKey.toBuffer(),
+          throttleUserDepositPda.toBuffer(),
+          withdrawalNonceSeed(0),
+        ],
+        program.programId
+      );
+
+      const proof = {
+        proofData: new Array(256).fill(0),
+        publicInputs: {
+          commitment: throttleCommitment,
+          nullifierHash: throttleNullifier,
+          amount: new anchor.BN(throttleDepositAmount),
+          merkleRoot: new Array(32).fill(0),
+        },
+        proofSlot: new anchor.BN(0),
+      };
+
+      await program.methods
+        .fundAgent(proof, null)
+        .accounts({
+          depositor: throttleDepositor.publicKey,
+          vault: vaultPda,
+          userDeposit: throttleUserDepositPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([throttleDepositor])
+        .rpc();
+
+      await program.methods
+        .requestWithdrawal(new anchor.BN(throttleDepositAmount))
+        .accounts({
+          requester: throttleDepositor.publicKey,
+          vault: vaultPda,
+          userDeposit: throttleUserDepositPda,
+          withdrawalRequest: throttleWithdrawalPda,
+          recipient: throttleRecipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([throttleDepositor])
+        .rpc();
+
+      await setMinVerifyInterval(1_000);
+    });
+
+    after(async () => {
+      await setMinVerifyInterval(0);
+    });
+
+    it("should throttle back-to-back verifications", async () => {
+      const accounts = {
+        requester: throttleDepositor.publicKey,
+        vault: vaultPda,
+        userDeposit: throttleUserDepositPda,
+        withdrawalRequest: throttleWithdrawalPda,
+      };
+
+      // First attempt is rejected by compliance but still counts
+      let slot = await provider.connection.getSlot();
+      await program.methods
+        .verifyWithdrawalCompliance(attestationFor(throttleRecipient.publicKey, slot, true))
+        .accounts(accounts)
+        .signers([throttleDepositor])
+        .rpc();
+
+      // Immediate retry is throttled even with a clean attestation
+      slot = await provider.connection.getSlot();
+      try {
+        await program.methods
+          .verifyWithdrawalCompliance(attestationFor(throttleRecipient.publicKey, slot, false))
+          .accounts(accounts)
+          .signers([throttleDepositor])
           .rpc();
         assert.fail("Should have thrown an error");
       } catch (err) {
-        // Should fail due to PDA constraint (vault doesn't match authority)
-        assert.ok(err.toString().includes("Error") || err.toString().includes("constraint"));
+        assert.ok(err.toString().includes("VerifyTooFrequent"));
+      }
+
+      const withdrawal = await program.account.withdrawalRequest.fetch(throttleWithdrawalPda);
+      assert.deepStrictEqual(withdrawal.status, { rejected: {} });
+      assert.strictEqual(withdrawal.riskScore, 85);
+    });
+  });
+
+  describe("Max Single Withdrawal", () => {
+    let capDepositor: Keypair;
+    let capRecipient: Keypair;
+    let capUserDepositPda: PublicKey;
+    let capWithdrawalPda: PublicKey;
+    const capCommitment = new Array(32).fill(96);
+    const capNullifier = new Array(32).fill(97);
+    const capDepositAmount = 0.05 * LAMPORTS_PER_SOL;
+    const withdrawalCap = 0.03 * LAMPORTS_PER_SOL;
+
+    const setMaxSingleWithdrawal = async (maxAmount: number) => {
+      await program.methods
+        .setMaxSingleWithdrawal(new anchor.BN(maxAmount))
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .rpc();
+    };
+
+    const requestWithdrawal = (amount: number) =>
+      program.methods
+        .requestWithdrawal(new anchor.BN(amount))
+        .accounts({
+          requester: capDepositor.publicKey,
+          vault: vaultPda,
+          userDeposit: capUserDepositPda,
+          withdrawalRequest: capWithdrawalPda,
+          recipient: capRecipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([capDepositor])
+        .rpc();
+
+    before(async () => {
+      capDepositor = Keypair.generate();
+      capRecipient = Keypair.generate();
+
+      const airdrop = await provider.connection.requestAirdrop(
+        capDepositor.publicKey,
+        2 * LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdrop);
+
+      [capUserDepositPda] = PublicKey.findProgramAddressSync(
+        [DEPOSIT_SEED, vaultPda.toBuffer(), Buffer.from(capCommitment)],
+        program.programId
+      );
+
+      [capWithdrawalPda] = PublicKey.findProgramAddressSync(
+        [
+          WITHDRAWAL_SEED,
+          vaultPda.toBuffer(),
+          capDepositor.publicKey.toBuffer(),
+          capUserDepositPda.toBuffer(),
+          withdrawalNonceSeed(0),
+        ],
+        program.programId
+      );
+
+      const proof = {
+        proofData: new Array(256).fill(0),
+        publicInputs: {
+          commitment: capCommitment,
+          nullifierHash: capNullifier,
+          amount: new anchor.BN(capDepositAmount),
+          merkleRoot: new Array(32).fill(0),
+        },
+        proofSlot: new anchor.BN(0),
+      };
+
+      await program.methods
+        .fundAgent(proof, null)
+        .accounts({
+          depositor: capDepositor.publicKey,
+          vault: vaultPda,
+          userDeposit: capUserDepositPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([capDepositor])
+        .rpc();
+
+      await setMaxSingleWithdrawal(withdrawalCap);
+    });
+
+    after(async () => {
+      await setMaxSingleWithdrawal(0);
+    });
+
+    it("should reject a withdrawal request above the cap", async () => {
+      try {
+        await requestWithdrawal(withdrawalCap + 1);
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("WithdrawalTooLarge"));
+      }
+    });
+
+    it("should accept a withdrawal request at the cap", async () => {
+      await requestWithdrawal(withdrawalCap);
+
+      const withdrawal = await program.account.withdrawalRequest.fetch(capWithdrawalPda);
+      assert.strictEqual(withdrawal.amount.toNumber(), withdrawalCap);
+      assert.deepStrictEqual(withdrawal.status, { pending: {} });
+    });
+  });
+
+  describe("Deposit Proof Freshness", () => {
+    let freshDepositor: Keypair;
+    const maxProofAgeSlots = 20;
+    const depositAmount = 0.05 * LAMPORTS_PER_SOL;
+
+    const setMaxProofAge = async (maxAgeSlots: number) => {
+      await program.methods
+        .setMaxProofAge(new anchor.BN(maxAgeSlots))
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .rpc();
+    };
+
+    const fundWithProofSlot = async (commitmentByte: number, proofSlot: number) => {
+      const commitment = new Array(32).fill(commitmentByte);
+      const [userDepositPda] = PublicKey.findProgramAddressSync(
+        [DEPOSIT_SEED, vaultPda.toBuffer(), Buffer.from(commitment)],
+        program.programId
+      );
+
+      const proof = {
+        proofData: new Array(256).fill(0),
+        publicInputs: {
+          commitment,
+          nullifierHash: new Array(32).fill(commitmentByte + 1),
+          amount: new anchor.BN(depositAmount),
+          merkleRoot: new Array(32).fill(0),
+        },
+        proofSlot: new anchor.BN(proofSlot),
+      };
+
+      await program.methods
+        .fundAgent(proof, null)
+        .accounts({
+          depositor: freshDepositor.publicKey,
+          vault: vaultPda,
+          userDeposit: userDepositPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([freshDepositor])
+        .rpc();
+
+      return userDepositPda;
+    };
+
+    before(async () => {
+      freshDepositor = Keypair.generate();
+
+      const airdrop = await provider.connection.requestAirdrop(
+        freshDepositor.publicKey,
+        2 * LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdrop);
+
+      await setMaxProofAge(maxProofAgeSlots);
+    });
+
+    after(async () => {
+      await setMaxProofAge(0);
+    });
+
+    it("should accept a fresh proof", async () => {
+      const slot = await provider.connection.getSlot();
+      const userDepositPda = await fundWithProofSlot(98, slot);
+
+      const deposit = await program.account.userDeposit.fetch(userDepositPda);
+      assert.strictEqual(deposit.amount.toNumber(), depositAmount);
+    });
+
+    it("should reject a proof older than the max age", async () => {
+      const slot = await provider.connection.getSlot();
+
+      try {
+        await fundWithProofSlot(100, slot - maxProofAgeSlots - 10);
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("StaleProof"));
+      }
+    });
+  });
+
+  describe("Reconcile Vault", () => {
+    it("should find no discrepancy in a consistent vault", async () => {
+      const before = await program.account.spectreVault.fetch(vaultPda);
+
+      await program.methods
+        .reconcileVault(false)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .rpc();
+
+      const after = await program.account.spectreVault.fetch(vaultPda);
+      assert.ok(after.availableBalance.eq(before.availableBalance));
+
+      // Available balance and reserves never exceed the lamports held above rent
+      const vaultLamports = await provider.connection.getBalance(vaultPda);
+      const info = await provider.connection.getAccountInfo(vaultPda);
+      const rent = await provider.connection.getMinimumBalanceForRentExemption(info.data.length);
+      const claimed = after.availableBalance
+        .add(after.feeRebatePool)
+        .add(after.profitReserve)
+        .add(after.stagedDeposits);
+      assert.ok(claimed.toNumber() <= vaultLamports - rent);
+    });
+
+    it("should reject reconciliation from a non-authority", async () => {
+      const stranger = Keypair.generate();
+
+      try {
+        await program.methods
+          .reconcileVault(true)
+          .accounts({
+            authority: stranger.publicKey,
+            vault: vaultPda,
+          })
+          .signers([stranger])
+          .rpc();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(!err.toString().includes("Should have thrown"));
+      }
+    });
+  });
+
+  describe("Attestation Replay", () => {
+    let replayDepositor: Keypair;
+    let replayRecipient: Keypair;
+    const replayDepositAmount = 0.05 * LAMPORTS_PER_SOL;
+    const withdrawals: { userDeposit: PublicKey; withdrawalRequest: PublicKey }[] = [];
+
+    const setRequireAttestationNonce = async (required: boolean) => {
+      await program.methods
+        .setRequireAttestationNonce(required)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .rpc();
+    };
+
+    const attestationWithNonce = async (nonce: anchor.BN) => {
+      const slot = await provider.connection.getSlot();
+      return {
+        address: replayRecipient.publicKey,
+        riskScore: 10,
+        riskLevel: { low: {} },
+        attestationSlot: new anchor.BN(slot - 5),
+        numHops: 0,
+        oracleSignature: new Array(64).fill(0),
+        hasMaliciousConnections: false,
+        nonce,
+      };
+    };
+
+    const completeWithNonce = async (index: number, nonce: anchor.BN) => {
+      await program.methods
+        .completeWithdrawal(await attestationWithNonce(nonce))
+        .accounts({
+          requester: replayDepositor.publicKey,
+          vault: vaultPda,
+          userDeposit: withdrawals[index].userDeposit,
+          withdrawalRequest: withdrawals[index].withdrawalRequest,
+          recipient: replayRecipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([replayDepositor])
+        .rpc();
+    };
+
+    // A single-recipient split is an ordinary withdrawal by another route
+    const completeSplitWithNonce = async (index: number, nonce: anchor.BN) => {
+      await program.methods
+        .completeSplitWithdrawal([
+          {
+            recipient: replayRecipient.publicKey,
+            amount: new anchor.BN(replayDepositAmount),
+            attestation: await attestationWithNonce(nonce),
+          },
+        ])
+        .accounts({
+          requester: replayDepositor.publicKey,
+          vault: vaultPda,
+          userDeposit: withdrawals[index].userDeposit,
+          withdrawalRequest: withdrawals[index].withdrawalRequest,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts([
+          { pubkey: replayRecipient.publicKey, isWritable: true, isSigner: false },
+        ])
+        .signers([replayDepositor])
+        .rpc();
+    };
+
+    before(async () => {
+      replayDepositor = Keypair.generate();
+      replayRecipient = Keypair.generate();
+
+      const airdrop = await provider.connection.requestAirdrop(
+        replayDepositor.publicKey,
+        2 * LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdrop);
+
+      // Two deposits, each with its own pending withdrawal
+      for (const commitmentByte of [102, 104, 143]) {
+        const commitment = new Array(32).fill(commitmentByte);
+        const [userDeposit] = PublicKey.findProgramAddressSync(
+          [DEPOSIT_SEED, vaultPda.toBuffer(), Buffer.from(commitment)],
+          program.programId
+        );
+        const [withdrawalRequest] = PublicKey.findProgramAddressSync(
+          [
+            WITHDRAWAL_SEED,
+            vaultPda.toBuffer(),
+            replayDepositor.publicKey.toBuffer(),
+            userDeposit.toBuffer(),
+            withdrawalNonceSeed(0),
+          ],
+          program.programId
+        );
+
+        const proof = {
+          proofData: new Array(256).fill(0),
+          publicInputs: {
+            commitment,
+            nullifierHash: new Array(32).fill(commitmentByte + 1),
+            amount: new anchor.BN(replayDepositAmount),
+            merkleRoot: new Array(32).fill(0),
+          },
+          proofSlot: new anchor.BN(0),
+        };
+
+        await program.methods
+          .fundAgent(proof, null)
+          .accounts({
+            depositor: replayDepositor.publicKey,
+            vault: vaultPda,
+            userDeposit,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([replayDepositor])
+          .rpc();
+
+        await program.methods
+          .requestWithdrawal(new anchor.BN(replayDepositAmount))
+          .accounts({
+            requester: replayDepositor.publicKey,
+            vault: vaultPda,
+            userDeposit,
+            withdrawalRequest,
+            recipient: replayRecipient.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([replayDepositor])
+          .rpc();
+
+        withdrawals.push({ userDeposit, withdrawalRequest });
+      }
+
+      await setRequireAttestationNonce(true);
+    });
+
+    after(async () => {
+      await setRequireAttestationNonce(false);
+    });
+
+    it("should reject an attestation nonce that was already used", async () => {
+      const vault = await program.account.spectreVault.fetch(vaultPda);
+      const nonce = vault.lastAttestationNonce.addn(1);
+
+      await completeWithNonce(0, nonce);
+
+      try {
+        await completeWithNonce(1, nonce);
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("AttestationReused"));
+      }
+
+      const withdrawal = await program.account.withdrawalRequest.fetch(
+        withdrawals[1].withdrawalRequest
+      );
+      assert.deepStrictEqual(withdrawal.status, { pending: {} });
+
+      // A fresh nonce goes through
+      await completeWithNonce(1, nonce.addn(1));
+      const after = await program.account.spectreVault.fetch(vaultPda);
+      assert.ok(after.lastAttestationNonce.eq(nonce.addn(1)));
+    });
+
+    it("should reject a reused attestation nonce in a split withdrawal", async () => {
+      const vault = await program.account.spectreVault.fetch(vaultPda);
+      const usedNonce = vault.lastAttestationNonce;
+
+      try {
+        await completeSplitWithNonce(2, usedNonce);
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("AttestationReused"));
+      }
+
+      const withdrawal = await program.account.withdrawalRequest.fetch(
+        withdrawals[2].withdrawalRequest
+      );
+      assert.deepStrictEqual(withdrawal.status, { pending: {} });
+
+      await completeSplitWithNonce(2, usedNonce.addn(1));
+      const after = await program.account.spectreVault.fetch(vaultPda);
+      assert.ok(after.lastAttestationNonce.eq(usedNonce.addn(1)));
+    });
+  });
+
+  describe("Vault Capacity", () => {
+    let capacityDepositor: Keypair;
+    const headroom = 0.08 * LAMPORTS_PER_SOL;
+
+    const setCapacity = async (maxTotalDeposited: number | anchor.BN) => {
+      await program.methods
+        .setCapacity(new anchor.BN(maxTotalDeposited))
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .rpc();
+    };
+
+    const fund = async (commitmentByte: number, amount: number) => {
+      const commitment = new Array(32).fill(commitmentByte);
+      const [userDepositPda] = PublicKey.findProgramAddressSync(
+        [DEPOSIT_SEED, vaultPda.toBuffer(), Buffer.from(commitment)],
+        program.programId
+      );
+
+      const proof = {
+        proofData: new Array(256).fill(0),
+        publicInputs: {
+          commitment,
+          nullifierHash: new Array(32).fill(commitmentByte + 1),
+          amount: new anchor.BN(amount),
+          merkleRoot: new Array(32).fill(0),
+        },
+        proofSlot: new anchor.BN(0),
+      };
+
+      await program.methods
+        .fundAgent(proof, null)
+        .accounts({
+          depositor: capacityDepositor.publicKey,
+          vault: vaultPda,
+          userDeposit: userDepositPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([capacityDepositor])
+        .rpc();
+    };
+
+    before(async () => {
+      capacityDepositor = Keypair.generate();
+
+      const airdrop = await provider.connection.requestAirdrop(
+        capacityDepositor.publicKey,
+        2 * LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdrop);
+
+      const vault = await program.account.spectreVault.fetch(vaultPda);
+      await setCapacity(vault.totalDeposited.addn(headroom));
+    });
+
+    after(async () => {
+      await setCapacity(0);
+    });
+
+    it("should reject a deposit that exceeds the capacity", async () => {
+      try {
+        await fund(106, 0.1 * LAMPORTS_PER_SOL);
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("VaultCapacityExceeded"));
+      }
+    });
+
+    it("should accept a deposit within the capacity", async () => {
+      const before = await program.account.spectreVault.fetch(vaultPda);
+      const amount = 0.05 * LAMPORTS_PER_SOL;
+
+      await fund(108, amount);
+
+      const after = await program.account.spectreVault.fetch(vaultPda);
+      assert.strictEqual(after.totalDeposited.sub(before.totalDeposited).toNumber(), amount);
+      assert.ok(after.totalDeposited.lte(after.maxTotalDeposited));
+    });
+  });
+
+  describe("Vault Age", () => {
+    const cap = 1000 * LAMPORTS_PER_SOL;
+
+    const setCapacity = (maxTotalDeposited: number) =>
+      program.methods
+        .setCapacity(new anchor.BN(maxTotalDeposited))
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .rpc();
+
+    const setMinAge = (minAgeSeconds: number) =>
+      program.methods
+        .setMinAgeToRaiseCapacity(new anchor.BN(minAgeSeconds))
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .rpc();
+
+    before(async () => {
+      await setCapacity(cap);
+      // The test vault is minutes old
+      await setMinAge(7 * 86_400);
+    });
+
+    after(async () => {
+      await setMinAge(0);
+      await setCapacity(0);
+    });
+
+    it("should reject raising the capacity of a young vault", async () => {
+      for (const maxTotalDeposited of [cap + 1, 0]) {
+        try {
+          await setCapacity(maxTotalDeposited);
+          assert.fail("Should have thrown an error");
+        } catch (err) {
+          assert.ok(err.toString().includes("VaultTooYoung"));
+        }
+      }
+
+      const vault = await program.account.spectreVault.fetch(vaultPda);
+      assert.ok(vault.maxTotalDeposited.eqn(cap));
+      assert.ok(vault.minAgeToRaiseCapacitySeconds.eqn(7 * 86_400));
+    });
+
+    it("should allow lowering the capacity of a young vault", async () => {
+      await setCapacity(cap / 2);
+      await setCapacity(cap);
+
+      const vault = await program.account.spectreVault.fetch(vaultPda);
+      assert.ok(vault.maxTotalDeposited.eqn(cap));
+    });
+  });
+
+  describe("Compliance Cache", () => {
+    const COMPLIANCE_CACHE_SEED = Buffer.from("compliance_cache");
+    let cacheDepositor: Keypair;
+    let cacheRecipient: Keypair;
+    let complianceCachePda: PublicKey;
+    const cacheDepositAmount = 0.05 * LAMPORTS_PER_SOL;
+    const withdrawals: { userDeposit: PublicKey; withdrawalRequest: PublicKey }[] = [];
+
+    const setCacheTtl = async (ttlSlots: number) => {
+      await program.methods
+        .setComplianceCacheTtl(new anchor.BN(ttlSlots))
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .rpc();
+    };
+
+    const complete = async (index: number, withAttestation: boolean) => {
+      const slot = await provider.connection.getSlot();
+      const attestation = withAttestation
+        ? {
+            address: cacheRecipient.publicKey,
+            riskScore: 10,
+            riskLevel: { low: {} },
+            attestationSlot: new anchor.BN(slot - 5),
+            numHops: 0,
+            oracleSignature: new Array(64).fill(0),
+            hasMaliciousConnections: false,
+            nonce: new anchor.BN(0),
+          }
+        : null;
+
+      await program.methods
+        .completeWithdrawal(attestation)
+        .accounts({
+          requester: cacheDepositor.publicKey,
+          vault: vaultPda,
+          userDeposit: withdrawals[index].userDeposit,
+          withdrawalRequest: withdrawals[index].withdrawalRequest,
+          recipient: cacheRecipient.publicKey,
+          complianceCache: complianceCachePda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([cacheDepositor])
+        .rpc();
+    };
+
+    before(async () => {
+      cacheDepositor = Keypair.generate();
+      cacheRecipient = Keypair.generate();
+
+      const airdrop = await provider.connection.requestAirdrop(
+        cacheDepositor.publicKey,
+        2 * LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdrop);
+
+      // Four deposits, each with its own pending withdrawal to the same recipient
+      for (const commitmentByte of [110, 112, 114, 125]) {
+        const commitment = new Array(32).fill(commitmentByte);
+        const [userDeposit] = PublicKey.findProgramAddressSync(
+          [DEPOSIT_SEED, vaultPda.toBuffer(), Buffer.from(commitment)],
+          program.programId
+        );
+        const [withdrawalRequest] = PublicKey.findProgramAddressSync(
+          [
+            WITHDRAWAL_SEED,
+            vaultPda.toBuffer(),
+            cacheDepositor.publicKey.toBuffer(),
+            userDeposit.toBuffer(),
+            withdrawalNonceSeed(0),
+          ],
+          program.programId
+        );
+
+        const proof = {
+          proofData: new Array(256).fill(0),
+          publicInputs: {
+            commitment,
+            nullifierHash: new Array(32).fill(commitmentByte + 1),
+            amount: new anchor.BN(cacheDepositAmount),
+            merkleRoot: new Array(32).fill(0),
+          },
+          proofSlot: new anchor.BN(0),
+        };
+
+        await program.methods
+          .fundAgent(proof, null)
+          .accounts({
+            depositor: cacheDepositor.publicKey,
+            vault: vaultPda,
+            userDeposit,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([cacheDepositor])
+          .rpc();
+
+        await program.methods
+          .requestWithdrawal(new anchor.BN(cacheDepositAmount))
+          .accounts({
+            requester: cacheDepositor.publicKey,
+            vault: vaultPda,
+            userDeposit,
+            withdrawalRequest,
+            recipient: cacheRecipient.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([cacheDepositor])
+          .rpc();
+
+        withdrawals.push({ userDeposit, withdrawalRequest });
+      }
+
+      [complianceCachePda] = PublicKey.findProgramAddressSync(
+        [COMPLIANCE_CACHE_SEED, vaultPda.toBuffer(), cacheRecipient.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initializeComplianceCache()
+        .accounts({
+          payer: cacheDepositor.publicKey,
+          vault: vaultPda,
+          recipient: cacheRecipient.publicKey,
+          complianceCache: complianceCachePda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([cacheDepositor])
+        .rpc();
+
+      await setCacheTtl(1_000);
+    });
+
+    after(async () => {
+      await setCacheTtl(0);
+    });
+
+    it("should reuse a cached result within the TTL", async () => {
+      // A passing attestation fills the cache
+      await complete(0, true);
+
+      const cache = await program.account.complianceCache.fetch(complianceCachePda);
+      assert.strictEqual(cache.riskScore, 10);
+      assert.ok(cache.verifiedSlot.gtn(0));
+
+      // The next withdrawal to the same recipient needs no attestation
+      await complete(1, false);
+
+      const withdrawal = await program.account.withdrawalRequest.fetch(
+        withdrawals[1].withdrawalRequest
+      );
+      assert.deepStrictEqual(withdrawal.status, { completed: {} });
+      assert.strictEqual(withdrawal.riskScore, 10);
+    });
+
+    it("should fall back to full verification once the entry expires", async () => {
+      const refresh = (expiresAt: number) =>
+        program.methods
+          .refreshComplianceCache(new anchor.BN(expiresAt))
+          .accounts({
+            authority: authority.publicKey,
+            vault: vaultPda,
+            recipient: cacheRecipient.publicKey,
+            complianceCache: complianceCachePda,
+          })
+          .signers([authority])
+          .rpc();
+
+      const now = await provider.connection.getBlockTime(await provider.connection.getSlot());
+
+      // Expiries must be in the future
+      try {
+        await refresh(now - 1);
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("InvalidCacheExpiry"));
+      }
+
+      await refresh(now + 2);
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+
+      // Still within the TTL, but the expired entry is ignored
+      try {
+        await complete(3, false);
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("ComplianceCacheMiss"));
+      }
+
+      await complete(3, true);
+      const withdrawal = await program.account.withdrawalRequest.fetch(
+        withdrawals[3].withdrawalRequest
+      );
+      assert.deepStrictEqual(withdrawal.status, { completed: {} });
+    });
+
+    it("should require an attestation once the cached result expires", async () => {
+      await setCacheTtl(1);
+      await new Promise((resolve) => setTimeout(resolve, 2000));
+
+      try {
+        await complete(2, false);
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("ComplianceCacheMiss"));
+      }
+
+      const withdrawal = await program.account.withdrawalRequest.fetch(
+        withdrawals[2].withdrawalRequest
+      );
+      assert.deepStrictEqual(withdrawal.status, { pending: {} });
+    });
+  });
+
+  describe("Withdrawal Reservation", () => {
+    let reserveDepositor: Keypair;
+    let reserveRecipient: Keypair;
+    let reserveUserDepositPda: PublicKey;
+    let reserveWithdrawalPda: PublicKey;
+    const reserveCommitment = new Array(32).fill(116);
+    const reserveNullifier = new Array(32).fill(117);
+    const reserveAmount = 0.05 * LAMPORTS_PER_SOL;
+
+    const tradable = (vault: any) => vault.availableBalance.sub(vault.reservedForWithdrawal);
+
+    before(async () => {
+      reserveDepositor = Keypair.generate();
+      reserveRecipient = Keypair.generate();
+
+      const airdrop = await provider.connection.requestAirdrop(
+        reserveDepositor.publicKey,
+        2 * LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdrop);
+
+      [reserveUserDepositPda] = PublicKey.findProgramAddressSync(
+        [DEPOSIT_SEED, vaultPda.toBuffer(), Buffer.from(reserveCommitment)],
+        program.programId
+      );
+      [reserveWithdrawalPda] = PublicKey.findProgramAddressSync(
+        [
+          WITHDRAWAL_SEED,
+          vaultPda.toBuffer(),
+          reserveDepositor.publicKey.toBuffer(),
+          reserveUserDepositPda.toBuffer(),
+          withdrawalNonceSeed(0),
+        ],
+        program.programId
+      );
+
+      const proof = {
+        proofData: new Array(256).fill(0),
+        publicInputs: {
+          commitment: reserveCommitment,
+          nullifierHash: reserveNullifier,
+          amount: new anchor.BN(reserveAmount),
+          merkleRoot: new Array(32).fill(0),
+        },
+        proofSlot: new anchor.BN(0),
+      };
+
+      await program.methods
+        .fundAgent(proof, null)
+        .accounts({
+          depositor: reserveDepositor.publicKey,
+          vault: vaultPda,
+          userDeposit: reserveUserDepositPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([reserveDepositor])
+        .rpc();
+    });
+
+    it("should reduce the tradable balance while a withdrawal is pending", async () => {
+      const before = await program.account.spectreVault.fetch(vaultPda);
+
+      await program.methods
+        .requestWithdrawal(new anchor.BN(reserveAmount))
+        .accounts({
+          requester: reserveDepositor.publicKey,
+          vault: vaultPda,
+          userDeposit: reserveUserDepositPda,
+          withdrawalRequest: reserveWithdrawalPda,
+          recipient: reserveRecipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([reserveDepositor])
+        .rpc();
+
+      const after = await program.account.spectreVault.fetch(vaultPda);
+      assert.ok(after.availableBalance.eq(before.availableBalance));
+      assert.strictEqual(
+        after.reservedForWithdrawal.sub(before.reservedForWithdrawal).toNumber(),
+        reserveAmount
+      );
+      assert.strictEqual(tradable(before).sub(tradable(after)).toNumber(), reserveAmount);
+    });
+
+    it("should release the reservation when the withdrawal completes", async () => {
+      const before = await program.account.spectreVault.fetch(vaultPda);
+      const slot = await provider.connection.getSlot();
+
+      await program.methods
+        .completeWithdrawal({
+          address: reserveRecipient.publicKey,
+          riskScore: 10,
+          riskLevel: { low: {} },
+          attestationSlot: new anchor.BN(slot - 5),
+          numHops: 0,
+          oracleSignature: new Array(64).fill(0),
+          hasMaliciousConnections: false,
+          nonce: new anchor.BN(0),
+        })
+        .accounts({
+          requester: reserveDepositor.publicKey,
+          vault: vaultPda,
+          userDeposit: reserveUserDepositPda,
+          withdrawalRequest: reserveWithdrawalPda,
+          recipient: reserveRecipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([reserveDepositor])
+        .rpc();
+
+      // Balance and reservation drop together, leaving the tradable balance unchanged
+      const after = await program.account.spectreVault.fetch(vaultPda);
+      assert.strictEqual(
+        before.reservedForWithdrawal.sub(after.reservedForWithdrawal).toNumber(),
+        reserveAmount
+      );
+      assert.ok(tradable(after).eq(tradable(before)));
+    });
+  });
+
+  describe("Fee Rebates", () => {
+    const smallAmount = 0.05 * LAMPORTS_PER_SOL;
+    const largeAmount = 0.15 * LAMPORTS_PER_SOL;
+    let smallDepositor: Keypair;
+    let largeDepositor: Keypair;
+
+    const depositPdaFor = (commitmentByte: number) =>
+      PublicKey.findProgramAddressSync(
+        [DEPOSIT_SEED, vaultPda.toBuffer(), Buffer.from(new Array(32).fill(commitmentByte))],
+        program.programId
+      )[0];
+
+    const deposit = async (owner: Keypair, commitmentByte: number, amount: number) => {
+      const airdrop = await provider.connection.requestAirdrop(owner.publicKey, 2 * LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(airdrop);
+
+      await program.methods
+        .fundAgent(
+          {
+            proofData: new Array(256).fill(0),
+            publicInputs: {
+              commitment: new Array(32).fill(commitmentByte),
+              nullifierHash: new Array(32).fill(commitmentByte + 1),
+              amount: new anchor.BN(amount),
+              merkleRoot: new Array(32).fill(0),
+            },
+            proofSlot: new anchor.BN(0),
+          },
+          null
+        )
+        .accounts({
+          depositor: owner.publicKey,
+          vault: vaultPda,
+          userDeposit: depositPdaFor(commitmentByte),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([owner])
+        .rpc();
+    };
+
+    const claim = async (owner: Keypair, commitmentByte: number) => {
+      const before = await provider.connection.getBalance(owner.publicKey);
+      await program.methods
+        .claimRebate()
+        .accounts({
+          owner: owner.publicKey,
+          vault: vaultPda,
+          userDeposit: depositPdaFor(commitmentByte),
+        })
+        .signers([owner])
+        .rpc();
+      return (await provider.connection.getBalance(owner.publicKey)) - before;
+    };
+
+    before(async () => {
+      smallDepositor = Keypair.generate();
+      largeDepositor = Keypair.generate();
+      await deposit(smallDepositor, 121, smallAmount);
+      await deposit(largeDepositor, 123, largeAmount);
+    });
+
+    it("should move funds from the tradable balance into the rebate pool", async () => {
+      const before = await program.account.spectreVault.fetch(vaultPda);
+
+      await program.methods
+        .fundRebatePool(new anchor.BN(0.01 * LAMPORTS_PER_SOL))
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .rpc();
+
+      const after = await program.account.spectreVault.fetch(vaultPda);
+      assert.strictEqual(
+        after.feeRebatePool.sub(before.feeRebatePool).toNumber(),
+        0.01 * LAMPORTS_PER_SOL
+      );
+      assert.strictEqual(
+        before.availableBalance.sub(after.availableBalance).toNumber(),
+        0.01 * LAMPORTS_PER_SOL
+      );
+      assert.ok(after.rebateIndex.gt(before.rebateIndex));
+    });
+
+    it("should pay depositors rebates proportional to their amounts", async () => {
+      const smallRebate = await claim(smallDepositor, 121);
+      const largeRebate = await claim(largeDepositor, 123);
+
+      assert.ok(smallRebate > 0);
+      // 3x the deposit earns 3x the rebate, up to index rounding
+      assert.ok(Math.abs(largeRebate - 3 * smallRebate) <= 3);
+    });
+
+    it("should pay nothing on a second claim", async () => {
+      assert.strictEqual(await claim(smallDepositor, 121), 0);
+    });
+  });
+
+  describe("Compliance Simulation", () => {
+    const simulate = async (recipient: PublicKey, riskScore: number, malicious: boolean) => {
+      const slot = await provider.connection.getSlot();
+      return program.methods
+        .simulateCompliance(
+          {
+            address: recipient,
+            riskScore,
+            riskLevel: malicious ? { critical: {} } : { low: {} },
+            attestationSlot: new anchor.BN(slot - 5),
+            numHops: malicious ? 2 : 0,
+            oracleSignature: new Array(64).fill(0),
+            hasMaliciousConnections: malicious,
+            nonce: new anchor.BN(0),
+          },
+          recipient
+        )
+        .accounts({ vault: vaultPda })
+        .view();
+    };
+
+    it("should report a pass for a clean recipient", async () => {
+      const result = await simulate(Keypair.generate().publicKey, 10, false);
+
+      assert.strictEqual(result.passed, true);
+      assert.strictEqual(result.riskScore, 10);
+      assert.strictEqual(result.reason, null);
+    });
+
+    it("should report the failure reason for a high-risk recipient", async () => {
+      const vaultBefore = await program.account.spectreVault.fetch(vaultPda);
+
+      const result = await simulate(Keypair.generate().publicKey, 85, true);
+
+      assert.strictEqual(result.passed, false);
+      assert.deepStrictEqual(result.reason, { maliciousConnections: {} });
+
+      // Simulation leaves the vault untouched
+      const vaultAfter = await program.account.spectreVault.fetch(vaultPda);
+      assert.ok(vaultAfter.lastAttestationNonce.eq(vaultBefore.lastAttestationNonce));
+    });
+  });
+
+  describe("Recipient Rent Cover", () => {
+    const commitment = new Array(32).fill(127);
+    const withdrawAmount = 100_000; // well below the rent-exempt minimum
+    let depositor: Keypair;
+    let recipient: Keypair;
+    let userDepositPda: PublicKey;
+    let withdrawalPda: PublicKey;
+
+    const setCoverRecipientRent = async (enabled: boolean) => {
+      await program.methods
+        .setCoverRecipientRent(enabled)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .rpc();
+    };
+
+    before(async () => {
+      depositor = Keypair.generate();
+      recipient = Keypair.generate();
+
+      const airdrop = await provider.connection.requestAirdrop(depositor.publicKey, 2 * LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(airdrop);
+
+      [userDepositPda] = PublicKey.findProgramAddressSync(
+        [DEPOSIT_SEED, vaultPda.toBuffer(), Buffer.from(commitment)],
+        program.programId
+      );
+      [withdrawalPda] = PublicKey.findProgramAddressSync(
+        [WITHDRAWAL_SEED, vaultPda.toBuffer(), depositor.publicKey.toBuffer(), userDepositPda.toBuffer(), withdrawalNonceSeed(0)],
+        program.programId
+      );
+
+      await program.methods
+        .fundAgent(
+          {
+            proofData: new Array(256).fill(0),
+            publicInputs: {
+              commitment,
+              nullifierHash: new Array(32).fill(128),
+              amount: new anchor.BN(0.05 * LAMPORTS_PER_SOL),
+              merkleRoot: new Array(32).fill(0),
+            },
+            proofSlot: new anchor.BN(0),
+          },
+          null
+        )
+        .accounts({
+          depositor: depositor.publicKey,
+          vault: vaultPda,
+          userDeposit: userDepositPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([depositor])
+        .rpc();
+
+      await program.methods
+        .requestWithdrawal(new anchor.BN(withdrawAmount))
+        .accounts({
+          requester: depositor.publicKey,
+          vault: vaultPda,
+          userDeposit: userDepositPda,
+          withdrawalRequest: withdrawalPda,
+          recipient: recipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([depositor])
+        .rpc();
+
+      await setCoverRecipientRent(true);
+    });
+
+    after(async () => {
+      await setCoverRecipientRent(false);
+    });
+
+    it("should leave a brand-new recipient rent-exempt", async () => {
+      assert.strictEqual(await provider.connection.getBalance(recipient.publicKey), 0);
+      const rentExempt = await provider.connection.getMinimumBalanceForRentExemption(0);
+      const vaultBefore = await program.account.spectreVault.fetch(vaultPda);
+
+      const slot = await provider.connection.getSlot();
+      await program.methods
+        .completeWithdrawal({
+          address: recipient.publicKey,
+          riskScore: 10,
+          riskLevel: { low: {} },
+          attestationSlot: new anchor.BN(slot - 5),
+          numHops: 0,
+          oracleSignature: new Array(64).fill(0),
+          hasMaliciousConnections: false,
+          nonce: new anchor.BN(0),
+        })
+        .accounts({
+          requester: depositor.publicKey,
+          vault: vaultPda,
+          userDeposit: userDepositPda,
+          withdrawalRequest: withdrawalPda,
+          recipient: recipient.publicKey,
+          complianceCache: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([depositor])
+        .rpc();
+
+      // The recipient holds exactly the rent-exempt minimum
+      assert.strictEqual(await provider.connection.getBalance(recipient.publicKey), rentExempt);
+
+      // The vault paid the payout plus the rent it covered
+      const vaultAfter = await program.account.spectreVault.fetch(vaultPda);
+      assert.strictEqual(
+        vaultBefore.availableBalance.sub(vaultAfter.availableBalance).toNumber(),
+        rentExempt
+      );
+
+      // Only the requested amount comes out of the deposit
+      const deposit = await program.account.userDeposit.fetch(userDepositPda);
+      assert.strictEqual(deposit.amount.toNumber(), 0.05 * LAMPORTS_PER_SOL - withdrawAmount);
+    });
+
+    it("should leave every brand-new split recipient rent-exempt", async () => {
+      const splitCommitment = new Array(32).fill(147);
+      const recipients = [Keypair.generate().publicKey, Keypair.generate().publicKey];
+      const rentExempt = await provider.connection.getMinimumBalanceForRentExemption(0);
+
+      const [splitDepositPda] = PublicKey.findProgramAddressSync(
+        [DEPOSIT_SEED, vaultPda.toBuffer(), Buffer.from(splitCommitment)],
+        program.programId
+      );
+      const [splitWithdrawalPda] = PublicKey.findProgramAddressSync(
+        [WITHDRAWAL_SEED, vaultPda.toBuffer(), depositor.publicKey.toBuffer(), splitDepositPda.toBuffer(), withdrawalNonceSeed(0)],
+        program.programId
+      );
+
+      await program.methods
+        .fundAgent(
+          {
+            proofData: new Array(256).fill(0),
+            publicInputs: {
+              commitment: splitCommitment,
+              nullifierHash: new Array(32).fill(148),
+              amount: new anchor.BN(0.05 * LAMPORTS_PER_SOL),
+              merkleRoot: new Array(32).fill(0),
+            },
+            proofSlot: new anchor.BN(0),
+          },
+          null
+        )
+        .accounts({
+          depositor: depositor.publicKey,
+          vault: vaultPda,
+          userDeposit: splitDepositPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([depositor])
+        .rpc();
+
+      await program.methods
+        .requestWithdrawal(new anchor.BN(2 * withdrawAmount))
+        .accounts({
+          requester: depositor.publicKey,
+          vault: vaultPda,
+          userDeposit: splitDepositPda,
+          withdrawalRequest: splitWithdrawalPda,
+          recipient: recipients[0],
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([depositor])
+        .rpc();
+
+      const vaultBefore = await program.account.spectreVault.fetch(vaultPda);
+      const slot = await provider.connection.getSlot();
+      await program.methods
+        .completeSplitWithdrawal(
+          recipients.map((address) => ({
+            recipient: address,
+            amount: new anchor.BN(withdrawAmount),
+            attestation: {
+              address,
+              riskScore: 10,
+              riskLevel: { low: {} },
+              attestationSlot: new anchor.BN(slot - 5),
+              numHops: 0,
+              oracleSignature: new Array(64).fill(0),
+              hasMaliciousConnections: false,
+              nonce: new anchor.BN(0),
+            },
+          }))
+        )
+        .accounts({
+          requester: depositor.publicKey,
+          vault: vaultPda,
+          userDeposit: splitDepositPda,
+          withdrawalRequest: splitWithdrawalPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(
+          recipients.map((pubkey) => ({ pubkey, isWritable: true, isSigner: false }))
+        )
+        .signers([depositor])
+        .rpc();
+
+      for (const address of recipients) {
+        assert.strictEqual(await provider.connection.getBalance(address), rentExempt);
+      }
+
+      // The vault paid both shares plus the rent it covered
+      const vaultAfter = await program.account.spectreVault.fetch(vaultPda);
+      assert.strictEqual(
+        vaultBefore.availableBalance.sub(vaultAfter.availableBalance).toNumber(),
+        2 * rentExempt
+      );
+    });
+  });
+
+  describe("Compliance Actions", () => {
+    const depositAmount = 0.05 * LAMPORTS_PER_SOL;
+    const withdrawAmount = 0.01 * LAMPORTS_PER_SOL;
+    let depositor: Keypair;
+
+    // Each case gets its own deposit, so its own withdrawal request PDA
+    const openWithdrawal = async (commitmentByte: number, recipient: PublicKey) => {
+      const commitment = new Array(32).fill(commitmentByte);
+      const [userDepositPda] = PublicKey.findProgramAddressSync(
+        [DEPOSIT_SEED, vaultPda.toBuffer(), Buffer.from(commitment)],
+        program.programId
+      );
+      const [withdrawalPda] = PublicKey.findProgramAddressSync(
+        [WITHDRAWAL_SEED, vaultPda.toBuffer(), depositor.publicKey.toBuffer(), userDepositPda.toBuffer(), withdrawalNonceSeed(0)],
+        program.programId
+      );
+
+      await program.methods
+        .fundAgent(
+          {
+            proofData: new Array(256).fill(0),
+            publicInputs: {
+              commitment,
+              nullifierHash: new Array(32).fill(commitmentByte + 1),
+              amount: new anchor.BN(depositAmount),
+              merkleRoot: new Array(32).fill(0),
+            },
+            proofSlot: new anchor.BN(0),
+          },
+          null
+        )
+        .accounts({
+          depositor: depositor.publicKey,
+          vault: vaultPda,
+          userDeposit: userDepositPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([depositor])
+        .rpc();
+
+      await program.methods
+        .requestWithdrawal(new anchor.BN(withdrawAmount))
+        .accounts({
+          requester: depositor.publicKey,
+          vault: vaultPda,
+          userDeposit: userDepositPda,
+          withdrawalRequest: withdrawalPda,
+          recipient,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([depositor])
+        .rpc();
+
+      return { userDepositPda, withdrawalPda };
+    };
+
+    const completeWithRisk = async (
+      pdas: { userDepositPda: PublicKey; withdrawalPda: PublicKey },
+      recipient: PublicKey,
+      riskScore: number,
+      riskLevel: object,
+      hasMaliciousConnections: boolean
+    ) => {
+      const slot = await provider.connection.getSlot();
+      return program.methods
+        .completeWithdrawal({
+          address: recipient,
+          riskScore,
+          riskLevel,
+          attestationSlot: new anchor.BN(slot - 5),
+          numHops: 0,
+          oracleSignature: new Array(64).fill(0),
+          hasMaliciousConnections,
+          nonce: new anchor.BN(0),
+        })
+        .accounts({
+          requester: depositor.publicKey,
+          vault: vaultPda,
+          userDeposit: pdas.userDepositPda,
+          withdrawalRequest: pdas.withdrawalPda,
+          recipient,
+          complianceCache: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([depositor])
+        .rpc();
+    };
+
+    before(async () => {
+      depositor = Keypair.generate();
+      const airdrop = await provider.connection.requestAirdrop(depositor.publicKey, 2 * LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(airdrop);
+    });
+
+    it("should allow a low-risk withdrawal without flagging it", async () => {
+      const recipient = Keypair.generate().publicKey;
+      const pdas = await openWithdrawal(129, recipient);
+
+      await completeWithRisk(pdas, recipient, 10, { low: {} }, false);
+
+      const withdrawal = await program.account.withdrawalRequest.fetch(pdas.withdrawalPda);
+      assert.deepStrictEqual(withdrawal.status, { completed: {} });
+      assert.strictEqual(withdrawal.complianceFlagged, false);
+    });
+
+    it("should allow an elevated-risk withdrawal but flag it", async () => {
+      const recipient = Keypair.generate().publicKey;
+      const pdas = await openWithdrawal(131, recipient);
+
+      await completeWithRisk(pdas, recipient, 25, { medium: {} }, false);
+
+      const withdrawal = await program.account.withdrawalRequest.fetch(pdas.withdrawalPda);
+      assert.deepStrictEqual(withdrawal.status, { completed: {} });
+      assert.strictEqual(withdrawal.complianceFlagged, true);
+      assert.strictEqual(withdrawal.riskScore, 25);
+      assert.strictEqual(await provider.connection.getBalance(recipient), withdrawAmount);
+    });
+
+    it("should block a high-risk withdrawal", async () => {
+      const recipient = Keypair.generate().publicKey;
+      const pdas = await openWithdrawal(133, recipient);
+
+      try {
+        await completeWithRisk(pdas, recipient, 85, { critical: {} }, true);
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("ComplianceCheckFailed"));
+      }
+
+      // The failed transaction rolls back, leaving the request pending
+      const withdrawal = await program.account.withdrawalRequest.fetch(pdas.withdrawalPda);
+      assert.deepStrictEqual(withdrawal.status, { pending: {} });
+      assert.strictEqual(withdrawal.complianceFlagged, false);
+      assert.strictEqual(await provider.connection.getBalance(recipient), 0);
+    });
+  });
+
+  describe("Compliance Risk Delta", () => {
+    const depositAmount = 0.05 * LAMPORTS_PER_SOL;
+    const withdrawAmount = 0.01 * LAMPORTS_PER_SOL;
+    let depositor: Keypair;
+
+    const setMaxRiskDelta = (maxRiskDelta: number) =>
+      program.methods
+        .setMaxRiskDelta(maxRiskDelta)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .rpc();
+
+    const attestationFor = async (recipient: PublicKey, riskScore: number, riskLevel: object) => {
+      const slot = await provider.connection.getSlot();
+      return {
+        address: recipient,
+        riskScore,
+        riskLevel,
+        attestationSlot: new anchor.BN(slot - 5),
+        numHops: 0,
+        oracleSignature: new Array(64).fill(0),
+        hasMaliciousConnections: false,
+        nonce: new anchor.BN(0),
+      };
+    };
+
+    // Open a withdrawal and approve it at risk score 10
+    const openVerifiedWithdrawal = async (commitmentByte: number, recipient: PublicKey) => {
+      const commitment = new Array(32).fill(commitmentByte);
+      const [userDepositPda] = PublicKey.findProgramAddressSync(
+        [DEPOSIT_SEED, vaultPda.toBuffer(), Buffer.from(commitment)],
+        program.programId
+      );
+      const [withdrawalPda] = PublicKey.findProgramAddressSync(
+        [WITHDRAWAL_SEED, vaultPda.toBuffer(), depositor.publicKey.toBuffer(), userDepositPda.toBuffer(), withdrawalNonceSeed(0)],
+        program.programId
+      );
+
+      await program.methods
+        .fundAgent(
+          {
+            proofData: new Array(256).fill(0),
+            publicInputs: {
+              commitment,
+              nullifierHash: new Array(32).fill(commitmentByte + 1),
+              amount: new anchor.BN(depositAmount),
+              merkleRoot: new Array(32).fill(0),
+            },
+            proofSlot: new anchor.BN(0),
+          },
+          null
+        )
+        .accounts({
+          depositor: depositor.publicKey,
+          vault: vaultPda,
+          userDeposit: userDepositPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([depositor])
+        .rpc();
+
+      await program.methods
+        .requestWithdrawal(new anchor.BN(withdrawAmount))
+        .accounts({
+          requester: depositor.publicKey,
+          vault: vaultPda,
+          userDeposit: userDepositPda,
+          withdrawalRequest: withdrawalPda,
+          recipient,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([depositor])
+        .rpc();
+
+      await program.methods
+        .verifyWithdrawalCompliance(await attestationFor(recipient, 10, { low: {} }))
+        .accounts({
+          requester: depositor.publicKey,
+          vault: vaultPda,
+          userDeposit: userDepositPda,
+          withdrawalRequest: withdrawalPda,
+        })
+        .signers([depositor])
+        .rpc();
+
+      return { userDepositPda, withdrawalPda };
+    };
+
+    const complete = async (
+      pdas: { userDepositPda: PublicKey; withdrawalPda: PublicKey },
+      recipient: PublicKey,
+      riskScore: number
+    ) =>
+      program.methods
+        .completeWithdrawal(await attestationFor(recipient, riskScore, { medium: {} }))
+        .accounts({
+          requester: depositor.publicKey,
+          vault: vaultPda,
+          userDeposit: pdas.userDepositPda,
+          withdrawalRequest: pdas.withdrawalPda,
+          recipient,
+          complianceCache: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([depositor])
+        .rpc();
+
+    before(async () => {
+      depositor = Keypair.generate();
+      const airdrop = await provider.connection.requestAirdrop(depositor.publicKey, 2 * LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(airdrop);
+
+      await setMaxRiskDelta(15);
+    });
+
+    after(async () => {
+      await setMaxRiskDelta(0);
+    });
+
+    it("should complete when the risk score rose within the allowed delta", async () => {
+      const recipient = Keypair.generate().publicKey;
+      const pdas = await openVerifiedWithdrawal(139, recipient);
+
+      await complete(pdas, recipient, 20);
+
+      const withdrawal = await program.account.withdrawalRequest.fetch(pdas.withdrawalPda);
+      assert.deepStrictEqual(withdrawal.status, { completed: {} });
+      assert.strictEqual(withdrawal.verifiedRiskScore, 10);
+      assert.strictEqual(withdrawal.riskScore, 20);
+    });
+
+    it("should reject when the risk score rose beyond the allowed delta", async () => {
+      const recipient = Keypair.generate().publicKey;
+      const pdas = await openVerifiedWithdrawal(141, recipient);
+
+      try {
+        await complete(pdas, recipient, 28);
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("RiskProfileChanged"));
+      }
+
+      const withdrawal = await program.account.withdrawalRequest.fetch(pdas.withdrawalPda);
+      assert.deepStrictEqual(withdrawal.status, { approved: {} });
+      assert.strictEqual(await provider.connection.getBalance(recipient), 0);
+    });
+
+    it("should reject a split payout when any recipient's risk score rose beyond the allowed delta", async () => {
+      const calmRecipient = Keypair.generate().publicKey;
+      const riskyRecipient = Keypair.generate().publicKey;
+      const pdas = await openVerifiedWithdrawal(145, calmRecipient);
+
+      try {
+        await program.methods
+          .completeSplitWithdrawal([
+            {
+              recipient: calmRecipient,
+              amount: new anchor.BN(withdrawAmount / 2),
+              attestation: await attestationFor(calmRecipient, 12, { low: {} }),
+            },
+            {
+              recipient: riskyRecipient,
+              amount: new anchor.BN(withdrawAmount / 2),
+              attestation: await attestationFor(riskyRecipient, 28, { medium: {} }),
+            },
+          ])
+          .accounts({
+            requester: depositor.publicKey,
+            vault: vaultPda,
+            userDeposit: pdas.userDepositPda,
+            withdrawalRequest: pdas.withdrawalPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts([
+            { pubkey: calmRecipient, isWritable: true, isSigner: false },
+            { pubkey: riskyRecipient, isWritable: true, isSigner: false },
+          ])
+          .signers([depositor])
+          .rpc();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("RiskProfileChanged"));
+      }
+
+      const withdrawal = await program.account.withdrawalRequest.fetch(pdas.withdrawalPda);
+      assert.deepStrictEqual(withdrawal.status, { approved: {} });
+      assert.strictEqual(await provider.connection.getBalance(calmRecipient), 0);
+      assert.strictEqual(await provider.connection.getBalance(riskyRecipient), 0);
+    });
+  });
+
+  describe("Operator Top-Up", () => {
+    it("should add to the tradable balance without counting as a deposit", async () => {
+      const amount = 0.2 * LAMPORTS_PER_SOL;
+      const vaultBefore = await program.account.spectreVault.fetch(vaultPda);
+      const lamportsBefore = await provider.connection.getBalance(vaultPda);
+
+      await program.methods
+        .topUpVault(new anchor.BN(amount))
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      const vaultAfter = await program.account.spectreVault.fetch(vaultPda);
+      assert.strictEqual(vaultAfter.availableBalance.sub(vaultBefore.availableBalance).toNumber(), amount);
+      assert.strictEqual(vaultAfter.operatorFunded.sub(vaultBefore.operatorFunded).toNumber(), amount);
+      assert.ok(vaultAfter.totalDeposited.eq(vaultBefore.totalDeposited));
+      assert.ok(vaultAfter.totalDepositsCount.eq(vaultBefore.totalDepositsCount));
+      assert.strictEqual(await provider.connection.getBalance(vaultPda), lamportsBefore + amount);
+    });
+
+    it("should reject a top-up from a non-authority", async () => {
+      const stranger = Keypair.generate();
+      const airdrop = await provider.connection.requestAirdrop(stranger.publicKey, LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(airdrop);
+
+      try {
+        await program.methods
+          .topUpVault(new anchor.BN(0.1 * LAMPORTS_PER_SOL))
+          .accounts({
+            authority: stranger.publicKey,
+            vault: vaultPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([stranger])
+          .rpc();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(!err.toString().includes("Should have thrown"));
+      }
+    });
+  });
+
+  describe("Pending Withdrawal Cap", () => {
+    const commitment = new Array(32).fill(135);
+    const withdrawAmount = 0.01 * LAMPORTS_PER_SOL;
+    let depositor: Keypair;
+    let recipient: Keypair;
+    let userDepositPda: PublicKey;
+
+    const setMaxPendingWithdrawals = async (maxPending: number) => {
+      await program.methods
+        .setMaxPendingWithdrawals(maxPending)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .rpc();
+    };
+
+    const withdrawalPdaFor = (nonce: number) =>
+      PublicKey.findProgramAddressSync(
+        [
+          WITHDRAWAL_SEED,
+          vaultPda.toBuffer(),
+          depositor.publicKey.toBuffer(),
+          userDepositPda.toBuffer(),
+          withdrawalNonceSeed(nonce),
+        ],
+        program.programId
+      )[0];
+
+    const requestWithdrawal = (nonce: number) =>
+      program.methods
+        .requestWithdrawal(new anchor.BN(withdrawAmount))
+        .accounts({
+          requester: depositor.publicKey,
+          vault: vaultPda,
+          userDeposit: userDepositPda,
+          withdrawalRequest: withdrawalPdaFor(nonce),
+          recipient: recipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([depositor])
+        .rpc();
+
+    before(async () => {
+      depositor = Keypair.generate();
+      recipient = Keypair.generate();
+
+      const airdrop = await provider.connection.requestAirdrop(depositor.publicKey, 2 * LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(airdrop);
+
+      [userDepositPda] = PublicKey.findProgramAddressSync(
+        [DEPOSIT_SEED, vaultPda.toBuffer(), Buffer.from(commitment)],
+        program.programId
+      );
+
+      await program.methods
+        .fundAgent(
+          {
+            proofData: new Array(256).fill(0),
+            publicInputs: {
+              commitment,
+              nullifierHash: new Array(32).fill(136),
+              amount: new anchor.BN(0.05 * LAMPORTS_PER_SOL),
+              merkleRoot: new Array(32).fill(0),
+            },
+            proofSlot: new anchor.BN(0),
+          },
+          null
+        )
+        .accounts({
+          depositor: depositor.publicKey,
+          vault: vaultPda,
+          userDeposit: userDepositPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([depositor])
+        .rpc();
+
+      await setMaxPendingWithdrawals(1);
+    });
+
+    after(async () => {
+      await setMaxPendingWithdrawals(0);
+    });
+
+    it("should count a pending request against the deposit", async () => {
+      await requestWithdrawal(0);
+
+      const deposit = await program.account.userDeposit.fetch(userDepositPda);
+      assert.strictEqual(deposit.pendingWithdrawals, 1);
+      assert.ok(deposit.withdrawalNonce.eqn(1));
+
+      const withdrawal = await program.account.withdrawalRequest.fetch(withdrawalPdaFor(0));
+      assert.ok(withdrawal.nonce.eqn(0));
+    });
+
+    it("should reject a request once the cap is reached", async () => {
+      try {
+        await requestWithdrawal(1);
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("TooManyPendingWithdrawals"));
+      }
+
+      const deposit = await program.account.userDeposit.fetch(userDepositPda);
+      assert.strictEqual(deposit.pendingWithdrawals, 1);
+    });
+
+    it("should release the slot when a request is cancelled", async () => {
+      const vaultBefore = await program.account.spectreVault.fetch(vaultPda);
+
+      await program.methods
+        .cancelWithdrawal()
+        .accounts({
+          requester: depositor.publicKey,
+          vault: vaultPda,
+          userDeposit: userDepositPda,
+          withdrawalRequest: withdrawalPdaFor(0),
+        })
+        .signers([depositor])
+        .rpc();
+
+      const deposit = await program.account.userDeposit.fetch(userDepositPda);
+      assert.strictEqual(deposit.pendingWithdrawals, 0);
+      assert.strictEqual(await provider.connection.getAccountInfo(withdrawalPdaFor(0)), null);
+
+      // The cancelled amount is no longer held back from trading
+      const vaultAfter = await program.account.spectreVault.fetch(vaultPda);
+      assert.strictEqual(
+        vaultBefore.reservedForWithdrawal.sub(vaultAfter.reservedForWithdrawal).toNumber(),
+        withdrawAmount
+      );
+    });
+
+    it("should release the slot once the request completes", async () => {
+      await requestWithdrawal(1);
+
+      const slot = await provider.connection.getSlot();
+      await program.methods
+        .completeWithdrawal({
+          address: recipient.publicKey,
+          riskScore: 10,
+          riskLevel: { low: {} },
+          attestationSlot: new anchor.BN(slot - 5),
+          numHops: 0,
+          oracleSignature: new Array(64).fill(0),
+          hasMaliciousConnections: false,
+          nonce: new anchor.BN(0),
+        })
+        .accounts({
+          requester: depositor.publicKey,
+          vault: vaultPda,
+          userDeposit: userDepositPda,
+          withdrawalRequest: withdrawalPdaFor(1),
+          recipient: recipient.publicKey,
+          complianceCache: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([depositor])
+        .rpc();
+
+      const deposit = await program.account.userDeposit.fetch(userDepositPda);
+      assert.strictEqual(deposit.pendingWithdrawals, 0);
+    });
+  });
+
+  describe("Deposit Info", () => {
+    it("should return a deposit view without the nullifier", async () => {
+      // Deposit made by the fee rebate tests (commitment 121, nullifier 122)
+      const [userDepositPda] = PublicKey.findProgramAddressSync(
+        [DEPOSIT_SEED, vaultPda.toBuffer(), Buffer.from(new Array(32).fill(121))],
+        program.programId
+      );
+      const deposit = await program.account.userDeposit.fetch(userDepositPda);
+
+      const info = await program.methods
+        .getDepositInfo()
+        .accounts({
+          vault: vaultPda,
+          userDeposit: userDepositPda,
+        })
+        .view();
+
+      assert.ok(info.amount.eq(deposit.amount));
+      assert.strictEqual(info.isActive, deposit.isActive);
+      assert.ok(info.createdAt.eq(deposit.createdAt));
+      assert.deepStrictEqual(info.commitmentFingerprint, new Array(8).fill(121));
+
+      // Neither the nullifier nor the full commitment is returned
+      assert.strictEqual(info.nullifierHash, undefined);
+      assert.strictEqual(info.commitment, undefined);
+    });
+  });
+
+  describe("Final State Verification", () => {
+    it("should have consistent vault state", async () => {
+      const vault = await program.account.spectreVault.fetch(vaultPda);
+
+      console.log("Final vault state:");
+      console.log("  Total deposited:", vault.totalDeposited.toNumber() / LAMPORTS_PER_SOL, "SOL");
+      console.log("  Available balance:", vault.availableBalance.toNumber() / LAMPORTS_PER_SOL, "SOL");
+      console.log("  Total deposits count:", vault.totalDepositsCount.toNumber());
+      console.log("  Total withdrawals count:", vault.totalWithdrawalsCount.toNumber());
+      console.log("  Is active:", vault.isActive);
+
+      // Verify invariants
+      assert.ok(vault.availableBalance.toNumber() <= vault.totalDeposited.toNumber());
+      assert.ok(vault.totalDepositsCount.toNumber() >= 1);
+      assert.ok(vault.totalWithdrawalsCount.toNumber() >= 1);
+      assert.strictEqual(vault.isActive, true);
+    });
+
+    it("should have correct vault lamport balance", async () => {
+      const vaultBalance = await provider.connection.getBalance(vaultPda);
+      const vault = await program.account.spectreVault.fetch(vaultPda);
+
+      // Vault lamport balance should be at least the available balance
+      // (plus rent-exempt minimum for the account)
+      console.log("  Vault lamport balance:", vaultBalance / LAMPORTS_PER_SOL, "SOL");
+      assert.ok(vaultBalance > 0);
+    });
+  });
+
+  // ============================================
+  // PHASE 2: THE BRAIN - TEE & Strategy Tests
+  // ============================================
+
+  describe("Phase 2 - Initialize Strategy", () => {
+    const STRATEGY_CONFIG_SEED = Buffer.from("strategy_config");
+    let strategyConfigPda: PublicKey;
+
+    before(async () => {
+      [strategyConfigPda] = PublicKey.findProgramAddressSync(
+        [STRATEGY_CONFIG_SEED, vaultPda.toBuffer()],
+        program.programId
+      );
+    });
+
+    it("should initialize strategy with default params", async () => {
+      const tx = await program.methods
+        .initializeStrategy(null) // Use default params
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      console.log("Initialize strategy transaction:", tx);
+
+      // Verify strategy config state
+      const config = await program.account.strategyConfig.fetch(strategyConfigPda);
+      assert.strictEqual(config.vault.toString(), vaultPda.toString());
+      assert.strictEqual(config.authority.toString(), authority.publicKey.toString());
+      assert.strictEqual(config.priceThresholdLow, 350);
+      assert.strictEqual(config.priceThresholdHigh, 650);
+      assert.strictEqual(config.trendThreshold, 100);
+      assert.strictEqual(config.volatilityCap, 400);
+      assert.strictEqual(config.isActive, true);
+      assert.strictEqual(config.totalSignals.toNumber(), 0);
+    });
+
+    it("should reject duplicate strategy initialization", async () => {
+      try {
+        await program.methods
+          .initializeStrategy(null)
+          .accounts({
+            authority: authority.publicKey,
+            vault: vaultPda,
+            strategyConfig: strategyConfigPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([authority])
+          .rpc();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("already in use"));
+      }
+    });
+  });
+
+  describe("Phase 2 - Delegate to TEE", () => {
+    it("should delegate vault to TEE successfully", async () => {
+      // Verify vault is not delegated before
+      let vault = await program.account.spectreVault.fetch(vaultPda);
+      assert.strictEqual(vault.isDelegated, false);
+
+      const tx = await program.methods
+        .delegateToTee()
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          delegationProgram: null, // No actual TEE in local testing
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      console.log("Delegate to TEE transaction:", tx);
+
+      // Verify vault is now delegated
+      vault = await program.account.spectreVault.fetch(vaultPda);
+      assert.strictEqual(vault.isDelegated, true);
+      assert.ok(vault.delegatedSlot.toNumber() > 0);
+    });
+
+    it("should reject double delegation", async () => {
+      try {
+        await program.methods
+          .delegateToTee()
+          .accounts({
+            authority: authority.publicKey,
+            vault: vaultPda,
+            delegationProgram: null,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([authority])
+          .rpc();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("VaultAlreadyDelegated"));
+      }
+    });
+
+    it("should reject delegation from non-authority", async () => {
+      const notAuthority = Keypair.generate();
+      const airdrop = await provider.connection.requestAirdrop(
+        notAuthority.publicKey,
+        1 * LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdrop);
+
+      try {
+        await program.methods
+          .delegateToTee()
+          .accounts({
+            authority: notAuthority.publicKey,
+            vault: vaultPda,
+            delegationProgram: null,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([notAuthority])
+          .rpc();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        // Should fail due to PDA constraint (vault doesn't match authority)
+        assert.ok(err.toString().includes("Error") || err.toString().includes("constraint"));
+      }
+    });
+  });
+
+  describe("Phase 2 - Generate Trade Signal", () => {
+    const STRATEGY_CONFIG_SEED = Buffer.from("strategy_config");
+    let strategyConfigPda: PublicKey;
+
+    before(async () => {
+      [strategyConfigPda] = PublicKey.findProgramAddressSync(
+        [STRATEGY_CONFIG_SEED, vaultPda.toBuffer()],
+        program.programId
+      );
+    });
+
+    it("should generate BUY signal for low price with positive trend", async () => {
+      // Input: price=300 (0.30), trend=50 (0.05), volatility=200 (0.20)
+      const marketInput = {
+        price: 300,
+        trend: 50,
+        volatility: 200,
+        timestamp: new anchor.BN(Date.now() / 1000),
+      };
+
+      const tx = await program.methods
+        .generateTradeSignal(marketInput)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+        })
+        .signers([authority])
+        .rpc();
+
+      console.log("Generate buy signal transaction:", tx);
+
+      // Check strategy config was updated
+      const config = await program.account.strategyConfig.fetch(strategyConfigPda);
+      assert.strictEqual(config.totalSignals.toNumber(), 1);
+      assert.strictEqual(config.lastSignal, 2); // Buy = 2
+    });
+
+    it("should generate STRONG BUY signal for very low price with strong trend", async () => {
+      // Input: price=250 (0.25), trend=150 (0.15), volatility=100 (0.10)
+      const marketInput = {
+        price: 250,
+        trend: 150,
+        volatility: 100,
+        timestamp: new anchor.BN(Date.now() / 1000),
+      };
+
+      await program.methods
+        .generateTradeSignal(marketInput)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+        })
+        .signers([authority])
+        .rpc();
+
+      const config = await program.account.strategyConfig.fetch(strategyConfigPda);
+      assert.strictEqual(config.totalSignals.toNumber(), 2);
+      assert.strictEqual(config.lastSignal, 1); // StrongBuy = 1
+    });
+
+    it("should generate SELL signal for high price with negative trend", async () => {
+      // Input: price=700 (0.70), trend=-50 (−0.05), volatility=200 (0.20)
+      const marketInput = {
+        price: 700,
+        trend: -50,
+        volatility: 200,
+        timestamp: new anchor.BN(Date.now() / 1000),
+      };
+
+      await program.methods
+        .generateTradeSignal(marketInput)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+        })
+        .signers([authority])
+        .rpc();
+
+      const config = await program.account.strategyConfig.fetch(strategyConfigPda);
+      assert.strictEqual(config.totalSignals.toNumber(), 3);
+      assert.strictEqual(config.lastSignal, 4); // Sell = 4
+    });
+
+    it("should generate STRONG SELL signal for very high price with strong negative trend", async () => {
+      // Input: price=750 (0.75), trend=-150 (−0.15), volatility=100 (0.10)
+      const marketInput = {
+        price: 750,
+        trend: -150,
+        volatility: 100,
+        timestamp: new anchor.BN(Date.now() / 1000),
+      };
+
+      await program.methods
+        .generateTradeSignal(marketInput)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+        })
+        .signers([authority])
+        .rpc();
+
+      const config = await program.account.strategyConfig.fetch(strategyConfigPda);
+      assert.strictEqual(config.totalSignals.toNumber(), 4);
+      assert.strictEqual(config.lastSignal, 5); // StrongSell = 5
+    });
+
+    it("should generate HOLD signal for high volatility", async () => {
+      // Input: price=300, trend=150, but volatility=450 (0.45) > cap (0.40)
+      const marketInput = {
+        price: 300,
+        trend: 150,
+        volatility: 450,
+        timestamp: new anchor.BN(Date.now() / 1000),
+      };
+
+      await program.methods
+        .generateTradeSignal(marketInput)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+        })
+        .signers([authority])
+        .rpc();
+
+      const config = await program.account.strategyConfig.fetch(strategyConfigPda);
+      assert.strictEqual(config.totalSignals.toNumber(), 5);
+      assert.strictEqual(config.lastSignal, 3); // Hold = 3
+    });
+
+    it("should generate HOLD signal for neutral price", async () => {
+      // Input: price=500 (0.50), trend=50 (0.05), volatility=200 (0.20)
+      const marketInput = {
+        price: 500,
+        trend: 50,
+        volatility: 200,
+        timestamp: new anchor.BN(Date.now() / 1000),
+      };
+
+      await program.methods
+        .generateTradeSignal(marketInput)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+        })
+        .signers([authority])
+        .rpc();
+
+      const config = await program.account.strategyConfig.fetch(strategyConfigPda);
+      assert.strictEqual(config.totalSignals.toNumber(), 6);
+      assert.strictEqual(config.lastSignal, 3); // Hold = 3
+    });
+
+    it("should generate a read-only signal without updating strategy stats", async () => {
+      const configBefore = await program.account.strategyConfig.fetch(strategyConfigPda);
+
+      const params = {
+        priceThresholdLow: 350,
+        priceThresholdHigh: 650,
+        trendThreshold: 100,
+        volatilityCap: 400,
+        defaultSignal: { hold: {} },
+        signalOverrides: new Array(9).fill(null),
+        minVolumeForSignal: new anchor.BN(0),
+        reserved: new Array(16).fill(0),
+      };
+      const marketInput = {
+        price: 300,
+        trend: 50,
+        volatility: 200,
+        timestamp: new anchor.BN(Date.now() / 1000),
+      };
+
+      const signal = await program.methods
+        .generateSignalReadonly(params, marketInput)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .view();
+
+      assert.deepStrictEqual(signal, { buy: {} });
+
+      const configAfter = await program.account.strategyConfig.fetch(strategyConfigPda);
+      assert.strictEqual(
+        configAfter.totalSignals.toNumber(),
+        configBefore.totalSignals.toNumber()
+      );
+      assert.strictEqual(configAfter.lastSignal, configBefore.lastSignal);
+    });
+
+    it("should fall back to the default signal when inputs are missing", async () => {
+      const MISSING_TREND = -2147483648; // i32::MIN
+      const MISSING_VOLATILITY = 4294967295; // u32::MAX
+
+      const params = {
+        priceThresholdLow: 350,
+        priceThresholdHigh: 650,
+        trendThreshold: 100,
+        volatilityCap: 400,
+        defaultSignal: { hold: {} },
+        signalOverrides: new Array(9).fill(null),
+        minVolumeForSignal: new anchor.BN(0),
+        reserved: new Array(16).fill(0),
+      };
+      // Would be a BUY with complete data
+      const marketInput = {
+        price: 300,
+        trend: 50,
+        volatility: 200,
+        timestamp: new anchor.BN(Date.now() / 1000),
+      };
+
+      for (const input of [
+        { ...marketInput, trend: MISSING_TREND },
+        { ...marketInput, volatility: MISSING_VOLATILITY },
+      ]) {
+        const signal = await program.methods
+          .generateSignalReadonly(params, input)
+          .accounts({
+            authority: authority.publicKey,
+            vault: vaultPda,
+          })
+          .signers([authority])
+          .view();
+
+        assert.deepStrictEqual(signal, { hold: {} });
+      }
+    });
+
+    it("should apply a signal override for the matching input bucket", async () => {
+      // Bucket 2 = price below low threshold with a positive trend
+      const signalOverrides = new Array(9).fill(null);
+      signalOverrides[2] = { hold: {} };
+
+      const params = {
+        priceThresholdLow: 350,
+        priceThresholdHigh: 650,
+        trendThreshold: 100,
+        volatilityCap: 400,
+        defaultSignal: { hold: {} },
+        signalOverrides,
+        minVolumeForSignal: new anchor.BN(0),
+        reserved: new Array(16).fill(0),
+      };
+      // Would be a BUY without the override
+      const marketInput = {
+        price: 300,
+        trend: 50,
+        volatility: 200,
+        timestamp: new anchor.BN(Date.now() / 1000),
+      };
+
+      const signal = await program.methods
+        .generateSignalReadonly(params, marketInput)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .view();
+
+      assert.deepStrictEqual(signal, { hold: {} });
+    });
+
+    it("should hold on a market with too little volume", async () => {
+      const params = {
+        priceThresholdLow: 350,
+        priceThresholdHigh: 650,
+        trendThreshold: 100,
+        volatilityCap: 400,
+        defaultSignal: { hold: {} },
+        signalOverrides: new Array(9).fill(null),
+        minVolumeForSignal: new anchor.BN(LAMPORTS_PER_SOL),
+        reserved: new Array(16).fill(0),
+      };
+      // Would be a BUY on a liquid market
+      const marketInput = {
+        price: 300,
+        trend: 50,
+        volatility: 200,
+        timestamp: new anchor.BN(Date.now() / 1000),
+        volume: new anchor.BN(0),
+      };
+
+      const signal = await program.methods
+        .generateSignalReadonly(params, marketInput)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .view();
+
+      assert.deepStrictEqual(signal, { hold: {} });
+
+      const liquidSignal = await program.methods
+        .generateSignalReadonly(params, { ...marketInput, volume: new anchor.BN(LAMPORTS_PER_SOL) })
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .view();
+
+      assert.deepStrictEqual(liquidSignal, { buy: {} });
+    });
+
+    it("should return the stored last signal decoded in the strategy config view", async () => {
+      // Input: price=700 (0.70), trend=-50 (−0.05), volatility=200 (0.20) -> SELL
+      await program.methods
+        .generateTradeSignal({
+          price: 700,
+          trend: -50,
+          volatility: 200,
+          timestamp: new anchor.BN(Date.now() / 1000),
+        })
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+        })
+        .signers([authority])
+        .rpc();
+
+      const config = await program.account.strategyConfig.fetch(strategyConfigPda);
+      const view = await program.methods
+        .getStrategyConfig()
+        .accounts({
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+        })
+        .view();
+
+      assert.strictEqual(config.lastSignal, 4); // Sell = 4
+      assert.deepStrictEqual(view.lastSignal, { sell: {} });
+      assert.strictEqual(view.params.priceThresholdLow, config.priceThresholdLow);
+      assert.strictEqual(view.isActive, config.isActive);
+      assert.ok(view.totalSignals.eq(config.totalSignals));
+      assert.strictEqual(
+        view.signalRateBps,
+        Math.floor((config.actionableSignals.toNumber() * 10_000) / config.totalSignals.toNumber())
+      );
+    });
+
+    it("should retain the newest signals in the signal log", async () => {
+      const SIGNAL_LOG_SEED = Buffer.from("signal_log");
+      const [signalLogPda] = PublicKey.findProgramAddressSync(
+        [SIGNAL_LOG_SEED, vaultPda.toBuffer()],
+        program.programId
+      );
+      const capacity = 4;
+
+      await program.methods
+        .initializeSignalLog(capacity)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          signalLog: signalLogPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      // N + 3 signals into a log of capacity N
+      for (let i = 0; i < capacity + 3; i++) {
+        await program.methods
+          .generateTradeSignal({
+            price: 300 + i,
+            trend: 50,
+            volatility: 200,
+            timestamp: new anchor.BN(Date.now() / 1000),
+          })
+          .accounts({
+            authority: authority.publicKey,
+            vault: vaultPda,
+            strategyConfig: strategyConfigPda,
+            signalLog: signalLogPda,
+          })
+          .signers([authority])
+          .rpc();
+      }
+
+      const log = await program.account.signalLog.fetch(signalLogPda);
+      assert.strictEqual(log.len, capacity);
+
+      const retainedPrices = log.entries
+        .slice(0, capacity)
+        .map((entry) => entry.price)
+        .sort((a, b) => a - b);
+      assert.deepStrictEqual(retainedPrices, [303, 304, 305, 306]);
+    });
+  });
+
+  describe("Phase 2 - Price Circuit Breaker", () => {
+    const STRATEGY_CONFIG_SEED = Buffer.from("strategy_config");
+    let strategyConfigPda: PublicKey;
+
+    const setMaxPriceMove = async (maxPriceMoveBps: number) => {
+      await program.methods
+        .setMaxPriceMove(maxPriceMoveBps)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+        })
+        .signers([authority])
+        .rpc();
+    };
+
+    const generateSignal = (price: number) =>
+      program.methods
+        .generateTradeSignal({
+          price,
+          trend: 0,
+          volatility: 200,
+          timestamp: new anchor.BN(Date.now() / 1000),
+        })
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+        })
+        .signers([authority])
+        .rpc();
+
+    before(async () => {
+      [strategyConfigPda] = PublicKey.findProgramAddressSync(
+        [STRATEGY_CONFIG_SEED, vaultPda.toBuffer()],
+        program.programId
+      );
+
+      // 10% breaker
+      await setMaxPriceMove(1_000);
+    });
+
+    after(async () => {
+      await setMaxPriceMove(0);
+    });
+
+    it("should reject a signal after a 30% price jump", async () => {
+      await generateSignal(500);
+      const before = await program.account.strategyConfig.fetch(strategyConfigPda);
+
+      try {
+        await generateSignal(650);
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("PriceMoveTooLarge"));
+      }
+
+      const after = await program.account.strategyConfig.fetch(strategyConfigPda);
+      assert.strictEqual(after.lastPrice, 500);
+      assert.ok(after.totalSignals.eq(before.totalSignals));
+    });
+
+    it("should accept a move within the breaker threshold", async () => {
+      await generateSignal(540);
+
+      const config = await program.account.strategyConfig.fetch(strategyConfigPda);
+      assert.strictEqual(config.lastPrice, 540);
+    });
+  });
+
+  describe("Phase 2 - Idle Undelegation", () => {
+    // MagicBlock magic context and program (only present on the TEE endpoint)
+    const MAGIC_CONTEXT = new PublicKey("MagicContext1111111111111111111111111111111");
+    const MAGIC_PROGRAM = new PublicKey("Magic11111111111111111111111111111111111111");
+
+    const setMaxDelegationIdle = async (slots: number) => {
+      await program.methods
+        .setMaxDelegationIdle(new anchor.BN(slots))
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .rpc();
+    };
+
+    const undelegateIfStale = (payer: Keypair) =>
+      program.methods
+        .undelegateIfStale()
+        .accounts({
+          payer: payer.publicKey,
+          vaultAuthority: authority.publicKey,
+          vault: vaultPda,
+          magicContext: MAGIC_CONTEXT,
+          magicProgram: MAGIC_PROGRAM,
+        })
+        .signers([payer])
+        .rpc();
+
+    let stranger: Keypair;
+
+    before(async () => {
+      stranger = Keypair.generate();
+      const airdrop = await provider.connection.requestAirdrop(stranger.publicKey, LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(airdrop);
+    });
+
+    after(async () => {
+      await setMaxDelegationIdle(0);
+    });
+
+    it("should reject undelegating a vault that is not idle", async () => {
+      await setMaxDelegationIdle(1_000_000_000_000);
+
+      try {
+        await undelegateIfStale(stranger);
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("DelegationNotStale"));
+      }
+    });
+
+    it("should reject undelegating when the idle limit is disabled", async () => {
+      await setMaxDelegationIdle(0);
+
+      try {
+        await undelegateIfStale(stranger);
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("DelegationNotStale"));
+      }
+    });
+
+    it("should let anyone undelegate an idle vault", async () => {
+      await setMaxDelegationIdle(1);
+      const slot = await provider.connection.getSlot();
+      const vault = await program.account.spectreVault.fetch(vaultPda);
+      assert.ok(slot - Math.max(vault.lastTradeSlot.toNumber(), vault.delegatedSlot.toNumber()) > 1);
+
+      // The idle check passes for a non-authority payer; on L1 the magic
+      // program is absent so the commit itself fails
+      try {
+        await undelegateIfStale(stranger);
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(!err.toString().includes("DelegationNotStale"));
+        assert.ok(err.toString().includes("UndelegationFailed"));
+      }
+    });
+  });
+
+  describe("Phase 2 - Staged Deposits", () => {
+    it("should stage a deposit made while the vault is delegated", async () => {
+      const depositor = Keypair.generate();
+      const airdrop = await provider.connection.requestAirdrop(depositor.publicKey, LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(airdrop);
+
+      const commitment = new Array(32).fill(137);
+      const [userDepositPda] = PublicKey.findProgramAddressSync(
+        [DEPOSIT_SEED, vaultPda.toBuffer(), Buffer.from(commitment)],
+        program.programId
+      );
+      const amount = 0.1 * LAMPORTS_PER_SOL;
+
+      const before = await program.account.spectreVault.fetch(vaultPda);
+      assert.strictEqual(before.isDelegated, true);
+
+      await program.methods
+        .fundAgent(
+          {
+            proofData: new Array(256).fill(0),
+            publicInputs: {
+              commitment,
+              nullifierHash: new Array(32).fill(138),
+              amount: new anchor.BN(amount),
+              merkleRoot: new Array(32).fill(0),
+            },
+            proofSlot: new anchor.BN(0),
+          },
+          null
+        )
+        .accounts({
+          depositor: depositor.publicKey,
+          vault: vaultPda,
+          userDeposit: userDepositPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([depositor])
+        .rpc();
+
+      // Counted as a deposit, but kept out of the TEE-controlled balance
+      const after = await program.account.spectreVault.fetch(vaultPda);
+      assert.strictEqual(after.stagedDeposits.sub(before.stagedDeposits).toNumber(), amount);
+      assert.ok(after.availableBalance.eq(before.availableBalance));
+      assert.strictEqual(after.totalDeposited.sub(before.totalDeposited).toNumber(), amount);
+    });
+  });
+
+  describe("Phase 2 - Update Model", () => {
+    it("should update model hash successfully", async () => {
+      const newModelHash = new Array(32).fill(0).map((_, i) => i % 256);
+
+      const tx = await program.methods
+        .updateModel(newModelHash)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .rpc();
+
+      console.log("Update model transaction:", tx);
+
+      const vault = await program.account.spectreVault.fetch(vaultPda);
+      assert.deepStrictEqual(Array.from(vault.modelHash), newModelHash);
+    });
+
+    it("should reject model update from non-authority", async () => {
+      const notAuthority = Keypair.generate();
+      const airdrop = await provider.connection.requestAirdrop(
+        notAuthority.publicKey,
+        1 * LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdrop);
+
+      try {
+        await program.methods
+          .updateModel(new Array(32).fill(99))
+          .accounts({
+            authority: notAuthority.publicKey,
+            vault: vaultPda,
+          })
+          .signers([notAuthority])
+          .rpc();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("Error") || err.toString().includes("constraint"));
+      }
+    });
+  });
+
+  describe("Phase 2 - Set Strategy Params", () => {
+    const STRATEGY_CONFIG_SEED = Buffer.from("strategy_config");
+    let strategyConfigPda: PublicKey;
+
+    before(async () => {
+      [strategyConfigPda] = PublicKey.findProgramAddressSync(
+        [STRATEGY_CONFIG_SEED, vaultPda.toBuffer()],
+        program.programId
+      );
+    });
+
+    it("should update strategy params successfully", async () => {
+      // Set aggressive params
+      const aggressiveParams = {
+        priceThresholdLow: 400,
+        priceThresholdHigh: 600,
+        trendThreshold: 50,
+        volatilityCap: 500,
+        defaultSignal: { hold: {} },
+        signalOverrides: new Array(9).fill(null),
+        minVolumeForSignal: new anchor.BN(0),
+        reserved: new Array(16).fill(0),
+      };
+
+      const tx = await program.methods
+        .setStrategyParams(aggressiveParams)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+        })
+        .signers([authority])
+        .rpc();
+
+      console.log("Set strategy params transaction:", tx);
+
+      const config = await program.account.strategyConfig.fetch(strategyConfigPda);
+      assert.strictEqual(config.priceThresholdLow, 400);
+      assert.strictEqual(config.priceThresholdHigh, 600);
+      assert.strictEqual(config.trendThreshold, 50);
+      assert.strictEqual(config.volatilityCap, 500);
+    });
+
+    it("should reject invalid params (low >= high)", async () => {
+      const invalidParams = {
+        priceThresholdLow: 700, // Higher than high threshold
+        priceThresholdHigh: 300,
+        trendThreshold: 100,
+        volatilityCap: 400,
+        defaultSignal: { hold: {} },
+        signalOverrides: new Array(9).fill(null),
+        minVolumeForSignal: new anchor.BN(0),
+        reserved: new Array(16).fill(0),
+      };
+
+      try {
+        await program.methods
+          .setStrategyParams(invalidParams)
+          .accounts({
+            authority: authority.publicKey,
+            vault: vaultPda,
+            strategyConfig: strategyConfigPda,
+          })
+          .signers([authority])
+          .rpc();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("InvalidStrategyParams"));
+      }
+    });
+
+    it("should reject equal low and high thresholds", async () => {
+      const invalidParams = {
+        priceThresholdLow: 500,
+        priceThresholdHigh: 500, // Must be strictly greater than low
+        trendThreshold: 100,
+        volatilityCap: 400,
+        defaultSignal: { hold: {} },
+        signalOverrides: new Array(9).fill(null),
+        minVolumeForSignal: new anchor.BN(0),
+        reserved: new Array(16).fill(0),
+      };
+
+      try {
+        await program.methods
+          .setStrategyParams(invalidParams)
+          .accounts({
+            authority: authority.publicKey,
+            vault: vaultPda,
+            strategyConfig: strategyConfigPda,
+          })
+          .signers([authority])
+          .rpc();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("InvalidStrategyParams"));
+      }
+    });
+
+    it("should reject zero volatility cap", async () => {
+      const invalidParams = {
+        priceThresholdLow: 350,
+        priceThresholdHigh: 650,
+        trendThreshold: 100,
+        volatilityCap: 0, // Invalid
+        defaultSignal: { hold: {} },
+        signalOverrides: new Array(9).fill(null),
+        minVolumeForSignal: new anchor.BN(0),
+        reserved: new Array(16).fill(0),
+      };
+
+      try {
+        await program.methods
+          .setStrategyParams(invalidParams)
+          .accounts({
+            authority: authority.publicKey,
+            vault: vaultPda,
+            strategyConfig: strategyConfigPda,
+          })
+          .signers([authority])
+          .rpc();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("InvalidStrategyParams"));
+      }
+    });
+  });
+
+  describe("Phase 2 - Protocol Strategy Bounds", () => {
+    const STRATEGY_CONFIG_SEED = Buffer.from("strategy_config");
+    const PROTOCOL_CONFIG_SEED = Buffer.from("protocol_config");
+    const unbounded = { min: new anchor.BN(0), max: new anchor.BN("18446744073709551615") };
+    let strategyConfigPda: PublicKey;
+    let protocolConfigPda: PublicKey;
+
+    const setBounds = (volatilityCap: { min: anchor.BN; max: anchor.BN }) =>
+      program.methods
+        .setStrategyParamBounds({
+          priceThresholdLow: unbounded,
+          priceThresholdHigh: unbounded,
+          trendThreshold: unbounded,
+          volatilityCap,
+          minVolumeForSignal: unbounded,
+        })
+        .accounts({
+          admin: authority.publicKey,
+          protocolConfig: protocolConfigPda,
+        })
+        .signers([authority])
+        .rpc();
+
+    const setParamsWithVolatilityCap = (volatilityCap: number) =>
+      program.methods
+        .setStrategyParams({
+          priceThresholdLow: 350,
+          priceThresholdHigh: 650,
+          trendThreshold: 100,
+          volatilityCap,
+          defaultSignal: { hold: {} },
+          signalOverrides: new Array(9).fill(null),
+          minVolumeForSignal: new anchor.BN(0),
+          reserved: new Array(16).fill(0),
+        })
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+          protocolConfig: protocolConfigPda,
+        })
+        .signers([authority])
+        .rpc();
+
+    before(async () => {
+      [strategyConfigPda] = PublicKey.findProgramAddressSync(
+        [STRATEGY_CONFIG_SEED, vaultPda.toBuffer()],
+        program.programId
+      );
+      [protocolConfigPda] = PublicKey.findProgramAddressSync(
+        [PROTOCOL_CONFIG_SEED],
+        program.programId
+      );
+
+      // No volatility cap below 0.3
+      await setBounds({ min: new anchor.BN(300), max: new anchor.BN(1000) });
+    });
+
+    after(async () => {
+      await setBounds(unbounded);
+    });
+
+    it("should reject params outside the protocol bounds", async () => {
+      try {
+        await setParamsWithVolatilityCap(250);
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("InvalidStrategyParams"));
+      }
+    });
+
+    it("should accept params within the protocol bounds", async () => {
+      await setParamsWithVolatilityCap(400);
+
+      const config = await program.account.strategyConfig.fetch(strategyConfigPda);
+      assert.strictEqual(config.volatilityCap, 400);
+    });
+
+    it("should reject empty bounds", async () => {
+      try {
+        await setBounds({ min: new anchor.BN(500), max: new anchor.BN(400) });
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("InvalidStrategyParams"));
+      }
+    });
+  });
+
+  describe("Phase 2 - Undelegate from TEE", () => {
+    it("should undelegate vault from TEE successfully", async () => {
+      // Verify vault is delegated before
+      let vault = await program.account.spectreVault.fetch(vaultPda);
+      assert.strictEqual(vault.isDelegated, true);
+      const staged = vault.stagedDeposits;
+      assert.ok(staged.gtn(0));
+      const availableBefore = vault.availableBalance;
+
+      const tx = await program.methods
+        .undelegateFromTee()
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          delegationProgram: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      console.log("Undelegate from TEE transaction:", tx);
+
+      // Verify vault is now undelegated
+      vault = await program.account.spectreVault.fetch(vaultPda);
+      assert.strictEqual(vault.isDelegated, false);
+
+      // Deposits staged during delegation are now tradable
+      assert.strictEqual(vault.stagedDeposits.toNumber(), 0);
+      assert.ok(vault.availableBalance.eq(availableBefore.add(staged)));
+    });
+
+    it("should reject undelegation when not delegated", async () => {
+      try {
+        await program.methods
+          .undelegateFromTee()
+          .accounts({
+            authority: authority.publicKey,
+            vault: vaultPda,
+            delegationProgram: null,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([authority])
+          .rpc();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("VaultNotDelegated"));
+      }
+    });
+  });
+
+  describe("Phase 2 - Strategy Determinism", () => {
+    const STRATEGY_CONFIG_SEED = Buffer.from("strategy_config");
+    let strategyConfigPda: PublicKey;
+
+    before(async () => {
+      [strategyConfigPda] = PublicKey.findProgramAddressSync(
+        [STRATEGY_CONFIG_SEED, vaultPda.toBuffer()],
+        program.programId
+      );
+
+      // Reset to default params for determinism test
+      const defaultParams = {
+        priceThresholdLow: 350,
+        priceThresholdHigh: 650,
+        trendThreshold: 100,
+        volatilityCap: 400,
+        defaultSignal: { hold: {} },
+        signalOverrides: new Array(9).fill(null),
+        minVolumeForSignal: new anchor.BN(0),
+        reserved: new Array(16).fill(0),
+      };
+
+      await program.methods
+        .setStrategyParams(defaultParams)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+        })
+        .signers([authority])
+        .rpc();
+    });
+
+    it("should produce same signal for same inputs (determinism)", async () => {
+      // Run same input multiple times
+      const marketInput = {
+        price: 300,
+        trend: 150,
+        volatility: 100,
+        timestamp: new anchor.BN(Date.now() / 1000),
+      };
+
+      // First run
+      await program.methods
+        .generateTradeSignal(marketInput)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+        })
+        .signers([authority])
+        .rpc();
+
+      let config = await program.account.strategyConfig.fetch(strategyConfigPda);
+      const firstSignal = config.lastSignal;
+
+      // Second run with same input
+      await program.methods
+        .generateTradeSignal(marketInput)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+        })
+        .signers([authority])
+        .rpc();
+
+      config = await program.account.strategyConfig.fetch(strategyConfigPda);
+      const secondSignal = config.lastSignal;
+
+      // Third run with same input
+      await program.methods
+        .generateTradeSignal(marketInput)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+        })
+        .signers([authority])
+        .rpc();
+
+      config = await program.account.strategyConfig.fetch(strategyConfigPda);
+      const thirdSignal = config.lastSignal;
+
+      // All signals should be identical (StrongBuy = 1)
+      assert.strictEqual(firstSignal, secondSignal);
+      assert.strictEqual(secondSignal, thirdSignal);
+      assert.strictEqual(firstSignal, 1); // StrongBuy
+    });
+  });
+
+  describe("Phase 2 - Final State Verification", () => {
+    const STRATEGY_CONFIG_SEED = Buffer.from("strategy_config");
+    let strategyConfigPda: PublicKey;
+
+    before(async () => {
+      [strategyConfigPda] = PublicKey.findProgramAddressSync(
+        [STRATEGY_CONFIG_SEED, vaultPda.toBuffer()],
+        program.programId
+      );
+    });
+
+    it("should have consistent Phase 2 state", async () => {
+      const vault = await program.account.spectreVault.fetch(vaultPda);
+      const config = await program.account.strategyConfig.fetch(strategyConfigPda);
+
+      console.log("\nPhase 2 Final State:");
+      console.log("  Vault delegated:", vault.isDelegated);
+      console.log("  Model hash (first 8 bytes):", vault.modelHash.slice(0, 8));
+      console.log("  Strategy active:", config.isActive);
+      console.log("  Total signals generated:", config.totalSignals.toNumber());
+      console.log("  Last signal:", config.lastSignal);
+      console.log("  Price thresholds:", config.priceThresholdLow, "-", config.priceThresholdHigh);
+      console.log("  Volatility cap:", config.volatilityCap);
+
+      // Verify invariants
+      assert.strictEqual(config.vault.toString(), vaultPda.toString());
+      assert.strictEqual(config.authority.toString(), authority.publicKey.toString());
+      assert.ok(config.totalSignals.toNumber() > 0);
+      assert.ok(config.priceThresholdLow < config.priceThresholdHigh);
+    });
+  });
+
+  // ============================================
+  // PHASE 3: THE HAND - Trading Tests
+  // ============================================
+
+  describe("Phase 3 - Execute Trade", () => {
+    const STRATEGY_CONFIG_SEED = Buffer.from("strategy_config");
+    let strategyConfigPda: PublicKey;
+
+    before(async () => {
+      [strategyConfigPda] = PublicKey.findProgramAddressSync(
+        [STRATEGY_CONFIG_SEED, vaultPda.toBuffer()],
+        program.programId
+      );
+    });
+
+    it("should execute trade with BUY signal", async () => {
+      // Input: price=300 (0.30), trend=50 (0.05), volatility=200 (0.20)
+      // Should generate BUY signal
+      const marketInput = {
+        price: 300,
+        trend: 50,
+        volatility: 200,
+        timestamp: new anchor.BN(Date.now() / 1000),
+      };
+
+      const tx = await program.methods
+        .executeTrade(marketInput, null)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      console.log("Execute trade (BUY) transaction:", tx);
+
+      // Verify vault state was updated
+      const vault = await program.account.spectreVault.fetch(vaultPda);
+      assert.ok(vault.totalVolume.toNumber() > 0);
+      assert.ok(vault.lastTradeSlot.toNumber() > 0);
+    });
+
+    it("should execute trade with SELL signal", async () => {
+      // Input: price=700 (0.70), trend=-50 (−0.05), volatility=200 (0.20)
+      // Should generate SELL signal
+      const marketInput = {
+        price: 700,
+        trend: -50,
+        volatility: 200,
+        timestamp: new anchor.BN(Date.now() / 1000),
+      };
+
+      const tx = await program.methods
+        .executeTrade(marketInput, null)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      console.log("Execute trade (SELL) transaction:", tx);
+    });
+
+    it("should NOT execute trade with HOLD signal", async () => {
+      // Input: price=500 (0.50), trend=0, volatility=200 (0.20)
+      // Should generate HOLD signal - no trade executed
+      const marketInput = {
+        price: 500,
+        trend: 0,
+        volatility: 200,
+        timestamp: new anchor.BN(Date.now() / 1000),
+      };
+
+      const vaultBefore = await program.account.spectreVault.fetch(vaultPda);
+      const volumeBefore = vaultBefore.totalVolume.toNumber();
+
+      await program.methods
+        .executeTrade(marketInput, null)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      // Volume should not change for HOLD signal
+      // Note: In mock mode, the trade doesn't actually transfer SOL,
+      // so we just verify it ran without error
+      console.log("Execute trade (HOLD) completed - no trade executed");
+    });
+
+    it("should execute trade with STRONG BUY signal (larger position)", async () => {
+      // Input: price=250 (0.25), trend=150 (0.15), volatility=100 (0.10)
+      // Should generate STRONG BUY signal with 2x position size
+      const marketInput = {
+        price: 250,
+        trend: 150,
+        volatility: 100,
+        timestamp: new anchor.BN(Date.now() / 1000),
+      };
+
+      const tx = await program.methods
+        .executeTrade(marketInput, null)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      console.log("Execute trade (STRONG BUY) transaction:", tx);
+    });
+  });
+
+  describe("Phase 3 - Open Position", () => {
+    const POSITION_SEED = Buffer.from("position");
+    let testMarketId: Keypair;
+    let positionPda: PublicKey;
+
+    before(async () => {
+      testMarketId = Keypair.generate();
+      [positionPda] = PublicKey.findProgramAddressSync(
+        [POSITION_SEED, vaultPda.toBuffer(), testMarketId.publicKey.toBuffer()],
+        program.programId
+      );
+    });
+
+    it("should open a YES position successfully", async () => {
+      const shares = new anchor.BN(100_000_000); // 100 shares
+      const entryPrice = new anchor.BN(500_000); // 0.5 per share
+      const investedAmount = new anchor.BN(50_000_000); // 0.05 SOL
+
+      const tx = await program.methods
+        .openPosition(
+          testMarketId.publicKey,
+          { yes: {} }, // TradeSide::Yes
+          shares,
+          entryPrice,
+          investedAmount,
+          null
+        )
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          position: positionPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      console.log("Open position transaction:", tx);
+
+      // Verify position state
+      const position = await program.account.position.fetch(positionPda);
+      assert.strictEqual(position.vault.toString(), vaultPda.toString());
+      assert.strictEqual(position.marketId.toString(), testMarketId.publicKey.toString());
+      assert.deepStrictEqual(position.side, { yes: {} });
+      assert.strictEqual(position.shares.toNumber(), 100_000_000);
+      assert.strictEqual(position.entryPrice.toNumber(), 500_000);
+      assert.strictEqual(position.investedAmount.toNumber(), 50_000_000);
+      assert.deepStrictEqual(position.status, { open: {} });
+
+      // Verify vault state updated
+      const vault = await program.account.spectreVault.fetch(vaultPda);
+      assert.strictEqual(vault.activePositions, 1);
+    });
+
+    it("should reject position with zero shares", async () => {
+      const badMarketId = Keypair.generate();
+      const [badPositionPda] = PublicKey.findProgramAddressSync(
+        [POSITION_SEED, vaultPda.toBuffer(), badMarketId.publicKey.toBuffer()],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .openPosition(
+            badMarketId.publicKey,
+            { yes: {} },
+            new anchor.BN(0), // Zero shares - invalid
+            new anchor.BN(500_000),
+            new anchor.BN(50_000_000),
+            null
+          )
+          .accounts({
+            authority: authority.publicKey,
+            vault: vaultPda,
+            position: badPositionPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([authority])
+          .rpc();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("InvalidTradeAmount"));
+      }
+    });
+
+    it("should reject position with zero price", async () => {
+      const badMarketId = Keypair.generate();
+      const [badPositionPda] = PublicKey.findProgramAddressSync(
+        [POSITION_SEED, vaultPda.toBuffer(), badMarketId.publicKey.toBuffer()],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .openPosition(
+            badMarketId.publicKey,
+            { no: {} },
+            new anchor.BN(100_000),
+            new anchor.BN(0), // Zero price - invalid
+            new anchor.BN(50_000_000),
+            null
+          )
+          .accounts({
+            authority: authority.publicKey,
+            vault: vaultPda,
+            position: badPositionPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([authority])
+          .rpc();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("InvalidPrice"));
+      }
+    });
+  });
+
+  describe("Phase 3 - Get Position PnL", () => {
+    const POSITION_SEED = Buffer.from("position");
+    let testMarketId: Keypair;
+    let positionPda: PublicKey;
+
+    before(async () => {
+      // Create a new position for PnL testing
+      testMarketId = Keypair.generate();
+      [positionPda] = PublicKey.findProgramAddressSync(
+        [POSITION_SEED, vaultPda.toBuffer(), testMarketId.publicKey.toBuffer()],
+        program.programId
+      );
+
+      // Open a position
+      await program.methods
+        .openPosition(
+          testMarketId.publicKey,
+          { yes: {} },
+          new anchor.BN(100_000_000), // 100 shares
+          new anchor.BN(500_000), // 0.5 per share
+          new anchor.BN(50_000_000), // 0.05 SOL invested
+          null
+        )
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          position: positionPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+    });
+
+    it("should calculate positive PnL for price increase", async () => {
+      // Price went up from 0.5 to 0.7
+      const currentPrice = new anchor.BN(700_000); // 0.7
+
+      const tx = await program.methods
+        .getPositionPnl(currentPrice)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          position: positionPda,
+        })
+        .signers([authority])
+        .rpc();
+
+      console.log("Get position PnL (profit) transaction:", tx);
+
+      // Position value at 0.7: 100 shares * 0.7 = 70M lamports
+      // Invested: 50M lamports
+      // PnL: +20M lamports
+      const position = await program.account.position.fetch(positionPda);
+      assert.deepStrictEqual(position.status, { open: {} });
+    });
+
+    it("should calculate negative PnL for price decrease", async () => {
+      // Price went down from 0.5 to 0.3
+      const currentPrice = new anchor.BN(300_000); // 0.3
+
+      const tx = await program.methods
+        .getPositionPnl(currentPrice)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          position: positionPda,
+        })
+        .signers([authority])
+        .rpc();
+
+      console.log("Get position PnL (loss) transaction:", tx);
+
+      // Position value at 0.3: 100 shares * 0.3 = 30M lamports
+      // Invested: 50M lamports
+      // PnL: -20M lamports
+    });
+  });
+
+  describe("Phase 3 - Close Position", () => {
+    const POSITION_SEED = Buffer.from("position");
+    let testMarketId: Keypair;
+    let positionPda: PublicKey;
+
+    before(async () => {
+      // Create a new position for closing
+      testMarketId = Keypair.generate();
+      [positionPda] = PublicKey.findProgramAddressSync(
+        [POSITION_SEED, vaultPda.toBuffer(), testMarketId.publicKey.toBuffer()],
+        program.programId
+      );
+
+      // Open a position
+      await program.methods
+        .openPosition(
+          testMarketId.publicKey,
+          { yes: {} },
+          new anchor.BN(100_000_000), // 100 shares
+          new anchor.BN(500_000), // 0.5 per share
+          new anchor.BN(50_000_000), // 0.05 SOL invested
+          null
+        )
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          position: positionPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+    });
+
+    it("should close position with profit", async () => {
+      const vaultBefore = await program.account.spectreVault.fetch(vaultPda);
+      const activePositionsBefore = vaultBefore.activePositions;
+
+      // Exit at price 0.7 (profit)
+      const exitPrice = new anchor.BN(700_000);
+
+      const tx = await program.methods
+        .closePosition(exitPrice)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          position: positionPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      console.log("Close position (profit) transaction:", tx);
+
+      // Verify position state
+      const position = await program.account.position.fetch(positionPda);
+      assert.deepStrictEqual(position.status, { closed: {} });
+      assert.strictEqual(position.exitPrice.toNumber(), 700_000);
+      assert.ok(position.realizedPnl.toNumber() > 0); // Profit
+
+      // Verify vault state
+      const vaultAfter = await program.account.spectreVault.fetch(vaultPda);
+      assert.strictEqual(vaultAfter.activePositions, activePositionsBefore - 1);
+
+      console.log("  Realized PnL:", position.realizedPnl.toNumber(), "lamports");
+    });
+
+    it("should reject closing already closed position", async () => {
+      try {
+        await program.methods
+          .closePosition(new anchor.BN(500_000))
+          .accounts({
+            authority: authority.publicKey,
+            vault: vaultPda,
+            position: positionPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([authority])
+          .rpc();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("PositionAlreadyClosed"));
+      }
+    });
+  });
+
+  describe("Phase 3 - Close Position with Loss", () => {
+    const POSITION_SEED = Buffer.from("position");
+    let testMarketId: Keypair;
+    let positionPda: PublicKey;
+
+    before(async () => {
+      // Create a new position for closing at a loss
+      testMarketId = Keypair.generate();
+      [positionPda] = PublicKey.findProgramAddressSync(
+        [POSITION_SEED, vaultPda.toBuffer(), testMarketId.publicKey.toBuffer()],
+        program.programId
+      );
+
+      // Open a position
+      await program.methods
+        .openPosition(
+          testMarketId.publicKey,
+          { no: {} }, // NO side this time
+          new anchor.BN(100_000_000),
+          new anchor.BN(500_000),
+          new anchor.BN(50_000_000),
+          null
+        )
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          position: positionPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+    });
+
+    it("should close position with loss", async () => {
+      // Exit at price 0.3 (loss)
+      const exitPrice = new anchor.BN(300_000);
+
+      const tx = await program.methods
+        .closePosition(exitPrice)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          position: positionPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      console.log("Close position (loss) transaction:", tx);
+
+      // Verify position state
+      const position = await program.account.position.fetch(positionPda);
+      assert.deepStrictEqual(position.status, { closed: {} });
+      assert.strictEqual(position.exitPrice.toNumber(), 300_000);
+      assert.ok(position.realizedPnl.toNumber() < 0); // Loss
+
+      console.log("  Realized PnL:", position.realizedPnl.toNumber(), "lamports");
+    });
+  });
+
+  describe("Phase 3 - Full Trading Loop", () => {
+    const POSITION_SEED = Buffer.from("position");
+    const STRATEGY_CONFIG_SEED = Buffer.from("strategy_config");
+    let strategyConfigPda: PublicKey;
+
+    before(async () => {
+      [strategyConfigPda] = PublicKey.findProgramAddressSync(
+        [STRATEGY_CONFIG_SEED, vaultPda.toBuffer()],
+        program.programId
+      );
+    });
+
+    it("should complete full trading loop: signal -> position -> close", async () => {
+      console.log("\n--- Full Trading Loop ---");
+
+      // 1. Generate a strong buy signal
+      const marketInput = {
+        price: 250,
+        trend: 200,
+        volatility: 50,
+        timestamp: new anchor.BN(Date.now() / 1000),
+      };
+
+      await program.methods
+        .executeTrade(marketInput, null)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      const config = await program.account.strategyConfig.fetch(strategyConfigPda);
+      console.log("  1. Signal generated:", config.lastSignal === 1 ? "STRONG BUY" : "OTHER");
+
+      // 2. Open a position based on the signal
+      const marketId = Keypair.generate();
+      const [positionPda] = PublicKey.findProgramAddressSync(
+        [POSITION_SEED, vaultPda.toBuffer(), marketId.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .openPosition(
+          marketId.publicKey,
+          { yes: {} },
+          new anchor.BN(200_000_000), // Strong signal = larger position
+          new anchor.BN(250_000), // Entry at 0.25
+          new anchor.BN(50_000_000),
+          null
+        )
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          position: positionPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      console.log("  2. Position opened");
+
+      // 3. Check PnL at a higher price
+      await program.methods
+        .getPositionPnl(new anchor.BN(400_000)) // Price moved to 0.4
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          position: positionPda,
+        })
+        .signers([authority])
+        .rpc();
+
+      console.log("  3. PnL checked");
+
+      // 4. Close the position at profit
+      await program.methods
+        .closePosition(new anchor.BN(500_000)) // Exit at 0.5
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          position: positionPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      const position = await program.account.position.fetch(positionPda);
+      console.log("  4. Position closed with PnL:", position.realizedPnl.toNumber(), "lamports");
+
+      assert.deepStrictEqual(position.status, { closed: {} });
+      assert.ok(position.realizedPnl.toNumber() > 0);
+    });
+  });
+
+  describe("Phase 3 - Signal Confirmation", () => {
+    const STRATEGY_CONFIG_SEED = Buffer.from("strategy_config");
+    let strategyConfigPda: PublicKey;
+
+    const normalBuyInput = () => ({
+      price: 300,
+      trend: 50,
+      volatility: 200,
+      timestamp: new anchor.BN(Date.now() / 1000),
+    });
+
+    const strongBuyInput = () => ({
+      price: 250,
+      trend: 150,
+      volatility: 100,
+      timestamp: new anchor.BN(Date.now() / 1000),
+    });
+
+    const setRequireConfirmation = async (required: boolean) => {
+      await program.methods
+        .setRequireConfirmationForNormal(required)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+        })
+        .signers([authority])
+        .rpc();
+    };
+
+    before(async () => {
+      [strategyConfigPda] = PublicKey.findProgramAddressSync(
+        [STRATEGY_CONFIG_SEED, vaultPda.toBuffer()],
+        program.programId
+      );
+
+      await setRequireConfirmation(true);
+    });
+
+    after(async () => {
+      await setRequireConfirmation(false);
+    });
+
+    it("should auto-execute a strong signal", async () => {
+      const volumeBefore = (await program.account.spectreVault.fetch(vaultPda)).totalVolume;
+
+      await program.methods
+        .executeTrade(strongBuyInput(), null)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      const vault = await program.account.spectreVault.fetch(vaultPda);
+      const config = await program.account.strategyConfig.fetch(strategyConfigPda);
+      assert.ok(vault.totalVolume.gt(volumeBefore));
+      assert.strictEqual(config.pendingSignal, null);
+    });
+
+    it("should hold a normal signal until confirmed", async () => {
+      const volumeBefore = (await program.account.spectreVault.fetch(vaultPda)).totalVolume;
+
+      await program.methods
+        .executeTrade(normalBuyInput(), null)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      let vault = await program.account.spectreVault.fetch(vaultPda);
+      let config = await program.account.strategyConfig.fetch(strategyConfigPda);
+      assert.ok(vault.totalVolume.eq(volumeBefore), "Normal signal should not trade");
+      assert.deepStrictEqual(config.pendingSignal, { buy: {} });
+
+      await program.methods
+        .confirmPendingTrade(null)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      vault = await program.account.spectreVault.fetch(vaultPda);
+      config = await program.account.strategyConfig.fetch(strategyConfigPda);
+      assert.ok(vault.totalVolume.gt(volumeBefore), "Confirmed signal should trade");
+      assert.strictEqual(config.pendingSignal, null);
+    });
+
+    it("should reject confirmation with no pending signal", async () => {
+      try {
+        await program.methods
+          .confirmPendingTrade(null)
+          .accounts({
+            authority: authority.publicKey,
+            vault: vaultPda,
+            strategyConfig: strategyConfigPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([authority])
+          .rpc();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("NoPendingSignal"));
+      }
+    });
+  });
+
+  describe("Phase 3 - Keeper", () => {
+    const STRATEGY_CONFIG_SEED = Buffer.from("strategy_config");
+    let strategyConfigPda: PublicKey;
+    let keeper: Keypair;
+
+    const marketInput = () => ({
+      price: 300,
+      trend: 50,
+      volatility: 200,
+      timestamp: new anchor.BN(Date.now() / 1000),
+    });
+
+    before(async () => {
+      [strategyConfigPda] = PublicKey.findProgramAddressSync(
+        [STRATEGY_CONFIG_SEED, vaultPda.toBuffer()],
+        program.programId
+      );
+      keeper = Keypair.generate();
+
+      await program.methods
+        .setKeeper(keeper.publicKey)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .rpc();
+    });
+
+    after(async () => {
+      await program.methods
+        .setKeeper(PublicKey.default)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .rpc();
+    });
+
+    it("should let the keeper execute trades", async () => {
+      const volumeBefore = (await program.account.spectreVault.fetch(vaultPda)).totalVolume;
+
+      await program.methods
+        .executeTrade(marketInput(), null)
+        .accounts({
+          authority: keeper.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([keeper])
+        .rpc();
+
+      const vault = await program.account.spectreVault.fetch(vaultPda);
+      assert.ok(vault.totalVolume.gt(volumeBefore));
+    });
+
+    it("should reject the keeper from admin actions", async () => {
+      try {
+        await program.methods
+          .setStrategyParams({
+            priceThresholdLow: 350,
+            priceThresholdHigh: 650,
+            trendThreshold: 100,
+            volatilityCap: 400,
+            defaultSignal: { hold: {} },
+            signalOverrides: new Array(9).fill(null),
+            minVolumeForSignal: new anchor.BN(0),
+            reserved: new Array(16).fill(0),
+          })
+          .accounts({
+            authority: keeper.publicKey,
+            vault: vaultPda,
+            strategyConfig: strategyConfigPda,
+          })
+          .signers([keeper])
+          .rpc();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(!err.toString().includes("Should have thrown"));
+      }
+    });
+
+    it("should reject trades from a signer that is neither authority nor keeper", async () => {
+      const stranger = Keypair.generate();
+
+      try {
+        await program.methods
+          .executeTrade(marketInput(), null)
+          .accounts({
+            authority: stranger.publicKey,
+            vault: vaultPda,
+            strategyConfig: strategyConfigPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([stranger])
+          .rpc();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("Unauthorized"));
+      }
+    });
+  });
+
+  describe("Phase 3 - Model Max Age", () => {
+    const STRATEGY_CONFIG_SEED = Buffer.from("strategy_config");
+    let strategyConfigPda: PublicKey;
+
+    const marketInput = () => ({
+      price: 300,
+      trend: 50,
+      volatility: 200,
+      timestamp: new anchor.BN(Date.now() / 1000),
+    });
+
+    const setModelMaxAge = async (maxAgeSeconds: number) => {
+      await program.methods
+        .setModelMaxAge(new anchor.BN(maxAgeSeconds))
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .rpc();
+    };
+
+    const generateSignal = () =>
+      program.methods
+        .generateTradeSignal(marketInput())
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+        })
+        .signers([authority])
+        .rpc();
+
+    before(async () => {
+      [strategyConfigPda] = PublicKey.findProgramAddressSync(
+        [STRATEGY_CONFIG_SEED, vaultPda.toBuffer()],
+        program.programId
+      );
+    });
+
+    after(async () => {
+      await setModelMaxAge(0);
+    });
+
+    it("should block signals until the model is updated", async () => {
+      await setModelMaxAge(2);
+      await new Promise((resolve) => setTimeout(resolve, 4000));
+
+      try {
+        await generateSignal();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("StaleModel"));
+      }
+
+      await program.methods
+        .updateModel(new Array(32).fill(7))
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .rpc();
+
+      await generateSignal();
+    });
+  });
+
+  describe("Phase 3 - Execute And Open", () => {
+    const STRATEGY_CONFIG_SEED = Buffer.from("strategy_config");
+    const POSITION_SEED = Buffer.from("position");
+    let strategyConfigPda: PublicKey;
+
+    before(async () => {
+      [strategyConfigPda] = PublicKey.findProgramAddressSync(
+        [STRATEGY_CONFIG_SEED, vaultPda.toBuffer()],
+        program.programId
+      );
+    });
+
+    const positionFor = (marketId: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [POSITION_SEED, vaultPda.toBuffer(), marketId.toBuffer()],
+        program.programId
+      )[0];
+
+    it("should open a position matching the executed trade", async () => {
+      const marketId = Keypair.generate().publicKey;
+      const positionPda = positionFor(marketId);
+      const vaultBefore = await program.account.spectreVault.fetch(vaultPda);
+
+      await program.methods
+        .executeAndOpen(
+          marketId,
+          {
+            price: 300,
+            trend: 50,
+            volatility: 200,
+            timestamp: new anchor.BN(Date.now() / 1000),
+          },
+          null
+        )
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+          position: positionPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      const vaultAfter = await program.account.spectreVault.fetch(vaultPda);
+      const position = await program.account.position.fetch(positionPda);
+
+      // The position records exactly what the trade spent
+      const traded = vaultBefore.availableBalance.sub(vaultAfter.availableBalance);
+      assert.ok(traded.gtn(0));
+      assert.ok(position.investedAmount.eq(traded));
+      assert.ok(vaultAfter.totalVolume.sub(vaultBefore.totalVolume).eq(traded));
+      assert.ok(vaultAfter.lifetimeVolume.sub(vaultBefore.lifetimeVolume).eq(traded));
+
+      assert.strictEqual(position.marketId.toString(), marketId.toString());
+      assert.deepStrictEqual(position.side, { yes: {} });
+      assert.deepStrictEqual(position.status, { open: {} });
+      assert.ok(position.shares.gtn(0));
+      assert.ok(position.entryPrice.gtn(0));
+      assert.strictEqual(vaultAfter.activePositions, vaultBefore.activePositions + 1);
+    });
+
+    it("should open nothing on a HOLD signal", async () => {
+      const marketId = Keypair.generate().publicKey;
+      const positionPda = positionFor(marketId);
+
+      try {
+        await program.methods
+          .executeAndOpen(
+            marketId,
+            {
+              price: 500,
+              trend: 0,
+              volatility: 200,
+              timestamp: new anchor.BN(Date.now() / 1000),
+            },
+            null
+          )
+          .accounts({
+            authority: authority.publicKey,
+            vault: vaultPda,
+            strategyConfig: strategyConfigPda,
+            position: positionPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([authority])
+          .rpc();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("InvalidTradeSignal"));
+      }
+
+      const info = await provider.connection.getAccountInfo(positionPda);
+      assert.strictEqual(info, null);
+    });
+
+    it("should emit a PositionClosedEvent whose fees reconcile", async () => {
+      const marketId = Keypair.generate().publicKey;
+      const positionPda = positionFor(marketId);
+
+      await program.methods
+        .executeAndOpen(
+          marketId,
+          {
+            price: 300,
+            trend: 50,
+            volatility: 200,
+            timestamp: new anchor.BN(Date.now() / 1000),
+          },
+          null
+        )
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+          position: positionPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      const position = await program.account.position.fetch(positionPda);
+      assert.ok(position.entryFees.gtn(0));
+
+      let listener: number;
+      const eventPromise = new Promise<any>((resolve) => {
+        listener = program.addEventListener("positionClosedEvent", (event) => {
+          if (event.position.equals(positionPda)) {
+            resolve(event);
+          }
+        });
+      });
+
+      await program.methods
+        .closePosition(new anchor.BN(600_000))
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          position: positionPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      const event = await eventPromise;
+      await program.removeEventListener(listener);
+
+      assert.ok(event.investedAmount.eq(position.investedAmount));
+      assert.ok(event.entryFees.eq(position.entryFees));
+      assert.ok(event.priorRealizedPnl.eqn(0));
+      assert.ok(
+        event.grossPnl.sub(event.entryFees).sub(event.exitFees).eq(event.netPnl)
+      );
+
+      const closed = await program.account.position.fetch(positionPda);
+      assert.ok(event.netPnl.eq(closed.realizedPnl));
+    });
+  });
+
+  describe("Phase 3 - Min Holding Period", () => {
+    const POSITION_SEED = Buffer.from("position");
+    const minHoldingSeconds = 2;
+    let testMarketId: Keypair;
+    let positionPda: PublicKey;
+
+    const setMinHoldingPeriod = async (minSeconds: number) => {
+      await program.methods
+        .setMinHoldingPeriod(new anchor.BN(minSeconds))
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .rpc();
+    };
+
+    const closePosition = () =>
+      program.methods
+        .closePosition(new anchor.BN(500_000))
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          position: positionPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+    before(async () => {
+      testMarketId = Keypair.generate();
+      [positionPda] = PublicKey.findProgramAddressSync(
+        [POSITION_SEED, vaultPda.toBuffer(), testMarketId.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await setMinHoldingPeriod(minHoldingSeconds);
+
+      await program.methods
+        .openPosition(
+          testMarketId.publicKey,
+          { yes: {} },
+          new anchor.BN(10_000_000),
+          new anchor.BN(500_000),
+          new anchor.BN(5_000_000),
+          null
+        )
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          position: positionPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+    });
+
+    after(async () => {
+      await setMinHoldingPeriod(0);
+    });
+
+    it("should reject closing a position before the holding period", async () => {
+      try {
+        await closePosition();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("PositionTooYoung"));
+      }
+
+      const position = await program.account.position.fetch(positionPda);
+      assert.deepStrictEqual(position.status, { open: {} });
+    });
+
+    it("should close a position once the holding period has passed", async () => {
+      await new Promise((resolve) => setTimeout(resolve, (minHoldingSeconds + 2) * 1000));
+
+      await closePosition();
+
+      const position = await program.account.position.fetch(positionPda);
+      assert.deepStrictEqual(position.status, { closed: {} });
+    });
+
+    it("should let a forced close skip the holding period", async () => {
+      const forcedMarketId = Keypair.generate();
+      const [forcedPositionPda] = PublicKey.findProgramAddressSync(
+        [POSITION_SEED, vaultPda.toBuffer(), forcedMarketId.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .openPosition(
+          forcedMarketId.publicKey,
+          { yes: {} },
+          new anchor.BN(10_000_000),
+          new anchor.BN(500_000),
+          new anchor.BN(5_000_000),
+          null
+        )
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          position: forcedPositionPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      await program.methods
+        .forceClosePosition(new anchor.BN(500_000))
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          position: forcedPositionPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      const position = await program.account.position.fetch(forcedPositionPda);
+      assert.deepStrictEqual(position.status, { closed: {} });
+    });
+  });
+
+  describe("Phase 3 - Decrease Position", () => {
+    const POSITION_SEED = Buffer.from("position");
+    let testMarketId: Keypair;
+    let positionPda: PublicKey;
+
+    const decreasePosition = (shares: number, exitPrice: number) =>
+      program.methods
+        .decreasePosition(new anchor.BN(shares), new anchor.BN(exitPrice))
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          position: positionPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+    before(async () => {
+      testMarketId = Keypair.generate();
+      [positionPda] = PublicKey.findProgramAddressSync(
+        [POSITION_SEED, vaultPda.toBuffer(), testMarketId.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .openPosition(
+          testMarketId.publicKey,
+          { yes: {} },
+          new anchor.BN(10_000_000),
+          new anchor.BN(500_000),
+          new anchor.BN(5_000_000),
+          null
+        )
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          position: positionPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+    });
+
+    it("should decrease a position by 30% and credit the vault", async () => {
+      const vaultBefore = await program.account.spectreVault.fetch(vaultPda);
+
+      // Sell 3M of 10M shares at 0.60 -> 1.8M lamports against 1.5M cost basis
+      await decreasePosition(3_000_000, 600_000);
+
+      const position = await program.account.position.fetch(positionPda);
+      assert.deepStrictEqual(position.status, { open: {} });
+      assert.strictEqual(position.shares.toNumber(), 7_000_000);
+      assert.strictEqual(position.investedAmount.toNumber(), 3_500_000);
+      assert.strictEqual(position.realizedPnl.toNumber(), 300_000);
+
+      const vaultAfter = await program.account.spectreVault.fetch(vaultPda);
+      assert.strictEqual(
+        vaultAfter.availableBalance.sub(vaultBefore.availableBalance).toNumber(),
+        1_800_000
+      );
+      assert.strictEqual(vaultAfter.activePositions, vaultBefore.activePositions);
+    });
+
+    it("should reject decreasing more shares than held", async () => {
+      try {
+        await decreasePosition(8_000_000, 600_000);
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("InsufficientBalance"));
+      }
+
+      const position = await program.account.position.fetch(positionPda);
+      assert.strictEqual(position.shares.toNumber(), 7_000_000);
+    });
+
+    it("should point a full-size decrease at close_position", async () => {
+      try {
+        await decreasePosition(7_000_000, 600_000);
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("DecreaseClosesPosition"));
+      }
+
+      const position = await program.account.position.fetch(positionPda);
+      assert.strictEqual(position.shares.toNumber(), 7_000_000);
+    });
+
+    it("should include accrued PnL when the rest is closed", async () => {
+      let listener: number;
+      const eventPromise = new Promise<any>((resolve) => {
+        listener = program.addEventListener("positionClosedEvent", (event) => {
+          if (event.position.equals(positionPda)) {
+            resolve(event);
+          }
+        });
+      });
+
+      // Remaining 7M shares at 0.50 -> 3.5M, flat against their cost basis
+      await program.methods
+        .closePosition(new anchor.BN(500_000))
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          position: positionPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      const position = await program.account.position.fetch(positionPda);
+      assert.deepStrictEqual(position.status, { closed: {} });
+      assert.strictEqual(position.realizedPnl.toNumber(), 300_000);
+
+      // The fee breakdown still reconciles with the decrease's PnL folded in
+      const event = await eventPromise;
+      await program.removeEventListener(listener);
+      assert.strictEqual(event.priorRealizedPnl.toNumber(), 300_000);
+      assert.ok(
+        event.grossPnl
+          .sub(event.entryFees)
+          .sub(event.exitFees)
+          .add(event.priorRealizedPnl)
+          .eq(event.netPnl)
+      );
+      assert.ok(event.netPnl.eq(position.realizedPnl));
+    });
+  });
+
+  describe("Phase 3 - Settlement Currency", () => {
+    const POSITION_SEED = Buffer.from("position");
+    let testMarketId: Keypair;
+    let positionPda: PublicKey;
+
+    const setSettlementMint = async (mint: PublicKey) => {
+      await program.methods
+        .setSettlementMint(mint)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .rpc();
+    };
+
+    const closePosition = () =>
+      program.methods
+        .closePosition(new anchor.BN(500_000))
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          position: positionPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+    before(async () => {
+      testMarketId = Keypair.generate();
+      [positionPda] = PublicKey.findProgramAddressSync(
+        [POSITION_SEED, vaultPda.toBuffer(), testMarketId.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .openPosition(
+          testMarketId.publicKey,
+          { yes: {} },
+          new anchor.BN(10_000_000),
+          new anchor.BN(500_000),
+          new anchor.BN(5_000_000),
+          null
+        )
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          position: positionPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+    });
+
+    after(async () => {
+      await setSettlementMint(PublicKey.default);
+    });
+
+    it("should record the vault's settlement currency on the position", async () => {
+      const position = await program.account.position.fetch(positionPda);
+      assert.ok(position.settlementMint.equals(PublicKey.default));
+    });
+
+    it("should reject closing a position in a different currency", async () => {
+      await setSettlementMint(Keypair.generate().publicKey);
+
+      try {
+        await closePosition();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("CurrencyMismatch"));
       }
+
+      const position = await program.account.position.fetch(positionPda);
+      assert.deepStrictEqual(position.status, { open: {} });
+    });
+
+    it("should close a position settling in the vault's currency", async () => {
+      await setSettlementMint(PublicKey.default);
+
+      await closePosition();
+
+      const position = await program.account.position.fetch(positionPda);
+      assert.deepStrictEqual(position.status, { closed: {} });
     });
   });
 
-  describe("Phase 2 - Generate Trade Signal", () => {
+  describe("Phase 3 - Loss Cooldown", () => {
+    const POSITION_SEED = Buffer.from("position");
     const STRATEGY_CONFIG_SEED = Buffer.from("strategy_config");
+    const cooldownSlots = 10;
     let strategyConfigPda: PublicKey;
+    let positionPda: PublicKey;
+
+    const setLossCooldown = async (slots: number) => {
+      await program.methods
+        .setLossCooldown(new anchor.BN(slots))
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .rpc();
+    };
+
+    const executeTrade = () =>
+      program.methods
+        .executeTrade(
+          {
+            price: 300,
+            trend: 50,
+            volatility: 200,
+            timestamp: new anchor.BN(Date.now() / 1000),
+          },
+          null
+        )
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
 
     before(async () => {
       [strategyConfigPda] = PublicKey.findProgramAddressSync(
         [STRATEGY_CONFIG_SEED, vaultPda.toBuffer()],
         program.programId
       );
-    });
+      const marketId = Keypair.generate().publicKey;
+      [positionPda] = PublicKey.findProgramAddressSync(
+        [POSITION_SEED, vaultPda.toBuffer(), marketId.toBuffer()],
+        program.programId
+      );
 
-    it("should generate BUY signal for low price with positive trend", async () => {
-      // Input: price=300 (0.30), trend=50 (0.05), volatility=200 (0.20)
-      const marketInput = {
-        price: 300,
-        trend: 50,
-        volatility: 200,
-        timestamp: new anchor.BN(Date.now() / 1000),
-      };
+      await setLossCooldown(cooldownSlots);
 
-      const tx = await program.methods
-        .generateTradeSignal(marketInput)
+      await program.methods
+        .openPosition(
+          marketId,
+          { yes: {} },
+          new anchor.BN(10_000_000),
+          new anchor.BN(500_000),
+          new anchor.BN(5_000_000),
+          null
+        )
         .accounts({
           authority: authority.publicKey,
           vault: vaultPda,
-          strategyConfig: strategyConfigPda,
+          position: positionPda,
+          systemProgram: SystemProgram.programId,
         })
         .signers([authority])
         .rpc();
-
-      console.log("Generate buy signal transaction:", tx);
-
-      // Check strategy config was updated
-      const config = await program.account.strategyConfig.fetch(strategyConfigPda);
-      assert.strictEqual(config.totalSignals.toNumber(), 1);
-      assert.strictEqual(config.lastSignal, 2); // Buy = 2
     });
 
-    it("should generate STRONG BUY signal for very low price with strong trend", async () => {
-      // Input: price=250 (0.25), trend=150 (0.15), volatility=100 (0.10)
-      const marketInput = {
-        price: 250,
-        trend: 150,
-        volatility: 100,
-        timestamp: new anchor.BN(Date.now() / 1000),
-      };
+    after(async () => {
+      await setLossCooldown(0);
+    });
 
+    it("should block trades right after a losing close", async () => {
+      // Exit at 0.30 against a 0.50 entry
       await program.methods
-        .generateTradeSignal(marketInput)
+        .closePosition(new anchor.BN(300_000))
         .accounts({
           authority: authority.publicKey,
           vault: vaultPda,
-          strategyConfig: strategyConfigPda,
+          position: positionPda,
+          systemProgram: SystemProgram.programId,
         })
         .signers([authority])
         .rpc();
 
-      const config = await program.account.strategyConfig.fetch(strategyConfigPda);
-      assert.strictEqual(config.totalSignals.toNumber(), 2);
-      assert.strictEqual(config.lastSignal, 1); // StrongBuy = 1
+      const vault = await program.account.spectreVault.fetch(vaultPda);
+      assert.ok(vault.lastLossSlot.toNumber() > 0);
+
+      try {
+        await executeTrade();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("LossCooldownActive"));
+      }
     });
 
-    it("should generate SELL signal for high price with negative trend", async () => {
-      // Input: price=700 (0.70), trend=-50 (−0.05), volatility=200 (0.20)
-      const marketInput = {
-        price: 700,
-        trend: -50,
-        volatility: 200,
-        timestamp: new anchor.BN(Date.now() / 1000),
-      };
+    it("should allow trades once the cooldown has passed", async () => {
+      const { lastLossSlot } = await program.account.spectreVault.fetch(vaultPda);
+      while ((await provider.connection.getSlot()) < lastLossSlot.toNumber() + cooldownSlots) {
+        await new Promise((resolve) => setTimeout(resolve, 500));
+      }
 
-      await program.methods
-        .generateTradeSignal(marketInput)
+      const volumeBefore = (await program.account.spectreVault.fetch(vaultPda)).totalVolume;
+      await executeTrade();
+
+      const vault = await program.account.spectreVault.fetch(vaultPda);
+      assert.ok(vault.totalVolume.gt(volumeBefore));
+    });
+  });
+
+  describe("Phase 3 - Batch Position PnL", () => {
+    const POSITION_SEED = Buffer.from("position");
+    // [shares, invested, current price, expected PnL]
+    const books = [
+      [100_000_000, 50_000_000, 700_000, 20_000_000],
+      [200_000_000, 80_000_000, 300_000, -20_000_000],
+      [50_000_000, 20_000_000, 500_000, 5_000_000],
+    ];
+    const positionPdas: PublicKey[] = [];
+
+    before(async () => {
+      for (const [shares, invested] of books) {
+        const marketId = Keypair.generate().publicKey;
+        const [positionPda] = PublicKey.findProgramAddressSync(
+          [POSITION_SEED, vaultPda.toBuffer(), marketId.toBuffer()],
+          program.programId
+        );
+        positionPdas.push(positionPda);
+
+        await program.methods
+          .openPosition(
+            marketId,
+            { yes: {} },
+            new anchor.BN(shares),
+            new anchor.BN((invested * 1_000_000) / shares), // entry price the invested amount paid
+            new anchor.BN(invested),
+            null
+          )
+          .accounts({
+            authority: authority.publicKey,
+            vault: vaultPda,
+            position: positionPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([authority])
+          .rpc();
+      }
+    });
+
+    const batchPnl = (prices: number[], positions: PublicKey[]) =>
+      program.methods
+        .batchGetPositionPnl(prices.map((p) => new anchor.BN(p)))
         .accounts({
           authority: authority.publicKey,
           vault: vaultPda,
-          strategyConfig: strategyConfigPda,
         })
+        .remainingAccounts(
+          positions.map((pubkey) => ({ pubkey, isSigner: false, isWritable: false }))
+        )
         .signers([authority])
-        .rpc();
+        .view();
 
-      const config = await program.account.strategyConfig.fetch(strategyConfigPda);
-      assert.strictEqual(config.totalSignals.toNumber(), 3);
-      assert.strictEqual(config.lastSignal, 4); // Sell = 4
+    it("should return per-position and total PnL", async () => {
+      const result = await batchPnl(books.map((b) => b[2]), positionPdas);
+
+      assert.deepStrictEqual(
+        result.positionPnls.map((pnl: anchor.BN) => pnl.toNumber()),
+        books.map((b) => b[3])
+      );
+      assert.strictEqual(result.totalPnl.toNumber(), 5_000_000);
     });
 
-    it("should generate STRONG SELL signal for very high price with strong negative trend", async () => {
-      // Input: price=750 (0.75), trend=-150 (−0.15), volatility=100 (0.10)
-      const marketInput = {
-        price: 750,
-        trend: -150,
-        volatility: 100,
-        timestamp: new anchor.BN(Date.now() / 1000),
-      };
+    it("should reject a price list that doesn't match the positions", async () => {
+      try {
+        await batchPnl([700_000, 300_000], positionPdas);
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("PriceCountMismatch"));
+      }
+    });
+  });
+
+  describe("Phase 3 - Exposure Cap", () => {
+    const POSITION_SEED = Buffer.from("position");
 
+    const setMaxTotalExposure = async (bps: number) => {
       await program.methods
-        .generateTradeSignal(marketInput)
+        .setMaxTotalExposure(bps)
         .accounts({
           authority: authority.publicKey,
           vault: vaultPda,
-          strategyConfig: strategyConfigPda,
         })
         .signers([authority])
         .rpc();
+    };
 
-      const config = await program.account.strategyConfig.fetch(strategyConfigPda);
-      assert.strictEqual(config.totalSignals.toNumber(), 4);
-      assert.strictEqual(config.lastSignal, 5); // StrongSell = 5
-    });
-
-    it("should generate HOLD signal for high volatility", async () => {
-      // Input: price=300, trend=150, but volatility=450 (0.45) > cap (0.40)
-      const marketInput = {
-        price: 300,
-        trend: 150,
-        volatility: 450,
-        timestamp: new anchor.BN(Date.now() / 1000),
-      };
+    const openPosition = async (investedAmount: number) => {
+      const marketId = Keypair.generate().publicKey;
+      const [positionPda] = PublicKey.findProgramAddressSync(
+        [POSITION_SEED, vaultPda.toBuffer(), marketId.toBuffer()],
+        program.programId
+      );
 
       await program.methods
-        .generateTradeSignal(marketInput)
+        .openPosition(
+          marketId,
+          { yes: {} },
+          new anchor.BN(investedAmount * 2),
+          new anchor.BN(500_000),
+          new anchor.BN(investedAmount),
+          null
+        )
         .accounts({
           authority: authority.publicKey,
           vault: vaultPda,
-          strategyConfig: strategyConfigPda,
+          position: positionPda,
+          systemProgram: SystemProgram.programId,
         })
         .signers([authority])
         .rpc();
+    };
 
-      const config = await program.account.strategyConfig.fetch(strategyConfigPda);
-      assert.strictEqual(config.totalSignals.toNumber(), 5);
-      assert.strictEqual(config.lastSignal, 3); // Hold = 3
+    after(async () => {
+      await setMaxTotalExposure(0);
     });
 
-    it("should generate HOLD signal for neutral price", async () => {
-      // Input: price=500 (0.50), trend=50 (0.05), volatility=200 (0.20)
-      const marketInput = {
-        price: 500,
-        trend: 50,
-        volatility: 200,
-        timestamp: new anchor.BN(Date.now() / 1000),
-      };
+    it("should reject a position that breaches the aggregate exposure cap", async () => {
+      // Leave at least 10M lamports of room above the current open exposure
+      let vault = await program.account.spectreVault.fetch(vaultPda);
+      const deposited = vault.totalDeposited.toNumber();
+      const bps = Math.ceil(((vault.totalOpenExposure.toNumber() + 10_000_000) * 10_000) / deposited);
+      assert.ok(bps <= 10_000);
+      await setMaxTotalExposure(bps);
+
+      vault = await program.account.spectreVault.fetch(vaultPda);
+      const cap = Math.floor((deposited * bps) / 10_000);
+      const room = cap - vault.totalOpenExposure.toNumber();
+
+      // One lamport over the remaining room is rejected
+      try {
+        await openPosition(room + 1);
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("ExposureCapExceeded"));
+      }
+
+      // Exactly the remaining room fits
+      await openPosition(room);
+      vault = await program.account.spectreVault.fetch(vaultPda);
+      assert.strictEqual(vault.totalOpenExposure.toNumber(), cap);
+    });
+  });
 
+  describe("Phase 3 - Contrarian Strategy", () => {
+    const STRATEGY_CONFIG_SEED = Buffer.from("strategy_config");
+    let strategyConfigPda: PublicKey;
+
+    const setContrarian = async (contrarian: boolean) => {
       await program.methods
-        .generateTradeSignal(marketInput)
+        .setContrarian(contrarian)
         .accounts({
           authority: authority.publicKey,
           vault: vaultPda,
@@ -1429,60 +6610,100 @@ describe("SPECTRE Protocol - Phase 1", () => {
         })
         .signers([authority])
         .rpc();
-
-      const config = await program.account.strategyConfig.fetch(strategyConfigPda);
-      assert.strictEqual(config.totalSignals.toNumber(), 6);
-      assert.strictEqual(config.lastSignal, 3); // Hold = 3
-    });
-  });
-
-  describe("Phase 2 - Update Model", () => {
-    it("should update model hash successfully", async () => {
-      const newModelHash = new Array(32).fill(0).map((_, i) => i % 256);
-
-      const tx = await program.methods
-        .updateModel(newModelHash)
+    };
+
+    // price 0.30 rising -> BUY, price 0.70 falling -> SELL
+    const executeTrade = (price: number, trend: number) =>
+      program.methods
+        .executeTrade(
+          {
+            price,
+            trend,
+            volatility: 200,
+            timestamp: new anchor.BN(Date.now() / 1000),
+          },
+          null
+        )
         .accounts({
           authority: authority.publicKey,
           vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+          systemProgram: SystemProgram.programId,
         })
         .signers([authority])
         .rpc();
 
-      console.log("Update model transaction:", tx);
-
-      const vault = await program.account.spectreVault.fetch(vaultPda);
-      assert.deepStrictEqual(Array.from(vault.modelHash), newModelHash);
-    });
-
-    it("should reject model update from non-authority", async () => {
-      const notAuthority = Keypair.generate();
-      const airdrop = await provider.connection.requestAirdrop(
-        notAuthority.publicKey,
-        1 * LAMPORTS_PER_SOL
-      );
-      await provider.connection.confirmTransaction(airdrop);
-
-      try {
+    const lastSignal = async () =>
+      (
         await program.methods
-          .updateModel(new Array(32).fill(99))
+          .getStrategyConfig()
           .accounts({
-            authority: notAuthority.publicKey,
             vault: vaultPda,
+            strategyConfig: strategyConfigPda,
           })
-          .signers([notAuthority])
-          .rpc();
-        assert.fail("Should have thrown an error");
-      } catch (err) {
-        assert.ok(err.toString().includes("Error") || err.toString().includes("constraint"));
-      }
+          .view()
+      ).lastSignal;
+
+    before(async () => {
+      [strategyConfigPda] = PublicKey.findProgramAddressSync(
+        [STRATEGY_CONFIG_SEED, vaultPda.toBuffer()],
+        program.programId
+      );
+      await setContrarian(true);
+    });
+
+    after(async () => {
+      await setContrarian(false);
+    });
+
+    it("should sell on a buy signal", async () => {
+      await executeTrade(300, 50);
+      assert.deepStrictEqual(await lastSignal(), { sell: {} });
+    });
+
+    it("should buy on a sell signal", async () => {
+      await executeTrade(700, -50);
+      assert.deepStrictEqual(await lastSignal(), { buy: {} });
     });
   });
 
-  describe("Phase 2 - Set Strategy Params", () => {
+  describe("Phase 3 - Strategy Warm-Up", () => {
     const STRATEGY_CONFIG_SEED = Buffer.from("strategy_config");
     let strategyConfigPda: PublicKey;
 
+    const setMinSignals = async (minSignals: number) => {
+      await program.methods
+        .setMinSignalsBeforeTrading(new anchor.BN(minSignals))
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+        })
+        .signers([authority])
+        .rpc();
+    };
+
+    // price 0.30 rising -> BUY
+    const executeBuy = () =>
+      program.methods
+        .executeTrade(
+          {
+            price: 300,
+            trend: 50,
+            volatility: 200,
+            timestamp: new anchor.BN(Date.now() / 1000),
+          },
+          null
+        )
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
     before(async () => {
       [strategyConfigPda] = PublicKey.findProgramAddressSync(
         [STRATEGY_CONFIG_SEED, vaultPda.toBuffer()],
@@ -1490,506 +6711,614 @@ describe("SPECTRE Protocol - Phase 1", () => {
       );
     });
 
-    it("should update strategy params successfully", async () => {
-      // Set aggressive params
-      const aggressiveParams = {
-        priceThresholdLow: 400,
-        priceThresholdHigh: 600,
-        trendThreshold: 50,
-        volatilityCap: 500,
-        reserved: new Array(16).fill(0),
-      };
+    after(async () => {
+      await setMinSignals(0);
+    });
 
-      const tx = await program.methods
-        .setStrategyParams(aggressiveParams)
+    it("should suppress trades until enough signals have been generated", async () => {
+      const config = await program.account.strategyConfig.fetch(strategyConfigPda);
+      // Two more signals are needed: the next one is only counted
+      await setMinSignals(config.totalSignals.toNumber() + 2);
+
+      const vaultBefore = await program.account.spectreVault.fetch(vaultPda);
+      await executeBuy();
+
+      const vaultWarming = await program.account.spectreVault.fetch(vaultPda);
+      assert.ok(vaultWarming.lastTradeSlot.eq(vaultBefore.lastTradeSlot));
+      assert.ok(vaultWarming.availableBalance.eq(vaultBefore.availableBalance));
+      const configWarming = await program.account.strategyConfig.fetch(strategyConfigPda);
+      assert.ok(configWarming.totalSignals.eq(config.totalSignals.addn(1)));
+
+      // The signal that completes the warm-up is traded on
+      await executeBuy();
+
+      const vaultAfter = await program.account.spectreVault.fetch(vaultPda);
+      assert.ok(vaultAfter.lastTradeSlot.gt(vaultBefore.lastTradeSlot));
+      assert.ok(vaultAfter.availableBalance.lt(vaultBefore.availableBalance));
+    });
+  });
+
+  describe("Phase 3 - Risk-Adjusted Gate", () => {
+    const STRATEGY_CONFIG_SEED = Buffer.from("strategy_config");
+    let strategyConfigPda: PublicKey;
+
+    const setMinScore = async (minScore: number) => {
+      await program.methods
+        .setMinRiskAdjustedScore(minScore)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          strategyConfig: strategyConfigPda,
+        })
+        .signers([authority])
+        .rpc();
+    };
+
+    // price 0.20 rising sharply with low volatility -> STRONG_BUY
+    const executeStrongBuy = (volatility: number) =>
+      program.methods
+        .executeTrade(
+          {
+            price: 200,
+            trend: 300,
+            volatility,
+            timestamp: new anchor.BN(Date.now() / 1000),
+          },
+          null
+        )
         .accounts({
           authority: authority.publicKey,
           vault: vaultPda,
           strategyConfig: strategyConfigPda,
+          systemProgram: SystemProgram.programId,
         })
         .signers([authority])
         .rpc();
 
-      console.log("Set strategy params transaction:", tx);
+    before(async () => {
+      [strategyConfigPda] = PublicKey.findProgramAddressSync(
+        [STRATEGY_CONFIG_SEED, vaultPda.toBuffer()],
+        program.programId
+      );
+      // 1000 / (1 + 0.02) = 980 passes, 1000 / (1 + 0.19) = 840 does not
+      await setMinScore(900);
+    });
 
-      const config = await program.account.strategyConfig.fetch(strategyConfigPda);
-      assert.strictEqual(config.priceThresholdLow, 400);
-      assert.strictEqual(config.priceThresholdHigh, 600);
-      assert.strictEqual(config.trendThreshold, 50);
-      assert.strictEqual(config.volatilityCap, 500);
+    after(async () => {
+      await setMinScore(0);
     });
 
-    it("should reject invalid params (low >= high)", async () => {
-      const invalidParams = {
-        priceThresholdLow: 700, // Higher than high threshold
-        priceThresholdHigh: 300,
-        trendThreshold: 100,
-        volatilityCap: 400,
-        reserved: new Array(16).fill(0),
-      };
+    it("should gate a strong signal in a volatile market", async () => {
+      const vaultBefore = await program.account.spectreVault.fetch(vaultPda);
+      await executeStrongBuy(190);
 
-      try {
-        await program.methods
-          .setStrategyParams(invalidParams)
-          .accounts({
-            authority: authority.publicKey,
-            vault: vaultPda,
-            strategyConfig: strategyConfigPda,
-          })
-          .signers([authority])
-          .rpc();
-        assert.fail("Should have thrown an error");
-      } catch (err) {
-        assert.ok(err.toString().includes("InvalidStrategyParams"));
-      }
+      const vaultAfter = await program.account.spectreVault.fetch(vaultPda);
+      assert.ok(vaultAfter.lastTradeSlot.eq(vaultBefore.lastTradeSlot));
+      assert.ok(vaultAfter.availableBalance.eq(vaultBefore.availableBalance));
     });
 
-    it("should reject zero volatility cap", async () => {
-      const invalidParams = {
-        priceThresholdLow: 350,
-        priceThresholdHigh: 650,
-        trendThreshold: 100,
-        volatilityCap: 0, // Invalid
-        reserved: new Array(16).fill(0),
-      };
+    it("should trade a strong signal in a calm market", async () => {
+      const vaultBefore = await program.account.spectreVault.fetch(vaultPda);
+      await executeStrongBuy(20);
+
+      const vaultAfter = await program.account.spectreVault.fetch(vaultPda);
+      assert.ok(vaultAfter.lastTradeSlot.gt(vaultBefore.lastTradeSlot));
+      assert.ok(vaultAfter.availableBalance.lt(vaultBefore.availableBalance));
+    });
 
+    it("should reject a minimum above the maximum score", async () => {
       try {
-        await program.methods
-          .setStrategyParams(invalidParams)
-          .accounts({
-            authority: authority.publicKey,
-            vault: vaultPda,
-            strategyConfig: strategyConfigPda,
-          })
-          .signers([authority])
-          .rpc();
+        await setMinScore(1001);
         assert.fail("Should have thrown an error");
-      } catch (err) {
+      } catch (err: any) {
         assert.ok(err.toString().includes("InvalidStrategyParams"));
       }
     });
   });
 
-  describe("Phase 2 - Undelegate from TEE", () => {
-    it("should undelegate vault from TEE successfully", async () => {
-      // Verify vault is delegated before
-      let vault = await program.account.spectreVault.fetch(vaultPda);
-      assert.strictEqual(vault.isDelegated, true);
+  describe("Phase 3 - Mark Position", () => {
+    const POSITION_SEED = Buffer.from("position");
+    const marketId = Keypair.generate().publicKey;
+    let positionPda: PublicKey;
 
-      const tx = await program.methods
-        .undelegateFromTee()
+    before(async () => {
+      [positionPda] = PublicKey.findProgramAddressSync(
+        [POSITION_SEED, vaultPda.toBuffer(), marketId.toBuffer()],
+        program.programId
+      );
+
+      // 100 shares at 0.5, 0.05 SOL invested
+      await program.methods
+        .openPosition(
+          marketId,
+          { yes: {} },
+          new anchor.BN(100_000_000),
+          new anchor.BN(500_000),
+          new anchor.BN(50_000_000),
+          null
+        )
         .accounts({
           authority: authority.publicKey,
           vault: vaultPda,
-          delegationProgram: null,
+          position: positionPda,
           systemProgram: SystemProgram.programId,
         })
         .signers([authority])
         .rpc();
+    });
 
-      console.log("Undelegate from TEE transaction:", tx);
+    it("should record the mark and emit its unrealized PnL", async () => {
+      let listener: number;
+      const eventPromise = new Promise<any>((resolve) => {
+        listener = program.addEventListener("positionMarkedEvent", (event) => {
+          if (event.position.equals(positionPda)) {
+            resolve(event);
+          }
+        });
+      });
 
-      // Verify vault is now undelegated
-      vault = await program.account.spectreVault.fetch(vaultPda);
-      assert.strictEqual(vault.isDelegated, false);
+      await program.methods
+        .markPosition(new anchor.BN(700_000))
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+          position: positionPda,
+        })
+        .signers([authority])
+        .rpc();
+
+      const event = await eventPromise;
+      await program.removeEventListener(listener);
+
+      // 100 shares * 0.7 - 0.05 SOL
+      assert.strictEqual(event.unrealizedPnl.toNumber(), 20_000_000);
+      assert.strictEqual(event.markPrice.toNumber(), 700_000);
+
+      const position = await program.account.position.fetch(positionPda);
+      assert.strictEqual(position.lastMarkPrice.toNumber(), 700_000);
+      assert.ok(position.lastMarkSlot.eq(event.markedSlot));
+      // Marking never changes the position itself
+      assert.strictEqual(position.shares.toNumber(), 100_000_000);
+      assert.strictEqual(position.investedAmount.toNumber(), 50_000_000);
+      assert.deepStrictEqual(position.status, { open: {} });
     });
 
-    it("should reject undelegation when not delegated", async () => {
+    it("should reject an invalid mark price", async () => {
       try {
         await program.methods
-          .undelegateFromTee()
+          .markPosition(new anchor.BN(0))
           .accounts({
             authority: authority.publicKey,
             vault: vaultPda,
-            delegationProgram: null,
-            systemProgram: SystemProgram.programId,
+            position: positionPda,
           })
           .signers([authority])
           .rpc();
         assert.fail("Should have thrown an error");
       } catch (err) {
-        assert.ok(err.toString().includes("VaultNotDelegated"));
+        assert.ok(err.toString().includes("InvalidPrice"));
       }
     });
   });
 
-  describe("Phase 2 - Strategy Determinism", () => {
-    const STRATEGY_CONFIG_SEED = Buffer.from("strategy_config");
-    let strategyConfigPda: PublicKey;
+  describe("Phase 3 - Position Cost Consistency", () => {
+    const POSITION_SEED = Buffer.from("position");
 
-    before(async () => {
-      [strategyConfigPda] = PublicKey.findProgramAddressSync(
-        [STRATEGY_CONFIG_SEED, vaultPda.toBuffer()],
+    const openPosition = (shares: number, entryPrice: number, investedAmount: number) => {
+      const marketId = Keypair.generate().publicKey;
+      const [positionPda] = PublicKey.findProgramAddressSync(
+        [POSITION_SEED, vaultPda.toBuffer(), marketId.toBuffer()],
         program.programId
       );
 
-      // Reset to default params for determinism test
-      const defaultParams = {
-        priceThresholdLow: 350,
-        priceThresholdHigh: 650,
-        trendThreshold: 100,
-        volatilityCap: 400,
-        reserved: new Array(16).fill(0),
-      };
-
-      await program.methods
-        .setStrategyParams(defaultParams)
+      return program.methods
+        .openPosition(
+          marketId,
+          { yes: {} },
+          new anchor.BN(shares),
+          new anchor.BN(entryPrice),
+          new anchor.BN(investedAmount),
+          null
+        )
         .accounts({
           authority: authority.publicKey,
           vault: vaultPda,
-          strategyConfig: strategyConfigPda,
+          position: positionPda,
+          systemProgram: SystemProgram.programId,
         })
         .signers([authority])
         .rpc();
+    };
+
+    it("should accept an invested amount within the fee tolerance", async () => {
+      // 10 shares at 0.5 = 5M lamports, plus 1% in fees
+      await openPosition(10_000_000, 500_000, 5_050_000);
     });
 
-    it("should produce same signal for same inputs (determinism)", async () => {
-      // Run same input multiple times
-      const marketInput = {
-        price: 300,
-        trend: 150,
-        volatility: 100,
-        timestamp: new anchor.BN(Date.now() / 1000),
-      };
+    it("should reject an invested amount far from shares * entry price", async () => {
+      // 20 shares at 0.5 are worth 10M lamports, not 5M
+      try {
+        await openPosition(20_000_000, 500_000, 5_000_000);
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("InconsistentPositionData"));
+      }
+    });
+  });
 
-      // First run
-      await program.methods
-        .generateTradeSignal(marketInput)
+  describe("Phase 3 - Scheduled Entry", () => {
+    const POSITION_SEED = Buffer.from("position");
+    const ENTRY_SCHEDULE_SEED = Buffer.from("entry_schedule");
+    const marketId = Keypair.generate().publicKey;
+    const totalSize = 20_000_000;
+    const intervalSeconds = 2;
+    let positionPda: PublicKey;
+    let schedulePda: PublicKey;
+    let activePositionsBefore: number;
+
+    const executeTranche = () =>
+      program.methods
+        .executeEntryTranche(null)
         .accounts({
           authority: authority.publicKey,
           vault: vaultPda,
-          strategyConfig: strategyConfigPda,
+          entrySchedule: schedulePda,
+          position: positionPda,
         })
         .signers([authority])
         .rpc();
 
-      let config = await program.account.strategyConfig.fetch(strategyConfigPda);
-      const firstSignal = config.lastSignal;
+    before(async () => {
+      [positionPda] = PublicKey.findProgramAddressSync(
+        [POSITION_SEED, vaultPda.toBuffer(), marketId.toBuffer()],
+        program.programId
+      );
+      [schedulePda] = PublicKey.findProgramAddressSync(
+        [ENTRY_SCHEDULE_SEED, vaultPda.toBuffer(), marketId.toBuffer()],
+        program.programId
+      );
+
+      const vault = await program.account.spectreVault.fetch(vaultPda);
+      activePositionsBefore = vault.activePositions;
 
-      // Second run with same input
       await program.methods
-        .generateTradeSignal(marketInput)
+        .createEntrySchedule(marketId, { yes: {} }, new anchor.BN(totalSize), 2, new anchor.BN(intervalSeconds))
         .accounts({
           authority: authority.publicKey,
           vault: vaultPda,
-          strategyConfig: strategyConfigPda,
+          position: positionPda,
+          entrySchedule: schedulePda,
+          systemProgram: SystemProgram.programId,
         })
         .signers([authority])
         .rpc();
+    });
 
-      config = await program.account.strategyConfig.fetch(strategyConfigPda);
-      const secondSignal = config.lastSignal;
+    it("should hold tranches outside the trading window", async () => {
+      const setTradingWindow = (start: number, end: number) =>
+        program.methods
+          .setTradingWindow(start, end)
+          .accounts({
+            authority: authority.publicKey,
+            vault: vaultPda,
+          })
+          .signers([authority])
+          .rpc();
 
-      // Third run with same input
-      await program.methods
-        .generateTradeSignal(marketInput)
-        .accounts({
-          authority: authority.publicKey,
-          vault: vaultPda,
-          strategyConfig: strategyConfigPda,
-        })
-        .signers([authority])
-        .rpc();
+      // A window that opens an hour from now
+      const secondsPerDay = 86_400;
+      const timeOfDay = Math.floor(Date.now() / 1000) % secondsPerDay;
+      await setTradingWindow((timeOfDay + 3_600) % secondsPerDay, (timeOfDay + 7_200) % secondsPerDay);
 
-      config = await program.account.strategyConfig.fetch(strategyConfigPda);
-      const thirdSignal = config.lastSignal;
+      try {
+        await executeTranche();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("OutsideTradingWindow"));
+      }
+      await setTradingWindow(0, secondsPerDay);
 
-      // All signals should be identical (StrongBuy = 1)
-      assert.strictEqual(firstSignal, secondSignal);
-      assert.strictEqual(secondSignal, thirdSignal);
-      assert.strictEqual(firstSignal, 1); // StrongBuy
+      const schedule = await program.account.entrySchedule.fetch(schedulePda);
+      assert.strictEqual(schedule.tranchesExecuted, 0);
     });
-  });
 
-  describe("Phase 2 - Final State Verification", () => {
-    const STRATEGY_CONFIG_SEED = Buffer.from("strategy_config");
-    let strategyConfigPda: PublicKey;
+    it("should execute the first tranche immediately", async () => {
+      // The empty position is not counted until it fills
+      const vaultBefore = await program.account.spectreVault.fetch(vaultPda);
+      assert.strictEqual(vaultBefore.activePositions, activePositionsBefore);
 
-    before(async () => {
-      [strategyConfigPda] = PublicKey.findProgramAddressSync(
-        [STRATEGY_CONFIG_SEED, vaultPda.toBuffer()],
-        program.programId
-      );
-    });
+      await executeTranche();
 
-    it("should have consistent Phase 2 state", async () => {
-      const vault = await program.account.spectreVault.fetch(vaultPda);
-      const config = await program.account.strategyConfig.fetch(strategyConfigPda);
+      const schedule = await program.account.entrySchedule.fetch(schedulePda);
+      assert.strictEqual(schedule.tranchesExecuted, 1);
 
-      console.log("\nPhase 2 Final State:");
-      console.log("  Vault delegated:", vault.isDelegated);
-      console.log("  Model hash (first 8 bytes):", vault.modelHash.slice(0, 8));
-      console.log("  Strategy active:", config.isActive);
-      console.log("  Total signals generated:", config.totalSignals.toNumber());
-      console.log("  Last signal:", config.lastSignal);
-      console.log("  Price thresholds:", config.priceThresholdLow, "-", config.priceThresholdHigh);
-      console.log("  Volatility cap:", config.volatilityCap);
+      const position = await program.account.position.fetch(positionPda);
+      assert.strictEqual(position.investedAmount.toNumber(), totalSize / 2);
+      assert.ok(position.shares.gtn(0));
 
-      // Verify invariants
-      assert.strictEqual(config.vault.toString(), vaultPda.toString());
-      assert.strictEqual(config.authority.toString(), authority.publicKey.toString());
-      assert.ok(config.totalSignals.toNumber() > 0);
-      assert.ok(config.priceThresholdLow < config.priceThresholdHigh);
+      const vaultAfter = await program.account.spectreVault.fetch(vaultPda);
+      assert.strictEqual(vaultAfter.activePositions, activePositionsBefore + 1);
     });
-  });
 
-  // ============================================
-  // PHASE 3: THE HAND - Trading Tests
-  // ============================================
+    it("should hold the next tranche until the interval has passed", async () => {
+      try {
+        await executeTranche();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("EntryTrancheNotDue"));
+      }
 
-  describe("Phase 3 - Execute Trade", () => {
-    const STRATEGY_CONFIG_SEED = Buffer.from("strategy_config");
-    let strategyConfigPda: PublicKey;
+      await new Promise((resolve) => setTimeout(resolve, (intervalSeconds + 2) * 1000));
+      await executeTranche();
 
-    before(async () => {
-      [strategyConfigPda] = PublicKey.findProgramAddressSync(
-        [STRATEGY_CONFIG_SEED, vaultPda.toBuffer()],
-        program.programId
-      );
+      const schedule = await program.account.entrySchedule.fetch(schedulePda);
+      assert.strictEqual(schedule.tranchesExecuted, 2);
+
+      const position = await program.account.position.fetch(positionPda);
+      assert.strictEqual(position.investedAmount.toNumber(), totalSize);
     });
 
-    it("should execute trade with BUY signal", async () => {
-      // Input: price=300 (0.30), trend=50 (0.05), volatility=200 (0.20)
-      // Should generate BUY signal
-      const marketInput = {
-        price: 300,
-        trend: 50,
-        volatility: 200,
-        timestamp: new anchor.BN(Date.now() / 1000),
-      };
+    it("should reject tranches once the schedule is complete", async () => {
+      try {
+        await executeTranche();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("EntryScheduleComplete"));
+      }
+    });
+  });
 
-      const tx = await program.methods
-        .executeTrade(marketInput)
+  describe("Phase 3 - Profit Compounding", () => {
+    const POSITION_SEED = Buffer.from("position");
+    let caller: Keypair;
+
+    const setCompoundInterval = (intervalSlots: number) =>
+      program.methods
+        .setAutoCompoundInterval(new anchor.BN(intervalSlots))
         .accounts({
           authority: authority.publicKey,
           vault: vaultPda,
-          strategyConfig: strategyConfigPda,
-          systemProgram: SystemProgram.programId,
         })
         .signers([authority])
         .rpc();
 
-      console.log("Execute trade (BUY) transaction:", tx);
-
-      // Verify vault state was updated
-      const vault = await program.account.spectreVault.fetch(vaultPda);
-      assert.ok(vault.totalVolume.toNumber() > 0);
-      assert.ok(vault.lastTradeSlot.toNumber() > 0);
-    });
-
-    it("should execute trade with SELL signal", async () => {
-      // Input: price=700 (0.70), trend=-50 (−0.05), volatility=200 (0.20)
-      // Should generate SELL signal
-      const marketInput = {
-        price: 700,
-        trend: -50,
-        volatility: 200,
-        timestamp: new anchor.BN(Date.now() / 1000),
-      };
-
-      const tx = await program.methods
-        .executeTrade(marketInput)
+    const setReserveBps = (bps: number) =>
+      program.methods
+        .setProfitReserveBps(bps)
         .accounts({
           authority: authority.publicKey,
           vault: vaultPda,
-          strategyConfig: strategyConfigPda,
-          systemProgram: SystemProgram.programId,
         })
         .signers([authority])
         .rpc();
 
-      console.log("Execute trade (SELL) transaction:", tx);
-    });
+    const compound = () =>
+      program.methods
+        .compoundProfits()
+        .accounts({
+          caller: caller.publicKey,
+          vault: vaultPda,
+        })
+        .signers([caller])
+        .rpc();
 
-    it("should NOT execute trade with HOLD signal", async () => {
-      // Input: price=500 (0.50), trend=0, volatility=200 (0.20)
-      // Should generate HOLD signal - no trade executed
-      const marketInput = {
-        price: 500,
-        trend: 0,
-        volatility: 200,
-        timestamp: new anchor.BN(Date.now() / 1000),
-      };
+    before(async () => {
+      caller = Keypair.generate();
+      const airdrop = await provider.connection.requestAirdrop(
+        caller.publicKey,
+        LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdrop);
 
-      const vaultBefore = await program.account.spectreVault.fetch(vaultPda);
-      const volumeBefore = vaultBefore.totalVolume.toNumber();
+      await setReserveBps(5_000);
 
+      // 10 shares at 0.5 closed at 0.6: 1M lamports of profit, half reserved
+      const marketId = Keypair.generate().publicKey;
+      const [positionPda] = PublicKey.findProgramAddressSync(
+        [POSITION_SEED, vaultPda.toBuffer(), marketId.toBuffer()],
+        program.programId
+      );
       await program.methods
-        .executeTrade(marketInput)
+        .openPosition(
+          marketId,
+          { yes: {} },
+          new anchor.BN(10_000_000),
+          new anchor.BN(500_000),
+          new anchor.BN(5_000_000),
+          null
+        )
         .accounts({
           authority: authority.publicKey,
           vault: vaultPda,
-          strategyConfig: strategyConfigPda,
+          position: positionPda,
           systemProgram: SystemProgram.programId,
         })
         .signers([authority])
         .rpc();
 
-      // Volume should not change for HOLD signal
-      // Note: In mock mode, the trade doesn't actually transfer SOL,
-      // so we just verify it ran without error
-      console.log("Execute trade (HOLD) completed - no trade executed");
-    });
-
-    it("should execute trade with STRONG BUY signal (larger position)", async () => {
-      // Input: price=250 (0.25), trend=150 (0.15), volatility=100 (0.10)
-      // Should generate STRONG BUY signal with 2x position size
-      const marketInput = {
-        price: 250,
-        trend: 150,
-        volatility: 100,
-        timestamp: new anchor.BN(Date.now() / 1000),
-      };
-
-      const tx = await program.methods
-        .executeTrade(marketInput)
+      const vaultBefore = await program.account.spectreVault.fetch(vaultPda);
+      await program.methods
+        .closePosition(new anchor.BN(600_000))
         .accounts({
           authority: authority.publicKey,
           vault: vaultPda,
-          strategyConfig: strategyConfigPda,
+          position: positionPda,
           systemProgram: SystemProgram.programId,
         })
         .signers([authority])
         .rpc();
 
-      console.log("Execute trade (STRONG BUY) transaction:", tx);
+      const vaultAfter = await program.account.spectreVault.fetch(vaultPda);
+      assert.ok(vaultAfter.profitReserve.eq(vaultBefore.profitReserve.addn(500_000)));
+      assert.ok(vaultAfter.availableBalance.eq(vaultBefore.availableBalance.addn(5_500_000)));
     });
-  });
 
-  describe("Phase 3 - Open Position", () => {
-    const POSITION_SEED = Buffer.from("position");
-    let testMarketId: Keypair;
-    let positionPda: PublicKey;
+    after(async () => {
+      await setCompoundInterval(0);
+      await setReserveBps(0);
+    });
 
-    before(async () => {
-      testMarketId = Keypair.generate();
-      [positionPda] = PublicKey.findProgramAddressSync(
-        [POSITION_SEED, vaultPda.toBuffer(), testMarketId.publicKey.toBuffer()],
-        program.programId
+    it("should reject compounding within the interval", async () => {
+      await setCompoundInterval(1_000_000);
+      try {
+        await compound();
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("CompoundNotDue"));
+      }
+    });
+
+    it("should compound the reserve once the interval has elapsed", async () => {
+      await setCompoundInterval(1);
+      await new Promise((resolve) => setTimeout(resolve, 1000));
+
+      const vaultBefore = await program.account.spectreVault.fetch(vaultPda);
+      assert.ok(vaultBefore.profitReserve.gtn(0));
+      await compound();
+
+      const vaultAfter = await program.account.spectreVault.fetch(vaultPda);
+      assert.ok(vaultAfter.profitReserve.eqn(0));
+      assert.ok(
+        vaultAfter.availableBalance.eq(vaultBefore.availableBalance.add(vaultBefore.profitReserve))
       );
+      assert.ok(vaultAfter.lastCompoundSlot.gt(vaultBefore.lastCompoundSlot));
     });
+  });
 
-    it("should open a YES position successfully", async () => {
-      const shares = new anchor.BN(100_000_000); // 100 shares
-      const entryPrice = new anchor.BN(500_000); // 0.5 per share
-      const investedAmount = new anchor.BN(50_000_000); // 0.05 SOL
+  describe("Phase 3 - Strategy Availability", () => {
+    const STRATEGY_CONFIG_SEED = Buffer.from("strategy_config");
+    let strategyConfigPda: PublicKey;
 
-      const tx = await program.methods
-        .openPosition(
-          testMarketId.publicKey,
-          { yes: {} }, // TradeSide::Yes
-          shares,
-          entryPrice,
-          investedAmount
-        )
+    // price 0.30 rising -> BUY
+    const marketInput = () => ({
+      price: 300,
+      trend: 50,
+      volatility: 200,
+      timestamp: new anchor.BN(Date.now() / 1000),
+    });
+
+    const setStrategyActive = (isActive: boolean) =>
+      program.methods
+        .setStrategyActive(isActive)
         .accounts({
           authority: authority.publicKey,
           vault: vaultPda,
-          position: positionPda,
-          systemProgram: SystemProgram.programId,
+          strategyConfig: strategyConfigPda,
         })
         .signers([authority])
         .rpc();
 
-      console.log("Open position transaction:", tx);
-
-      // Verify position state
-      const position = await program.account.position.fetch(positionPda);
-      assert.strictEqual(position.vault.toString(), vaultPda.toString());
-      assert.strictEqual(position.marketId.toString(), testMarketId.publicKey.toString());
-      assert.deepStrictEqual(position.side, { yes: {} });
-      assert.strictEqual(position.shares.toNumber(), 100_000_000);
-      assert.strictEqual(position.entryPrice.toNumber(), 500_000);
-      assert.strictEqual(position.investedAmount.toNumber(), 50_000_000);
-      assert.deepStrictEqual(position.status, { open: {} });
+    before(async () => {
+      [strategyConfigPda] = PublicKey.findProgramAddressSync(
+        [STRATEGY_CONFIG_SEED, vaultPda.toBuffer()],
+        program.programId
+      );
+    });
 
-      // Verify vault state updated
-      const vault = await program.account.spectreVault.fetch(vaultPda);
-      assert.strictEqual(vault.activePositions, 1);
+    after(async () => {
+      await setStrategyActive(true);
     });
 
-    it("should reject position with zero shares", async () => {
-      const badMarketId = Keypair.generate();
-      const [badPositionPda] = PublicKey.findProgramAddressSync(
-        [POSITION_SEED, vaultPda.toBuffer(), badMarketId.publicKey.toBuffer()],
+    it("should fail with StrategyNotInitialized for a vault without a strategy", async () => {
+      const newAuthority = Keypair.generate();
+      const airdrop = await provider.connection.requestAirdrop(
+        newAuthority.publicKey,
+        LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdrop);
+
+      const [newVaultPda] = PublicKey.findProgramAddressSync(
+        [VAULT_SEED, newAuthority.publicKey.toBuffer()],
+        program.programId
+      );
+      const [newStrategyConfigPda] = PublicKey.findProgramAddressSync(
+        [STRATEGY_CONFIG_SEED, newVaultPda.toBuffer()],
         program.programId
       );
 
+      await program.methods
+        .initialize(null, null)
+        .accounts({
+          authority: newAuthority.publicKey,
+          vault: newVaultPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([newAuthority])
+        .rpc();
+
+      const vault = await program.account.spectreVault.fetch(newVaultPda);
+      assert.strictEqual(vault.hasStrategy, false);
+
       try {
         await program.methods
-          .openPosition(
-            badMarketId.publicKey,
-            { yes: {} },
-            new anchor.BN(0), // Zero shares - invalid
-            new anchor.BN(500_000),
-            new anchor.BN(50_000_000)
-          )
+          .executeTrade(marketInput(), null)
           .accounts({
-            authority: authority.publicKey,
-            vault: vaultPda,
-            position: badPositionPda,
+            authority: newAuthority.publicKey,
+            vault: newVaultPda,
+            strategyConfig: newStrategyConfigPda,
             systemProgram: SystemProgram.programId,
           })
-          .signers([authority])
+          .signers([newAuthority])
           .rpc();
         assert.fail("Should have thrown an error");
       } catch (err) {
-        assert.ok(err.toString().includes("InvalidTradeAmount"));
+        assert.ok(err.toString().includes("StrategyNotInitialized"));
       }
     });
 
-    it("should reject position with zero price", async () => {
-      const badMarketId = Keypair.generate();
-      const [badPositionPda] = PublicKey.findProgramAddressSync(
-        [POSITION_SEED, vaultPda.toBuffer(), badMarketId.publicKey.toBuffer()],
-        program.programId
-      );
+    it("should fail with StrategyNotActive once the strategy is switched off", async () => {
+      const vault = await program.account.spectreVault.fetch(vaultPda);
+      assert.strictEqual(vault.hasStrategy, true);
+
+      await setStrategyActive(false);
 
       try {
         await program.methods
-          .openPosition(
-            badMarketId.publicKey,
-            { no: {} },
-            new anchor.BN(100_000),
-            new anchor.BN(0), // Zero price - invalid
-            new anchor.BN(50_000_000)
-          )
+          .executeTrade(marketInput(), null)
           .accounts({
             authority: authority.publicKey,
             vault: vaultPda,
-            position: badPositionPda,
+            strategyConfig: strategyConfigPda,
             systemProgram: SystemProgram.programId,
           })
           .signers([authority])
           .rpc();
         assert.fail("Should have thrown an error");
       } catch (err) {
-        assert.ok(err.toString().includes("InvalidPrice"));
+        assert.ok(err.toString().includes("StrategyNotActive"));
       }
     });
   });
 
-  describe("Phase 3 - Get Position PnL", () => {
+  describe("Phase 3 - Performance Fee", () => {
     const POSITION_SEED = Buffer.from("position");
-    let testMarketId: Keypair;
-    let positionPda: PublicKey;
+    let feeRecipient: Keypair;
 
-    before(async () => {
-      // Create a new position for PnL testing
-      testMarketId = Keypair.generate();
-      [positionPda] = PublicKey.findProgramAddressSync(
-        [POSITION_SEED, vaultPda.toBuffer(), testMarketId.publicKey.toBuffer()],
+    const setPerformanceFee = (bps: number, recipient: PublicKey) =>
+      program.methods
+        .setPerformanceFee(bps, recipient)
+        .accounts({
+          authority: authority.publicKey,
+          vault: vaultPda,
+        })
+        .signers([authority])
+        .rpc();
+
+    // Open 10 shares at 0.5 (5M lamports) and close them at `exitPrice`
+    const openAndClose = async (exitPrice: number, withRecipient: boolean) => {
+      const marketId = Keypair.generate().publicKey;
+      const [positionPda] = PublicKey.findProgramAddressSync(
+        [POSITION_SEED, vaultPda.toBuffer(), marketId.toBuffer()],
         program.programId
       );
-
-      // Open a position
       await program.methods
         .openPosition(
-          testMarketId.publicKey,
+          marketId,
           { yes: {} },
-          new anchor.BN(100_000_000), // 100 shares
-          new anchor.BN(500_000), // 0.5 per share
-          new anchor.BN(50_000_000) // 0.05 SOL invested
+          new anchor.BN(10_000_000),
+          new anchor.BN(500_000),
+          new anchor.BN(5_000_000),
+          null
         )
         .accounts({
           authority: authority.publicKey,
@@ -1999,198 +7328,175 @@ describe("SPECTRE Protocol - Phase 1", () => {
         })
         .signers([authority])
         .rpc();
-    });
-
-    it("should calculate positive PnL for price increase", async () => {
-      // Price went up from 0.5 to 0.7
-      const currentPrice = new anchor.BN(700_000); // 0.7
 
-      const tx = await program.methods
-        .getPositionPnl(currentPrice)
+      await program.methods
+        .closePosition(new anchor.BN(exitPrice))
         .accounts({
           authority: authority.publicKey,
           vault: vaultPda,
           position: positionPda,
+          feeRecipient: withRecipient ? feeRecipient.publicKey : null,
+          systemProgram: SystemProgram.programId,
         })
         .signers([authority])
         .rpc();
+    };
 
-      console.log("Get position PnL (profit) transaction:", tx);
+    before(async () => {
+      feeRecipient = Keypair.generate();
+      const airdrop = await provider.connection.requestAirdrop(
+        feeRecipient.publicKey,
+        LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdrop);
 
-      // Position value at 0.7: 100 shares * 0.7 = 70M lamports
-      // Invested: 50M lamports
-      // PnL: +20M lamports
-      const position = await program.account.position.fetch(positionPda);
-      assert.deepStrictEqual(position.status, { open: {} });
+      await setPerformanceFee(2_000, feeRecipient.publicKey);
     });
 
-    it("should calculate negative PnL for price decrease", async () => {
-      // Price went down from 0.5 to 0.3
-      const currentPrice = new anchor.BN(300_000); // 0.3
-
-      const tx = await program.methods
-        .getPositionPnl(currentPrice)
+    const setHighWaterMark = (enabled: boolean) =>
+      program.methods
+        .setHighWaterMarkEnabled(enabled)
         .accounts({
           authority: authority.publicKey,
           vault: vaultPda,
-          position: positionPda,
         })
         .signers([authority])
         .rpc();
 
-      console.log("Get position PnL (loss) transaction:", tx);
-
-      // Position value at 0.3: 100 shares * 0.3 = 30M lamports
-      // Invested: 50M lamports
-      // PnL: -20M lamports
+    after(async () => {
+      await setHighWaterMark(false);
+      await setPerformanceFee(0, PublicKey.default);
     });
-  });
 
-  describe("Phase 3 - Close Position", () => {
-    const POSITION_SEED = Buffer.from("position");
-    let testMarketId: Keypair;
-    let positionPda: PublicKey;
+    it("should take 20% of a profit as the performance fee", async () => {
+      const recipientBefore = await provider.connection.getBalance(feeRecipient.publicKey);
+      const vaultBefore = await program.account.spectreVault.fetch(vaultPda);
 
-    before(async () => {
-      // Create a new position for closing
-      testMarketId = Keypair.generate();
-      [positionPda] = PublicKey.findProgramAddressSync(
-        [POSITION_SEED, vaultPda.toBuffer(), testMarketId.publicKey.toBuffer()],
-        program.programId
-      );
+      // Exit at 0.6: 1M lamports of profit, 200k in fees
+      await openAndClose(600_000, true);
 
-      // Open a position
-      await program.methods
-        .openPosition(
-          testMarketId.publicKey,
-          { yes: {} },
-          new anchor.BN(100_000_000), // 100 shares
-          new anchor.BN(500_000), // 0.5 per share
-          new anchor.BN(50_000_000) // 0.05 SOL invested
-        )
-        .accounts({
-          authority: authority.publicKey,
-          vault: vaultPda,
-          position: positionPda,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([authority])
-        .rpc();
+      const recipientAfter = await provider.connection.getBalance(feeRecipient.publicKey);
+      assert.strictEqual(recipientAfter - recipientBefore, 200_000);
+      const vaultAfter = await program.account.spectreVault.fetch(vaultPda);
+      assert.ok(vaultAfter.availableBalance.eq(vaultBefore.availableBalance.addn(800_000)));
     });
 
-    it("should close position with profit", async () => {
-      const vaultBefore = await program.account.spectreVault.fetch(vaultPda);
-      const activePositionsBefore = vaultBefore.activePositions;
-
-      // Exit at price 0.7 (profit)
-      const exitPrice = new anchor.BN(700_000);
+    it("should reject a performance fee above the maximum", async () => {
+      try {
+        await setPerformanceFee(5_000, feeRecipient.publicKey);
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("InvalidAmount"));
+      }
 
-      const tx = await program.methods
-        .closePosition(exitPrice)
-        .accounts({
-          authority: authority.publicKey,
-          vault: vaultPda,
-          position: positionPda,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([authority])
-        .rpc();
+      const vault = await program.account.spectreVault.fetch(vaultPda);
+      assert.strictEqual(vault.performanceFeeBps, 2_000);
+    });
 
-      console.log("Close position (profit) transaction:", tx);
+    it("should charge nothing on a losing close", async () => {
+      const recipientBefore = await provider.connection.getBalance(feeRecipient.publicKey);
+      const vaultBefore = await program.account.spectreVault.fetch(vaultPda);
 
-      // Verify position state
-      const position = await program.account.position.fetch(positionPda);
-      assert.deepStrictEqual(position.status, { closed: {} });
-      assert.strictEqual(position.exitPrice.toNumber(), 700_000);
-      assert.ok(position.realizedPnl.toNumber() > 0); // Profit
+      // Exit at 0.4: 1M lamports lost; no fee recipient needed
+      await openAndClose(400_000, false);
 
-      // Verify vault state
+      assert.strictEqual(await provider.connection.getBalance(feeRecipient.publicKey), recipientBefore);
       const vaultAfter = await program.account.spectreVault.fetch(vaultPda);
-      assert.strictEqual(vaultAfter.activePositions, activePositionsBefore - 1);
-
-      console.log("  Realized PnL:", position.realizedPnl.toNumber(), "lamports");
+      assert.ok(vaultAfter.availableBalance.eq(vaultBefore.availableBalance.subn(1_000_000)));
     });
 
-    it("should reject closing already closed position", async () => {
+    it("should require the fee recipient for a profitable close", async () => {
       try {
-        await program.methods
-          .closePosition(new anchor.BN(500_000))
-          .accounts({
-            authority: authority.publicKey,
-            vault: vaultPda,
-            position: positionPda,
-            systemProgram: SystemProgram.programId,
-          })
-          .signers([authority])
-          .rpc();
+        await openAndClose(600_000, false);
         assert.fail("Should have thrown an error");
       } catch (err) {
-        assert.ok(err.toString().includes("PositionAlreadyClosed"));
+        assert.ok(err.toString().includes("InvalidFeeRecipient"));
       }
     });
-  });
 
-  describe("Phase 3 - Close Position with Loss", () => {
-    const POSITION_SEED = Buffer.from("position");
-    let testMarketId: Keypair;
-    let positionPda: PublicKey;
+    it("should not charge fees on a recovery below the high-water mark", async () => {
+      await setHighWaterMark(true);
 
-    before(async () => {
-      // Create a new position for closing at a loss
-      testMarketId = Keypair.generate();
-      [positionPda] = PublicKey.findProgramAddressSync(
-        [POSITION_SEED, vaultPda.toBuffer(), testMarketId.publicKey.toBuffer()],
-        program.programId
+      // Lose 1M, then win it back: a recovery, not a new high
+      await openAndClose(400_000, false);
+      const recipientBefore = await provider.connection.getBalance(feeRecipient.publicKey);
+      await openAndClose(600_000, true);
+      assert.strictEqual(
+        await provider.connection.getBalance(feeRecipient.publicKey),
+        recipientBefore
       );
 
-      // Open a position
-      await program.methods
-        .openPosition(
-          testMarketId.publicKey,
-          { no: {} }, // NO side this time
-          new anchor.BN(100_000_000),
-          new anchor.BN(500_000),
-          new anchor.BN(50_000_000)
-        )
-        .accounts({
-          authority: authority.publicKey,
-          vault: vaultPda,
-          position: positionPda,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([authority])
-        .rpc();
+      // Another 1M win is charged only on the part above the mark
+      const vault = await program.account.spectreVault.fetch(vaultPda);
+      const aboveMark = anchor.BN.max(
+        vault.cumulativeRealizedPnl.addn(1_000_000).sub(vault.highWaterMark),
+        new anchor.BN(0)
+      );
+      await openAndClose(600_000, true);
+      const recipientAfter = await provider.connection.getBalance(feeRecipient.publicKey);
+      assert.strictEqual(recipientAfter - recipientBefore, aboveMark.muln(2_000).divn(10_000).toNumber());
+
+      const vaultAfter = await program.account.spectreVault.fetch(vaultPda);
+      assert.ok(vaultAfter.highWaterMark.gte(vaultAfter.cumulativeRealizedPnl));
     });
+  });
 
-    it("should close position with loss", async () => {
-      // Exit at price 0.3 (loss)
-      const exitPrice = new anchor.BN(300_000);
+  describe("Phase 3 - Execute Trade Compute Budget", () => {
+    const STRATEGY_CONFIG_SEED = Buffer.from("strategy_config");
+    // Mirrors EXECUTE_TRADE_COMPUTE_BUDGET in the program
+    const EXECUTE_TRADE_COMPUTE_BUDGET = 100_000;
+    let strategyConfigPda: PublicKey;
 
-      const tx = await program.methods
-        .closePosition(exitPrice)
+    const computeUnitsFor = async (price: number, trend: number) => {
+      const sig = await program.methods
+        .executeTrade(
+          {
+            price,
+            trend,
+            volatility: 200,
+            timestamp: new anchor.BN(Date.now() / 1000),
+          },
+          null
+        )
         .accounts({
           authority: authority.publicKey,
           vault: vaultPda,
-          position: positionPda,
+          strategyConfig: strategyConfigPda,
           systemProgram: SystemProgram.programId,
         })
         .signers([authority])
-        .rpc();
+        .rpc({ commitment: "confirmed" });
 
-      console.log("Close position (loss) transaction:", tx);
+      const tx = await provider.connection.getTransaction(sig, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      return tx.meta.computeUnitsConsumed;
+    };
 
-      // Verify position state
-      const position = await program.account.position.fetch(positionPda);
-      assert.deepStrictEqual(position.status, { closed: {} });
-      assert.strictEqual(position.exitPrice.toNumber(), 300_000);
-      assert.ok(position.realizedPnl.toNumber() < 0); // Loss
+    before(async () => {
+      [strategyConfigPda] = PublicKey.findProgramAddressSync(
+        [STRATEGY_CONFIG_SEED, vaultPda.toBuffer()],
+        program.programId
+      );
+    });
 
-      console.log("  Realized PnL:", position.realizedPnl.toNumber(), "lamports");
+    it("should stay within the compute budget when trading", async () => {
+      // price 0.30 rising -> BUY
+      const units = await computeUnitsFor(300, 50);
+      console.log("  execute_trade (BUY) compute units:", units);
+      assert.ok(units <= EXECUTE_TRADE_COMPUTE_BUDGET);
+    });
+
+    it("should stay within the compute budget on HOLD", async () => {
+      // price 0.50 flat -> HOLD
+      const units = await computeUnitsFor(500, 0);
+      console.log("  execute_trade (HOLD) compute units:", units);
+      assert.ok(units <= EXECUTE_TRADE_COMPUTE_BUDGET);
     });
   });
 
-  describe("Phase 3 - Full Trading Loop", () => {
-    const POSITION_SEED = Buffer.from("position");
+  describe("Phase 3 - Signal Bias", () => {
     const STRATEGY_CONFIG_SEED = Buffer.from("strategy_config");
     let strategyConfigPda: PublicKey;
 
@@ -2201,87 +7507,101 @@ describe("SPECTRE Protocol - Phase 1", () => {
       );
     });
 
-    it("should complete full trading loop: signal -> position -> close", async () => {
-      console.log("\n--- Full Trading Loop ---");
-
-      // 1. Generate a strong buy signal
-      const marketInput = {
-        price: 250,
-        trend: 200,
-        volatility: 50,
-        timestamp: new anchor.BN(Date.now() / 1000),
-      };
+    it("should shift toward buys after a run of strong buy signals", async () => {
+      const before = await program.account.strategyConfig.fetch(strategyConfigPda);
 
-      await program.methods
-        .executeTrade(marketInput)
-        .accounts({
-          authority: authority.publicKey,
-          vault: vaultPda,
-          strategyConfig: strategyConfigPda,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([authority])
-        .rpc();
+      for (let i = 0; i < 3; i++) {
+        await program.methods
+          .generateTradeSignal({
+            price: 200,
+            trend: 300,
+            volatility: 50,
+            timestamp: new anchor.BN(Date.now() / 1000),
+          })
+          .accounts({
+            authority: authority.publicKey,
+            vault: vaultPda,
+            strategyConfig: strategyConfigPda,
+          })
+          .signers([authority])
+          .rpc();
+      }
 
-      const config = await program.account.strategyConfig.fetch(strategyConfigPda);
-      console.log("  1. Signal generated:", config.lastSignal === 1 ? "STRONG BUY" : "OTHER");
+      const after = await program.account.strategyConfig.fetch(strategyConfigPda);
+      assert.ok(after.signalBias > before.signalBias);
+    });
+  });
 
-      // 2. Open a position based on the signal
-      const marketId = Keypair.generate();
-      const [positionPda] = PublicKey.findProgramAddressSync(
-        [POSITION_SEED, vaultPda.toBuffer(), marketId.publicKey.toBuffer()],
-        program.programId
-      );
+  describe("Phase 3 - Quote Staleness", () => {
+    const maxQuoteAgeSlots = 20;
 
+    const setMaxQuoteAge = async (maxAgeSlots: number) => {
       await program.methods
-        .openPosition(
-          marketId.publicKey,
-          { yes: {} },
-          new anchor.BN(200_000_000), // Strong signal = larger position
-          new anchor.BN(250_000), // Entry at 0.25
-          new anchor.BN(50_000_000)
-        )
+        .setMaxQuoteAge(new anchor.BN(maxAgeSlots))
         .accounts({
           authority: authority.publicKey,
           vault: vaultPda,
-          position: positionPda,
-          systemProgram: SystemProgram.programId,
         })
         .signers([authority])
         .rpc();
+    };
+
+    // 0.60 / 0.40 market quoted with a 2% spread
+    const buildQuote = (generatedSlot: number) => ({
+      bid: {
+        side: { yes: {} },
+        amount: new anchor.BN(1_000_000),
+        orderType: { limit: {} },
+        limitPrice: new anchor.BN(590_000),
+        maxSlippageBps: new anchor.BN(0),
+        minPriceImprovementBps: new anchor.BN(0),
+        slippageSizeCoefficientBps: new anchor.BN(0),
+      },
+      ask: {
+        side: { no: {} },
+        amount: new anchor.BN(1_000_000),
+        orderType: { limit: {} },
+        limitPrice: new anchor.BN(390_000),
+        maxSlippageBps: new anchor.BN(0),
+        minPriceImprovementBps: new anchor.BN(0),
+        slippageSizeCoefficientBps: new anchor.BN(0),
+      },
+      generatedSlot: new anchor.BN(generatedSlot),
+    });
 
-      console.log("  2. Position opened");
-
-      // 3. Check PnL at a higher price
+    const submitQuote = async (generatedSlot: number) => {
       await program.methods
-        .getPositionPnl(new anchor.BN(400_000)) // Price moved to 0.4
+        .submitQuote(buildQuote(generatedSlot))
         .accounts({
           authority: authority.publicKey,
           vault: vaultPda,
-          position: positionPda,
         })
         .signers([authority])
         .rpc();
+    };
 
-      console.log("  3. PnL checked");
+    before(async () => {
+      await setMaxQuoteAge(maxQuoteAgeSlots);
+    });
 
-      // 4. Close the position at profit
-      await program.methods
-        .closePosition(new anchor.BN(500_000)) // Exit at 0.5
-        .accounts({
-          authority: authority.publicKey,
-          vault: vaultPda,
-          position: positionPda,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([authority])
-        .rpc();
+    after(async () => {
+      await setMaxQuoteAge(0);
+    });
 
-      const position = await program.account.position.fetch(positionPda);
-      console.log("  4. Position closed with PnL:", position.realizedPnl.toNumber(), "lamports");
+    it("should accept a fresh quote", async () => {
+      const slot = await provider.connection.getSlot();
+      await submitQuote(slot);
+    });
 
-      assert.deepStrictEqual(position.status, { closed: {} });
-      assert.ok(position.realizedPnl.toNumber() > 0);
+    it("should reject a quote older than the max age", async () => {
+      const slot = await provider.connection.getSlot();
+
+      try {
+        await submitQuote(slot - maxQuoteAgeSlots - 10);
+        assert.fail("Should have thrown an error");
+      } catch (err) {
+        assert.ok(err.toString().includes("StaleQuote"));
+      }
     });
   });
 
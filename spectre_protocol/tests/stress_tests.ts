@@ -49,7 +49,7 @@ describe("SPECTRE Protocol - Stress Tests", () => {
 
     // Initialize vault
     await program.methods
-      .initialize(null)
+      .initialize(null, null)
       .accounts({
         authority: stressAuthority.publicKey,
         vault: stressVaultPda,
@@ -93,10 +93,11 @@ describe("SPECTRE Protocol - Stress Tests", () => {
           amount: new anchor.BN(1 * LAMPORTS_PER_SOL),
           merkleRoot: new Array(32).fill(0),
         },
+        proofSlot: new anchor.BN(0),
       };
 
       await program.methods
-        .fundAgent(proof)
+        .fundAgent(proof, null)
         .accounts({
           depositor: depositor.publicKey,
           vault: stressVaultPda,
@@ -138,10 +139,11 @@ describe("SPECTRE Protocol - Stress Tests", () => {
           amount: new anchor.BN(largeAmount),
           merkleRoot: new Array(32).fill(0),
         },
+        proofSlot: new anchor.BN(0),
       };
 
       await program.methods
-        .fundAgent(proof)
+        .fundAgent(proof, null)
         .accounts({
           depositor: depositor.publicKey,
           vault: stressVaultPda,
@@ -175,7 +177,8 @@ describe("SPECTRE Protocol - Stress Tests", () => {
           { yes: {} },
           largeShares,
           entryPrice,
-          investedAmount
+          investedAmount,
+          null
         )
         .accounts({
           authority: stressAuthority.publicKey,
@@ -225,7 +228,8 @@ describe("SPECTRE Protocol - Stress Tests", () => {
           { yes: {} },
           new anchor.BN(100_000_000),
           maxPrice,
-          new anchor.BN(100_000_000)
+          new anchor.BN(100_000_000),
+          null
         )
         .accounts({
           authority: stressAuthority.publicKey,
@@ -271,7 +275,7 @@ describe("SPECTRE Protocol - Stress Tests", () => {
       };
 
       await program.methods
-        .executeTrade(marketInput)
+        .executeTrade(marketInput, null)
         .accounts({
           authority: stressAuthority.publicKey,
           vault: stressVaultPda,
@@ -293,6 +297,8 @@ describe("SPECTRE Protocol - Stress Tests", () => {
         priceThresholdHigh: 500,
         trendThreshold: 1,
         volatilityCap: 1,
+        defaultSignal: { hold: {} },
+        signalOverrides: new Array(9).fill(null),
         reserved: new Array(16).fill(0),
       };
 
@@ -317,6 +323,8 @@ describe("SPECTRE Protocol - Stress Tests", () => {
           priceThresholdHigh: 650,
           trendThreshold: 100,
           volatilityCap: 400,
+          defaultSignal: { hold: {} },
+          signalOverrides: new Array(9).fill(null),
           reserved: new Array(16).fill(0),
         })
         .accounts({
@@ -425,7 +433,8 @@ describe("SPECTRE Protocol - Stress Tests", () => {
             i % 2 === 0 ? { yes: {} } : { no: {} },
             new anchor.BN(10_000_000),
             new anchor.BN(500_000),
-            new anchor.BN(5_000_000)
+            new anchor.BN(5_000_000),
+            null
           )
           .accounts({
             authority: stressAuthority.publicKey,
@@ -474,7 +483,7 @@ describe("SPECTRE Protocol - Stress Tests", () => {
       const invested = new anchor.BN(50_000_000);
 
       await program.methods
-        .openPosition(marketId.publicKey, { yes: {} }, shares, price, invested)
+        .openPosition(marketId.publicKey, { yes: {} }, shares, price, invested, null)
         .accounts({
           authority: stressAuthority.publicKey,
           vault: stressVaultPda,
@@ -516,7 +525,8 @@ describe("SPECTRE Protocol - Stress Tests", () => {
           { yes: {} },
           new anchor.BN(20_000_000),
           new anchor.BN(500_000),
-          invested
+          invested,
+          null
         )
         .accounts({
           authority: stressAuthority.publicKey,
@@ -558,7 +568,7 @@ describe("SPECTRE Protocol - Stress Tests", () => {
       const exitPrice = new anchor.BN(1_000_000); // 1.0 (100%)
 
       await program.methods
-        .openPosition(marketId.publicKey, { yes: {} }, shares, entryPrice, invested)
+        .openPosition(marketId.publicKey, { yes: {} }, shares, entryPrice, invested, null)
         .accounts({
           authority: stressAuthority.publicKey,
           vault: stressVaultPda,
@@ -584,6 +594,51 @@ describe("SPECTRE Protocol - Stress Tests", () => {
       assert.strictEqual(position.realizedPnl.toNumber(), 50_000_000);
       console.log("  100% gain PnL:", position.realizedPnl.toNumber());
     });
+
+    it("should reject exit price above 100%", async () => {
+      const marketId = Keypair.generate();
+      const [positionPda] = PublicKey.findProgramAddressSync(
+        [POSITION_SEED, stressVaultPda.toBuffer(), marketId.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .openPosition(
+          marketId.publicKey,
+          { yes: {} },
+          new anchor.BN(20_000_000),
+          new anchor.BN(500_000),
+          new anchor.BN(10_000_000),
+          null
+        )
+        .accounts({
+          authority: stressAuthority.publicKey,
+          vault: stressVaultPda,
+          position: positionPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([stressAuthority])
+        .rpc();
+
+      try {
+        await program.methods
+          .closePosition(new anchor.BN("18446744073709551615"))
+          .accounts({
+            authority: stressAuthority.publicKey,
+            vault: stressVaultPda,
+            position: positionPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([stressAuthority])
+          .rpc();
+        assert.fail("Should have rejected out-of-range exit price");
+      } catch (err) {
+        assert.ok(err.toString().includes("InvalidPrice"));
+      }
+
+      const position = await program.account.position.fetch(positionPda);
+      assert.deepStrictEqual(position.status, { open: {} });
+    });
   });
 
   // ============================================
@@ -614,7 +669,8 @@ describe("SPECTRE Protocol - Stress Tests", () => {
           trend: 100,
           volatility: 200,
           timestamp: new anchor.BN(Date.now() / 1000),
-        })
+        },
+          null)
         .accounts({
           authority: stressAuthority.publicKey,
           vault: stressVaultPda,
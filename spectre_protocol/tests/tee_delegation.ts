@@ -142,7 +142,7 @@ describe('SPECTRE TEE Delegation', () => {
         const modelHash = new Array(32).fill(0);
 
         await program.methods
-          .initialize(modelHash)
+          .initialize(modelHash, null)
           .accounts({
             authority: authority,
             vault: vaultPda,
@@ -384,7 +384,7 @@ describe('TEE Strategy Execution', () => {
 
     try {
       const tx = await program.methods
-        .executeTrade(marketInput)
+        .executeTrade(marketInput, null)
         .accounts({
           authority: authority,
           vault: vaultPda,
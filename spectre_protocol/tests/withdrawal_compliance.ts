@@ -162,7 +162,7 @@ describe('SPECTRE Withdrawal Compliance', () => {
         const modelHash = new Array(32).fill(0);
 
         await program.methods
-          .initialize(modelHash)
+          .initialize(modelHash, null)
           .accounts({
             authority,
             vault: vaultPda,